@@ -114,4 +114,8 @@ pub enum Subcommand {
 
     /// Db meta columns information.
     FrontierDb(fc_cli::FrontierDbCmd),
+
+    /// Generate and verify micropayment vouchers.
+    #[command(subcommand)]
+    Voucher(micropayment_voucher::cli::VoucherCmd),
 }
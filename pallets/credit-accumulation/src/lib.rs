@@ -49,7 +49,11 @@ pub mod pallet {
     use frame_system::pallet_prelude::*;
     #[cfg(feature = "runtime-benchmarks")]
     use node_primitives::AccountCreator;
-    use node_primitives::{credit::CreditInterface, VerifySignatureInterface};
+    use node_primitives::{
+        credit::CreditInterface,
+        deeper_node::{attestation_message, device_migration_message, NodeInterface},
+        VerifySignatureInterface,
+    };
     use sp_core::crypto::UncheckedFrom;
     use sp_core::sr25519;
     use sp_io::crypto::sr25519_verify;
@@ -64,6 +68,8 @@ pub mod pallet {
         type Currency: Currency<Self::AccountId>;
         // CreditInterface of credit pallet
         type CreditInterface: CreditInterface<Self::AccountId, BalanceOf<Self>>;
+        // NodeInterface of deeper-node pallet
+        type NodeInterface: NodeInterface<Self::AccountId, Self::BlockNumber>;
         // Weight information for extrinsics in this pallet.
         type WeightInfo: WeightInfo;
         #[cfg(feature = "runtime-benchmarks")]
@@ -105,6 +111,8 @@ pub mod pallet {
         InvalidSignature,
         /// Invalid atomos nonce
         InvalidAtomosNonce,
+        /// device is not attested by a manufacturer
+        DeviceNotAttested,
     }
 
     // Dispatchable functions allows users to interact with the pallet and invoke state changes.
@@ -125,6 +133,10 @@ pub mod pallet {
                 nonce == atmos_nonce_of_server,
                 Error::<T>::InvalidAtomosNonce
             );
+            ensure!(
+                T::NodeInterface::attestation_satisfied(&server),
+                Error::<T>::DeviceNotAttested
+            );
 
             Self::verify_atomos_signature(nonce, &signature, server.clone())?;
             Self::deposit_event(Event::AtmosSignatureValid(server.clone()));
@@ -206,6 +218,44 @@ pub mod pallet {
             ensure!(verified, Error::<T>::InvalidSignature);
             Ok(().into())
         }
+
+        /// Verify the manufacturer's signature on the attestation message of a device.
+        pub fn verify_device_attestation(
+            manufacturer: &T::AccountId,
+            device: &T::AccountId,
+            signature: &[u8],
+        ) -> bool {
+            Self::verify_account_signature(
+                manufacturer,
+                &attestation_message(manufacturer, device),
+                signature,
+            )
+        }
+
+        /// Verify the new account's signature on the migration message of an old device.
+        pub fn verify_device_migration(
+            old: &T::AccountId,
+            new: &T::AccountId,
+            signature: &[u8],
+        ) -> bool {
+            Self::verify_account_signature(new, &device_migration_message(old, new), signature)
+        }
+
+        // verify a sr25519 signature of the account on the message
+        fn verify_account_signature(
+            signer: &T::AccountId,
+            message: &[u8],
+            signature: &[u8],
+        ) -> bool {
+            let pk: [u8; 32] = match signer.encode().try_into() {
+                Ok(pk) => pk,
+                Err(_) => return false,
+            };
+            match sr25519::Signature::from_slice(signature) {
+                Some(sig) => sr25519_verify(&sig, message, &sr25519::Public::from_raw(pk)),
+                None => false,
+            }
+        }
     }
 
     impl<T: Config> VerifySignatureInterface<T::AccountId> for Pallet<T> {
@@ -220,5 +270,21 @@ pub mod pallet {
         ) -> bool {
             Self::verify_atomos_new_signature(nonce, &signature, sender.clone()).is_ok()
         }
+
+        fn verify_device_attestation(
+            manufacturer: &T::AccountId,
+            device: &T::AccountId,
+            signature: &[u8],
+        ) -> bool {
+            Self::verify_device_attestation(manufacturer, device, signature)
+        }
+
+        fn verify_device_migration(
+            old: &T::AccountId,
+            new: &T::AccountId,
+            signature: &[u8],
+        ) -> bool {
+            Self::verify_device_migration(old, new, signature)
+        }
     }
 }
@@ -20,9 +20,10 @@ use frame_support::traits::{AsEnsureOriginWithArg, ConstU128, ConstU32, OnFinali
 
 use frame_system as system;
 use node_primitives::credit::{CreditData, CreditLevel};
+use node_primitives::user_privileges::{Privilege, UserPrivilegeInterface};
 use node_primitives::{Balance, Moment};
 use sp_core::testing::SR25519;
-use sp_core::{crypto::AccountId32, H256};
+use sp_core::{crypto::AccountId32, H160, H256};
 use sp_keystore::SyncCryptoStore;
 use sp_keystore::{testing::KeyStore, KeystoreExt};
 use sp_runtime::{
@@ -164,6 +165,14 @@ impl pallet_uniques::Config for Test {
 
 parameter_types! {
     pub const MaxBurnCreditPerAddress: u32 = 50;
+    pub const MinSettingEnactmentDelay: u32 = 2;
+    pub const MaxScheduledSettingsPerEra: u32 = 10;
+    pub const MaxCreditHistory: u32 = 100;
+    pub const PriceQuorum: u32 = 1;
+    pub const PriceStaleAfter: u64 = 24 * 60 * 60;
+    pub const MaxPriceHistory: u32 = 10;
+    pub const PriceAggregationInterval: u64 = 0;
+    pub const PriceFeedInterval: u64 = 0;
 }
 
 impl pallet_credit::Config for Test {
@@ -181,13 +190,45 @@ impl pallet_credit::Config for Test {
     type BurnedTo = ();
     type UserPrivilegeInterface = ();
     type MaxBurnCreditPerAddress = MaxBurnCreditPerAddress;
+    type CreditSettingOrigin = frame_system::EnsureRoot<AccountId>;
+    type MinSettingEnactmentDelay = MinSettingEnactmentDelay;
+    type MaxScheduledSettingsPerEra = MaxScheduledSettingsPerEra;
+    type MaxCreditHistory = MaxCreditHistory;
+    type PriceQuorum = PriceQuorum;
+    type PriceStaleAfter = PriceStaleAfter;
+    type MaxPriceHistory = MaxPriceHistory;
+    type PriceAggregationInterval = PriceAggregationInterval;
+    type PriceFeed = ();
+    type PriceFeedInterval = PriceFeedInterval;
+    type MaxPriceFeedEndpoints = ConstU32<2>;
+    type MaxPriceFeedEndpointLength = ConstU32<64>;
+    type CreditBadgeCollection = ();
+    type MaxCreditChanges = ConstU32<10>;
 }
 
 parameter_types! {
     pub const MinLockAmt: u32 = 100;
     pub const MaxDurationEras: u8 = 7;
     pub const MaxIpLength: usize = 256;
+    pub const MaxSweepDevices: u32 = 10;
+    pub const MaxServersPerRegion: u32 = 100;
+    pub const MaxFirmwareLength: u32 = 64;
+    pub const DeviceMigrationCooldown: u64 = 100;
+    pub static AllPrivileged: bool = true;
 }
+
+/// every account has every privilege unless `AllPrivileged` is unset
+pub struct MockPrivileges;
+impl UserPrivilegeInterface<AccountId> for MockPrivileges {
+    fn has_privilege(_user: &AccountId, _p: Privilege) -> bool {
+        AllPrivileged::get()
+    }
+
+    fn has_evm_privilege(_user: &H160, _p: Privilege) -> bool {
+        AllPrivileged::get()
+    }
+}
+
 impl pallet_deeper_node::Config for Test {
     type RuntimeEvent = RuntimeEvent;
     type Currency = Balances;
@@ -196,7 +237,14 @@ impl pallet_deeper_node::Config for Test {
     type BlocksPerEra = BlocksPerEra;
     type MaxIpLength = MaxIpLength;
     type WeightInfo = ();
-    type VerifySignatureInterface = ();
+    type VerifySignatureInterface = CreditAccumulation;
+    type CreditInterface = Credit;
+    type MaxSweepDevices = MaxSweepDevices;
+    type MaxServersPerRegion = MaxServersPerRegion;
+    type MaxFirmwareLength = MaxFirmwareLength;
+    type UserPrivilegeInterface = MockPrivileges;
+    type DeviceMigration = ();
+    type DeviceMigrationCooldown = DeviceMigrationCooldown;
 }
 
 parameter_types! {
@@ -207,6 +255,7 @@ impl pallet_credit_accumulation::Config for Test {
     type RuntimeEvent = RuntimeEvent;
     type Currency = Balances;
     type CreditInterface = Credit;
+    type NodeInterface = DeeperNode;
     type WeightInfo = ();
 }
 
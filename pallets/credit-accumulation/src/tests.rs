@@ -20,6 +20,7 @@ use frame_support::{
 };
 use frame_system::RawOrigin;
 use hex_literal::hex;
+use node_primitives::deeper_node::{attestation_message, device_migration_message};
 use pallet_credit::LastCreditUpdateTimestamp;
 use pallet_deeper_node::OnboardTime;
 use sp_core::testing::SR25519;
@@ -238,3 +239,117 @@ fn verify_atomos_signature() {
         ));
     });
 }
+
+#[test]
+fn add_credit_by_traffic_requires_attestation() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(CreditAccumulation::set_atmos_pubkey(
+            RawOrigin::Root.into(),
+            bob(),
+        ));
+        DeeperNode::setup_region_map();
+        assert_ok!(DeeperNode::register_device(
+            RuntimeOrigin::signed(alice()),
+            vec![1, 2, 3, 4],
+            "US".as_bytes().to_vec()
+        ));
+        // every account is a manufacturer and device admin in the mock
+        assert_ok!(DeeperNode::set_attestation_required(
+            RuntimeOrigin::signed(bob()),
+            true
+        ));
+
+        let nonce: u64 = 0;
+        let signature: [u8; 64] = hex!("5071a1a526b1d2d1833e4de43d1ce22ad3506de2e10ee4a9c18c0b310c54286b9cb10bfb4ee12be6b93e91337de0fa2ea2edd787d083db36211109bdc8438989");
+        assert_eq!(
+            CreditAccumulation::add_credit_by_traffic(
+                RuntimeOrigin::signed(alice()),
+                nonce,
+                signature.into()
+            ),
+            Err(DispatchErrorWithPostInfo::from(Error::<Test>::DeviceNotAttested))
+        );
+
+        // bob's key is in the test keystore
+        let pub_key = sp_core::sr25519::Public::from_raw(bob().into());
+        let wrong_device = sr25519_sign(SR25519, &pub_key, &attestation_message(&bob(), &bob()))
+            .unwrap();
+        assert_eq!(
+            DeeperNode::attest_device(
+                RuntimeOrigin::signed(alice()),
+                bob(),
+                wrong_device.encode()
+            ),
+            Err(DispatchErrorWithPostInfo::from(
+                pallet_deeper_node::Error::<Test>::SignatureVerifyFailed
+            ))
+        );
+        let attestation =
+            sr25519_sign(SR25519, &pub_key, &attestation_message(&bob(), &alice())).unwrap();
+        assert_ok!(DeeperNode::attest_device(
+            RuntimeOrigin::signed(alice()),
+            bob(),
+            attestation.encode()
+        ));
+        assert_ok!(CreditAccumulation::add_credit_by_traffic(
+            RuntimeOrigin::signed(alice()),
+            nonce,
+            signature.into()
+        ));
+    });
+}
+
+#[test]
+fn migrate_device_with_signature() {
+    new_test_ext().execute_with(|| {
+        AllPrivileged::set(false);
+        System::set_block_number(1);
+        assert_ok!(DeeperNode::im_online(RuntimeOrigin::signed(alice())));
+
+        // bob's key is in the test keystore, the new account signs the migration
+        let pub_key = sp_core::sr25519::Public::from_raw(bob().into());
+        let wrong_target = sr25519_sign(
+            SR25519,
+            &pub_key,
+            &device_migration_message(&alice(), &charlie()),
+        )
+        .unwrap();
+        assert_eq!(
+            DeeperNode::migrate_device(
+                RuntimeOrigin::signed(alice()),
+                alice(),
+                bob(),
+                wrong_target.encode()
+            ),
+            Err(DispatchErrorWithPostInfo::from(
+                pallet_deeper_node::Error::<Test>::SignatureVerifyFailed
+            ))
+        );
+        let signature = sr25519_sign(
+            SR25519,
+            &pub_key,
+            &device_migration_message(&alice(), &bob()),
+        )
+        .unwrap();
+        // only the old account submits the signed migration
+        assert_eq!(
+            DeeperNode::migrate_device(
+                RuntimeOrigin::signed(bob()),
+                alice(),
+                bob(),
+                signature.encode()
+            ),
+            Err(DispatchErrorWithPostInfo::from(
+                pallet_deeper_node::Error::<Test>::NotDeviceOwner
+            ))
+        );
+        assert_ok!(DeeperNode::migrate_device(
+            RuntimeOrigin::signed(alice()),
+            alice(),
+            bob(),
+            signature.encode()
+        ));
+        assert_eq!(OnboardTime::<Test>::get(alice()), None);
+        assert_eq!(OnboardTime::<Test>::get(bob()), Some(1));
+    });
+}
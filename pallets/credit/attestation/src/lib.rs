@@ -0,0 +1,328 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Portable credit attestations of deeper chain
+//!
+//! An attestation proves the `UserCredit` entry of an account at a finalized block: a storage
+//! proof against the state root of the block header, and a GRANDPA finality proof of the header.
+//! Nodes serve attestations with the `credit_getAttestation` RPC, and this crate verifies them
+//! without trusting any node, given the GRANDPA authority set the verifier tracks.
+//!
+//! A verified attestation is posted to EVM chains by a relayer as [`VerifiedCredit::evm_message`].
+
+use codec::{Decode, Encode};
+use finality_grandpa::voter_set::VoterSet;
+use node_primitives::{credit::CreditData, Block, BlockNumber, Hash};
+use sc_finality_grandpa::{FinalityProof, GrandpaJustification};
+use serde::{Deserialize, Serialize};
+use sp_core::{
+    hashing::{blake2_128, keccak_256, twox_128},
+    Bytes,
+};
+use sp_finality_grandpa::{AuthorityList, SetId};
+use sp_runtime::traits::{BlakeTwo256, Header as HeaderT};
+use sp_state_machine::{read_proof_check, StorageProof};
+use std::fmt;
+
+pub use node_primitives::{AccountId, Header};
+
+/// Name of the credit pallet in the deeper runtime.
+pub const CREDIT_PALLET_NAME: &[u8] = b"Credit";
+
+/// Signature of the EVM message, its keccak256 is the first word of the message.
+pub const EVM_MESSAGE_TYPE: &[u8] = b"DeeperCreditAttestation(bytes32 account,uint64 credit,uint8 level,uint16 campaignId,uint32 blockNumber,bytes32 blockHash)";
+
+/// Errors of attestation verification.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Finality proof can not be decoded or does not lead from the attested header to the
+    /// justified block.
+    InvalidFinalityProof,
+    /// Trusted authority set is empty.
+    InvalidAuthoritySet,
+    /// GRANDPA justification is not signed by the trusted authority set.
+    InvalidJustification(String),
+    /// Storage proof does not match the state root of the attested header.
+    InvalidStorageProof,
+    /// Proven value is not a credit data.
+    InvalidCreditData,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidFinalityProof => write!(f, "invalid finality proof"),
+            Error::InvalidAuthoritySet => write!(f, "empty authority set"),
+            Error::InvalidJustification(e) => write!(f, "invalid GRANDPA justification: {}", e),
+            Error::InvalidStorageProof => write!(f, "invalid storage proof"),
+            Error::InvalidCreditData => write!(f, "proven value is not a credit data"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Storage key of the `UserCredit` entry of the account: the pallet and storage prefixes
+/// followed by the `Blake2_128Concat` hash of the account.
+///
+/// Byte-identical to `pallet_credit::UserCredit::<Runtime>::hashed_key_for`.
+pub fn user_credit_key(account: &AccountId) -> Vec<u8> {
+    let encoded = account.encode();
+    let mut key = Vec::new();
+    key.extend_from_slice(&twox_128(CREDIT_PALLET_NAME));
+    key.extend_from_slice(&twox_128(b"UserCredit"));
+    key.extend_from_slice(&blake2_128(&encoded));
+    key.extend_from_slice(&encoded);
+    key
+}
+
+/// Credit data of an account at a finalized block with the proofs of both, served by the
+/// `credit_getAttestation` RPC.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreditAttestation<Account = AccountId, BlockHeader = Header> {
+    pub account: Account,
+    /// header of the attested block
+    pub header: BlockHeader,
+    /// trie nodes proving the `UserCredit` entry of the account against the state root
+    pub storage_proof: Vec<Bytes>,
+    /// SCALE encoded GRANDPA `FinalityProof` of the attested block
+    pub finality_proof: Bytes,
+}
+
+/// Credit data proven by an attestation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifiedCredit {
+    pub account: AccountId,
+    pub block_number: BlockNumber,
+    pub block_hash: Hash,
+    /// none if the account has no credit data at the block
+    pub credit_data: Option<CreditData>,
+}
+
+impl CreditAttestation {
+    /// Verify the attestation against the GRANDPA authority set `set_id` of the attested block,
+    /// which the verifier trusts, e.g. tracked from a checkpoint with `grandpa_proveFinality`.
+    pub fn verify(
+        &self,
+        set_id: SetId,
+        authorities: &AuthorityList,
+    ) -> Result<VerifiedCredit, Error> {
+        self.verify_finality(set_id, authorities)?;
+        let credit_data = self.verify_storage()?;
+        Ok(VerifiedCredit {
+            account: self.account.clone(),
+            block_number: *self.header.number(),
+            block_hash: self.header.hash(),
+            credit_data,
+        })
+    }
+
+    /// check the finality proof: the headers following the attested one lead to the block
+    /// finalized by the justification
+    fn verify_finality(&self, set_id: SetId, authorities: &AuthorityList) -> Result<(), Error> {
+        let proof = FinalityProof::<Header>::decode(&mut &self.finality_proof[..])
+            .map_err(|_| Error::InvalidFinalityProof)?;
+        let mut justified = (self.header.hash(), *self.header.number());
+        for header in &proof.unknown_headers {
+            if *header.parent_hash() != justified.0 || *header.number() != justified.1 + 1 {
+                return Err(Error::InvalidFinalityProof);
+            }
+            justified = (header.hash(), *header.number());
+        }
+        if proof.block != justified.0 {
+            return Err(Error::InvalidFinalityProof);
+        }
+
+        let voters =
+            VoterSet::new(authorities.iter().cloned()).ok_or(Error::InvalidAuthoritySet)?;
+        GrandpaJustification::<Block>::decode_and_verify_finalizes(
+            &proof.justification,
+            justified,
+            set_id,
+            &voters,
+        )
+        .map_err(|e| Error::InvalidJustification(e.to_string()))?;
+        Ok(())
+    }
+
+    /// check the storage proof against the state root and decode the proven credit data
+    fn verify_storage(&self) -> Result<Option<CreditData>, Error> {
+        let key = user_credit_key(&self.account);
+        let proof = StorageProof::new(self.storage_proof.iter().map(|node| node.to_vec()));
+        let mut values =
+            read_proof_check::<BlakeTwo256, _>(*self.header.state_root(), proof, [&key])
+                .map_err(|_| Error::InvalidStorageProof)?;
+        values
+            .remove(&key)
+            .flatten()
+            .map(|value| CreditData::decode(&mut &value[..]).map_err(|_| Error::InvalidCreditData))
+            .transpose()
+    }
+}
+
+/// 32 bytes ABI word of an unsigned integer
+fn abi_word(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+impl VerifiedCredit {
+    /// ABI encoded message a relayer posts to EVM chains:
+    /// `abi.encode(keccak256(EVM_MESSAGE_TYPE), account, credit, level, campaignId, blockNumber,
+    /// blockHash)`. An account without credit data is posted with zero credit, level and
+    /// campaign.
+    pub fn evm_message(&self) -> Vec<u8> {
+        let (credit, level, campaign_id) = self.credit_data.as_ref().map_or((0, 0, 0), |data| {
+            (
+                data.credit,
+                data.current_credit_level as u64,
+                data.campaign_id as u64,
+            )
+        });
+        let account: &[u8; 32] = self.account.as_ref();
+        let mut message = Vec::with_capacity(7 * 32);
+        message.extend_from_slice(&keccak_256(EVM_MESSAGE_TYPE));
+        message.extend_from_slice(account);
+        message.extend_from_slice(&abi_word(credit));
+        message.extend_from_slice(&abi_word(level));
+        message.extend_from_slice(&abi_word(campaign_id));
+        message.extend_from_slice(&abi_word(self.block_number as u64));
+        message.extend_from_slice(self.block_hash.as_bytes());
+        message
+    }
+
+    /// keccak256 of the EVM message, the hash the relayer signs.
+    pub fn evm_message_hash(&self) -> [u8; 32] {
+        keccak_256(&self.evm_message())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use node_primitives::credit::CreditLevel;
+    use sp_core::storage::StateVersion;
+    use sp_runtime::Digest;
+    use sp_state_machine::{prove_read, InMemoryBackend};
+
+    fn credit_data() -> CreditData {
+        CreditData {
+            campaign_id: 4,
+            credit: 300,
+            initial_credit_level: CreditLevel::Three,
+            rank_in_initial_credit_level: 1,
+            number_of_referees: 0,
+            reward_eras: 3650,
+            current_credit_level: CreditLevel::Three,
+        }
+    }
+
+    /// attestation of `credit_data` with a state holding only its `UserCredit` entry
+    fn attestation(account: AccountId) -> CreditAttestation {
+        let key = user_credit_key(&account);
+        let backend = InMemoryBackend::<BlakeTwo256>::from((
+            vec![(None, vec![(key.clone(), Some(credit_data().encode()))])],
+            StateVersion::V1,
+        ));
+        let state_root = *backend.root();
+        let proof = prove_read(backend, [&key]).unwrap();
+        CreditAttestation {
+            account,
+            header: Header::new(
+                7,
+                Default::default(),
+                state_root,
+                Default::default(),
+                Digest::default(),
+            ),
+            storage_proof: proof.into_iter_nodes().map(Into::into).collect(),
+            finality_proof: Bytes(Vec::new()),
+        }
+    }
+
+    #[test]
+    fn verify_storage_proof() {
+        let mut attestation = attestation(AccountId::new([1u8; 32]));
+        assert_eq!(attestation.verify_storage(), Ok(Some(credit_data())));
+
+        attestation.header.state_root = Hash::repeat_byte(1);
+        assert_eq!(
+            attestation.verify_storage(),
+            Err(Error::InvalidStorageProof)
+        );
+    }
+
+    #[test]
+    fn finality_proof_must_reach_attested_header() {
+        let mut attestation = attestation(AccountId::new([1u8; 32]));
+        let authorities = vec![(sp_core::ed25519::Public::from_raw([1u8; 32]).into(), 1)];
+        assert_eq!(
+            attestation.verify(0, &authorities),
+            Err(Error::InvalidFinalityProof)
+        );
+
+        // the next header does not follow the attested one
+        let next = Header::new(
+            9,
+            Default::default(),
+            Default::default(),
+            attestation.header.hash(),
+            Digest::default(),
+        );
+        attestation.finality_proof = Bytes(
+            FinalityProof::<Header> {
+                block: next.hash(),
+                justification: Vec::new(),
+                unknown_headers: vec![next],
+            }
+            .encode(),
+        );
+        assert_eq!(
+            attestation.verify(0, &authorities),
+            Err(Error::InvalidFinalityProof)
+        );
+    }
+
+    #[test]
+    fn evm_message_layout() {
+        let verified = VerifiedCredit {
+            account: AccountId::new([1u8; 32]),
+            block_number: 7,
+            block_hash: Hash::repeat_byte(2),
+            credit_data: Some(credit_data()),
+        };
+        let message = verified.evm_message();
+        assert_eq!(message.len(), 7 * 32);
+        assert_eq!(&message[..32], &keccak_256(EVM_MESSAGE_TYPE));
+        assert_eq!(&message[32..64], &[1u8; 32]);
+        assert_eq!(&message[64..96], &abi_word(300));
+        assert_eq!(&message[96..128], &abi_word(3));
+        assert_eq!(&message[128..160], &abi_word(4));
+        assert_eq!(&message[160..192], &abi_word(7));
+        assert_eq!(&message[192..], &[2u8; 32]);
+        assert_eq!(verified.evm_message_hash(), keccak_256(&message));
+    }
+
+    #[test]
+    fn attestation_json_round_trip() {
+        let attestation = attestation(AccountId::new([1u8; 32]));
+        let json = serde_json::to_string(&attestation).unwrap();
+        assert_eq!(
+            serde_json::from_str::<CreditAttestation>(&json).unwrap(),
+            attestation
+        );
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runtime API definition for the credit pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use node_primitives::{
+    credit::{
+        Campaign, CampaignId, CreditChange, CreditCounter, CreditData, CreditHistorySummary,
+        CreditLevel, CreditSource, EraIndex,
+    },
+    BlockNumber,
+};
+use sp_core::H160;
+use sp_std::prelude::*;
+
+sp_api::decl_runtime_apis! {
+    pub trait CreditApi<AccountId, Balance> where
+        AccountId: Codec,
+        Balance: Codec,
+    {
+        /// current credit data of the account
+        fn get_credit_data(account_id: AccountId) -> Option<CreditData>;
+
+        /// recent (era, credit data) records of the account and the summary of the older
+        /// records compacted out of the recent window
+        fn get_credit_history(
+            account_id: AccountId,
+        ) -> (Vec<(EraIndex, CreditData)>, Option<CreditHistorySummary>);
+
+        /// current credit level of the account
+        fn get_credit_level(account_id: AccountId) -> Option<CreditLevel>;
+
+        /// dry-run of the poc reward for eras between `from` and `to`,
+        /// state changes made during the calculation are discarded
+        fn estimate_reward(account_id: AccountId, from: EraIndex, to: EraIndex) -> Option<Balance>;
+
+        /// deeper account mapped to the evm address
+        fn evm_to_account(evm_address: H160) -> Option<AccountId>;
+
+        /// registered campaign
+        fn get_campaign(campaign_id: CampaignId) -> Option<Campaign<Balance>>;
+
+        /// remaining budget of a registered campaign, None if it is not registered or uncapped
+        fn campaign_remaining_budget(campaign_id: CampaignId) -> Option<Balance>;
+
+        /// time weighted average DPR price over the last `window` seconds,
+        /// None if no price was aggregated yet
+        fn dpr_twap(window: u64) -> Option<Balance>;
+
+        /// storage key of the `UserCredit` entry of the account, proven by credit attestations
+        fn user_credit_storage_key(account_id: AccountId) -> Vec<u8>;
+
+        /// credit added and removed by each source of credit changes of the account
+        fn get_credit_breakdown(account_id: AccountId) -> Vec<(CreditSource, CreditCounter)>;
+
+        /// the most recent `count` credit changes of the account with their source, newest first
+        fn get_credit_changes(account_id: AccountId, count: u32) -> Vec<CreditChange<BlockNumber>>;
+    }
+}
@@ -0,0 +1,448 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! RPC interface for the credit pallet.
+//!
+//! Every method takes either a deeper account or an evm address that has been
+//! mapped to a deeper account through `pallet_deeper_node::reward_mapping`.
+//!
+//! `credit_getAttestation` serves portable credit attestations, verified by the
+//! `credit-attestation` crate without trusting the node.
+
+use std::sync::Arc;
+
+use codec::Codec;
+use credit_attestation::CreditAttestation;
+use jsonrpsee::{
+    core::{async_trait, Error as JsonRpseeError, RpcResult},
+    proc_macros::rpc,
+    types::error::{CallError, ErrorObject},
+};
+use node_primitives::{
+    credit::{
+        Campaign, CampaignId, CreditChange, CreditCounter, CreditData, CreditHistorySummary,
+        CreditLevel, CreditSource, EraIndex,
+    },
+    BlockNumber,
+};
+use sc_client_api::ProofProvider;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::H160;
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::{
+    generic::BlockId,
+    traits::{Block as BlockT, Header as HeaderT, NumberFor},
+};
+
+pub use pallet_credit_rpc_runtime_api::CreditApi as CreditRuntimeApi;
+
+/// An account to query, given either as a deeper account or as a mapped evm address.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CreditAccount<AccountId> {
+    /// evm address mapped to a deeper account
+    Evm(H160),
+    /// deeper account
+    Substrate(AccountId),
+}
+
+/// Proves the finality of finalized blocks for credit attestations, implemented by the GRANDPA
+/// `FinalityProofProvider` of the node.
+pub trait ProveFinality<Block: BlockT>: Send + Sync {
+    /// SCALE encoded GRANDPA `FinalityProof` of the block, none if it cannot be proven yet.
+    fn prove_finality(&self, number: NumberFor<Block>) -> Result<Option<Vec<u8>>, String>;
+}
+
+impl<BE, Block> ProveFinality<Block> for sc_finality_grandpa::FinalityProofProvider<BE, Block>
+where
+    Block: BlockT,
+    BE: sc_client_api::Backend<Block> + Send + Sync + 'static,
+    NumberFor<Block>: finality_grandpa::BlockNumberOps,
+{
+    fn prove_finality(&self, number: NumberFor<Block>) -> Result<Option<Vec<u8>>, String> {
+        sc_finality_grandpa::FinalityProofProvider::prove_finality(self, number)
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[rpc(server)]
+pub trait CreditApi<BlockHash, AccountId, Balance, Header> {
+    /// Current credit data of the account.
+    #[method(name = "credit_getScore")]
+    fn get_score(
+        &self,
+        account: CreditAccount<AccountId>,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Option<CreditData>>;
+
+    /// Recent credit history of the account and the summary of the older history compacted out
+    /// of the recent window.
+    #[method(name = "credit_getHistory")]
+    fn get_history(
+        &self,
+        account: CreditAccount<AccountId>,
+        at: Option<BlockHash>,
+    ) -> RpcResult<(Vec<(EraIndex, CreditData)>, Option<CreditHistorySummary>)>;
+
+    /// Current credit level of the account.
+    #[method(name = "credit_getLevel")]
+    fn get_level(
+        &self,
+        account: CreditAccount<AccountId>,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Option<CreditLevel>>;
+
+    /// Dry-run of the poc reward of the account for eras between `from` and `to`.
+    #[method(name = "credit_estimateReward")]
+    fn estimate_reward(
+        &self,
+        account: CreditAccount<AccountId>,
+        from: EraIndex,
+        to: EraIndex,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Option<NumberOrHex>>;
+
+    /// Registered campaign.
+    #[method(name = "credit_getCampaign")]
+    fn get_campaign(
+        &self,
+        campaign_id: CampaignId,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Option<Campaign<Balance>>>;
+
+    /// Remaining budget of a registered campaign, none if it is not registered or uncapped.
+    #[method(name = "credit_getCampaignRemainingBudget")]
+    fn get_campaign_remaining_budget(
+        &self,
+        campaign_id: CampaignId,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Option<NumberOrHex>>;
+
+    /// Time weighted average DPR price over the last `window` seconds, none if no price was
+    /// aggregated yet.
+    #[method(name = "credit_getDprTwap")]
+    fn get_dpr_twap(&self, window: u64, at: Option<BlockHash>) -> RpcResult<Option<NumberOrHex>>;
+
+    /// Credit added and removed by each source of credit changes of the account.
+    #[method(name = "credit_getBreakdown")]
+    fn get_breakdown(
+        &self,
+        account: CreditAccount<AccountId>,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Vec<(CreditSource, CreditCounter)>>;
+
+    /// The most recent `count` credit changes of the account with their source, newest first.
+    #[method(name = "credit_getChanges")]
+    fn get_changes(
+        &self,
+        account: CreditAccount<AccountId>,
+        count: u32,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Vec<CreditChange<BlockNumber>>>;
+
+    /// Attestation of the credit data of the account at a finalized block, the latest
+    /// finalized one by default.
+    #[method(name = "credit_getAttestation")]
+    fn get_attestation(
+        &self,
+        account: CreditAccount<AccountId>,
+        at: Option<BlockHash>,
+    ) -> RpcResult<CreditAttestation<AccountId, Header>>;
+}
+
+/// Provides RPC methods to query the credit of an account.
+pub struct Credit<C, Block: BlockT, Balance> {
+    client: Arc<C>,
+    finality_provider: Option<Arc<dyn ProveFinality<Block>>>,
+    _marker: std::marker::PhantomData<(Block, Balance)>,
+}
+
+impl<C, Block: BlockT, Balance> Credit<C, Block, Balance> {
+    /// Create new `Credit` with the given reference to the client.
+    pub fn new(client: Arc<C>) -> Self {
+        Self {
+            client,
+            finality_provider: None,
+            _marker: Default::default(),
+        }
+    }
+
+    /// Serve credit attestations with the finality proofs of `finality_provider`.
+    pub fn with_finality_provider(
+        mut self,
+        finality_provider: Arc<dyn ProveFinality<Block>>,
+    ) -> Self {
+        self.finality_provider = Some(finality_provider);
+        self
+    }
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+    /// The call to runtime failed.
+    RuntimeError,
+    /// The evm address is not mapped to any deeper account.
+    AccountNotMapped,
+    /// The credit attestation cannot be served.
+    AttestationUnavailable,
+}
+
+impl From<Error> for i32 {
+    fn from(e: Error) -> i32 {
+        match e {
+            Error::RuntimeError => 1,
+            Error::AccountNotMapped => 2,
+            Error::AttestationUnavailable => 3,
+        }
+    }
+}
+
+fn runtime_error(message: &str, e: impl std::fmt::Debug) -> JsonRpseeError {
+    CallError::Custom(ErrorObject::owned(
+        Error::RuntimeError.into(),
+        message,
+        Some(format!("{:?}", e)),
+    ))
+    .into()
+}
+
+fn attestation_error(message: &str, e: impl std::fmt::Debug) -> JsonRpseeError {
+    CallError::Custom(ErrorObject::owned(
+        Error::AttestationUnavailable.into(),
+        message,
+        Some(format!("{:?}", e)),
+    ))
+    .into()
+}
+
+impl<C, Block, Balance> Credit<C, Block, Balance>
+where
+    Block: BlockT,
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+    Balance: Codec,
+{
+    fn block_id(&self, at: Option<<Block as BlockT>::Hash>) -> BlockId<Block> {
+        BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash))
+    }
+
+    fn resolve_account<AccountId>(
+        &self,
+        at: &BlockId<Block>,
+        account: CreditAccount<AccountId>,
+    ) -> RpcResult<AccountId>
+    where
+        AccountId: Codec,
+        C::Api: CreditRuntimeApi<Block, AccountId, Balance>,
+    {
+        match account {
+            CreditAccount::Substrate(account_id) => Ok(account_id),
+            CreditAccount::Evm(evm_address) => self
+                .client
+                .runtime_api()
+                .evm_to_account(at, evm_address)
+                .map_err(|e| runtime_error("Unable to query mapped account.", e))?
+                .ok_or_else(|| {
+                    CallError::Custom(ErrorObject::owned(
+                        Error::AccountNotMapped.into(),
+                        "Evm address is not mapped to a deeper account.",
+                        Some(format!("{:?}", evm_address)),
+                    ))
+                    .into()
+                }),
+        }
+    }
+}
+
+#[async_trait]
+impl<C, Block, AccountId, Balance>
+    CreditApiServer<<Block as BlockT>::Hash, AccountId, Balance, <Block as BlockT>::Header>
+    for Credit<C, Block, Balance>
+where
+    Block: BlockT,
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + ProofProvider<Block>,
+    C: Send + Sync + 'static,
+    C::Api: CreditRuntimeApi<Block, AccountId, Balance>,
+    AccountId: Codec + Clone + Send + Sync + 'static + Serialize + for<'de> Deserialize<'de>,
+    Balance: Codec + Copy + Into<NumberOrHex> + Serialize + Send + Sync + 'static,
+{
+    fn get_score(
+        &self,
+        account: CreditAccount<AccountId>,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Option<CreditData>> {
+        let at = self.block_id(at);
+        let account_id = self.resolve_account(&at, account)?;
+        self.client
+            .runtime_api()
+            .get_credit_data(&at, account_id)
+            .map_err(|e| runtime_error("Unable to query credit data.", e))
+    }
+
+    fn get_history(
+        &self,
+        account: CreditAccount<AccountId>,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<(Vec<(EraIndex, CreditData)>, Option<CreditHistorySummary>)> {
+        let at = self.block_id(at);
+        let account_id = self.resolve_account(&at, account)?;
+        self.client
+            .runtime_api()
+            .get_credit_history(&at, account_id)
+            .map_err(|e| runtime_error("Unable to query credit history.", e))
+    }
+
+    fn get_level(
+        &self,
+        account: CreditAccount<AccountId>,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Option<CreditLevel>> {
+        let at = self.block_id(at);
+        let account_id = self.resolve_account(&at, account)?;
+        self.client
+            .runtime_api()
+            .get_credit_level(&at, account_id)
+            .map_err(|e| runtime_error("Unable to query credit level.", e))
+    }
+
+    fn estimate_reward(
+        &self,
+        account: CreditAccount<AccountId>,
+        from: EraIndex,
+        to: EraIndex,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Option<NumberOrHex>> {
+        let at = self.block_id(at);
+        let account_id = self.resolve_account(&at, account)?;
+        let reward = self
+            .client
+            .runtime_api()
+            .estimate_reward(&at, account_id, from, to)
+            .map_err(|e| runtime_error("Unable to estimate reward.", e))?;
+        Ok(reward.map(Into::into))
+    }
+
+    fn get_campaign(
+        &self,
+        campaign_id: CampaignId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Option<Campaign<Balance>>> {
+        let at = self.block_id(at);
+        self.client
+            .runtime_api()
+            .get_campaign(&at, campaign_id)
+            .map_err(|e| runtime_error("Unable to query campaign.", e))
+    }
+
+    fn get_campaign_remaining_budget(
+        &self,
+        campaign_id: CampaignId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Option<NumberOrHex>> {
+        let at = self.block_id(at);
+        let budget = self
+            .client
+            .runtime_api()
+            .campaign_remaining_budget(&at, campaign_id)
+            .map_err(|e| runtime_error("Unable to query campaign budget.", e))?;
+        Ok(budget.map(Into::into))
+    }
+
+    fn get_dpr_twap(
+        &self,
+        window: u64,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Option<NumberOrHex>> {
+        let at = self.block_id(at);
+        let price = self
+            .client
+            .runtime_api()
+            .dpr_twap(&at, window)
+            .map_err(|e| runtime_error("Unable to query DPR price.", e))?;
+        Ok(price.map(Into::into))
+    }
+
+    fn get_breakdown(
+        &self,
+        account: CreditAccount<AccountId>,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Vec<(CreditSource, CreditCounter)>> {
+        let at = self.block_id(at);
+        let account_id = self.resolve_account(&at, account)?;
+        self.client
+            .runtime_api()
+            .get_credit_breakdown(&at, account_id)
+            .map_err(|e| runtime_error("Unable to query credit breakdown.", e))
+    }
+
+    fn get_changes(
+        &self,
+        account: CreditAccount<AccountId>,
+        count: u32,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Vec<CreditChange<BlockNumber>>> {
+        let at = self.block_id(at);
+        let account_id = self.resolve_account(&at, account)?;
+        self.client
+            .runtime_api()
+            .get_credit_changes(&at, account_id, count)
+            .map_err(|e| runtime_error("Unable to query credit changes.", e))
+    }
+
+    fn get_attestation(
+        &self,
+        account: CreditAccount<AccountId>,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<CreditAttestation<AccountId, <Block as BlockT>::Header>> {
+        let finality_provider = self
+            .finality_provider
+            .as_ref()
+            .ok_or_else(|| attestation_error("Finality proofs are not served.", ()))?;
+        let info = self.client.info();
+        let hash = at.unwrap_or(info.finalized_hash);
+        let header = self
+            .client
+            .header(BlockId::Hash(hash))
+            .map_err(|e| runtime_error("Unable to query header.", e))?
+            .filter(|header| {
+                *header.number() <= info.finalized_number
+                    && self.client.hash(*header.number()).ok().flatten() == Some(hash)
+            })
+            .ok_or_else(|| attestation_error("Block is not finalized.", hash))?;
+
+        let at = BlockId::Hash(hash);
+        let account_id = self.resolve_account(&at, account)?;
+        let key = self
+            .client
+            .runtime_api()
+            .user_credit_storage_key(&at, account_id.clone())
+            .map_err(|e| runtime_error("Unable to query credit storage key.", e))?;
+        let storage_proof = self
+            .client
+            .read_proof(&hash, &mut std::iter::once(key.as_slice()))
+            .map_err(|e| attestation_error("Unable to prove credit data.", e))?;
+        let finality_proof = finality_provider
+            .prove_finality(*header.number())
+            .map_err(|e| attestation_error("Unable to prove finality.", e))?
+            .ok_or_else(|| attestation_error("Finality proof is not available yet.", hash))?;
+        Ok(CreditAttestation {
+            account: account_id,
+            header,
+            storage_proof: storage_proof.into_iter_nodes().map(Into::into).collect(),
+            finality_proof: finality_proof.into(),
+        })
+    }
+}
@@ -20,11 +20,11 @@
 use super::*;
 use crate::Pallet as Credit;
 pub use frame_benchmarking::{account, benchmarks, vec, whitelist_account, whitelisted_caller};
-use frame_support::assert_ok;
 use frame_support::traits::Currency;
+use frame_support::{assert_ok, BoundedVec};
 use frame_system::RawOrigin;
 use node_primitives::{
-    credit::{CreditData, CreditLevel, CreditSetting},
+    credit::{Campaign, CreditData, CreditLevel, CreditSetting, StakingType},
     user_privileges::Privilege,
 };
 use sp_core::H160;
@@ -94,7 +94,7 @@ benchmarks! {
         let user = create_funded_user::<T>("user",USER_SEED, 1000);
         UserCredit::<T>::insert(&user,credit_data.clone());
         credit_data.credit = 101;
-        UserCreditHistory::<T>::insert(&user,vec![(1,credit_data)]);
+        CreditHistory::<T>::insert(&user,vec![(1,credit_data)]);
     }: _(RawOrigin::Signed(user.clone()), 1)
     verify {
         assert_eq!(UserCredit::<T>::get(&user).unwrap().credit,101);
@@ -112,10 +112,10 @@ benchmarks! {
         };
         let user = create_funded_user::<T>("user",USER_SEED, 1000);
         UserCredit::<T>::insert(&user,credit_data.clone());
-        UserCreditHistory::<T>::insert(&user,vec![(6,credit_data.clone())]);
+        CreditHistory::<T>::insert(&user,vec![(6,credit_data.clone())]);
     }: _(RawOrigin::Root, user.clone(), 7)
     verify {
-        assert_eq!(UserCreditHistory::<T>::get(&user), vec![(7, credit_data)]);
+        assert_eq!(CreditHistory::<T>::get(&user).unwrap(), vec![(7, credit_data)]);
     }
 
     update_nft_class_credit {
@@ -202,12 +202,87 @@ benchmarks! {
        set_dpr_price {
         let user: T::AccountId = account("b", 1, USER_SEED);
         let account_lookup: <T::Lookup as StaticLookup>::Source = T::Lookup::unlookup(user.clone());
-        let _ = pallet_user_privileges::Pallet::<T>::set_user_privilege(RawOrigin::Root.into(),account_lookup,Privilege::CreditAdmin);
+        let _ = pallet_user_privileges::Pallet::<T>::set_user_privilege(RawOrigin::Root.into(),account_lookup,Privilege::OracleWorker);
 
         let existential_deposit = <T as pallet::Config>::Currency::minimum_balance();
-    }: _(RawOrigin::Signed(user),existential_deposit,H160::zero() )
+    }: _(RawOrigin::Signed(user.clone()),existential_deposit,H160::zero() )
     verify {
+        assert_eq!(PriceSubmissions::<T>::get(&user).map(|(price, _)| price), Some(existential_deposit));
+    }
+
+    schedule_credit_setting {
+        let credit_setting = CreditSetting::<BalanceOf<T>> {
+            campaign_id: 0,
+            credit_level: CreditLevel::One,
+            staking_balance: 20_000u32.into(),
+            base_apy: Percent::from_percent(39),
+            bonus_apy: Percent::from_percent(0),
+            max_rank_with_bonus: 1u32,
+            tax_rate: Percent::from_percent(10),
+            max_referees_with_rewards: 1,
+            reward_per_referee: 18u32.into(),
+        };
+        let enact_era = T::MinSettingEnactmentDelay::get().max(1);
+    }: _(RawOrigin::Root, enact_era, credit_setting)
+    verify {
+        assert_eq!(ScheduledCreditSettings::<T>::get(enact_era).len(), 1);
+    }
+
+    cancel_credit_setting {
+        let credit_setting = CreditSetting::<BalanceOf<T>> {
+            campaign_id: 0,
+            credit_level: CreditLevel::One,
+            staking_balance: 20_000u32.into(),
+            base_apy: Percent::from_percent(39),
+            bonus_apy: Percent::from_percent(0),
+            max_rank_with_bonus: 1u32,
+            tax_rate: Percent::from_percent(10),
+            max_referees_with_rewards: 1,
+            reward_per_referee: 18u32.into(),
+        };
+        let enact_era = T::MinSettingEnactmentDelay::get().max(1);
+        assert_ok!(Credit::<T>::schedule_credit_setting(RawOrigin::Root.into(), enact_era, credit_setting));
+    }: _(RawOrigin::Root, enact_era, 0, CreditLevel::One)
+    verify {
+        assert!(!ScheduledCreditSettings::<T>::contains_key(enact_era));
+    }
+
+    set_credit_level_thresholds {
+        let thresholds = [50, 150, 250, 350, 450, 550, 650, 750];
+    }: _(RawOrigin::Root, thresholds)
+    verify {
+        assert_eq!(LevelThresholds::<T>::get(), thresholds);
+    }
+
+    set_campaign {
+        let campaign = Campaign::<BalanceOf<T>> {
+            name: b"usdt staking".to_vec(),
+            start_era: 0,
+            end_era: Some(365),
+            budget: Some(<T as pallet::Config>::Currency::minimum_balance()),
+            staking_type: StakingType::Usdt,
+            min_credit: 100,
+            successor: Some(6),
+        };
+    }: _(RawOrigin::Root, 5, campaign.clone())
+    verify {
+        assert_eq!(Campaigns::<T>::get(5), Some(campaign));
+    }
 
+    set_price_feed_endpoints {
+        let user = create_funded_user::<T>("user", USER_SEED, 1000);
+        let user_lookup = T::Lookup::unlookup(user.clone());
+        let _ = pallet_user_privileges::Pallet::<T>::set_user_privilege(RawOrigin::Root.into(),user_lookup,Privilege::CreditAdmin);
+        let length = T::MaxPriceFeedEndpointLength::get() as usize;
+        let endpoint = PriceFeedEndpoint {
+            url: vec![b'u'; length],
+            price_field: vec![b'p'; length],
+        };
+        let endpoints: BoundedVec<_, T::MaxPriceFeedEndpoints> =
+            vec![endpoint; T::MaxPriceFeedEndpoints::get() as usize].try_into().unwrap();
+    }: _(RawOrigin::Signed(user), endpoints.clone())
+    verify {
+        assert_eq!(PriceFeedEndpoints::<T>::get(), endpoints);
     }
 
 }
@@ -227,6 +302,11 @@ mod tests {
             assert_ok!(Pallet::<Test>::test_benchmark_force_modify_credit_history());
             assert_ok!(Pallet::<Test>::test_benchmark_update_nft_class_credit());
             assert_ok!(Pallet::<Test>::test_benchmark_burn_nft());
+            assert_ok!(Pallet::<Test>::test_benchmark_schedule_credit_setting());
+            assert_ok!(Pallet::<Test>::test_benchmark_cancel_credit_setting());
+            assert_ok!(Pallet::<Test>::test_benchmark_set_credit_level_thresholds());
+            assert_ok!(Pallet::<Test>::test_benchmark_set_campaign());
+            assert_ok!(Pallet::<Test>::test_benchmark_set_price_feed_endpoints());
         });
     }
 }
@@ -0,0 +1,258 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Soulbound credit level badges: the credit level of an account is mirrored as an item of the
+//! reserved `CreditBadgeCollection`, minted, updated and burned as the level changes. The
+//! collection is created by the pallet at genesis or once in the V7 migration and its items are
+//! locked, i.e. badges cannot be transferred.
+
+use crate::{
+    ClassIdOf, Config, CreditBadgeCursor, CreditBadges, Event, InstanceIdOf, NextCreditBadgeId,
+    Pallet, Releases, StorageVersion, UserCredit,
+};
+use frame_support::{
+    ensure,
+    storage::with_storage_layer,
+    traits::{tokens::Locker, Get},
+    weights::Weight,
+    PalletId,
+};
+use frame_system::RawOrigin;
+use node_primitives::credit::{CreditData, CreditInterface, CreditLevel, EraIndex};
+use scale_info::prelude::string::ToString;
+use sp_runtime::{
+    traits::{AccountIdConversion, StaticLookup, Zero},
+    DispatchError, DispatchResult,
+};
+use sp_std::{convert::TryInto, vec::Vec};
+
+/// owner and issuer of the badge collection
+const BADGE_PALLET_ID: PalletId = PalletId(*b"dpr/cbdg");
+
+/// metadata of a badge: `{"level":<level>,"campaign":<campaign id>,"era":<as-of era>}`
+pub fn credit_badge_metadata(credit_data: &CreditData, era: EraIndex) -> Vec<u8> {
+    let mut metadata = Vec::new();
+    metadata.extend_from_slice(b"{\"level\":");
+    metadata.extend_from_slice(
+        (credit_data.current_credit_level as u8)
+            .to_string()
+            .as_bytes(),
+    );
+    metadata.extend_from_slice(b",\"campaign\":");
+    metadata.extend_from_slice(credit_data.campaign_id.to_string().as_bytes());
+    metadata.extend_from_slice(b",\"era\":");
+    metadata.extend_from_slice(era.to_string().as_bytes());
+    metadata.push(b'}');
+    metadata
+}
+
+/// badges are soulbound, no item of the badge collection can be transferred
+impl<T: Config> Locker<ClassIdOf<T>, InstanceIdOf<T>> for Pallet<T> {
+    fn is_locked(collection: ClassIdOf<T>, _item: InstanceIdOf<T>) -> bool {
+        T::CreditBadgeCollection::get() == Some(collection)
+            && pallet_uniques::Pallet::<T>::collection_owner(collection)
+                == Some(Self::credit_badge_account())
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    /// owner and issuer of the badge collection
+    pub fn credit_badge_account() -> T::AccountId {
+        BADGE_PALLET_ID.into_account_truncating()
+    }
+
+    /// mint, update or burn the badge of the account after its credit level may have changed,
+    /// accounts at credit level zero or without credit data have no badge
+    pub(crate) fn sync_credit_badge(account_id: &T::AccountId) -> Weight {
+        let collection = match T::CreditBadgeCollection::get() {
+            Some(collection) => collection,
+            None => return Weight::zero(),
+        };
+        let weight = T::DbWeight::get().reads_writes(2, 0);
+        let credit_data = Self::user_credit(account_id)
+            .filter(|credit_data| credit_data.current_credit_level != CreditLevel::Zero);
+        let badge = Self::credit_badge(account_id);
+        let result = with_storage_layer(|| match (badge, credit_data) {
+            (Some((_, level)), Some(credit_data)) if level == credit_data.current_credit_level => {
+                Ok(())
+            }
+            (badge, Some(credit_data)) => Self::update_credit_badge(
+                account_id,
+                collection,
+                badge.map(|(item, _)| item),
+                &credit_data,
+            ),
+            (Some((item, _)), None) => Self::burn_credit_badge(account_id, collection, item),
+            (None, None) => Ok(()),
+        });
+        if let Err(e) = result {
+            crate::log!(
+                error,
+                "failed to sync the credit badge of {:?}: {:?}",
+                account_id,
+                e
+            );
+        }
+        weight.saturating_add(T::DbWeight::get().reads_writes(5, 6))
+    }
+
+    /// upper bound of the weight returned by `sync_credit_badge`
+    pub(crate) fn max_sync_credit_badge_weight() -> Weight {
+        T::DbWeight::get().reads_writes(7, 6)
+    }
+
+    /// V6 to V7: the badge collection is created once and the badges of the credit data
+    /// stored before are minted a batch per block. Badges stay disabled if the collection id
+    /// was taken before the upgrade.
+    pub(crate) fn migrate_to_v7() -> Weight {
+        if let Some(collection) = T::CreditBadgeCollection::get() {
+            match Self::create_credit_badge_collection(collection) {
+                Ok(()) => CreditBadgeCursor::<T>::put(Vec::<u8>::new()),
+                Err(e) => {
+                    crate::log!(
+                        error,
+                        "failed to create the credit badge collection {:?}: {:?}",
+                        collection,
+                        e
+                    );
+                    Self::deposit_event(Event::CreditBadgeCollectionTaken(collection));
+                }
+            }
+        }
+        StorageVersion::<T>::put(Releases::V7_0_0);
+        T::DbWeight::get().reads_writes(1, 4)
+    }
+
+    /// Sync the badges of up to `limit` stored credit data, continuing from
+    /// `CreditBadgeCursor`, which is removed once all are done.
+    pub(crate) fn migrate_credit_badges(limit: usize) -> Weight {
+        let cursor = match CreditBadgeCursor::<T>::get() {
+            Some(cursor) => cursor,
+            None => return T::DbWeight::get().reads(1),
+        };
+        let mut iter = if cursor.is_empty() {
+            UserCredit::<T>::iter_keys()
+        } else {
+            UserCredit::<T>::iter_keys_from(cursor)
+        };
+        let mut weight = T::DbWeight::get().reads_writes(2, 1);
+        for _ in 0..limit {
+            match iter.next() {
+                Some(account_id) => {
+                    weight = weight
+                        .saturating_add(T::DbWeight::get().reads(1))
+                        .saturating_add(Self::sync_credit_badge(&account_id));
+                }
+                None => {
+                    CreditBadgeCursor::<T>::kill();
+                    return weight;
+                }
+            }
+        }
+        CreditBadgeCursor::<T>::put(iter.last_raw_key().to_vec());
+        weight
+    }
+
+    fn update_credit_badge(
+        account_id: &T::AccountId,
+        collection: ClassIdOf<T>,
+        item: Option<InstanceIdOf<T>>,
+        credit_data: &CreditData,
+    ) -> DispatchResult {
+        let issuer = Self::credit_badge_account();
+        let origin: T::RuntimeOrigin = RawOrigin::Signed(issuer.clone()).into();
+        // the holder may have burned the badge in the uniques pallet
+        let item = match item
+            .filter(|item| pallet_uniques::Pallet::<T>::owner(collection, *item).is_some())
+        {
+            Some(item) => item,
+            None => {
+                Self::ensure_credit_badge_collection(collection, &issuer)?;
+                let item = NextCreditBadgeId::<T>::get();
+                pallet_uniques::Pallet::<T>::mint(
+                    origin.clone(),
+                    collection,
+                    item,
+                    T::Lookup::unlookup(account_id.clone()),
+                )?;
+                NextCreditBadgeId::<T>::put(item.saturating_add(1));
+                item
+            }
+        };
+        let metadata = credit_badge_metadata(credit_data, Self::get_current_era())
+            .try_into()
+            .map_err(|_| DispatchError::Other("credit badge metadata too long"))?;
+        pallet_uniques::Pallet::<T>::set_metadata(origin, collection, item, metadata, false)?;
+        CreditBadges::<T>::insert(account_id, (item, credit_data.current_credit_level));
+        Self::deposit_event(Event::CreditBadgeUpdated(
+            account_id.clone(),
+            item,
+            credit_data.current_credit_level,
+        ));
+        Ok(())
+    }
+
+    fn burn_credit_badge(
+        account_id: &T::AccountId,
+        collection: ClassIdOf<T>,
+        item: InstanceIdOf<T>,
+    ) -> DispatchResult {
+        CreditBadges::<T>::remove(account_id);
+        if pallet_uniques::Pallet::<T>::owner(collection, item).is_some() {
+            let issuer = Self::credit_badge_account();
+            pallet_uniques::Pallet::<T>::burn(
+                RawOrigin::Signed(issuer).into(),
+                collection,
+                item,
+                None,
+            )?;
+        }
+        Self::deposit_event(Event::CreditBadgeBurned(account_id.clone(), item));
+        Ok(())
+    }
+
+    /// create the badge collection owned by the pallet, fails if anyone else took the
+    /// reserved collection id
+    pub(crate) fn create_credit_badge_collection(collection: ClassIdOf<T>) -> DispatchResult {
+        let issuer = Self::credit_badge_account();
+        match pallet_uniques::Pallet::<T>::collection_owner(collection) {
+            Some(owner) if owner == issuer => Ok(()),
+            Some(_) => Err(DispatchError::Other("credit badge collection taken")),
+            None => pallet_uniques::Pallet::<T>::do_create_collection(
+                collection,
+                issuer.clone(),
+                issuer.clone(),
+                Zero::zero(),
+                true,
+                pallet_uniques::Event::ForceCreated {
+                    collection,
+                    owner: issuer,
+                },
+            ),
+        }
+    }
+
+    /// badges are only minted into the collection created by the pallet
+    fn ensure_credit_badge_collection(
+        collection: ClassIdOf<T>,
+        issuer: &T::AccountId,
+    ) -> DispatchResult {
+        ensure!(
+            pallet_uniques::Pallet::<T>::collection_owner(collection).as_ref() == Some(issuer),
+            DispatchError::Other("credit badge collection not created by the pallet")
+        );
+        Ok(())
+    }
+}
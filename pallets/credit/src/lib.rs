@@ -25,7 +25,9 @@ mod tests;
 
 #[cfg(any(feature = "runtime-benchmarks", test))]
 pub mod benchmarking;
+mod credit_badge;
 mod credit_setting;
+pub mod price_feed;
 pub mod weights;
 pub(crate) const LOG_TARGET: &'static str = "credit";
 pub(crate) const USDT_CAMPAIGN_ID: u16 = 5;
@@ -47,6 +49,7 @@ pub use weights::WeightInfo;
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
+    use crate::price_feed::SubmitPrice;
     use frame_support::traits::{
         Currency, ExistenceRequirement, OnUnbalanced, UnixTime, WithdrawReasons,
     };
@@ -55,11 +58,13 @@ pub mod pallet {
     };
     use frame_system::pallet_prelude::*;
     use node_primitives::credit::{
-        CampaignId, CreditData, CreditInterface, CreditLevel, CreditSetting, EraIndex,
-        CREDIT_CAP_ONE_ERAS, DEFAULT_REWARD_ERAS, OLD_REWARD_ERAS,
+        Campaign, CampaignId, CreditChange, CreditCounter, CreditData, CreditHistorySummary,
+        CreditInterface, CreditLevel, CreditLevelThresholds, CreditSetting, CreditSource, EraIndex,
+        RewardDeferred, StakingType, CREDIT_CAP_ONE_ERAS, DEFAULT_CREDIT_LEVEL_THRESHOLDS,
+        DEFAULT_REWARD_ERAS, OLD_REWARD_ERAS,
     };
     use node_primitives::{
-        deeper_node::NodeInterface,
+        deeper_node::{DeviceMigration, NodeInterface},
         user_privileges::{Privilege, UserPrivilegeInterface},
         DPR,
     };
@@ -76,7 +81,7 @@ pub mod pallet {
 
     /// Configure the pallet by specifying the parameters and types on which it depends.
     #[pallet::config]
-    pub trait Config: frame_system::Config + pallet_uniques::Config {
+    pub trait Config: frame_system::Config + pallet_uniques::Config<ItemId = u32> {
         /// Because this pallet emits events, it depends on the runtime's definition of an event.
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
         /// Number of blocks per era.
@@ -107,6 +112,62 @@ pub mod pallet {
 
         #[pallet::constant]
         type MaxBurnCreditPerAddress: Get<u32>;
+
+        /// Origin that schedules and cancels credit setting changes, e.g. council or referendum
+        type CreditSettingOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// Minimum number of eras between scheduling a credit setting change and its enactment
+        #[pallet::constant]
+        type MinSettingEnactmentDelay: Get<EraIndex>;
+
+        /// Maximum number of credit setting changes enacted in one era
+        #[pallet::constant]
+        type MaxScheduledSettingsPerEra: Get<u32>;
+
+        /// Maximum number of recent credit history entries kept per account once rewarded, older
+        /// ones are compacted into a summary
+        #[pallet::constant]
+        type MaxCreditHistory: Get<u32>;
+
+        /// Minimum number of oracle workers with a fresh price submission to aggregate a price
+        #[pallet::constant]
+        type PriceQuorum: Get<u32>;
+
+        /// Seconds after which a price submission or the aggregated price is stale
+        #[pallet::constant]
+        type PriceStaleAfter: Get<u64>;
+
+        /// Maximum number of aggregated prices kept for the time weighted average price
+        #[pallet::constant]
+        type MaxPriceHistory: Get<u32>;
+
+        /// Minimum seconds between two aggregated prices, `MaxPriceHistory` aggregated prices
+        /// cover at least `MaxPriceHistory * PriceAggregationInterval` seconds
+        #[pallet::constant]
+        type PriceAggregationInterval: Get<u64>;
+
+        /// Submits the price fetched by the offchain worker, `()` disables the price feed
+        type PriceFeed: SubmitPrice<BalanceOf<Self>>;
+
+        /// Blocks between two price fetches of the offchain worker, zero disables the fetches
+        #[pallet::constant]
+        type PriceFeedInterval: Get<Self::BlockNumber>;
+
+        /// Maximum number of endpoints of the price feed
+        #[pallet::constant]
+        type MaxPriceFeedEndpoints: Get<u32>;
+
+        /// Maximum length of the url and of the price field of a price feed endpoint
+        #[pallet::constant]
+        type MaxPriceFeedEndpointLength: Get<u32>;
+
+        /// Reserved uniques collection of the soulbound credit level badges, created at genesis
+        /// or in the runtime upgrade, `None` disables the badges
+        type CreditBadgeCollection: Get<Option<ClassIdOf<Self>>>;
+
+        /// Maximum number of recent credit changes kept per account
+        #[pallet::constant]
+        type MaxCreditChanges: Get<u32>;
     }
 
     pub type BalanceOf<T> =
@@ -119,13 +180,38 @@ pub mod pallet {
     pub type ClassIdOf<T> = <T as pallet_uniques::Config>::CollectionId;
     pub type InstanceIdOf<T> = <T as pallet_uniques::Config>::ItemId;
 
+    /// HTTP endpoint the offchain worker fetches the DPR price from
+    #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+    pub struct PriceFeedEndpoint {
+        /// url of a JSON response
+        pub url: Vec<u8>,
+        /// JSON field holding the DPR price in USDT, e.g. `last`
+        pub price_field: Vec<u8>,
+    }
+
     #[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
     pub enum Releases {
         V1_0_0,
         V2_0_0,
         V3_0_0,
+        V4_0_0,
+        V5_0_0,
+        V6_0_0,
+        V7_0_0,
     }
 
+    /// number of credit data re-levelled per block after the level thresholds change
+    const RELEVEL_BATCH_SIZE: usize = 100;
+
+    /// number of legacy credit histories compacted per block during the migration
+    const CREDIT_HISTORY_MIGRATION_BATCH_SIZE: usize = 100;
+
+    /// number of credit badges of existing credit data synced per block during the migration
+    const CREDIT_BADGE_MIGRATION_BATCH_SIZE: usize = 50;
+
+    /// maximum length of a campaign name
+    const MAX_CAMPAIGN_NAME_LEN: usize = 64;
+
     #[pallet::pallet]
     #[pallet::generate_store(pub(super) trait Store)]
     #[pallet::without_storage_info]
@@ -141,12 +227,28 @@ pub mod pallet {
     pub type UserStakingCredit<T: Config> =
         StorageMap<_, Blake2_128Concat, T::AccountId, u64, OptionQuery>;
 
-    /// user credit history is empty until user's device gets onboard
+    /// unbounded credit history, drained into `CreditHistory` by the V6 migration
     #[pallet::storage]
-    #[pallet::getter(fn user_credit_history)]
-    pub type UserCreditHistory<T: Config> =
+    pub(super) type UserCreditHistory<T: Config> =
         StorageMap<_, Blake2_128Concat, T::AccountId, Vec<(EraIndex, CreditData)>, ValueQuery>;
 
+    /// recent credit history, absent until user's device gets onboard. Entries beyond
+    /// `MaxCreditHistory` are only compacted once the eras they are in effect at are rewarded.
+    #[pallet::storage]
+    pub type CreditHistory<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, Vec<(EraIndex, CreditData)>, OptionQuery>;
+
+    /// era the latest `get_reward` of the account started from, the eras before it are rewarded
+    #[pallet::storage]
+    pub type UnrewardedSince<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, EraIndex, OptionQuery>;
+
+    /// summary of the credit history older than `CreditHistory`
+    #[pallet::storage]
+    #[pallet::getter(fn credit_history_summary)]
+    pub type CreditHistorySummaries<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, CreditHistorySummary, OptionQuery>;
+
     #[pallet::storage]
     #[pallet::getter(fn total_daily_burn_dpr)]
     pub type TotalDailyBurnDPR<T: Config> =
@@ -202,6 +304,58 @@ pub mod pallet {
     pub type CampaignIdSwitch<T: Config> =
         StorageMap<_, Twox64Concat, CampaignId, CampaignId, OptionQuery>;
 
+    /// registered campaigns, campaigns not registered keep their hard-coded rules
+    #[pallet::storage]
+    #[pallet::getter(fn campaign)]
+    pub type Campaigns<T: Config> =
+        StorageMap<_, Twox64Concat, CampaignId, Campaign<BalanceOf<T>>, OptionQuery>;
+
+    /// rewards paid by each registered campaign
+    #[pallet::storage]
+    #[pallet::getter(fn campaign_spent)]
+    pub type CampaignSpent<T: Config> =
+        StorageMap<_, Twox64Concat, CampaignId, BalanceOf<T>, ValueQuery>;
+
+    /// rewards of each campaign returned by the last `get_reward` of the account, charged to
+    /// the budgets of registered campaigns once the delegator is paid
+    #[pallet::storage]
+    #[pallet::getter(fn unpaid_campaign_rewards)]
+    pub type UnpaidCampaignRewards<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, Vec<(CampaignId, BalanceOf<T>)>, OptionQuery>;
+
+    /// credit level badge of each account: item in the badge collection, credit level
+    #[pallet::storage]
+    #[pallet::getter(fn credit_badge)]
+    pub type CreditBadges<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, (InstanceIdOf<T>, CreditLevel), OptionQuery>;
+
+    /// item of the next minted credit level badge
+    #[pallet::storage]
+    pub(crate) type NextCreditBadgeId<T: Config> = StorageValue<_, InstanceIdOf<T>, ValueQuery>;
+
+    /// credit added and removed by each source of each account
+    #[pallet::storage]
+    #[pallet::getter(fn credit_by_source)]
+    pub type CreditBySource<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Twox64Concat,
+        CreditSource,
+        CreditCounter,
+        ValueQuery,
+    >;
+
+    /// most recent credit changes of each account, oldest first
+    #[pallet::storage]
+    pub type RecentCreditChanges<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        BoundedVec<CreditChange<T::BlockNumber>, T::MaxCreditChanges>,
+        ValueQuery,
+    >;
+
     #[pallet::storage]
     #[pallet::getter(fn switch_accounts)]
     pub type NotSwitchAccounts<T: Config> =
@@ -284,6 +438,26 @@ pub mod pallet {
     #[pallet::storage]
     pub(super) type StorageVersion<T: Config> = StorageValue<_, Releases>;
 
+    #[pallet::type_value]
+    pub fn DefaultCreditLevelThresholds() -> CreditLevelThresholds {
+        DEFAULT_CREDIT_LEVEL_THRESHOLDS
+    }
+
+    /// minimum credit score of each credit level above zero
+    #[pallet::storage]
+    #[pallet::getter(fn credit_level_thresholds)]
+    pub type LevelThresholds<T: Config> =
+        StorageValue<_, CreditLevelThresholds, ValueQuery, DefaultCreditLevelThresholds>;
+
+    /// raw key of the last user credit re-levelled, present while re-levelling is in progress
+    #[pallet::storage]
+    pub(super) type RelevelCursor<T: Config> = StorageValue<_, Vec<u8>>;
+
+    /// raw key of the last user credit whose badge was synced, present while the badges of
+    /// the credit data stored before V7 are minted
+    #[pallet::storage]
+    pub(super) type CreditBadgeCursor<T: Config> = StorageValue<_, Vec<u8>>;
+
     #[pallet::storage]
     #[pallet::getter(fn dpr_price)]
     pub(super) type DprPrice<T: Config> = StorageValue<_, BalanceOf<T>, OptionQuery>;
@@ -298,8 +472,32 @@ pub mod pallet {
     pub(super) type UserStakingBalance<T: Config> =
         StorageMap<_, Blake2_128Concat, T::AccountId, (BalanceOf<T>, BalanceOf<T>), OptionQuery>;
 
+    /// latest price submitted by each oracle worker and its unix timestamp in seconds
+    #[pallet::storage]
+    #[pallet::getter(fn price_submission)]
+    pub(super) type PriceSubmissions<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, (BalanceOf<T>, u64), OptionQuery>;
+
+    /// whether a price was submitted in the current block
     #[pallet::storage]
-    pub(super) type CurrentPrices<T: Config> = StorageValue<_, Vec<BalanceOf<T>>, ValueQuery>;
+    pub(super) type PriceSubmitted<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+    /// unix timestamp in seconds of the last aggregated price
+    #[pallet::storage]
+    #[pallet::getter(fn dpr_price_updated_at)]
+    pub(super) type DprPriceUpdatedAt<T: Config> = StorageValue<_, u64, OptionQuery>;
+
+    /// aggregated prices with their unix timestamps in seconds, oldest first
+    #[pallet::storage]
+    #[pallet::getter(fn dpr_price_history)]
+    pub type DprPriceHistory<T: Config> =
+        StorageValue<_, BoundedVec<(u64, BalanceOf<T>), T::MaxPriceHistory>, ValueQuery>;
+
+    /// endpoints of the offchain worker price feed
+    #[pallet::storage]
+    #[pallet::getter(fn price_feed_endpoints)]
+    pub type PriceFeedEndpoints<T: Config> =
+        StorageValue<_, BoundedVec<PriceFeedEndpoint, T::MaxPriceFeedEndpoints>, ValueQuery>;
 
     #[pallet::type_value]
     pub fn UsdtDefaultId() -> u16 {
@@ -314,8 +512,16 @@ pub mod pallet {
     #[pallet::getter(fn get_maintain_devices)]
     pub(crate) type MaintainDevices<T: Config> = StorageValue<_, Vec<T::AccountId>, ValueQuery>;
 
+    /// Credit setting changes enacted at the beginning of an era
+    #[pallet::storage]
+    #[pallet::getter(fn scheduled_credit_settings)]
+    pub type ScheduledCreditSettings<T: Config> =
+        StorageMap<_, Twox64Concat, EraIndex, Vec<CreditSetting<BalanceOf<T>>>, ValueQuery>;
+
+    /// Block number of the last offline slash of a device
     #[pallet::storage]
-    pub(crate) type GenesisChangeRewardEra<T: Config> = StorageValue<_, u32, ValueQuery>;
+    pub(crate) type LastOfflineSlash<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, T::BlockNumber, OptionQuery>;
 
     #[pallet::genesis_config]
     pub struct GenesisConfig<T: Config> {
@@ -342,7 +548,14 @@ pub mod pallet {
             for uc in self.user_credit_data.clone().into_iter() {
                 <UserCredit<T>>::insert(uc.0, uc.1);
             }
-            StorageVersion::<T>::put(Releases::V3_0_0);
+            if let Some(collection) = T::CreditBadgeCollection::get() {
+                Pallet::<T>::create_credit_badge_collection(collection)
+                    .expect("the credit badge collection is free at genesis");
+            }
+            for (account_id, _) in self.user_credit_data.iter() {
+                Pallet::<T>::sync_credit_badge(account_id);
+            }
+            StorageVersion::<T>::put(Releases::V7_0_0);
         }
     }
 
@@ -357,7 +570,7 @@ pub mod pallet {
         CreditDataAddedByTraffic(T::AccountId, u64),
         CreditDataAddedByTip(T::AccountId, u64),
         CreditDataAddedByBurnNft(T::AccountId, u64),
-        //Status: 1-Invalid Inputs; 2-InvalidCreditData; 3-NoReward; 4-InvalidCreditHistory; 5-ExpiryEra; 6-CreditMap is empty;
+        //Status: 1-Invalid Inputs; 2-InvalidCreditData; 3-NoReward; 4-InvalidCreditHistory; 5-ExpiryEra; 6-CreditMap is empty; 7-Deferred until the DPR price is fresh;
         GetRewardResult(T::AccountId, EraIndex, EraIndex, u8),
         CreditHistoryUpdateSuccess(T::AccountId, EraIndex),
         CreditHistoryUpdateFailed(T::AccountId, EraIndex),
@@ -369,6 +582,26 @@ pub mod pallet {
         SetAdmin(T::AccountId),
         UnstakingResult(T::AccountId, String),
         DPRPrice(BalanceOf<T>, H160),
+        /// credit setting change scheduled: enactment era, setting
+        CreditSettingScheduled(EraIndex, CreditSetting<BalanceOf<T>>),
+        /// scheduled credit setting change cancelled: enactment era, campaign id, credit level
+        CreditSettingCancelled(EraIndex, CampaignId, CreditLevel),
+        /// credit level thresholds updated
+        CreditLevelThresholdsUpdated(CreditLevelThresholds),
+        /// DPR price aggregated: median price, number of oracle workers
+        DprPriceAggregated(BalanceOf<T>, u32),
+        /// campaign registered or updated
+        CampaignUpdated(CampaignId),
+        /// campaign budget exhausted, rewards beyond it are not paid
+        CampaignBudgetExhausted(CampaignId),
+        /// credit level badge minted or updated: account, item, credit level
+        CreditBadgeUpdated(T::AccountId, InstanceIdOf<T>, CreditLevel),
+        /// credit level badge burned: account, item
+        CreditBadgeBurned(T::AccountId, InstanceIdOf<T>),
+        /// price feed endpoints replaced: number of endpoints
+        PriceFeedEndpointsUpdated(u32),
+        /// credit badges disabled, the collection id was taken before the pallet created it
+        CreditBadgeCollectionTaken(ClassIdOf<T>),
     }
 
     #[pallet::error]
@@ -403,6 +636,18 @@ pub mod pallet {
         PriceZero,
         /// not device admin
         NotDeviceAdmin,
+        /// enactment era is earlier than the minimum enactment delay allows
+        EnactmentTooEarly,
+        /// too many credit setting changes scheduled in the era
+        TooManyScheduledSettings,
+        /// no credit setting change scheduled for the campaign and level in the era
+        ScheduledSettingNotFound,
+        /// credit level thresholds are not positive and strictly ascending
+        InvalidCreditLevelThresholds,
+        /// campaign name too long or end era before start era
+        InvalidCampaign,
+        /// url or price field of a price feed endpoint too long
+        PriceFeedEndpointTooLong,
     }
 
     #[pallet::hooks]
@@ -411,34 +656,58 @@ pub mod pallet {
             Self::set_price();
         }
 
+        fn offchain_worker(now: T::BlockNumber) {
+            if let Err(e) = Self::offchain_price_feed(now) {
+                log!(warn, "price feed failed: {}", e);
+            }
+        }
+
         fn on_initialize(now: T::BlockNumber) -> Weight {
             let remainder = now % T::BlocksPerEra::get();
             let mut weight = T::DbWeight::get().reads(1 as u64);
-            const SIX_MOUNTH: u32 = 180;
             if remainder == T::BlockNumber::default() {
-                let change_era = GenesisChangeRewardEra::<T>::get();
-                if change_era == 0 {
-                    return weight;
-                }
-                let int_era = Self::get_current_era().saturating_sub(change_era);
-                let remainder_era = int_era % SIX_MOUNTH;
-                weight += T::DbWeight::get().writes(2 as u64);
-                // now six month reached
-                if remainder_era == 0 {
-                    let times = int_era / SIX_MOUNTH;
-                    if times > 0 && times <= 5 {
-                        let changed_settings = credit_setting::sub_genesis_apy::<T>(times as u8);
-                        let len = changed_settings.len();
-                        for setting in &changed_settings {
-                            Self::_update_credit_setting(setting.clone());
-                        }
-                        weight += T::DbWeight::get()
-                            .writes(2 as u64)
-                            .saturating_mul(len as u64)
-                    }
+                // enact the credit setting changes scheduled for the new era
+                let changed_settings = ScheduledCreditSettings::<T>::take(Self::get_current_era());
+                weight += T::DbWeight::get().writes(1 as u64);
+                let len = changed_settings.len();
+                for setting in changed_settings {
+                    Self::_update_credit_setting(setting.clone());
+                    Self::deposit_event(Event::CreditSettingUpdated(setting));
                 }
+                weight += T::DbWeight::get()
+                    .writes(2 as u64)
+                    .saturating_mul(len as u64)
             }
-            weight
+            weight = weight.saturating_add(Self::relevel_credit_data(RELEVEL_BATCH_SIZE));
+            if StorageVersion::<T>::get() == Some(Releases::V5_0_0) {
+                weight = weight.saturating_add(Self::migrate_credit_history(
+                    CREDIT_HISTORY_MIGRATION_BATCH_SIZE,
+                ));
+            }
+            weight = weight.saturating_add(Self::migrate_credit_badges(
+                CREDIT_BADGE_MIGRATION_BATCH_SIZE,
+            ));
+            weight.saturating_add(T::DbWeight::get().reads(1))
+        }
+
+        fn on_runtime_upgrade() -> Weight {
+            // the submissions of the block were kept in `CurrentPrices` before the median price
+            frame_support::storage::unhashed::kill(&frame_support::storage::storage_prefix(
+                <Pallet<T> as PalletInfoAccess>::name().as_bytes(),
+                b"CurrentPrices",
+            ));
+            let mut weight = T::DbWeight::get().reads_writes(1, 1);
+            match StorageVersion::<T>::get() {
+                // V5 to V6 runs a batch per block in on_initialize and continues to V7
+                Some(Releases::V5_0_0) | Some(Releases::V7_0_0) => return weight,
+                Some(Releases::V6_0_0) => return weight.saturating_add(Self::migrate_to_v7()),
+                Some(Releases::V4_0_0) => (),
+                _ => weight = weight.saturating_add(Self::migrate_genesis_apy_schedule()),
+            }
+            // stored credit levels are recomputed a batch per block from now on
+            RelevelCursor::<T>::put(Vec::<u8>::new());
+            StorageVersion::<T>::put(Releases::V5_0_0);
+            weight.saturating_add(T::DbWeight::get().writes(2))
         }
     }
 
@@ -459,6 +728,107 @@ pub mod pallet {
             Ok(().into())
         }
 
+        /// Schedule a credit setting change enacted at the beginning of `enact_era`, replacing
+        /// the change already scheduled for the same campaign and level in that era.
+        #[pallet::weight(<T as pallet::Config>::WeightInfo::schedule_credit_setting())]
+        pub fn schedule_credit_setting(
+            origin: OriginFor<T>,
+            enact_era: EraIndex,
+            credit_setting: CreditSetting<BalanceOf<T>>,
+        ) -> DispatchResultWithPostInfo {
+            T::CreditSettingOrigin::ensure_origin(origin)?;
+            let earliest_era =
+                Self::get_current_era().saturating_add(T::MinSettingEnactmentDelay::get().max(1));
+            ensure!(enact_era >= earliest_era, Error::<T>::EnactmentTooEarly);
+            ScheduledCreditSettings::<T>::try_mutate(enact_era, |settings| {
+                match settings.iter_mut().find(|s| {
+                    s.campaign_id == credit_setting.campaign_id
+                        && s.credit_level == credit_setting.credit_level
+                }) {
+                    Some(setting) => *setting = credit_setting.clone(),
+                    None => {
+                        ensure!(
+                            (settings.len() as u32) < T::MaxScheduledSettingsPerEra::get(),
+                            Error::<T>::TooManyScheduledSettings
+                        );
+                        settings.push(credit_setting.clone());
+                    }
+                }
+                Ok::<(), Error<T>>(())
+            })?;
+            Self::deposit_event(Event::CreditSettingScheduled(enact_era, credit_setting));
+            Ok(().into())
+        }
+
+        /// Cancel a credit setting change that has not been enacted yet.
+        #[pallet::weight(<T as pallet::Config>::WeightInfo::cancel_credit_setting())]
+        pub fn cancel_credit_setting(
+            origin: OriginFor<T>,
+            enact_era: EraIndex,
+            campaign_id: CampaignId,
+            credit_level: CreditLevel,
+        ) -> DispatchResultWithPostInfo {
+            T::CreditSettingOrigin::ensure_origin(origin)?;
+            ScheduledCreditSettings::<T>::try_mutate_exists(enact_era, |maybe_settings| {
+                let settings = maybe_settings
+                    .as_mut()
+                    .ok_or(Error::<T>::ScheduledSettingNotFound)?;
+                let index = settings
+                    .iter()
+                    .position(|s| s.campaign_id == campaign_id && s.credit_level == credit_level)
+                    .ok_or(Error::<T>::ScheduledSettingNotFound)?;
+                settings.remove(index);
+                if settings.is_empty() {
+                    *maybe_settings = None;
+                }
+                Ok::<(), Error<T>>(())
+            })?;
+            Self::deposit_event(Event::CreditSettingCancelled(
+                enact_era,
+                campaign_id,
+                credit_level,
+            ));
+            Ok(().into())
+        }
+
+        /// Update the minimum credit score of each credit level above zero. Stored credit data
+        /// is re-levelled a batch per block afterwards.
+        #[pallet::weight(<T as pallet::Config>::WeightInfo::set_credit_level_thresholds())]
+        pub fn set_credit_level_thresholds(
+            origin: OriginFor<T>,
+            thresholds: CreditLevelThresholds,
+        ) -> DispatchResultWithPostInfo {
+            T::CreditSettingOrigin::ensure_origin(origin)?;
+            ensure!(
+                thresholds[0] > 0 && thresholds.windows(2).all(|w| w[0] < w[1]),
+                Error::<T>::InvalidCreditLevelThresholds
+            );
+            LevelThresholds::<T>::put(thresholds);
+            RelevelCursor::<T>::put(Vec::<u8>::new());
+            Self::deposit_event(Event::CreditLevelThresholdsUpdated(thresholds));
+            Ok(().into())
+        }
+
+        /// Register a campaign or update a registered one, the rewards it already paid are kept.
+        #[pallet::weight(<T as pallet::Config>::WeightInfo::set_campaign())]
+        pub fn set_campaign(
+            origin: OriginFor<T>,
+            campaign_id: CampaignId,
+            campaign: Campaign<BalanceOf<T>>,
+        ) -> DispatchResultWithPostInfo {
+            T::CreditSettingOrigin::ensure_origin(origin)?;
+            ensure!(
+                campaign.name.len() <= MAX_CAMPAIGN_NAME_LEN
+                    && campaign
+                        .end_era
+                        .map_or(true, |end_era| end_era >= campaign.start_era),
+                Error::<T>::InvalidCampaign
+            );
+            Campaigns::<T>::insert(campaign_id, campaign);
+            Self::deposit_event(Event::CampaignUpdated(campaign_id));
+            Ok(().into())
+        }
+
         #[pallet::weight(<T as pallet::Config>::WeightInfo::force_modify_credit_history())]
         pub fn force_modify_credit_history(
             origin: OriginFor<T>,
@@ -466,8 +836,9 @@ pub mod pallet {
             expected_era: EraIndex,
         ) -> DispatchResultWithPostInfo {
             ensure_root(origin)?; // requires sudo
-            if UserCreditHistory::<T>::contains_key(&account_id) {
-                let is_success = UserCreditHistory::<T>::mutate(&account_id, |history| {
+            if Self::has_credit_history(&account_id) {
+                let (mut history, summary) = Self::load_credit_history(&account_id);
+                let modify = |history: &mut Vec<(EraIndex, CreditData)>| {
                     if history.len() > 0 {
                         for i in 0..history.len() {
                             if (i + 1 < history.len()
@@ -485,8 +856,11 @@ pub mod pallet {
                         }
                     }
                     false
-                });
+                };
+                // the first records can't be modified any more once compacted
+                let is_success = summary.is_none() && modify(&mut history);
                 if is_success {
+                    Self::store_credit_history(&account_id, history, None);
                     Self::deposit_event(Event::CreditHistoryUpdateSuccess(
                         account_id,
                         expected_era,
@@ -524,16 +898,16 @@ pub mod pallet {
                 .ok_or(Error::<T>::InvalidCreditData)?
                 .credit;
             let max_credit = {
-                let history = UserCreditHistory::<T>::get(&sender);
+                let (history, summary) = Self::load_credit_history(&sender);
                 if history.is_empty() {
                     T::MinCreditToDelegate::get()
                 } else {
                     let max_credit = history
                         .into_iter()
-                        .max_by(|x, y| (x.1.credit).cmp(&y.1.credit))
-                        .unwrap()
-                        .1
-                        .credit;
+                        .map(|(_, credit_data)| credit_data.credit)
+                        .chain(summary.map(|summary| summary.max_credit))
+                        .max()
+                        .unwrap();
                     if max_credit > T::MinCreditToDelegate::get() {
                         max_credit
                     } else {
@@ -557,6 +931,7 @@ pub mod pallet {
             )?;
             T::BurnedTo::on_unbalanced(burned);
             Self::_update_credit(&sender, target_credit);
+            Self::record_credit_change(&sender, CreditSource::BurnDpr, cur_credit, target_credit);
             Self::update_credit_history(&sender, Self::get_current_era());
             Self::burn_record(amount);
             Self::deposit_event(Event::<T>::BurnForAddCredit(sender.clone(), credit_score));
@@ -776,7 +1151,30 @@ pub mod pallet {
                 _ => {}
             }
 
-            CurrentPrices::<T>::mutate(|prices| prices.push(price));
+            PriceSubmissions::<T>::insert(&who, (price, T::UnixTime::now().as_secs()));
+            PriceSubmitted::<T>::put(true);
+            Ok(().into())
+        }
+
+        /// Replace the endpoints the offchain worker fetches the DPR price from
+        #[pallet::weight(T::WeightInfo::set_price_feed_endpoints())]
+        pub fn set_price_feed_endpoints(
+            origin: OriginFor<T>,
+            endpoints: BoundedVec<PriceFeedEndpoint, T::MaxPriceFeedEndpoints>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(Self::is_admin(&who), Error::<T>::NotAdmin);
+            let max_length = T::MaxPriceFeedEndpointLength::get() as usize;
+            ensure!(
+                endpoints
+                    .iter()
+                    .all(|endpoint| endpoint.url.len() <= max_length
+                        && endpoint.price_field.len() <= max_length),
+                Error::<T>::PriceFeedEndpointTooLong
+            );
+            let count = endpoints.len() as u32;
+            PriceFeedEndpoints::<T>::put(endpoints);
+            Self::deposit_event(Event::PriceFeedEndpointsUpdated(count));
             Ok(().into())
         }
 
@@ -837,66 +1235,159 @@ pub mod pallet {
             T::UserPrivilegeInterface::has_evm_privilege(&address, Privilege::EvmCreditOperation)
         }
 
+        /// aggregate the fresh submissions into the median price once `PriceQuorum` oracle
+        /// workers submitted, dropping the stale ones, at most once every
+        /// `PriceAggregationInterval` seconds
         fn set_price() {
-            let prices = CurrentPrices::<T>::take();
-            let len = prices.len();
-            if len == 0 {
+            if !PriceSubmitted::<T>::get() {
                 return;
             }
-            let sum = {
-                let mut sum: BalanceOf<T> = 0u32.into();
-                for num in prices {
-                    sum += num;
+            let now = T::UnixTime::now().as_secs();
+            if let Some(updated_at) = Self::dpr_price_updated_at() {
+                // the submissions are aggregated once the interval has passed
+                if now.saturating_sub(updated_at) < T::PriceAggregationInterval::get() {
+                    return;
                 }
-                sum
-            };
-            DprPrice::<T>::put(sum / (len as u32).into());
+            }
+            PriceSubmitted::<T>::kill();
+            let mut prices = Vec::new();
+            let mut stale_workers = Vec::new();
+            for (worker, (price, timestamp)) in PriceSubmissions::<T>::iter() {
+                if now.saturating_sub(timestamp) > T::PriceStaleAfter::get() {
+                    stale_workers.push(worker);
+                } else {
+                    prices.push(price);
+                }
+            }
+            for worker in stale_workers {
+                PriceSubmissions::<T>::remove(worker);
+            }
+            let len = prices.len() as u32;
+            if len == 0 || len < T::PriceQuorum::get() {
+                return;
+            }
+            let price = Self::median_price(prices);
+            DprPrice::<T>::put(price);
+            DprPriceUpdatedAt::<T>::put(now);
+            DprPriceHistory::<T>::mutate(|history| {
+                if history.len() as u32 >= T::MaxPriceHistory::get() && !history.is_empty() {
+                    history.remove(0);
+                }
+                let _ = history.try_push((now, price));
+            });
+            Self::deposit_event(Event::DprPriceAggregated(price, len));
+        }
+
+        /// median of non-empty `prices`, the mean of the middle two for an even number
+        pub(crate) fn median_price(mut prices: Vec<BalanceOf<T>>) -> BalanceOf<T> {
+            prices.sort();
+            let mid = prices.len() / 2;
+            if prices.len() % 2 == 0 {
+                prices[mid - 1].saturating_add(prices[mid]) / 2u32.into()
+            } else {
+                prices[mid]
+            }
         }
 
+        /// aggregated DPR price, None if it has not been updated for `PriceStaleAfter` seconds
+        pub fn fresh_dpr_price() -> Option<BalanceOf<T>> {
+            let updated_at = Self::dpr_price_updated_at()?;
+            if T::UnixTime::now().as_secs().saturating_sub(updated_at) > T::PriceStaleAfter::get() {
+                return None;
+            }
+            Self::dpr_price()
+        }
+
+        /// time weighted average of the aggregated prices over the last `window` seconds
+        pub fn dpr_twap(window: u64) -> Option<BalanceOf<T>> {
+            let history = Self::dpr_price_history();
+            let now = T::UnixTime::now().as_secs();
+            let start = now.saturating_sub(window);
+            let mut weighted_sum: BalanceOf<T> = Zero::zero();
+            let mut total_time = 0u64;
+            for (i, (timestamp, price)) in history.iter().enumerate() {
+                // each price holds until the next one is aggregated
+                let end = history.get(i + 1).map_or(now, |(next, _)| *next);
+                let from = cmp::max(*timestamp, start);
+                if end <= from {
+                    continue;
+                }
+                let duration = end - from;
+                weighted_sum = weighted_sum
+                    .saturating_add(price.saturating_mul(duration.unique_saturated_into()));
+                total_time += duration;
+            }
+            if total_time == 0 {
+                return history.last().map(|(_, price)| *price);
+            }
+            Some(weighted_sum / total_time.unique_saturated_into())
+        }
+
+        /// add or slash the credit score of the account mapped to `evm_user`,
+        /// returns the old and the new credit score
         pub fn evm_update_credit(
             caller: &H160,
             evm_user: &H160,
             score: u64,
             add_flag: bool,
-        ) -> bool {
-            if !Self::is_evm_credit_operation_address(&caller) {
-                return false;
-            }
-            let user = T::NodeInterface::get_accounts_evm_deeper(evm_user);
-            if user.is_none() {
-                return false;
-            }
-            let user = user.unwrap();
+        ) -> Result<(u64, u64), Error<T>> {
+            ensure!(
+                Self::is_evm_credit_operation_address(caller),
+                Error::<T>::NotAdmin
+            );
+            let user = T::NodeInterface::get_accounts_evm_deeper(evm_user)
+                .ok_or(Error::<T>::AccountNotFound)?;
+            // do not init credit data, because entering the default campaign need some contition
+            let mut credit_data =
+                UserCredit::<T>::get(&user).ok_or(Error::<T>::AccountNoExistInUserCredit)?;
+            let old_credit = credit_data.credit;
 
             if add_flag {
-                let credit_data = {
-                    match UserCredit::<T>::get(&user) {
-                        Some(mut credit_data) => {
-                            let new_score = credit_data.credit.saturating_add(score);
-                            credit_data.update(new_score);
-                            credit_data
-                        }
-                        None => {
-                            // do not init credit data, because entering the default campaign need some contition
-                            return false;
-                        }
-                    }
-                };
-                Self::do_add_credit_with_event(user, credit_data);
+                let new_score = credit_data.credit.saturating_add(score);
+                credit_data.update(new_score, &Self::credit_level_thresholds());
+                Self::do_add_credit_with_event(user.clone(), credit_data);
             } else {
                 Self::slash_credit(&user, Some(score));
             }
-            true
+            let new_credit = Self::get_credit_score(&user).unwrap_or(0);
+            Self::record_credit_change(&user, CreditSource::Evm, old_credit, new_credit);
+            Ok((old_credit, new_credit))
+        }
+
+        /// worst case weight of the credit change record and of the badge sync following a
+        /// credit update of `evm_update_credit`
+        pub fn max_evm_credit_change_weight() -> Weight {
+            T::DbWeight::get()
+                .reads_writes(2, 2)
+                .saturating_add(Self::max_sync_credit_badge_weight())
         }
 
         pub fn slash_offline_device_credit(account_id: &T::AccountId) -> Weight {
             let mut weight = T::DbWeight::get().reads_writes(1, 0);
             let eras = T::NodeInterface::get_eras_offline(&account_id);
             if eras > 0 && eras % 3 == 0 {
+                // the device stays in this era for BlocksPerEra blocks, only slash it once
+                let now = <frame_system::Pallet<T>>::block_number();
+                weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 0));
+                if let Some(last) = LastOfflineSlash::<T>::get(account_id) {
+                    if now.saturating_sub(last) < T::BlocksPerEra::get() {
+                        return weight;
+                    }
+                }
                 let addrs = MaintainDevices::<T>::get();
                 if !addrs.contains(account_id) {
                     // slash one credit for being offline every 3 eras
+                    let old_credit = Self::get_credit_score(account_id).unwrap_or(0);
                     weight = weight.saturating_add(Self::slash_credit(&account_id, None));
+                    let new_credit = Self::get_credit_score(account_id).unwrap_or(0);
+                    weight = weight.saturating_add(Self::record_credit_change(
+                        account_id,
+                        CreditSource::OfflineSlash,
+                        old_credit,
+                        new_credit,
+                    ));
+                    LastOfflineSlash::<T>::insert(account_id, now);
+                    weight = weight.saturating_add(T::DbWeight::get().reads_writes(0, 1));
                 }
             }
             weight
@@ -908,10 +1399,11 @@ pub mod pallet {
                 UserCredit::<T>::mutate(account_id, |v| match v {
                     Some(credit_data) => {
                         credit_data.credit = score;
-                        credit_data.current_credit_level = CreditLevel::get_credit_level(score);
+                        credit_data.current_credit_level = Self::credit_level_of(score);
                     }
                     _ => (),
                 });
+                Self::sync_credit_badge(account_id);
                 Self::deposit_event(Event::CreditUpdateSuccess((*account_id).clone(), score));
                 true
             } else {
@@ -920,12 +1412,64 @@ pub mod pallet {
             }
         }
 
+        /// count a credit change of the account by its source and keep it in the recent changes
+        fn record_credit_change(
+            account_id: &T::AccountId,
+            source: CreditSource,
+            old_credit: u64,
+            new_credit: u64,
+        ) -> Weight {
+            if old_credit == new_credit {
+                return Weight::zero();
+            }
+            CreditBySource::<T>::mutate(account_id, source, |counter| {
+                if new_credit > old_credit {
+                    counter.added = counter.added.saturating_add(new_credit - old_credit);
+                } else {
+                    counter.removed = counter.removed.saturating_add(old_credit - new_credit);
+                }
+            });
+            let change = CreditChange {
+                block_number: <frame_system::Pallet<T>>::block_number(),
+                source,
+                old_credit,
+                new_credit,
+            };
+            RecentCreditChanges::<T>::mutate(account_id, |changes| {
+                if changes.len() as u32 >= T::MaxCreditChanges::get() && !changes.is_empty() {
+                    changes.remove(0);
+                }
+                // only fails if no change is kept
+                let _ = changes.try_push(change);
+            });
+            T::DbWeight::get().reads_writes(2, 2)
+        }
+
+        /// credit added and removed by each source of credit changes of the account
+        pub fn credit_breakdown(account_id: &T::AccountId) -> Vec<(CreditSource, CreditCounter)> {
+            let mut breakdown: Vec<_> = CreditBySource::<T>::iter_prefix(account_id).collect();
+            breakdown.sort_by_key(|(source, _)| *source);
+            breakdown
+        }
+
+        /// the most recent `count` credit changes of the account, newest first
+        pub fn recent_credit_changes(
+            account_id: &T::AccountId,
+            count: u32,
+        ) -> Vec<CreditChange<T::BlockNumber>> {
+            RecentCreditChanges::<T>::get(account_id)
+                .into_iter()
+                .rev()
+                .take(count as usize)
+                .collect()
+        }
+
         pub fn update_credit_history(account_id: &T::AccountId, current_era: EraIndex) -> Weight {
             let user_credit_data = Self::user_credit(&account_id).unwrap();
             let mut weight = T::DbWeight::get().reads_writes(1, 0);
 
-            let mut user_credit_history = Self::user_credit_history(&account_id);
-            weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 0));
+            let (mut user_credit_history, summary) = Self::load_credit_history(&account_id);
+            weight = weight.saturating_add(T::DbWeight::get().reads_writes(2, 0));
 
             if !user_credit_history.is_empty() {
                 // update credit history only if it's not empty
@@ -935,8 +1479,8 @@ pub mod pallet {
                 } else {
                     user_credit_history.push((current_era, user_credit_data));
                 }
-                UserCreditHistory::<T>::insert(&account_id, user_credit_history);
-                weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+                Self::store_credit_history(&account_id, user_credit_history, summary);
+                weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 3));
             }
             weight
         }
@@ -946,8 +1490,104 @@ pub mod pallet {
             credit_data: CreditData,
             era: u32,
         ) -> Weight {
-            UserCreditHistory::<T>::insert(account_id, vec![(era, credit_data)]);
-            T::DbWeight::get().reads_writes(0, 1)
+            Self::store_credit_history(account_id, vec![(era, credit_data)], None);
+            T::DbWeight::get().reads_writes(0, 3)
+        }
+
+        /// recent credit history of the account, empty until its device gets onboard
+        pub fn user_credit_history(account_id: &T::AccountId) -> Vec<(EraIndex, CreditData)> {
+            Self::load_credit_history(account_id).0
+        }
+
+        fn has_credit_history(account_id: &T::AccountId) -> bool {
+            CreditHistory::<T>::contains_key(account_id)
+                || UserCreditHistory::<T>::contains_key(account_id)
+        }
+
+        /// recent credit history and the summary of the older one, compacting a history not
+        /// migrated yet on the fly
+        pub fn load_credit_history(
+            account_id: &T::AccountId,
+        ) -> (Vec<(EraIndex, CreditData)>, Option<CreditHistorySummary>) {
+            match CreditHistory::<T>::get(account_id) {
+                Some(history) => (history, CreditHistorySummaries::<T>::get(account_id)),
+                None => Self::compact_credit_history(
+                    account_id,
+                    UserCreditHistory::<T>::get(account_id),
+                    None,
+                ),
+            }
+        }
+
+        /// evict the oldest entries beyond `MaxCreditHistory` into the summary. The summary
+        /// only keeps the latest evicted entry, so an entry is evicted only if the one it
+        /// replaces there is not in effect at any unrewarded era.
+        fn compact_credit_history(
+            account_id: &T::AccountId,
+            mut history: Vec<(EraIndex, CreditData)>,
+            mut summary: Option<CreditHistorySummary>,
+        ) -> (Vec<(EraIndex, CreditData)>, Option<CreditHistorySummary>) {
+            let max_len = T::MaxCreditHistory::get() as usize;
+            // accounts never delegated have nothing to be rewarded, their history is reset when
+            // they start delegating
+            let unrewarded_since = UnrewardedSince::<T>::get(account_id).unwrap_or(EraIndex::MAX);
+            let evicted = history
+                .iter()
+                .enumerate()
+                .take_while(|(i, (era, _))| {
+                    history.len() - i > max_len
+                        && ((*i == 0 && summary.is_none()) || *era <= unrewarded_since)
+                })
+                .count();
+            for (era, credit_data) in history.drain(..evicted) {
+                match summary.as_mut() {
+                    Some(summary) => summary.evict(era, credit_data),
+                    None => summary = Some(CreditHistorySummary::new(era, credit_data)),
+                }
+            }
+            (history, summary)
+        }
+
+        fn store_credit_history(
+            account_id: &T::AccountId,
+            history: Vec<(EraIndex, CreditData)>,
+            summary: Option<CreditHistorySummary>,
+        ) {
+            UserCreditHistory::<T>::remove(account_id);
+            if history.is_empty() {
+                CreditHistory::<T>::remove(account_id);
+                CreditHistorySummaries::<T>::remove(account_id);
+                return;
+            }
+            let (history, summary) = Self::compact_credit_history(account_id, history, summary);
+            CreditHistory::<T>::insert(account_id, history);
+            match summary {
+                Some(summary) => CreditHistorySummaries::<T>::insert(account_id, summary),
+                None => CreditHistorySummaries::<T>::remove(account_id),
+            }
+        }
+
+        fn remove_credit_history(account_id: &T::AccountId) {
+            UserCreditHistory::<T>::remove(account_id);
+            CreditHistory::<T>::remove(account_id);
+            CreditHistorySummaries::<T>::remove(account_id);
+            UnrewardedSince::<T>::remove(account_id);
+        }
+
+        /// Compact up to `limit` legacy credit histories, finishing the V6 migration once none
+        /// is left.
+        pub(crate) fn migrate_credit_history(limit: usize) -> Weight {
+            let mut count = 0;
+            for (account_id, history) in UserCreditHistory::<T>::drain().take(limit) {
+                Self::store_credit_history(&account_id, history, None);
+                count += 1;
+            }
+            let mut weight =
+                T::DbWeight::get().reads_writes(count as u64 + 1, 3 * count as u64 + 1);
+            if count < limit {
+                weight = weight.saturating_add(Self::migrate_to_v7());
+            }
+            weight
         }
 
         fn get_onboard_era(account_id: &T::AccountId) -> Option<EraIndex> {
@@ -1014,7 +1654,7 @@ pub mod pallet {
         /// credit data check
         fn check_credit_data(data: &CreditData) -> DispatchResult {
             ensure!(
-                CreditLevel::get_credit_level(data.credit) == data.current_credit_level,
+                Self::credit_level_of(data.credit) == data.current_credit_level,
                 Error::<T>::InvalidCreditData
             );
             let credit_setting = Self::credit_settings(data.campaign_id, data.initial_credit_level);
@@ -1094,11 +1734,21 @@ pub mod pallet {
             if NotSwitchAccounts::<T>::contains_key(who) {
                 return false;
             }
-            let new_id = Self::campaign_id_switch(old_data.campaign_id);
-            if new_id.is_none() {
-                return false;
+            let new_id = match Self::campaign(old_data.campaign_id) {
+                Some(campaign) => campaign.successor,
+                None => Self::campaign_id_switch(old_data.campaign_id),
+            };
+            let new_id = match new_id {
+                Some(new_id) => new_id,
+                None => return false,
+            };
+            if let Some(successor) = Self::campaign(new_id) {
+                if !Self::is_campaign_open(&successor, expire_era)
+                    || old_data.credit < successor.min_credit
+                {
+                    return false;
+                }
             }
-            let new_id = new_id.unwrap();
 
             if old_data.campaign_id == new_id {
                 old_data.reward_eras += 180;
@@ -1109,6 +1759,7 @@ pub mod pallet {
 
             UserCredit::<T>::insert(who, old_data);
             Self::update_credit_history(who, expire_era);
+            Self::sync_credit_badge(who);
             true
         }
 
@@ -1118,12 +1769,13 @@ pub mod pallet {
                     Some(data) => *data = credit_data.clone(),
                     _ => (),
                 });
-                if !Self::user_credit_history(&account_id).is_empty() {
+                if Self::has_credit_history(&account_id) {
                     Self::update_credit_history(&account_id, Self::get_current_era());
                 }
             } else {
                 UserCredit::<T>::insert(&account_id, credit_data.clone());
             }
+            Self::sync_credit_badge(&account_id);
         }
 
         fn do_add_credit_with_event(account_id: T::AccountId, credit_data: CreditData) {
@@ -1142,6 +1794,7 @@ pub mod pallet {
         fn calc_usdt_daily_poc_reward(
             account_id: &T::AccountId,
             credit_data: &CreditData,
+            price: BalanceOf<T>,
         ) -> (BalanceOf<T>, Weight) {
             let mut weight = Weight::zero();
             let staking_balance = {
@@ -1159,13 +1812,7 @@ pub mod pallet {
             weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 0));
 
             let staking_usdt = staking_balance.0;
-            let price = Self::dpr_price();
-            weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 0));
-            if price.is_none() {
-                return (0u32.into(), weight);
-            }
-            let dpr_amount =
-                Self::calc_price_dpr(staking_usdt, price.unwrap(), DPR.unique_saturated_into());
+            let dpr_amount = Self::calc_price_dpr(staking_usdt, price, DPR.unique_saturated_into());
             let current_credit_level = credit_data.current_credit_level;
             let credit_setting =
                 Self::credit_settings(credit_data.campaign_id, current_credit_level);
@@ -1212,15 +1859,80 @@ pub mod pallet {
 
         // both campaign id is dpr staking or usdt staking
         fn is_same_campaign_type(lhs: u16, rhs: u16) -> bool {
-            let dpr_campaign_ids = vec![0, 1, 2, 3, 4];
-            let usdt_campaign_ids = vec![5];
+            match (
+                Self::campaign_staking_type(lhs),
+                Self::campaign_staking_type(rhs),
+            ) {
+                (Some(lhs), Some(rhs)) => lhs == rhs,
+                _ => false,
+            }
+        }
 
-            if usdt_campaign_ids.contains(&lhs) && usdt_campaign_ids.contains(&rhs) {
-                return true;
-            } else if dpr_campaign_ids.contains(&lhs) && dpr_campaign_ids.contains(&rhs) {
-                return true;
+        /// staking type of the registered campaign, or of the hard-coded campaigns
+        fn campaign_staking_type(campaign_id: CampaignId) -> Option<StakingType> {
+            match Self::campaign(campaign_id) {
+                Some(campaign) => Some(campaign.staking_type),
+                None => match campaign_id {
+                    0..=4 => Some(StakingType::Dpr),
+                    USDT_CAMPAIGN_ID => Some(StakingType::Usdt),
+                    _ => None,
+                },
             }
-            false
+        }
+
+        fn is_campaign_open(campaign: &Campaign<BalanceOf<T>>, era: EraIndex) -> bool {
+            campaign.start_era <= era && campaign.end_era.map_or(true, |end_era| era <= end_era)
+        }
+
+        /// remaining budget of a registered campaign, None if it is not registered or uncapped
+        pub fn campaign_remaining_budget(campaign_id: CampaignId) -> Option<BalanceOf<T>> {
+            let budget = Self::campaign(campaign_id)?.budget?;
+            Some(budget.saturating_sub(Self::campaign_spent(campaign_id)))
+        }
+
+        /// `reward` capped by the remaining budget of a registered campaign, None if the campaign
+        /// is not registered
+        fn cap_campaign_reward(
+            campaign_id: CampaignId,
+            reward: BalanceOf<T>,
+        ) -> Option<BalanceOf<T>> {
+            let campaign = Self::campaign(campaign_id)?;
+            let capped = match campaign.budget {
+                Some(budget) => cmp::min(
+                    reward,
+                    budget.saturating_sub(Self::campaign_spent(campaign_id)),
+                ),
+                None => reward,
+            };
+            if capped < reward {
+                Self::deposit_event(Event::CampaignBudgetExhausted(campaign_id));
+            }
+            Some(capped)
+        }
+
+        /// charge `paid` to the campaigns of the last reward of the account, in campaign order
+        fn spend_campaign_budget(account_id: &T::AccountId, paid: BalanceOf<T>) -> Weight {
+            let campaign_rewards = match UnpaidCampaignRewards::<T>::take(account_id) {
+                Some(campaign_rewards) => campaign_rewards,
+                None => return T::DbWeight::get().reads_writes(1, 0),
+            };
+            let mut weight = T::DbWeight::get().reads_writes(1, 1);
+            let mut unspent = paid;
+            for (campaign_id, reward) in campaign_rewards {
+                let spent = cmp::min(reward, unspent);
+                if spent.is_zero() {
+                    break;
+                }
+                unspent = unspent.saturating_sub(spent);
+                weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 0));
+                if Campaigns::<T>::contains_key(campaign_id) {
+                    CampaignSpent::<T>::mutate(campaign_id, |total| {
+                        *total = total.saturating_add(spent)
+                    });
+                    weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+                }
+            }
+            weight
         }
 
         pub fn calc_price_dpr(
@@ -1234,6 +1946,99 @@ pub mod pallet {
             let res = U256::from(numerator) * U256::from(base) / U256::from(denominator);
             res.low_u128().unique_saturated_into()
         }
+
+        /// credit level of the score with the on-chain level thresholds
+        pub fn credit_level_of(credit_score: u64) -> CreditLevel {
+            CreditLevel::from_score(credit_score, &Self::credit_level_thresholds())
+        }
+
+        /// credit data of the account with its level from the current thresholds, the stored
+        /// level is stale until `relevel_credit_data` reaches the account
+        pub fn user_credit_relevelled(account_id: &T::AccountId) -> Option<CreditData> {
+            Self::user_credit(account_id).map(|mut credit_data| {
+                credit_data.relevel(&Self::credit_level_thresholds());
+                credit_data
+            })
+        }
+
+        /// Re-level up to `limit` stored credit data with the current level thresholds,
+        /// continuing from `RelevelCursor`, which is removed once all are done.
+        pub(crate) fn relevel_credit_data(limit: usize) -> Weight {
+            let cursor = match RelevelCursor::<T>::get() {
+                Some(cursor) => cursor,
+                None => return T::DbWeight::get().reads(1),
+            };
+            let thresholds = Self::credit_level_thresholds();
+            let mut iter = if cursor.is_empty() {
+                UserCredit::<T>::iter()
+            } else {
+                UserCredit::<T>::iter_from(cursor)
+            };
+            let mut reads = 2u64;
+            let mut writes = 1u64;
+            let mut badge_weight = Weight::zero();
+            let mut count = 0;
+            while count < limit {
+                match iter.next() {
+                    Some((account_id, mut credit_data)) => {
+                        reads += 1;
+                        count += 1;
+                        if credit_data.relevel(&thresholds) {
+                            UserCredit::<T>::insert(&account_id, credit_data);
+                            writes += 1;
+                            badge_weight =
+                                badge_weight.saturating_add(Self::sync_credit_badge(&account_id));
+                        }
+                    }
+                    None => {
+                        RelevelCursor::<T>::kill();
+                        return T::DbWeight::get()
+                            .reads_writes(reads, writes)
+                            .saturating_add(badge_weight);
+                    }
+                }
+            }
+            RelevelCursor::<T>::put(iter.last_raw_key().to_vec());
+            T::DbWeight::get()
+                .reads_writes(reads, writes)
+                .saturating_add(badge_weight)
+        }
+
+        /// All scheduled credit setting changes ordered by enactment era.
+        pub fn all_scheduled_credit_settings() -> Vec<(EraIndex, CreditSetting<BalanceOf<T>>)> {
+            let mut scheduled: Vec<_> = ScheduledCreditSettings::<T>::iter()
+                .flat_map(|(era, settings)| settings.into_iter().map(move |s| (era, s)))
+                .collect();
+            scheduled.sort_by_key(|(era, _)| *era);
+            scheduled
+        }
+
+        // replace the hard-coded six-month base apy reduction of the genesis campaign, which
+        // started at GenesisChangeRewardEra, with scheduled credit setting changes
+        fn migrate_genesis_apy_schedule() -> Weight {
+            use frame_support::storage::migration::take_storage_value;
+            const SIX_MONTHS: EraIndex = 180;
+
+            let pallet = <Pallet<T> as PalletInfoAccess>::name().as_bytes();
+            let change_era =
+                take_storage_value::<u32>(pallet, b"GenesisChangeRewardEra", &[]).unwrap_or(0);
+            let mut writes = 2u64;
+            if change_era != 0 {
+                let current_era = Self::get_current_era();
+                for times in 1..=5u32 {
+                    let enact_era = change_era.saturating_add(times * SIX_MONTHS);
+                    if enact_era > current_era {
+                        ScheduledCreditSettings::<T>::insert(
+                            enact_era,
+                            credit_setting::sub_genesis_apy::<T>(times as u8),
+                        );
+                        writes += 1;
+                    }
+                }
+            }
+            StorageVersion::<T>::put(Releases::V4_0_0);
+            T::DbWeight::get().reads_writes(2, writes)
+        }
     }
 
     impl<T: Config> CreditInterface<T::AccountId, BalanceOf<T>> for Pallet<T> {
@@ -1288,12 +2093,12 @@ pub mod pallet {
                 match UserCredit::<T>::get(account_id.clone()) {
                     Some(mut credit_data) => {
                         let new_score = credit_data.credit.saturating_add(credit_gap);
-                        credit_data.update(new_score);
+                        credit_data.update(new_score, &Self::credit_level_thresholds());
                         credit_data
                     }
                     None => {
                         let default_id = campaign_id.unwrap_or(Self::default_campaign_id());
-                        CreditData::new(default_id, credit_gap)
+                        CreditData::new(default_id, credit_gap, &Self::credit_level_thresholds())
                     }
                 }
             };
@@ -1330,7 +2135,8 @@ pub mod pallet {
             let penalty = score.unwrap_or(T::CreditAttenuationStep::get());
             if penalty == u64::MAX {
                 let credit_data = UserCredit::<T>::take(account_id);
-                UserCreditHistory::<T>::remove(account_id);
+                Self::remove_credit_history(account_id);
+                weight = weight.saturating_add(Self::sync_credit_badge(account_id));
                 Self::deposit_event(Event::CreditScoreSlashed(
                     (*account_id).clone(),
                     credit_data.unwrap_or_default().credit,
@@ -1341,7 +2147,7 @@ pub mod pallet {
                     if let Some(credit_data) = v {
                         credit_data.credit = credit_data.credit.saturating_sub(penalty);
                         credit_data.current_credit_level =
-                            CreditLevel::get_credit_level(credit_data.credit);
+                            Self::credit_level_of(credit_data.credit);
 
                         Self::deposit_event(Event::CreditScoreSlashed(
                             (*account_id).clone(),
@@ -1358,42 +2164,53 @@ pub mod pallet {
                     account_id,
                     Self::get_current_era(),
                 ));
+                weight = weight.saturating_add(Self::sync_credit_badge(account_id));
             }
 
             weight
         }
 
+        fn slash_offline_device_credit(account_id: &T::AccountId) -> Weight {
+            Pallet::<T>::slash_offline_device_credit(account_id)
+        }
+
         fn get_credit_level(credit_score: u64) -> CreditLevel {
-            CreditLevel::get_credit_level(credit_score)
+            Self::credit_level_of(credit_score)
+        }
+
+        fn credit_level_gap(lhs: CreditLevel, rhs: CreditLevel) -> u64 {
+            CreditLevel::credit_level_gap_by_thresholds(lhs, rhs, &Self::credit_level_thresholds())
         }
 
         fn get_reward(
             account_id: &T::AccountId,
             from: EraIndex,
             to: EraIndex,
-        ) -> (Option<BalanceOf<T>>, Weight) {
+        ) -> (Result<Option<BalanceOf<T>>, RewardDeferred>, Weight) {
             // silently ignore invalid inputs
             let cur_era = Self::get_current_era();
             if from > to || to >= cur_era {
                 Self::deposit_event(Event::GetRewardResult(account_id.clone(), from, to, 1));
-                return (None, Weight::zero());
+                return (Ok(None), Weight::zero());
             }
+            // the eras before `from` won't be asked for again
+            UnrewardedSince::<T>::insert(account_id, from);
 
-            let optional_credit_data = Self::user_credit(account_id);
-            let mut weight = T::DbWeight::get().reads_writes(1, 0);
+            let optional_credit_data = Self::user_credit_relevelled(account_id);
+            let mut weight = T::DbWeight::get().reads_writes(2, 1);
             if optional_credit_data.is_none() {
                 Self::deposit_event(Event::GetRewardResult(account_id.clone(), from, to, 2));
-                return (None, weight);
+                return (Ok(None), weight);
             }
 
             let credit_data = optional_credit_data.unwrap();
             if credit_data.reward_eras == 0 {
                 Self::deposit_event(Event::GetRewardResult(account_id.clone(), from, to, 3));
-                return (None, weight);
+                return (Ok(None), weight);
             }
 
             weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 0));
-            if Self::user_credit_history(account_id).is_empty() {
+            if !Self::has_credit_history(account_id) {
                 weight = weight.saturating_add(Self::init_credit_history(
                     account_id,
                     credit_data.clone(),
@@ -1402,42 +2219,106 @@ pub mod pallet {
             }
             // TODO: for those not continue delegating's account, also need slash credit
             weight = weight.saturating_add(Self::slash_offline_device_credit(account_id));
-            let credit_history = Self::user_credit_history(account_id);
-            weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 0));
+            let (credit_history, summary) = Self::load_credit_history(account_id);
+            weight = weight.saturating_add(T::DbWeight::get().reads_writes(2, 0));
             if credit_history.is_empty() {
                 Self::deposit_event(Event::GetRewardResult(account_id.clone(), from, to, 4));
-                return (None, weight);
+                return (Ok(None), weight);
             }
-            let delegate_era = credit_history[0].0;
-            let expiry_era = delegate_era + credit_data.reward_eras - 1;
-            if from == expiry_era || credit_data.campaign_id == 0 || credit_data.campaign_id == 1 {
-                // switcch campaign forehead
-                Self::do_switch_campaign(account_id, credit_data, cur_era);
-            } else if from > expiry_era {
+            let delegate_era = summary
+                .as_ref()
+                .map_or(credit_history[0].0, |summary| summary.first_era);
+            // the latest compacted entry is in effect until the first recent one, levels of
+            // entries not re-levelled yet are taken from the current thresholds
+            let thresholds = Self::credit_level_thresholds();
+            weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 0));
+            let credit_history: Vec<_> = summary
+                .map(|summary| summary.last_evicted)
+                .into_iter()
+                .chain(credit_history)
+                .map(|(era, mut credit_data)| {
+                    credit_data.relevel(&thresholds);
+                    (era, credit_data)
+                })
+                .collect();
+            let mut expiry_era = delegate_era + credit_data.reward_eras - 1;
+            if let Some(end_era) = Self::campaign(credit_data.campaign_id).and_then(|c| c.end_era) {
+                expiry_era = cmp::min(expiry_era, end_era);
+            }
+            weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 0));
+            let switch_campaign =
+                from == expiry_era || credit_data.campaign_id == 0 || credit_data.campaign_id == 1;
+            if !switch_campaign && from > expiry_era {
                 Self::deposit_event(Event::GetRewardResult(account_id.clone(), from, to, 5));
-                return (None, weight);
+                return (Ok(None), weight);
             }
 
             let credit_map = Self::get_credit_map(credit_history, from, cmp::min(to, expiry_era));
+            // usdt campaign rewards wait for a fresh price, the eras are rewarded once it is
+            let has_usdt_rewards = credit_map.keys().any(|credit_data| {
+                Self::campaign_staking_type(credit_data.campaign_id) == Some(StakingType::Usdt)
+            });
+            let price = Self::fresh_dpr_price();
+            weight = weight.saturating_add(T::DbWeight::get().reads_writes(2, 0));
+            if has_usdt_rewards && price.is_none() {
+                Self::deposit_event(Event::GetRewardResult(account_id.clone(), from, to, 7));
+                return (Err(RewardDeferred), weight);
+            }
+
+            if switch_campaign {
+                // switcch campaign forehead
+                Self::do_switch_campaign(account_id, credit_data, cur_era);
+            }
             if credit_map.is_empty() {
                 Self::deposit_event(Event::GetRewardResult(account_id.clone(), from, to, 6));
-                return (None, weight);
+                return (Ok(None), weight);
             }
 
-            let mut poc_reward = BalanceOf::<T>::zero();
+            let mut campaign_rewards: BTreeMap<CampaignId, BalanceOf<T>> = BTreeMap::new();
             for (credit_data, num_of_eras) in credit_map {
+                let min_credit = Self::campaign(credit_data.campaign_id)
+                    .map_or(0, |campaign| campaign.min_credit);
+                weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 0));
+                if credit_data.credit < min_credit {
+                    continue;
+                }
                 let (daily_poc_reward, added_weight) = {
-                    if credit_data.campaign_id == USDT_CAMPAIGN_ID {
-                        Self::calc_usdt_daily_poc_reward(account_id, &credit_data)
+                    if Self::campaign_staking_type(credit_data.campaign_id)
+                        == Some(StakingType::Usdt)
+                    {
+                        Self::calc_usdt_daily_poc_reward(
+                            account_id,
+                            &credit_data,
+                            price.unwrap_or_default(),
+                        )
                     } else {
                         Self::calc_normal_daily_poc_reward(&credit_data)
                     }
                 };
                 weight += added_weight;
-                poc_reward =
-                    poc_reward.saturating_add(daily_poc_reward.saturating_mul(num_of_eras.into()));
+                let reward = campaign_rewards
+                    .entry(credit_data.campaign_id)
+                    .or_insert_with(Zero::zero);
+                *reward =
+                    reward.saturating_add(daily_poc_reward.saturating_mul(num_of_eras.into()));
+            }
+
+            let mut poc_reward = BalanceOf::<T>::zero();
+            let mut unpaid_campaign_rewards = Vec::new();
+            for (campaign_id, reward) in campaign_rewards {
+                weight = weight.saturating_add(T::DbWeight::get().reads_writes(2, 0));
+                let reward = Self::cap_campaign_reward(campaign_id, reward).unwrap_or(reward);
+                poc_reward = poc_reward.saturating_add(reward);
+                unpaid_campaign_rewards.push((campaign_id, reward));
             }
-            (Some(poc_reward), weight)
+            // budgets are charged with what the delegator is actually paid, see `reward_paid`
+            UnpaidCampaignRewards::<T>::insert(account_id, unpaid_campaign_rewards);
+            weight = weight.saturating_add(T::DbWeight::get().reads_writes(0, 1));
+            (Ok(Some(poc_reward)), weight)
+        }
+
+        fn reward_paid(account_id: &T::AccountId, paid: BalanceOf<T>) -> Weight {
+            Self::spend_campaign_budget(account_id, paid)
         }
 
         /// update credit score by traffic
@@ -1461,10 +2342,15 @@ pub mod pallet {
                 now_as_secs,
             );
             if time_eras >= CREDIT_CAP_ONE_ERAS {
-                let new_credit = Self::get_credit_score(&server_id)
-                    .unwrap_or(0)
-                    .saturating_add(One::one());
+                let old_credit = Self::get_credit_score(&server_id).unwrap_or(0);
+                let new_credit = old_credit.saturating_add(One::one());
                 if Self::_update_credit(&server_id, new_credit) {
+                    Self::record_credit_change(
+                        &server_id,
+                        CreditSource::Traffic,
+                        old_credit,
+                        new_credit,
+                    );
                     LastCreditUpdateTimestamp::<T>::insert(&server_id, now_as_secs);
                     Self::update_credit_history(&server_id, current_era);
                     Self::deposit_event(Event::CreditDataAddedByTraffic(
@@ -1498,11 +2384,11 @@ pub mod pallet {
                 return;
             }
             let current_era = Self::get_current_era();
-            let new_credit = Self::get_credit_score(&who)
-                .unwrap_or(0)
-                .saturating_add(add_credit);
+            let old_credit = Self::get_credit_score(&who).unwrap_or(0);
+            let new_credit = old_credit.saturating_add(add_credit);
 
             if Self::_update_credit(&who, new_credit) {
+                Self::record_credit_change(&who, CreditSource::Tip, old_credit, new_credit);
                 Self::update_credit_history(&who, current_era);
                 Self::deposit_event(Event::CreditDataAddedByTip(who.clone(), new_credit));
             } else {
@@ -1517,11 +2403,11 @@ pub mod pallet {
 
         fn update_credit_by_burn_nft(who: T::AccountId, add_credit: u64) -> DispatchResult {
             let current_era = Self::get_current_era();
-            let new_credit = Self::get_credit_score(&who)
-                .unwrap_or(0)
-                .saturating_add(add_credit);
+            let old_credit = Self::get_credit_score(&who).unwrap_or(0);
+            let new_credit = old_credit.saturating_add(add_credit);
 
             if Self::_update_credit(&who, new_credit) {
+                Self::record_credit_change(&who, CreditSource::BurnNft, old_credit, new_credit);
                 Self::update_credit_history(&who, current_era);
                 Self::deposit_event(Event::CreditDataAddedByBurnNft(who.clone(), new_credit));
             } else {
@@ -1546,6 +2432,7 @@ pub mod pallet {
                 );
                 return false;
             }
+            UnrewardedSince::<T>::insert(account_id, era);
             Self::init_credit_history(account_id, credit_data.unwrap(), era);
             true
         }
@@ -1584,14 +2471,17 @@ pub mod pallet {
                 return Err(Error::<T>::AccountNoExistInUserCredit.into());
             }
 
-            let new_score = whole_score.unwrap().saturating_sub(staking_score.unwrap());
+            let whole_score = whole_score.unwrap();
+            let new_score = whole_score.saturating_sub(staking_score.unwrap());
             let camp_id = Self::default_campaign_id();
             // when unstaking,change campaign id to defalut campaign id
-            let credit_data = CreditData::new(camp_id, new_score);
+            let credit_data = CreditData::new(camp_id, new_score, &Self::credit_level_thresholds());
             UserCredit::<T>::insert(user, credit_data);
+            Self::record_credit_change(user, CreditSource::UnstakingSlash, whole_score, new_score);
             Self::deposit_event(Event::CreditUpdateSuccess(user_clone, new_score));
             UserStakingCredit::<T>::remove(user);
             Self::update_credit_history(&user, Self::get_current_era());
+            Self::sync_credit_badge(user);
 
             Ok(())
         }
@@ -1625,6 +2515,75 @@ pub mod pallet {
         }
     }
 
+    impl<T: Config> DeviceMigration<T::AccountId> for Pallet<T> {
+        fn can_migrate_device(_old: &T::AccountId, new: &T::AccountId) -> bool {
+            !UserCredit::<T>::contains_key(new) && !Self::has_credit_history(new)
+        }
+
+        fn migrate_device(old: &T::AccountId, new: &T::AccountId) -> Weight {
+            if let Some(credit_data) = UserCredit::<T>::take(old) {
+                UserCredit::<T>::insert(new, credit_data);
+            }
+            if let Some(staking_credit) = UserStakingCredit::<T>::take(old) {
+                UserStakingCredit::<T>::insert(new, staking_credit);
+            }
+            let credit_history = if Self::has_credit_history(old) {
+                Some(Self::load_credit_history(old))
+            } else {
+                None
+            };
+            if let Some(era) = UnrewardedSince::<T>::take(old) {
+                UnrewardedSince::<T>::insert(new, era);
+            }
+            if let Some((history, summary)) = credit_history {
+                Self::remove_credit_history(old);
+                Self::store_credit_history(new, history, summary);
+            }
+            if let Some(era) = LastCreditUpdate::<T>::take(old) {
+                LastCreditUpdate::<T>::insert(new, era);
+            }
+            if let Some(timestamp) = LastCreditUpdateTimestamp::<T>::take(old) {
+                LastCreditUpdateTimestamp::<T>::insert(new, timestamp);
+            }
+            if let Some(not_switch) = NotSwitchAccounts::<T>::take(old) {
+                NotSwitchAccounts::<T>::insert(new, not_switch);
+            }
+            let burn_nft_credit = CreditFromBurnNft::<T>::take(old);
+            if burn_nft_credit > 0 {
+                CreditFromBurnNft::<T>::insert(new, burn_nft_credit);
+            }
+            if let Some(staking_balance) = UserStakingBalance::<T>::take(old) {
+                UserStakingBalance::<T>::insert(new, staking_balance);
+            }
+            if let Some(block_number) = LastOfflineSlash::<T>::take(old) {
+                LastOfflineSlash::<T>::insert(new, block_number);
+            }
+            let counters: Vec<_> = CreditBySource::<T>::drain_prefix(old).collect();
+            for (source, counter) in counters {
+                CreditBySource::<T>::insert(new, source, counter);
+            }
+            let changes = RecentCreditChanges::<T>::take(old);
+            if !changes.is_empty() {
+                RecentCreditChanges::<T>::insert(new, changes);
+            }
+            MaintainDevices::<T>::mutate(|addrs| {
+                if let Some(addr) = addrs.iter_mut().find(|addr| **addr == *old) {
+                    *addr = new.clone();
+                }
+            });
+            T::DbWeight::get()
+                .reads_writes(16, 28)
+                .saturating_add(Self::sync_credit_badge(old))
+                .saturating_add(Self::sync_credit_badge(new))
+        }
+
+        fn max_migrate_device_weight() -> Weight {
+            T::DbWeight::get()
+                .reads_writes(16, 28)
+                .saturating_add(Self::max_sync_credit_badge_weight().saturating_mul(2))
+        }
+    }
+
     #[cfg(feature = "std")]
     impl<T: Config> GenesisConfig<T> {
         /// Direct implementation of `GenesisBuild::build_storage`.
@@ -25,8 +25,8 @@ use frame_support::{
 
 use sp_core::H256;
 use sp_runtime::{
-    testing::Header,
-    traits::{BlakeTwo256, IdentityLookup},
+    testing::{Header, TestSignature, TestXt, UintAuthorityId},
+    traits::{BlakeTwo256, Extrinsic as ExtrinsicT, IdentityLookup},
     Percent, Permill,
 };
 
@@ -141,6 +141,10 @@ parameter_types! {
     pub const MinLockAmt: u32 = 100;
     pub const MaxDurationEras: u8 = 7;
     pub const MaxIpLength: usize = 256;
+    pub const MaxSweepDevices: u32 = 10;
+    pub const MaxServersPerRegion: u32 = 100;
+    pub const MaxFirmwareLength: u32 = 64;
+    pub const DeviceMigrationCooldown: u64 = 100;
 }
 impl pallet_deeper_node::Config for Test {
     type RuntimeEvent = RuntimeEvent;
@@ -151,6 +155,13 @@ impl pallet_deeper_node::Config for Test {
     type MaxIpLength = MaxIpLength;
     type WeightInfo = ();
     type VerifySignatureInterface = ();
+    type CreditInterface = Credit;
+    type MaxSweepDevices = MaxSweepDevices;
+    type MaxServersPerRegion = MaxServersPerRegion;
+    type MaxFirmwareLength = MaxFirmwareLength;
+    type UserPrivilegeInterface = ();
+    type DeviceMigration = Credit;
+    type DeviceMigrationCooldown = DeviceMigrationCooldown;
 }
 
 pub const MILLISECS_PER_BLOCK: Moment = 5000;
@@ -213,11 +224,21 @@ impl pallet_uniques::Config for Test {
     type ValueLimit = ConstU32<50>;
     type WeightInfo = ();
     type CreateOrigin = AsEnsureOriginWithArg<frame_system::EnsureSigned<u64>>;
-    type Locker = ();
+    type Locker = Credit;
 }
 
 parameter_types! {
     pub const MaxBurnCreditPerAddress: u32 = 50;
+    pub const MinSettingEnactmentDelay: u32 = 2;
+    pub const MaxScheduledSettingsPerEra: u32 = 10;
+    pub static MaxCreditHistory: u32 = 20;
+    pub static PriceQuorum: u32 = 1;
+    pub static CreditBadgeCollection: Option<u32> = None;
+    pub const MaxCreditChanges: u32 = 3;
+    pub const PriceStaleAfter: u64 = 24 * 60 * 60;
+    pub const MaxPriceHistory: u32 = 10;
+    pub static PriceAggregationInterval: u64 = 0;
+    pub const PriceFeedInterval: u64 = 10;
 }
 
 impl pallet_credit::Config for Test {
@@ -235,6 +256,58 @@ impl pallet_credit::Config for Test {
     type BurnedTo = Treasury;
     type UserPrivilegeInterface = UserPrivileges;
     type MaxBurnCreditPerAddress = MaxBurnCreditPerAddress;
+    type CreditSettingOrigin = frame_system::EnsureRoot<u64>;
+    type MinSettingEnactmentDelay = MinSettingEnactmentDelay;
+    type MaxScheduledSettingsPerEra = MaxScheduledSettingsPerEra;
+    type MaxCreditHistory = MaxCreditHistory;
+    type PriceQuorum = PriceQuorum;
+    type PriceStaleAfter = PriceStaleAfter;
+    type MaxPriceHistory = MaxPriceHistory;
+    type PriceAggregationInterval = PriceAggregationInterval;
+    type PriceFeed = price_feed::SignedPriceFeed<Test, TestAuthId>;
+    type PriceFeedInterval = PriceFeedInterval;
+    type MaxPriceFeedEndpoints = ConstU32<2>;
+    type MaxPriceFeedEndpointLength = ConstU32<64>;
+    type CreditBadgeCollection = CreditBadgeCollection;
+    type MaxCreditChanges = MaxCreditChanges;
+}
+
+pub type Extrinsic = TestXt<RuntimeCall, ()>;
+
+impl frame_system::offchain::SigningTypes for Test {
+    type Public = UintAuthorityId;
+    type Signature = TestSignature;
+}
+
+impl<LocalCall> frame_system::offchain::SendTransactionTypes<LocalCall> for Test
+where
+    RuntimeCall: From<LocalCall>,
+{
+    type OverarchingCall = RuntimeCall;
+    type Extrinsic = Extrinsic;
+}
+
+impl<LocalCall> frame_system::offchain::CreateSignedTransaction<LocalCall> for Test
+where
+    RuntimeCall: From<LocalCall>,
+{
+    fn create_transaction<C: frame_system::offchain::AppCrypto<Self::Public, Self::Signature>>(
+        call: RuntimeCall,
+        _public: UintAuthorityId,
+        _account: u64,
+        nonce: u64,
+    ) -> Option<(RuntimeCall, <Extrinsic as ExtrinsicT>::SignaturePayload)> {
+        Some((call, (nonce, ())))
+    }
+}
+
+/// oracle worker keys of the test keystore, see `UintAuthorityId::set_all_keys`
+pub struct TestAuthId;
+
+impl frame_system::offchain::AppCrypto<UintAuthorityId, TestSignature> for TestAuthId {
+    type RuntimeAppPublic = UintAuthorityId;
+    type GenericPublic = UintAuthorityId;
+    type GenericSignature = TestSignature;
 }
 
 // Build genesis storage according to the mock runtime.
@@ -0,0 +1,180 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Offchain worker price feed: fetches the DPR/USDT price from the configured HTTP endpoints
+//! and submits their median with `set_dpr_price`, signed by an oracle worker key of the keystore.
+
+use crate::{BalanceOf, Call, Config, Pallet, PriceFeedEndpoint};
+use frame_support::traits::Get;
+use frame_system::offchain::{AppCrypto, CreateSignedTransaction, SendSignedTransaction, Signer};
+use sp_core::{crypto::KeyTypeId, H160};
+use sp_runtime::{
+    offchain::{http, Duration},
+    traits::{UniqueSaturatedFrom, Zero},
+};
+use sp_std::{marker::PhantomData, vec::Vec};
+
+/// Key type of the oracle worker key signing the price feed, insert it with `author_insertKey`
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"dprp");
+
+/// deadline of a price request
+const HTTP_TIMEOUT_MS: u64 = 5_000;
+
+/// decimals of a price, same as DPR
+const PRICE_DECIMALS: usize = 18;
+
+pub mod crypto {
+    use super::KEY_TYPE;
+    use sp_runtime::{
+        app_crypto::{app_crypto, sr25519},
+        MultiSignature, MultiSigner,
+    };
+    app_crypto!(sr25519, KEY_TYPE);
+
+    pub struct OracleAuthId;
+
+    impl frame_system::offchain::AppCrypto<MultiSigner, MultiSignature> for OracleAuthId {
+        type RuntimeAppPublic = Public;
+        type GenericSignature = sp_core::sr25519::Signature;
+        type GenericPublic = sp_core::sr25519::Public;
+    }
+}
+
+/// Submits the price fetched by the offchain worker
+pub trait SubmitPrice<Balance> {
+    fn submit_price(price: Balance) -> Result<(), &'static str>;
+}
+
+/// no price feed
+impl<Balance> SubmitPrice<Balance> for () {
+    fn submit_price(_price: Balance) -> Result<(), &'static str> {
+        Err("price feed disabled")
+    }
+}
+
+/// Submits the price with `set_dpr_price` signed by any `AuthorityId` key of the keystore, the
+/// account of the key needs the `OracleWorker` privilege
+pub struct SignedPriceFeed<T, AuthorityId>(PhantomData<(T, AuthorityId)>);
+
+impl<T, AuthorityId> SubmitPrice<BalanceOf<T>> for SignedPriceFeed<T, AuthorityId>
+where
+    T: Config + CreateSignedTransaction<Call<T>>,
+    AuthorityId: AppCrypto<T::Public, T::Signature>,
+{
+    fn submit_price(price: BalanceOf<T>) -> Result<(), &'static str> {
+        let signer = Signer::<T, AuthorityId>::any_account();
+        match signer.send_signed_transaction(|_| Call::set_dpr_price {
+            price,
+            _worker: H160::zero(),
+        }) {
+            Some((_, Ok(()))) => Ok(()),
+            Some((_, Err(()))) => Err("failed to submit the price"),
+            None => Err("no oracle worker key in the keystore"),
+        }
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    /// fetch the price from every endpoint every `PriceFeedInterval` blocks and submit the median
+    pub(crate) fn offchain_price_feed(now: T::BlockNumber) -> Result<(), &'static str> {
+        let interval = T::PriceFeedInterval::get();
+        if interval.is_zero() || !(now % interval).is_zero() {
+            return Ok(());
+        }
+        let endpoints = Self::price_feed_endpoints();
+        if endpoints.is_empty() {
+            return Ok(());
+        }
+        let prices: Vec<BalanceOf<T>> = endpoints
+            .iter()
+            .filter_map(|endpoint| match Self::fetch_price(endpoint) {
+                Ok(price) => Some(price),
+                Err(e) => {
+                    crate::log!(
+                        warn,
+                        "failed to fetch price from {:?}: {:?}",
+                        endpoint.url,
+                        e
+                    );
+                    None
+                }
+            })
+            .collect();
+        if prices.is_empty() {
+            return Err("no price fetched");
+        }
+        T::PriceFeed::submit_price(Self::median_price(prices))
+    }
+
+    fn fetch_price(endpoint: &PriceFeedEndpoint) -> Result<BalanceOf<T>, http::Error> {
+        let url = sp_std::str::from_utf8(&endpoint.url).map_err(|_| http::Error::Unknown)?;
+        let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(HTTP_TIMEOUT_MS));
+        let pending = http::Request::get(url)
+            .deadline(deadline)
+            .send()
+            .map_err(|_| http::Error::IoError)?;
+        let response = pending
+            .try_wait(deadline)
+            .map_err(|_| http::Error::DeadlineReached)??;
+        if response.code != 200 {
+            return Err(http::Error::Unknown);
+        }
+        let body = response.body().collect::<Vec<u8>>();
+        let price = parse_price(&body, &endpoint.price_field).ok_or(http::Error::Unknown)?;
+        Ok(UniqueSaturatedFrom::unique_saturated_from(price))
+    }
+}
+
+/// decimal number of `field` in the JSON `body`, quoted or not, with `PRICE_DECIMALS` decimals
+pub(crate) fn parse_price(body: &[u8], field: &[u8]) -> Option<u128> {
+    let mut key = Vec::with_capacity(field.len() + 2);
+    key.push(b'"');
+    key.extend_from_slice(field);
+    key.push(b'"');
+    let start = body
+        .windows(key.len())
+        .position(|window| window == key.as_slice())?
+        + key.len();
+
+    let mut rest = body[start..].iter().skip_while(|c| c.is_ascii_whitespace());
+    if rest.next() != Some(&b':') {
+        return None;
+    }
+    let mut rest = rest
+        .skip_while(|c| c.is_ascii_whitespace() || **c == b'"')
+        .peekable();
+
+    let mut price: u128 = 0;
+    let mut digits = 0;
+    while let Some(c) = rest.next_if(|c| c.is_ascii_digit()) {
+        price = price.checked_mul(10)?.checked_add((*c - b'0') as u128)?;
+        digits += 1;
+    }
+    let mut decimals = 0;
+    if rest.next_if_eq(&&b'.').is_some() {
+        while let Some(c) = rest.next_if(|c| c.is_ascii_digit()) {
+            // truncate the digits beyond the price decimals
+            if decimals < PRICE_DECIMALS {
+                price = price.checked_mul(10)?.checked_add((*c - b'0') as u128)?;
+                decimals += 1;
+            }
+            digits += 1;
+        }
+    }
+    if digits == 0 {
+        return None;
+    }
+    price.checked_mul(10u128.pow((PRICE_DECIMALS - decimals) as u32))
+}
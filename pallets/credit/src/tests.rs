@@ -14,15 +14,31 @@
 // limitations under the License.
 
 #[cfg(test)]
-use crate::{mock::*, CampaignIdSwitch, Error, MaintainDevices, UserCredit, UserCreditHistory};
-use frame_support::traits::Currency;
-use frame_support::{assert_err, assert_noop, assert_ok, dispatch::DispatchError};
+use crate::{
+    mock::*, price_feed, BalanceOf, CampaignIdSwitch, CreditBadgeCursor, CreditHistory,
+    CreditHistorySummaries, Error, MaintainDevices, PriceFeedEndpoint, Releases, RelevelCursor,
+    ScheduledCreditSettings, StorageVersion, UnrewardedSince, UserCredit, UserCreditHistory,
+};
+use codec::Decode;
+use frame_support::traits::{ConstU32, Currency, Hooks, OnRuntimeUpgrade};
+use frame_support::{assert_err, assert_noop, assert_ok, dispatch::DispatchError, BoundedVec};
 use frame_system::RawOrigin;
 use node_primitives::{
-    credit::{CreditData, CreditInterface, CreditLevel, CreditSetting},
+    credit::{
+        Campaign, CreditChange, CreditCounter, CreditData, CreditInterface, CreditLevel,
+        CreditSetting, CreditSource, RewardDeferred, StakingType, DEFAULT_REWARD_ERAS,
+    },
+    deeper_node::DeviceMigration,
     user_privileges::Privilege,
 };
-use sp_core::H160;
+use sp_core::{
+    offchain::{
+        testing::{PendingRequest, TestOffchainExt, TestTransactionPoolExt},
+        OffchainDbExt, OffchainWorkerExt, TransactionPoolExt,
+    },
+    H160,
+};
+use sp_runtime::testing::UintAuthorityId;
 use sp_runtime::traits::BadOrigin;
 use sp_runtime::Percent;
 
@@ -152,7 +168,7 @@ fn add_or_update_credit_data() {
             credit_data.clone()
         ));
         assert_eq!(Credit::user_credit(14), Some(credit_data.clone()));
-        assert_eq!(Credit::user_credit_history(14), vec![]);
+        assert_eq!(Credit::user_credit_history(&14), vec![]);
         assert_eq!(
             <frame_system::Pallet<Test>>::events()
                 .pop()
@@ -199,9 +215,12 @@ fn add_or_update_credit_data_check_credit_history_and_reward() {
         assert_ok!(DeeperNode::im_online(RuntimeOrigin::signed(3)));
         // era 1
         run_to_block(BLOCKS_PER_ERA);
-        assert_eq!(Credit::user_credit_history(3), vec![]);
+        assert_eq!(Credit::user_credit_history(&3), vec![]);
         assert!(Credit::init_delegator_history(&3, 0));
-        assert_eq!(Credit::get_reward(&3, 0, 0).0, Some(21369858941948251800));
+        assert_eq!(
+            Credit::get_reward(&3, 0, 0).0,
+            Ok(Some(21369858941948251800))
+        );
         let credit_historys = vec![(
             0,
             CreditData {
@@ -214,7 +233,7 @@ fn add_or_update_credit_data_check_credit_history_and_reward() {
                 reward_eras: 1,
             },
         )];
-        assert_eq!(Credit::user_credit_history(3), credit_historys);
+        assert_eq!(Credit::user_credit_history(&3), credit_historys);
 
         let credit_data = CreditData {
             campaign_id: 0,
@@ -258,15 +277,21 @@ fn add_or_update_credit_data_check_credit_history_and_reward() {
                 },
             ),
         ];
-        assert_eq!(Credit::user_credit_history(3), credit_historys);
+        assert_eq!(Credit::user_credit_history(&3), credit_historys);
 
         // era 2
         run_to_block(BLOCKS_PER_ERA * 2);
-        assert_eq!(Credit::get_reward(&3, 1, 1).0, Some(223068450647875213020));
+        assert_eq!(
+            Credit::get_reward(&3, 1, 1).0,
+            Ok(Some(223068450647875213020))
+        );
 
         // era 3
         run_to_block(BLOCKS_PER_ERA * 3);
-        assert_eq!(Credit::get_reward(&3, 2, 2).0, Some(223068450647875213020));
+        assert_eq!(
+            Credit::get_reward(&3, 2, 2).0,
+            Ok(Some(223068450647875213020))
+        );
     });
 }
 
@@ -380,7 +405,7 @@ fn update_credit_by_burn_nft() {
 #[test]
 fn get_reward_work() {
     new_test_ext().execute_with(|| {
-        assert_eq!(Credit::get_reward(&3, 0, 0).0, None);
+        assert_eq!(Credit::get_reward(&3, 0, 0).0, Ok(None));
         assert!(Credit::init_delegator_history(&3, 0));
         assert!(Credit::init_delegator_history(&7, 0));
         assert!(Credit::init_delegator_history(&8, 0));
@@ -388,19 +413,52 @@ fn get_reward_work() {
         assert!(Credit::init_delegator_history(&10, 0));
         assert!(Credit::init_delegator_history(&11, 0));
         run_to_block(BLOCKS_PER_ERA); // era 1
-        assert_eq!(Credit::get_reward(&3, 0, 0).0, Some(21369858941948251800));
-        assert_eq!(Credit::get_reward(&7, 0, 0).0, Some(223068450647875213020));
-        assert_eq!(Credit::get_reward(&8, 0, 0).0, Some(223068450647875213020));
-        assert_eq!(Credit::get_reward(&9, 0, 0).0, Some(223068450647875213020));
-        assert_eq!(Credit::get_reward(&10, 0, 0).0, Some(223068450647875213020));
-        assert_eq!(Credit::get_reward(&11, 0, 0).0, Some(56416427606743384752));
+        assert_eq!(
+            Credit::get_reward(&3, 0, 0).0,
+            Ok(Some(21369858941948251800))
+        );
+        assert_eq!(
+            Credit::get_reward(&7, 0, 0).0,
+            Ok(Some(223068450647875213020))
+        );
+        assert_eq!(
+            Credit::get_reward(&8, 0, 0).0,
+            Ok(Some(223068450647875213020))
+        );
+        assert_eq!(
+            Credit::get_reward(&9, 0, 0).0,
+            Ok(Some(223068450647875213020))
+        );
+        assert_eq!(
+            Credit::get_reward(&10, 0, 0).0,
+            Ok(Some(223068450647875213020))
+        );
+        assert_eq!(
+            Credit::get_reward(&11, 0, 0).0,
+            Ok(Some(56416427606743384752))
+        );
         run_to_block(BLOCKS_PER_ERA * 2); // era 2, credit expires at era 1
-        assert_eq!(Credit::get_reward(&3, 1, 1).0, None);
-        assert_eq!(Credit::get_reward(&7, 1, 1).0, Some(223068450647875213020));
-        assert_eq!(Credit::get_reward(&8, 1, 1).0, Some(223068450647875213020));
-        assert_eq!(Credit::get_reward(&9, 1, 1).0, Some(223068450647875213020));
-        assert_eq!(Credit::get_reward(&10, 1, 1).0, Some(223068450647875213020));
-        assert_eq!(Credit::get_reward(&11, 1, 1).0, Some(56416427606743384752));
+        assert_eq!(Credit::get_reward(&3, 1, 1).0, Ok(None));
+        assert_eq!(
+            Credit::get_reward(&7, 1, 1).0,
+            Ok(Some(223068450647875213020))
+        );
+        assert_eq!(
+            Credit::get_reward(&8, 1, 1).0,
+            Ok(Some(223068450647875213020))
+        );
+        assert_eq!(
+            Credit::get_reward(&9, 1, 1).0,
+            Ok(Some(223068450647875213020))
+        );
+        assert_eq!(
+            Credit::get_reward(&10, 1, 1).0,
+            Ok(Some(223068450647875213020))
+        );
+        assert_eq!(
+            Credit::get_reward(&11, 1, 1).0,
+            Ok(Some(56416427606743384752))
+        );
     });
 }
 
@@ -410,7 +468,10 @@ fn get_reward_with_slash_credit_with_bonus() {
         assert_eq!(Credit::user_credit(&7).unwrap().credit, 400);
         assert!(Credit::init_delegator_history(&7, 0));
         run_to_block(BLOCKS_PER_ERA);
-        assert_eq!(Credit::get_reward(&7, 0, 0).0, Some(223068450647875213020));
+        assert_eq!(
+            Credit::get_reward(&7, 0, 0).0,
+            Ok(Some(223068450647875213020))
+        );
 
         Credit::slash_credit(&7, None);
         assert_eq!(
@@ -418,7 +479,10 @@ fn get_reward_with_slash_credit_with_bonus() {
             400 - CREDIT_ATTENUATION_STEP
         );
         run_to_block(BLOCKS_PER_ERA * 2);
-        assert_eq!(Credit::get_reward(&7, 1, 1).0, Some(111517786970905338624));
+        assert_eq!(
+            Credit::get_reward(&7, 1, 1).0,
+            Ok(Some(111517786970905338624))
+        );
     });
 }
 
@@ -426,8 +490,29 @@ fn get_reward_with_slash_credit_with_bonus() {
 fn get_reward_failed() {
     new_test_ext().execute_with(|| {
         run_to_block(BLOCKS_PER_ERA);
-        assert_eq!(Credit::get_reward(&5, 0, 0).0, None); // 5 credit 0
-        assert_eq!(Credit::get_reward(&8, 0, 0).0, None); // 8 not contains in storage
+        assert_eq!(Credit::get_reward(&5, 0, 0).0, Ok(None)); // 5 credit 0
+        assert_eq!(Credit::get_reward(&8, 0, 0).0, Ok(None)); // 8 not contains in storage
+    });
+}
+
+#[test]
+fn slash_offline_device_credit_once_per_era() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(DeeperNode::im_online(RuntimeOrigin::signed(3)));
+
+        run_to_block(BLOCKS_PER_ERA * 3);
+        Credit::slash_offline_device_credit(&3);
+        Credit::slash_offline_device_credit(&3);
+        assert_eq!(Credit::user_credit(&3).unwrap().credit, 99);
+
+        run_to_block(BLOCKS_PER_ERA * 4 - 1);
+        Credit::slash_offline_device_credit(&3);
+        assert_eq!(Credit::user_credit(&3).unwrap().credit, 99);
+
+        run_to_block(BLOCKS_PER_ERA * 6);
+        Credit::slash_offline_device_credit(&3);
+        Credit::slash_offline_device_credit(&3);
+        assert_eq!(Credit::user_credit(&3).unwrap().credit, 98);
     });
 }
 
@@ -504,7 +589,7 @@ fn update_credit_history_when_era_is_the_same() {
             },
         )];
 
-        assert_eq!(Credit::user_credit_history(1), credit_historys);
+        assert_eq!(Credit::user_credit_history(&1), credit_historys);
     });
 }
 
@@ -556,7 +641,7 @@ fn update_credit_history_when_era_is_non_zero() {
             ),
         ];
 
-        assert_eq!(Credit::user_credit_history(1), credit_historys);
+        assert_eq!(Credit::user_credit_history(&1), credit_historys);
     });
 }
 
@@ -567,7 +652,7 @@ fn burn_dpr_add_credit() {
         let _ = Balances::deposit_creating(&1, 5000);
         let _ = Balances::deposit_creating(&3, 10000);
         // genesis 1's credit score 100
-        UserCreditHistory::<Test>::insert(
+        CreditHistory::<Test>::insert(
             1,
             vec![
                 (
@@ -598,7 +683,7 @@ fn burn_dpr_add_credit() {
         );
 
         // genesis 3's credit score 100
-        UserCreditHistory::<Test>::insert(
+        CreditHistory::<Test>::insert(
             3,
             vec![
                 (
@@ -652,16 +737,25 @@ fn switch_campaign_duration() {
         assert!(Credit::init_delegator_history(&13, 1));
         // run_to_block, era=1
         run_to_block(BLOCKS_PER_ERA * 2);
-        assert_eq!(Credit::get_reward(&13, 1, 1).0, Some(60263002216294070076));
+        assert_eq!(
+            Credit::get_reward(&13, 1, 1).0,
+            Ok(Some(60263002216294070076))
+        );
 
         run_to_block(BLOCKS_PER_ERA * 3);
-        assert_eq!(Credit::get_reward(&13, 2, 2).0, Some(56416427606743384752));
+        assert_eq!(
+            Credit::get_reward(&13, 2, 2).0,
+            Ok(Some(56416427606743384752))
+        );
 
         assert_eq!(Credit::user_credit(13).unwrap().campaign_id, 1);
         assert_eq!(Credit::user_credit(13).unwrap().reward_eras, 3650);
 
         run_to_block(BLOCKS_PER_ERA * 4);
-        assert_eq!(Credit::get_reward(&13, 3, 3).0, Some(56416427606743384752));
+        assert_eq!(
+            Credit::get_reward(&13, 3, 3).0,
+            Ok(Some(56416427606743384752))
+        );
     });
 }
 
@@ -675,10 +769,16 @@ fn switch_campaign_same_id() {
         assert!(Credit::init_delegator_history(&13, 1));
         // run_to_block, era=1
         run_to_block(BLOCKS_PER_ERA * 2);
-        assert_eq!(Credit::get_reward(&13, 1, 1).0, Some(60263002216294070076));
+        assert_eq!(
+            Credit::get_reward(&13, 1, 1).0,
+            Ok(Some(60263002216294070076))
+        );
 
         run_to_block(BLOCKS_PER_ERA * 3);
-        assert_eq!(Credit::get_reward(&13, 2, 2).0, Some(60263002216294070076));
+        assert_eq!(
+            Credit::get_reward(&13, 2, 2).0,
+            Ok(Some(60263002216294070076))
+        );
 
         assert_eq!(Credit::user_credit(13).unwrap().campaign_id, 0);
         assert_eq!(Credit::user_credit(13).unwrap().reward_eras, 1 + 360);
@@ -688,7 +788,7 @@ fn switch_campaign_same_id() {
 #[test]
 fn force_modify_credit_history() {
     new_test_ext().execute_with(|| {
-        UserCreditHistory::<Test>::insert(
+        CreditHistory::<Test>::insert(
             1,
             vec![
                 (
@@ -719,7 +819,7 @@ fn force_modify_credit_history() {
         );
         assert!(Credit::force_modify_credit_history(RuntimeOrigin::root().into(), 1, 8).is_ok());
         assert_eq!(
-            Credit::user_credit_history(1),
+            Credit::user_credit_history(&1),
             vec![
                 (
                     8,
@@ -750,7 +850,7 @@ fn force_modify_credit_history() {
 
         assert!(Credit::force_modify_credit_history(RuntimeOrigin::root().into(), 1, 6).is_err()); // do not modify
         assert_eq!(
-            Credit::user_credit_history(1),
+            Credit::user_credit_history(&1),
             vec![
                 (
                     8,
@@ -781,7 +881,7 @@ fn force_modify_credit_history() {
 
         assert!(Credit::force_modify_credit_history(RuntimeOrigin::root().into(), 1, 10).is_ok());
         assert_eq!(
-            Credit::user_credit_history(1),
+            Credit::user_credit_history(&1),
             vec![(
                 10,
                 CreditData {
@@ -798,7 +898,7 @@ fn force_modify_credit_history() {
 
         assert!(Credit::force_modify_credit_history(RuntimeOrigin::root().into(), 1, 12).is_ok());
         assert_eq!(
-            Credit::user_credit_history(1),
+            Credit::user_credit_history(&1),
             vec![(
                 12,
                 CreditData {
@@ -815,7 +915,7 @@ fn force_modify_credit_history() {
 
         assert!(Credit::force_modify_credit_history(RuntimeOrigin::root().into(), 1, 12).is_ok());
         assert_eq!(
-            Credit::user_credit_history(1),
+            Credit::user_credit_history(&1),
             vec![(
                 12,
                 CreditData {
@@ -1070,7 +1170,10 @@ fn new_campaign_usdt_reward() {
         ));
         assert!(Credit::init_delegator_history(&1000, 1));
         run_to_block(BLOCKS_PER_ERA * 2);
-        assert_eq!(Credit::get_reward(&1000, 1, 1).0, Some(1643835616438356164));
+        assert_eq!(
+            Credit::get_reward(&1000, 1, 1).0,
+            Ok(Some(1643835616438356164))
+        );
         let new_credit_data = CreditData {
             campaign_id: 5,
             credit: 202,
@@ -1093,7 +1196,10 @@ fn new_campaign_usdt_reward() {
         );
 
         run_to_block(BLOCKS_PER_ERA * 3);
-        assert_eq!(Credit::get_reward(&1000, 2, 2).0, Some(4109589041095890410));
+        assert_eq!(
+            Credit::get_reward(&1000, 2, 2).0,
+            Ok(Some(4109589041095890410))
+        );
 
         assert_ok!(UserPrivileges::set_user_privilege(
             RuntimeOrigin::root(),
@@ -1106,7 +1212,25 @@ fn new_campaign_usdt_reward() {
         ));
         run_to_block(BLOCKS_PER_ERA * 4);
         // Even not staking, user also get reward based on virtual staking balance 25 USDT,
-        assert_eq!(Credit::get_reward(&1000, 3, 3).0, Some(821917808219178082));
+        assert_eq!(
+            Credit::get_reward(&1000, 3, 3).0,
+            Ok(Some(821917808219178082))
+        );
+
+        // the reward waits for a fresh price instead of being lost
+        run_to_block(BLOCKS_PER_ERA * 5);
+        Timestamp::set_timestamp(Timestamp::get() + (PriceStaleAfter::get() + 1) * 1000);
+        assert_eq!(Credit::get_reward(&1000, 4, 4).0, Err(RewardDeferred));
+        assert_ok!(Credit::set_dpr_price(
+            RuntimeOrigin::signed(1),
+            25_000_000_000_000_000,
+            H160::zero()
+        ));
+        Credit::on_finalize(BLOCKS_PER_ERA * 5);
+        assert_eq!(
+            Credit::get_reward(&1000, 4, 4).0,
+            Ok(Some(821917808219178082))
+        );
     });
 }
 
@@ -1161,6 +1285,256 @@ fn set_dpr_price_test() {
     });
 }
 
+#[test]
+fn dpr_price_median_quorum_and_staleness() {
+    new_test_ext().execute_with(|| {
+        run_to_block(1);
+        for worker in 1..=4 {
+            assert_ok!(UserPrivileges::set_user_privilege(
+                RuntimeOrigin::root(),
+                worker,
+                Privilege::OracleWorker
+            ));
+        }
+        PriceQuorum::set(&3);
+        assert_ok!(Credit::set_dpr_price(
+            RuntimeOrigin::signed(1),
+            100,
+            H160::zero()
+        ));
+        assert_ok!(Credit::set_dpr_price(
+            RuntimeOrigin::signed(2),
+            300,
+            H160::zero()
+        ));
+        run_to_block(2);
+        // no quorum yet
+        assert_eq!(Credit::dpr_price(), None);
+
+        assert_ok!(Credit::set_dpr_price(
+            RuntimeOrigin::signed(3),
+            120,
+            H160::zero()
+        ));
+        run_to_block(3);
+        assert_eq!(Credit::dpr_price(), Some(120));
+        assert_eq!(Credit::fresh_dpr_price(), Some(120));
+        System::assert_has_event(RuntimeEvent::from(crate::Event::DprPriceAggregated(120, 3)));
+
+        // a single outlier can't skew the median
+        assert_ok!(Credit::set_dpr_price(
+            RuntimeOrigin::signed(4),
+            10_000,
+            H160::zero()
+        ));
+        run_to_block(4);
+        assert_eq!(Credit::dpr_price(), Some(210));
+        assert_eq!(
+            Credit::dpr_price_history().into_inner(),
+            vec![(40, 120), (45, 210)]
+        );
+        assert_eq!(Credit::dpr_twap(10), Some(165));
+        assert_eq!(Credit::dpr_twap(5), Some(210));
+
+        // stale submissions are dropped and the stale price pauses usdt rewards
+        Timestamp::set_timestamp((50 + PriceStaleAfter::get() + 1) * 1000);
+        assert_eq!(Credit::fresh_dpr_price(), None);
+        assert_ok!(Credit::set_dpr_price(
+            RuntimeOrigin::signed(1),
+            110,
+            H160::zero()
+        ));
+        Credit::on_finalize(4);
+        assert_eq!(Credit::price_submission(2), None);
+        assert!(Credit::price_submission(1).is_some());
+        assert_eq!(Credit::dpr_price(), Some(210));
+        assert_eq!(Credit::fresh_dpr_price(), None);
+    });
+}
+
+#[test]
+fn dpr_price_aggregation_interval() {
+    PriceAggregationInterval::set(10);
+    new_test_ext().execute_with(|| {
+        run_to_block(1);
+        assert_ok!(UserPrivileges::set_user_privilege(
+            RuntimeOrigin::root(),
+            1,
+            Privilege::OracleWorker
+        ));
+        assert_ok!(Credit::set_dpr_price(
+            RuntimeOrigin::signed(1),
+            100,
+            H160::zero()
+        ));
+        run_to_block(2);
+        assert_eq!(Credit::dpr_price(), Some(100));
+
+        // 5 seconds later the submission waits for the interval to pass
+        assert_ok!(Credit::set_dpr_price(
+            RuntimeOrigin::signed(1),
+            200,
+            H160::zero()
+        ));
+        run_to_block(3);
+        assert_eq!(Credit::dpr_price(), Some(100));
+        run_to_block(4);
+        assert_eq!(Credit::dpr_price(), Some(200));
+        assert_eq!(
+            Credit::dpr_price_history().into_inner(),
+            vec![(35, 100), (45, 200)]
+        );
+    });
+}
+
+#[test]
+fn runtime_upgrade_kills_current_prices() {
+    new_test_ext().execute_with(|| {
+        let key = frame_support::storage::storage_prefix(b"Credit", b"CurrentPrices");
+        frame_support::storage::unhashed::put(&key, &vec![100u128, 120]);
+        Credit::on_runtime_upgrade();
+        assert!(!frame_support::storage::unhashed::exists(&key));
+    });
+}
+
+#[test]
+fn parse_price() {
+    assert_eq!(
+        price_feed::parse_price(br#"{"last":"0.0251"}"#, b"last"),
+        Some(25_100_000_000_000_000)
+    );
+    assert_eq!(
+        price_feed::parse_price(br#"{"data": {"price" : 2}}"#, b"price"),
+        Some(2_000_000_000_000_000_000)
+    );
+    // digits beyond 18 decimals are truncated
+    assert_eq!(
+        price_feed::parse_price(br#"{"p":0.0000000000000000019}"#, b"p"),
+        Some(1)
+    );
+    assert_eq!(price_feed::parse_price(br#"{"last":"-"}"#, b"last"), None);
+    assert_eq!(price_feed::parse_price(br#"{"last":"1"}"#, b"price"), None);
+}
+
+#[test]
+fn offchain_price_feed() {
+    let (offchain, offchain_state) = TestOffchainExt::new();
+    let (pool, pool_state) = TestTransactionPoolExt::new();
+    let mut ext = new_test_ext();
+    ext.register_extension(OffchainDbExt::new(offchain.clone()));
+    ext.register_extension(OffchainWorkerExt::new(offchain));
+    ext.register_extension(TransactionPoolExt::new(pool));
+    {
+        let mut state = offchain_state.write();
+        state.expect_request(PendingRequest {
+            method: "GET".into(),
+            uri: "http://localhost/ticker".into(),
+            response: Some(br#"{"symbol":"DPR_USDT","last":"0.0251"}"#.to_vec()),
+            sent: true,
+            ..Default::default()
+        });
+        state.expect_request(PendingRequest {
+            method: "GET".into(),
+            uri: "http://localhost/price".into(),
+            response: Some(br#"{"data":{"price":0.0249}}"#.to_vec()),
+            sent: true,
+            ..Default::default()
+        });
+    }
+
+    ext.execute_with(|| {
+        UintAuthorityId::set_all_keys(vec![1u64]);
+        assert_ok!(UserPrivileges::set_user_privilege(
+            RuntimeOrigin::root(),
+            1,
+            Privilege::CreditAdmin
+        ));
+        assert_ok!(Credit::set_price_feed_endpoints(
+            RuntimeOrigin::signed(1),
+            vec![
+                PriceFeedEndpoint {
+                    url: b"http://localhost/ticker".to_vec(),
+                    price_field: b"last".to_vec(),
+                },
+                PriceFeedEndpoint {
+                    url: b"http://localhost/price".to_vec(),
+                    price_field: b"price".to_vec(),
+                },
+            ]
+            .try_into()
+            .unwrap()
+        ));
+
+        // only fetched every PriceFeedInterval blocks
+        Credit::offchain_worker(9);
+        assert!(pool_state.read().transactions.is_empty());
+
+        Credit::offchain_worker(10);
+        let tx = pool_state.write().transactions.pop().unwrap();
+        assert!(pool_state.read().transactions.is_empty());
+        let tx = Extrinsic::decode(&mut &*tx).unwrap();
+        assert_eq!(tx.signature.unwrap().0, 0);
+        assert_eq!(
+            tx.call,
+            RuntimeCall::Credit(crate::Call::set_dpr_price {
+                price: 25_000_000_000_000_000,
+                _worker: H160::zero(),
+            })
+        );
+    });
+}
+
+#[test]
+fn set_price_feed_endpoints() {
+    new_test_ext().execute_with(|| {
+        run_to_block(1);
+        let endpoint = |url: &[u8]| PriceFeedEndpoint {
+            url: url.to_vec(),
+            price_field: b"last".to_vec(),
+        };
+        let endpoints = |endpoints: Vec<PriceFeedEndpoint>| -> BoundedVec<_, ConstU32<2>> {
+            endpoints.try_into().unwrap()
+        };
+
+        assert_noop!(
+            Credit::set_price_feed_endpoints(
+                RuntimeOrigin::signed(1),
+                endpoints(vec![endpoint(b"http://localhost/ticker")])
+            ),
+            Error::<Test>::NotAdmin
+        );
+        assert_ok!(UserPrivileges::set_user_privilege(
+            RuntimeOrigin::root(),
+            1,
+            Privilege::CreditAdmin
+        ));
+        assert_noop!(
+            Credit::set_price_feed_endpoints(
+                RuntimeOrigin::signed(1),
+                endpoints(vec![endpoint(&[b'u'; 65])])
+            ),
+            Error::<Test>::PriceFeedEndpointTooLong
+        );
+
+        let updated = endpoints(vec![
+            endpoint(b"http://localhost/ticker"),
+            endpoint(&[b'u'; 64]),
+        ]);
+        assert_ok!(Credit::set_price_feed_endpoints(
+            RuntimeOrigin::signed(1),
+            updated.clone()
+        ));
+        assert_eq!(Credit::price_feed_endpoints(), updated);
+        assert_eq!(
+            <frame_system::Pallet<Test>>::events()
+                .pop()
+                .expect("should contains events")
+                .event,
+            crate::tests::RuntimeEvent::from(crate::Event::PriceFeedEndpointsUpdated(2))
+        );
+    });
+}
+
 #[test]
 fn set_and_unset_maintain_device() {
     new_test_ext().execute_with(|| {
@@ -1179,3 +1553,799 @@ fn set_and_unset_maintain_device() {
         assert_eq!(MaintainDevices::<Test>::get(), vec![2]);
     });
 }
+
+fn scheduled_setting(credit_level: CreditLevel, base_apy: u8) -> CreditSetting<BalanceOf<Test>> {
+    CreditSetting {
+        campaign_id: 0,
+        credit_level,
+        staking_balance: 20_000,
+        base_apy: Percent::from_percent(base_apy),
+        bonus_apy: Percent::from_percent(0),
+        max_rank_with_bonus: 1u32,
+        tax_rate: Percent::from_percent(10),
+        max_referees_with_rewards: 1,
+        reward_per_referee: 18,
+    }
+}
+
+#[test]
+fn schedule_credit_setting() {
+    new_test_ext().execute_with(|| {
+        let setting = scheduled_setting(CreditLevel::One, 30);
+        assert_noop!(
+            Credit::schedule_credit_setting(RuntimeOrigin::signed(1), 2, setting.clone()),
+            BadOrigin
+        );
+        // MinSettingEnactmentDelay is 2 eras
+        assert_noop!(
+            Credit::schedule_credit_setting(RawOrigin::Root.into(), 1, setting.clone()),
+            Error::<Test>::EnactmentTooEarly
+        );
+        assert_ok!(Credit::schedule_credit_setting(
+            RawOrigin::Root.into(),
+            2,
+            setting.clone()
+        ));
+        assert_eq!(Credit::scheduled_credit_settings(2), vec![setting]);
+
+        // a second change for the same campaign and level in the era replaces the first one
+        let replaced = scheduled_setting(CreditLevel::One, 25);
+        assert_ok!(Credit::schedule_credit_setting(
+            RawOrigin::Root.into(),
+            2,
+            replaced.clone()
+        ));
+        let other_level = scheduled_setting(CreditLevel::Two, 35);
+        assert_ok!(Credit::schedule_credit_setting(
+            RawOrigin::Root.into(),
+            5,
+            other_level.clone()
+        ));
+        assert_eq!(Credit::scheduled_credit_settings(2), vec![replaced.clone()]);
+        assert_eq!(
+            Credit::all_scheduled_credit_settings(),
+            vec![(2, replaced), (5, other_level)]
+        );
+    });
+}
+
+#[test]
+fn schedule_credit_setting_bounded_per_era() {
+    new_test_ext().execute_with(|| {
+        let levels = [
+            CreditLevel::Zero,
+            CreditLevel::One,
+            CreditLevel::Two,
+            CreditLevel::Three,
+            CreditLevel::Four,
+            CreditLevel::Five,
+            CreditLevel::Six,
+            CreditLevel::Seven,
+            CreditLevel::Eight,
+        ];
+        // MaxScheduledSettingsPerEra is 10
+        for (campaign_id, level) in levels
+            .iter()
+            .map(|level| (0, *level))
+            .chain(Some((1, CreditLevel::Zero)))
+        {
+            let mut setting = scheduled_setting(level, 30);
+            setting.campaign_id = campaign_id;
+            assert_ok!(Credit::schedule_credit_setting(
+                RawOrigin::Root.into(),
+                3,
+                setting
+            ));
+        }
+        assert_eq!(Credit::scheduled_credit_settings(3).len(), 10);
+        let mut setting = scheduled_setting(CreditLevel::Eight, 30);
+        setting.campaign_id = 1;
+        assert_noop!(
+            Credit::schedule_credit_setting(RawOrigin::Root.into(), 3, setting),
+            Error::<Test>::TooManyScheduledSettings
+        );
+    });
+}
+
+#[test]
+fn cancel_credit_setting() {
+    new_test_ext().execute_with(|| {
+        let setting = scheduled_setting(CreditLevel::One, 30);
+        assert_ok!(Credit::schedule_credit_setting(
+            RawOrigin::Root.into(),
+            2,
+            setting.clone()
+        ));
+        assert_noop!(
+            Credit::cancel_credit_setting(RuntimeOrigin::signed(1), 2, 0, CreditLevel::One),
+            BadOrigin
+        );
+        assert_noop!(
+            Credit::cancel_credit_setting(RawOrigin::Root.into(), 2, 0, CreditLevel::Two),
+            Error::<Test>::ScheduledSettingNotFound
+        );
+        assert_noop!(
+            Credit::cancel_credit_setting(RawOrigin::Root.into(), 3, 0, CreditLevel::One),
+            Error::<Test>::ScheduledSettingNotFound
+        );
+        assert_ok!(Credit::cancel_credit_setting(
+            RawOrigin::Root.into(),
+            2,
+            0,
+            CreditLevel::One
+        ));
+        assert!(!ScheduledCreditSettings::<Test>::contains_key(2));
+
+        // a cancelled change is never enacted
+        let before = Credit::credit_settings(0, CreditLevel::One);
+        run_to_block(3 * BLOCKS_PER_ERA);
+        assert_eq!(Credit::credit_settings(0, CreditLevel::One), before);
+    });
+}
+
+#[test]
+fn scheduled_credit_setting_enacted_at_era() {
+    new_test_ext().execute_with(|| {
+        let setting = scheduled_setting(CreditLevel::One, 30);
+        assert_ok!(Credit::schedule_credit_setting(
+            RawOrigin::Root.into(),
+            2,
+            setting.clone()
+        ));
+        run_to_block(2 * BLOCKS_PER_ERA - 1);
+        assert_ne!(Credit::credit_settings(0, CreditLevel::One), setting);
+        run_to_block(2 * BLOCKS_PER_ERA);
+        assert_eq!(Credit::credit_settings(0, CreditLevel::One), setting);
+        assert!(!ScheduledCreditSettings::<Test>::contains_key(2));
+        // enacted changes can no longer be cancelled
+        assert_noop!(
+            Credit::cancel_credit_setting(RawOrigin::Root.into(), 2, 0, CreditLevel::One),
+            Error::<Test>::ScheduledSettingNotFound
+        );
+    });
+}
+
+#[test]
+fn migrate_genesis_apy_schedule() {
+    new_test_ext().execute_with(|| {
+        use frame_support::storage::migration::{get_storage_value, put_storage_value};
+
+        StorageVersion::<Test>::put(Releases::V3_0_0);
+        put_storage_value(b"Credit", b"GenesisChangeRewardEra", &[], 1u32);
+        // the first reduction at era 181 has already happened
+        run_to_block(200 * BLOCKS_PER_ERA);
+        Credit::on_runtime_upgrade();
+
+        assert_eq!(StorageVersion::<Test>::get(), Some(Releases::V5_0_0));
+        assert_eq!(RelevelCursor::<Test>::get(), Some(Vec::new()));
+        assert_eq!(
+            get_storage_value::<u32>(b"Credit", b"GenesisChangeRewardEra", &[]),
+            None
+        );
+        let scheduled_eras: Vec<_> = Credit::all_scheduled_credit_settings()
+            .into_iter()
+            .map(|(era, _)| era)
+            .collect();
+        assert!(!scheduled_eras.contains(&181));
+        assert_eq!(
+            Credit::scheduled_credit_settings(361),
+            crate::credit_setting::sub_genesis_apy::<Test>(2)
+        );
+        assert_eq!(
+            Credit::scheduled_credit_settings(901),
+            crate::credit_setting::sub_genesis_apy::<Test>(5)
+        );
+        assert!(!ScheduledCreditSettings::<Test>::contains_key(1081));
+    });
+}
+
+#[test]
+fn set_credit_level_thresholds() {
+    new_test_ext().execute_with(|| {
+        let thresholds = [100, 150, 200, 250, 300, 350, 400, 450];
+        assert_noop!(
+            Credit::set_credit_level_thresholds(RuntimeOrigin::signed(1), thresholds),
+            BadOrigin
+        );
+        assert_noop!(
+            Credit::set_credit_level_thresholds(
+                RawOrigin::Root.into(),
+                [0, 150, 200, 250, 300, 350, 400, 450]
+            ),
+            Error::<Test>::InvalidCreditLevelThresholds
+        );
+        assert_noop!(
+            Credit::set_credit_level_thresholds(
+                RawOrigin::Root.into(),
+                [100, 150, 150, 250, 300, 350, 400, 450]
+            ),
+            Error::<Test>::InvalidCreditLevelThresholds
+        );
+
+        assert_eq!(Credit::get_credit_level(250), CreditLevel::Two);
+        assert_ok!(Credit::set_credit_level_thresholds(
+            RawOrigin::Root.into(),
+            thresholds
+        ));
+        assert_eq!(Credit::credit_level_thresholds(), thresholds);
+        assert_eq!(Credit::get_credit_level(250), CreditLevel::Four);
+        assert_eq!(Credit::get_credit_level(1000), CreditLevel::Eight);
+        assert_eq!(
+            Credit::credit_level_gap(CreditLevel::Three, CreditLevel::One),
+            100
+        );
+
+        // slashing levels with the new thresholds
+        Credit::slash_credit(&7, Some(60));
+        let credit_data = Credit::user_credit(&7).unwrap();
+        assert_eq!(credit_data.credit, 340);
+        assert_eq!(credit_data.current_credit_level, CreditLevel::Five);
+    });
+}
+
+#[test]
+fn relevel_credit_data() {
+    new_test_ext().execute_with(|| {
+        CreditBadgeCollection::set(&Some(1000));
+        StorageVersion::<Test>::put(Releases::V6_0_0);
+        Credit::on_runtime_upgrade();
+        assert_ok!(Credit::set_credit_level_thresholds(
+            RawOrigin::Root.into(),
+            [100, 150, 200, 250, 300, 350, 400, 450]
+        ));
+        // stored levels are stale until re-levelled
+        assert_eq!(
+            Credit::user_credit(&11).unwrap().current_credit_level,
+            CreditLevel::Two
+        );
+        // but read with the current thresholds
+        assert_eq!(
+            Credit::user_credit_relevelled(&11)
+                .unwrap()
+                .current_credit_level,
+            CreditLevel::Three
+        );
+        assert_eq!(
+            Credit::user_credit_relevelled(&11).unwrap().credit,
+            Credit::user_credit(&11).unwrap().credit
+        );
+
+        let mut batches = 0;
+        while RelevelCursor::<Test>::get().is_some() {
+            Credit::relevel_credit_data(2);
+            batches += 1;
+        }
+        assert!(batches > 1);
+        for (_, credit_data) in UserCredit::<Test>::iter() {
+            assert_eq!(
+                credit_data.current_credit_level,
+                Credit::credit_level_of(credit_data.credit)
+            );
+        }
+        assert_eq!(
+            Credit::user_credit(&11).unwrap().current_credit_level,
+            CreditLevel::Three
+        );
+        assert_eq!(
+            Credit::user_credit(&7).unwrap().current_credit_level,
+            CreditLevel::Seven
+        );
+        // badges follow the new levels
+        assert_eq!(
+            Credit::credit_badge(&11).map(|(_, level)| level),
+            Some(CreditLevel::Three)
+        );
+        assert_eq!(
+            Credit::credit_badge(&7).map(|(_, level)| level),
+            Some(CreditLevel::Seven)
+        );
+    });
+}
+
+// credit 400 and 300 in turns every two eras from era 0 to era 58
+fn long_credit_history() -> Vec<(u32, CreditData)> {
+    (0..30u32)
+        .map(|i| {
+            let (credit, level) = if i % 2 == 0 {
+                (400, CreditLevel::Four)
+            } else {
+                (300, CreditLevel::Three)
+            };
+            (
+                2 * i,
+                CreditData {
+                    campaign_id: 0,
+                    credit,
+                    initial_credit_level: CreditLevel::Four,
+                    rank_in_initial_credit_level: 80u32,
+                    number_of_referees: 7,
+                    current_credit_level: level,
+                    reward_eras: 270,
+                },
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn migrate_credit_history() {
+    new_test_ext().execute_with(|| {
+        let history = long_credit_history();
+        StorageVersion::<Test>::put(Releases::V5_0_0);
+        UserCreditHistory::<Test>::insert(7, history.clone());
+        UserCreditHistory::<Test>::insert(3, history[..2].to_vec());
+        // compacted on the fly before being migrated
+        assert_eq!(Credit::user_credit_history(&7), history[10..].to_vec());
+
+        Credit::migrate_credit_history(1);
+        assert_eq!(StorageVersion::<Test>::get(), Some(Releases::V5_0_0));
+        Credit::migrate_credit_history(1);
+        Credit::migrate_credit_history(1);
+        assert_eq!(StorageVersion::<Test>::get(), Some(Releases::V7_0_0));
+        assert_eq!(UserCreditHistory::<Test>::iter().count(), 0);
+
+        assert_eq!(
+            CreditHistory::<Test>::get(3).unwrap(),
+            history[..2].to_vec()
+        );
+        assert!(!CreditHistorySummaries::<Test>::contains_key(3));
+        assert_eq!(
+            CreditHistory::<Test>::get(7).unwrap(),
+            history[10..].to_vec()
+        );
+        let summary = Credit::credit_history_summary(7).unwrap();
+        assert_eq!(summary.first_era, 0);
+        assert_eq!(summary.max_credit, 400);
+        assert_eq!(
+            summary.last_change_per_level,
+            vec![(CreditLevel::Four, 16), (CreditLevel::Three, 18)]
+        );
+        assert_eq!(summary.last_evicted, history[9].clone());
+
+        // appending keeps MaxCreditHistory entries
+        run_to_block(60 * BLOCKS_PER_ERA);
+        Credit::update_credit_history(&7, 60);
+        assert_eq!(Credit::user_credit_history(&7).len(), 20);
+        assert_eq!(Credit::user_credit_history(&7)[0], history[11]);
+        let summary = Credit::credit_history_summary(7).unwrap();
+        assert_eq!(summary.first_era, 0);
+        assert_eq!(summary.last_evicted, history[10].clone());
+        assert_eq!(
+            summary.last_change_per_level,
+            vec![(CreditLevel::Four, 20), (CreditLevel::Three, 18)]
+        );
+    });
+}
+
+#[test]
+fn get_reward_with_compacted_credit_history() {
+    let reward = |max_credit_history: u32| {
+        MaxCreditHistory::set(&max_credit_history);
+        new_test_ext().execute_with(|| {
+            // compacted when loaded if longer than MaxCreditHistory
+            UserCreditHistory::<Test>::insert(7, long_credit_history());
+            run_to_block(60 * BLOCKS_PER_ERA);
+            // era 19 is covered by the latest compacted entry at era 18
+            Credit::get_reward(&7, 19, 25).0
+        })
+    };
+    let uncompacted = reward(30);
+    assert!(matches!(uncompacted, Ok(Some(_))));
+    assert_eq!(reward(20), uncompacted);
+}
+
+#[test]
+fn compaction_keeps_unrewarded_credit_history() {
+    let reward = |compact: bool| {
+        new_test_ext().execute_with(|| {
+            let history = long_credit_history();
+            CreditHistory::<Test>::insert(7, history.clone());
+            UnrewardedSince::<Test>::insert(7, 5);
+            run_to_block(60 * BLOCKS_PER_ERA);
+            if compact {
+                // the entry at era 4 is in effect at era 5, the later ones are kept
+                Credit::update_credit_history(&7, 60);
+                assert_eq!(Credit::user_credit_history(&7)[0], history[3]);
+                assert_eq!(
+                    Credit::credit_history_summary(7).unwrap().last_evicted,
+                    history[2].clone()
+                );
+            }
+            let reward = Credit::get_reward(&7, 5, 30).0;
+            if compact {
+                // compacted to MaxCreditHistory entries once rewarded
+                let _ = Credit::get_reward(&7, 31, 59);
+                Credit::update_credit_history(&7, 61);
+                assert_eq!(Credit::user_credit_history(&7).len(), 20);
+            }
+            reward
+        })
+    };
+    let uncompacted = reward(false);
+    assert!(matches!(uncompacted, Ok(Some(_))));
+    assert_eq!(reward(true), uncompacted);
+}
+
+#[test]
+fn migrate_device() {
+    new_test_ext().execute_with(|| {
+        let history = long_credit_history();
+        UserCreditHistory::<Test>::insert(3, history.clone());
+        crate::CreditFromBurnNft::<Test>::insert(3, 5);
+        MaintainDevices::<Test>::put(vec![2, 3]);
+        assert!(!<Credit as DeviceMigration<_>>::can_migrate_device(&3, &1));
+        assert!(<Credit as DeviceMigration<_>>::can_migrate_device(&3, &50));
+
+        let credit_data = Credit::user_credit(3).unwrap();
+        <Credit as DeviceMigration<_>>::migrate_device(&3, &50);
+        assert_eq!(Credit::user_credit(3), None);
+        assert_eq!(Credit::user_credit(50), Some(credit_data));
+        assert!(Credit::user_credit_history(&3).is_empty());
+        assert!(!UserCreditHistory::<Test>::contains_key(3));
+        assert_eq!(Credit::user_credit_history(&50), history[10..].to_vec());
+        assert_eq!(Credit::credit_history_summary(50).unwrap().max_credit, 400);
+        assert_eq!(crate::CreditFromBurnNft::<Test>::get(3), 0);
+        assert_eq!(crate::CreditFromBurnNft::<Test>::get(50), 5);
+        assert_eq!(MaintainDevices::<Test>::get(), vec![2, 50]);
+    });
+}
+
+#[test]
+fn campaign_budget_and_end_era() {
+    new_test_ext().execute_with(|| {
+        let reward_per_era = 223068450647875213020;
+        let campaign = Campaign {
+            name: b"genesis".to_vec(),
+            start_era: 0,
+            end_era: Some(1),
+            budget: Some(reward_per_era + 1_000),
+            staking_type: StakingType::Dpr,
+            min_credit: 0,
+            successor: None,
+        };
+        assert_noop!(
+            Credit::set_campaign(RuntimeOrigin::signed(1), 0, campaign.clone()),
+            BadOrigin
+        );
+        assert_noop!(
+            Credit::set_campaign(
+                RawOrigin::Root.into(),
+                0,
+                Campaign {
+                    start_era: 2,
+                    ..campaign.clone()
+                }
+            ),
+            Error::<Test>::InvalidCampaign
+        );
+        assert_ok!(Credit::set_campaign(
+            RawOrigin::Root.into(),
+            0,
+            campaign.clone()
+        ));
+        assert_eq!(Credit::campaign(0), Some(campaign));
+        assert_eq!(
+            Credit::campaign_remaining_budget(0),
+            Some(reward_per_era + 1_000)
+        );
+
+        assert!(Credit::init_delegator_history(&7, 0));
+        run_to_block(BLOCKS_PER_ERA);
+        assert_eq!(Credit::get_reward(&7, 0, 0).0, Ok(Some(reward_per_era)));
+        // charged once paid
+        assert_eq!(
+            Credit::campaign_remaining_budget(0),
+            Some(reward_per_era + 1_000)
+        );
+        Credit::reward_paid(&7, reward_per_era);
+        assert_eq!(Credit::campaign_remaining_budget(0), Some(1_000));
+        assert_eq!(Credit::unpaid_campaign_rewards(&7), None);
+
+        // capped by the remaining budget
+        run_to_block(BLOCKS_PER_ERA * 2);
+        assert_eq!(Credit::get_reward(&7, 1, 1).0, Ok(Some(1_000)));
+        System::assert_has_event(RuntimeEvent::from(crate::Event::CampaignBudgetExhausted(0)));
+        // only the paid part is charged, e.g. with the mining reward pool running low
+        Credit::reward_paid(&7, 400);
+        assert_eq!(Credit::campaign_remaining_budget(0), Some(600));
+        assert_eq!(Credit::campaign_spent(0), reward_per_era + 400);
+
+        // the campaign ended at era 1
+        run_to_block(BLOCKS_PER_ERA * 3);
+        assert_eq!(Credit::get_reward(&7, 2, 2).0, Ok(None));
+    });
+}
+
+#[test]
+fn campaign_successor_and_min_credit() {
+    new_test_ext().execute_with(|| {
+        let campaign = |successor, min_credit| Campaign {
+            name: Vec::new(),
+            start_era: 0,
+            end_era: None,
+            budget: None,
+            staking_type: StakingType::Dpr,
+            min_credit,
+            successor,
+        };
+        assert_ok!(Credit::set_campaign(
+            RawOrigin::Root.into(),
+            0,
+            campaign(Some(4), 0)
+        ));
+        assert_ok!(Credit::set_campaign(
+            RawOrigin::Root.into(),
+            4,
+            campaign(None, 500)
+        ));
+        assert!(Credit::init_delegator_history(&7, 0));
+        run_to_block(BLOCKS_PER_ERA);
+
+        // not eligible for the successor with credit 400
+        Credit::get_reward(&7, 0, 0);
+        assert_eq!(Credit::user_credit(7).unwrap().campaign_id, 0);
+
+        assert_ok!(Credit::set_campaign(
+            RawOrigin::Root.into(),
+            4,
+            campaign(None, 400)
+        ));
+        Credit::get_reward(&7, 0, 0);
+        let credit_data = Credit::user_credit(7).unwrap();
+        assert_eq!(credit_data.campaign_id, 4);
+        assert_eq!(credit_data.reward_eras, DEFAULT_REWARD_ERAS);
+
+        // eras below the minimum credit of the campaign are not rewarded
+        assert_ok!(Credit::set_campaign(
+            RawOrigin::Root.into(),
+            4,
+            campaign(None, 500)
+        ));
+        run_to_block(BLOCKS_PER_ERA * 2);
+        assert_eq!(Credit::get_reward(&7, 1, 1).0, Ok(Some(0)));
+    });
+}
+
+#[test]
+fn credit_badge() {
+    new_test_ext().execute_with(|| {
+        CreditBadgeCollection::set(&Some(1000));
+        StorageVersion::<Test>::put(Releases::V6_0_0);
+        Credit::on_runtime_upgrade();
+        run_to_block(1);
+
+        // minted once the credit level is above zero
+        assert_ok!(Credit::update_credit_by_burn_nft(1, 100));
+        assert_eq!(Credit::credit_badge(1), Some((0, CreditLevel::One)));
+        assert_eq!(Uniques::owner(1000, 0), Some(1));
+        assert_eq!(
+            Uniques::collection_owner(1000),
+            Some(Credit::credit_badge_account())
+        );
+        System::assert_has_event(RuntimeEvent::from(crate::Event::CreditBadgeUpdated(
+            1,
+            0,
+            CreditLevel::One,
+        )));
+
+        // soulbound
+        assert_noop!(
+            Uniques::transfer(RuntimeOrigin::signed(1), 1000, 0, 2),
+            pallet_uniques::Error::<Test>::Locked
+        );
+
+        // upgraded with the credit level
+        assert_ok!(Credit::update_credit_by_burn_nft(1, 100));
+        assert_eq!(Credit::credit_badge(1), Some((0, CreditLevel::Two)));
+        System::assert_has_event(RuntimeEvent::from(crate::Event::CreditBadgeUpdated(
+            1,
+            0,
+            CreditLevel::Two,
+        )));
+
+        // burned at credit level zero
+        Credit::slash_credit(&1, Some(200));
+        assert_eq!(Credit::credit_badge(1), None);
+        assert_eq!(Uniques::owner(1000, 0), None);
+        System::assert_has_event(RuntimeEvent::from(crate::Event::CreditBadgeBurned(1, 0)));
+
+        // a new item is minted for the next badge
+        assert_ok!(Credit::update_credit_by_burn_nft(1, 300));
+        assert_eq!(Credit::credit_badge(1), Some((1, CreditLevel::Three)));
+        assert_eq!(Uniques::owner(1000, 1), Some(1));
+
+        // credit added by other pallets
+        Credit::add_or_update_credit(1, 100, None);
+        assert_eq!(Credit::credit_badge(1), Some((1, CreditLevel::Four)));
+    });
+}
+
+#[test]
+fn migrate_credit_badges() {
+    new_test_ext().execute_with(|| {
+        CreditBadgeCollection::set(&Some(1000));
+        StorageVersion::<Test>::put(Releases::V6_0_0);
+        Credit::on_runtime_upgrade();
+        assert_eq!(StorageVersion::<Test>::get(), Some(Releases::V7_0_0));
+        assert_eq!(CreditBadgeCursor::<Test>::get(), Some(Vec::new()));
+        assert_eq!(Credit::credit_badge(&7), None);
+
+        let mut batches = 0;
+        while CreditBadgeCursor::<Test>::get().is_some() {
+            Credit::migrate_credit_badges(2);
+            batches += 1;
+        }
+        assert!(batches > 1);
+        for (account_id, credit_data) in UserCredit::<Test>::iter() {
+            assert_eq!(
+                Credit::credit_badge(&account_id).map(|(_, level)| level),
+                Some(credit_data.current_credit_level).filter(|level| *level != CreditLevel::Zero)
+            );
+        }
+        assert!(Credit::credit_badge(&7).is_some());
+
+        // migrated once
+        Credit::on_runtime_upgrade();
+        assert_eq!(CreditBadgeCursor::<Test>::get(), None);
+    });
+}
+
+#[test]
+fn credit_badge_collection_taken() {
+    new_test_ext().execute_with(|| {
+        CreditBadgeCollection::set(&Some(1000));
+        assert_ok!(Uniques::force_create(RawOrigin::Root.into(), 1000, 2, true));
+        run_to_block(1);
+        StorageVersion::<Test>::put(Releases::V6_0_0);
+        Credit::on_runtime_upgrade();
+        assert_eq!(Uniques::collection_owner(1000), Some(2));
+        System::assert_has_event(RuntimeEvent::from(
+            crate::Event::CreditBadgeCollectionTaken(1000),
+        ));
+        assert_eq!(StorageVersion::<Test>::get(), Some(Releases::V7_0_0));
+        assert_eq!(CreditBadgeCursor::<Test>::get(), None);
+
+        // no badge is minted into a collection the pallet does not own
+        assert_ok!(Credit::update_credit_by_burn_nft(1, 100));
+        assert_eq!(Credit::credit_badge(1), None);
+        assert_eq!(Uniques::owner(1000, 0), None);
+        // and the items of the collection are not locked
+        assert_ok!(Uniques::mint(RuntimeOrigin::signed(2), 1000, 5, 2));
+        assert_ok!(Uniques::transfer(RuntimeOrigin::signed(2), 1000, 5, 3));
+    });
+}
+
+#[test]
+fn credit_badge_metadata() {
+    let credit_data = CreditData {
+        campaign_id: 4,
+        credit: 300,
+        initial_credit_level: CreditLevel::One,
+        rank_in_initial_credit_level: 1u32,
+        number_of_referees: 1,
+        reward_eras: 1,
+        current_credit_level: CreditLevel::Three,
+    };
+    assert_eq!(
+        crate::credit_badge::credit_badge_metadata(&credit_data, 12),
+        b"{\"level\":3,\"campaign\":4,\"era\":12}".to_vec()
+    );
+}
+
+#[test]
+fn credit_breakdown_and_recent_changes() {
+    new_test_ext().execute_with(|| {
+        run_to_block(1);
+        assert_ok!(Credit::update_credit_by_burn_nft(1, 8));
+        assert_ok!(DeeperNode::im_online(RuntimeOrigin::signed(1)));
+        Credit::update_credit_by_tip(1, 5);
+        run_to_block(2);
+        Credit::update_credit_by_tip(1, 2);
+
+        assert_ok!(UserPrivileges::set_user_privilege(
+            RuntimeOrigin::root(),
+            2,
+            Privilege::CreditAdmin
+        ));
+        assert_ok!(Credit::set_user_staking_credit(
+            RuntimeOrigin::signed(2),
+            vec!((1, 4))
+        ));
+        assert_ok!(Credit::unstaking_slash_credit(RuntimeOrigin::signed(2), 1));
+        assert_eq!(Credit::get_credit_score(&1), Some(11));
+
+        assert_eq!(
+            Credit::credit_breakdown(&1),
+            vec![
+                (
+                    CreditSource::Tip,
+                    CreditCounter {
+                        added: 7,
+                        removed: 0
+                    }
+                ),
+                (
+                    CreditSource::BurnNft,
+                    CreditCounter {
+                        added: 8,
+                        removed: 0
+                    }
+                ),
+                (
+                    CreditSource::UnstakingSlash,
+                    CreditCounter {
+                        added: 0,
+                        removed: 4
+                    }
+                ),
+            ]
+        );
+
+        // only the latest MaxCreditChanges changes are kept
+        let change = |block_number, source, old_credit, new_credit| CreditChange {
+            block_number,
+            source,
+            old_credit,
+            new_credit,
+        };
+        assert_eq!(
+            Credit::recent_credit_changes(&1, 2),
+            vec![
+                change(2, CreditSource::UnstakingSlash, 15, 11),
+                change(2, CreditSource::Tip, 13, 15),
+            ]
+        );
+        assert_eq!(
+            Credit::recent_credit_changes(&1, 10),
+            vec![
+                change(2, CreditSource::UnstakingSlash, 15, 11),
+                change(2, CreditSource::Tip, 13, 15),
+                change(1, CreditSource::Tip, 8, 13),
+            ]
+        );
+    });
+}
+
+#[test]
+fn evm_update_credit() {
+    new_test_ext().execute_with(|| {
+        run_to_block(1);
+        let operator = H160::from_low_u64_be(0x0a);
+        let evm_user = H160::from_low_u64_be(0x0b);
+        let update = |score, add_flag| {
+            Credit::evm_update_credit(&operator, &evm_user, score, add_flag)
+                .map_err(DispatchError::from)
+        };
+        assert_noop!(update(10, true), Error::<Test>::NotAdmin);
+
+        assert_ok!(UserPrivileges::set_user_privilege(
+            RuntimeOrigin::root(),
+            2,
+            Privilege::EvmAddressSetter
+        ));
+        assert_ok!(UserPrivileges::set_evm_privilege(
+            RuntimeOrigin::signed(2),
+            operator,
+            Privilege::EvmCreditOperation
+        ));
+        assert_noop!(update(10, true), Error::<Test>::AccountNotFound);
+
+        pallet_deeper_node::RewardsAccountsEVMtoDeeper::<Test>::insert(evm_user, 100);
+        assert_noop!(update(10, true), Error::<Test>::AccountNoExistInUserCredit);
+
+        pallet_deeper_node::RewardsAccountsEVMtoDeeper::<Test>::insert(evm_user, 3);
+        assert_eq!(update(10, true), Ok((100, 110)));
+        assert_eq!(update(30, false), Ok((110, 80)));
+        assert_eq!(Credit::get_credit_score(&3), Some(80));
+        assert_eq!(
+            Credit::credit_breakdown(&3),
+            vec![(
+                CreditSource::Evm,
+                CreditCounter {
+                    added: 10,
+                    removed: 30
+                }
+            )]
+        );
+    });
+}
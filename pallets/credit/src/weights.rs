@@ -57,6 +57,11 @@ pub trait WeightInfo {
     fn set_switch_campaign() -> Weight;
     fn set_not_switch_accounts() -> Weight;
     fn set_dpr_price() -> Weight;
+    fn schedule_credit_setting() -> Weight;
+    fn cancel_credit_setting() -> Weight;
+    fn set_credit_level_thresholds() -> Weight;
+    fn set_campaign() -> Weight;
+    fn set_price_feed_endpoints() -> Weight;
 }
 
 /// Weights for pallet_credit using the Substrate node and recommended hardware.
@@ -106,8 +111,30 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
             .saturating_add(T::DbWeight::get().writes(2 as u64))
     }
     fn set_dpr_price() -> Weight {
-        Weight::from_ref_time(16_112_000 as u64)
+        Weight::from_ref_time(17_384_000 as u64)
             .saturating_add(T::DbWeight::get().reads(3 as u64))
+            .saturating_add(T::DbWeight::get().writes(2 as u64))
+    }
+    fn schedule_credit_setting() -> Weight {
+        Weight::from_ref_time(15_240_000 as u64)
+            .saturating_add(T::DbWeight::get().reads(1 as u64))
+            .saturating_add(T::DbWeight::get().writes(1 as u64))
+    }
+    fn cancel_credit_setting() -> Weight {
+        Weight::from_ref_time(14_516_000 as u64)
+            .saturating_add(T::DbWeight::get().reads(1 as u64))
+            .saturating_add(T::DbWeight::get().writes(1 as u64))
+    }
+    fn set_credit_level_thresholds() -> Weight {
+        Weight::from_ref_time(11_932_000 as u64).saturating_add(T::DbWeight::get().writes(2 as u64))
+    }
+    fn set_campaign() -> Weight {
+        Weight::from_ref_time(10_418_000 as u64).saturating_add(T::DbWeight::get().writes(1 as u64))
+    }
+    // not measured yet, regenerate with `--extrinsic=set_price_feed_endpoints`
+    fn set_price_feed_endpoints() -> Weight {
+        Weight::from_ref_time(24_000_000 as u64)
+            .saturating_add(T::DbWeight::get().reads(1 as u64))
             .saturating_add(T::DbWeight::get().writes(1 as u64))
     }
 }
@@ -159,8 +186,32 @@ impl WeightInfo for () {
             .saturating_add(RocksDbWeight::get().writes(2 as u64))
     }
     fn set_dpr_price() -> Weight {
-        Weight::from_ref_time(16_112_000 as u64)
+        Weight::from_ref_time(17_384_000 as u64)
             .saturating_add(RocksDbWeight::get().reads(3 as u64))
+            .saturating_add(RocksDbWeight::get().writes(2 as u64))
+    }
+    fn schedule_credit_setting() -> Weight {
+        Weight::from_ref_time(15_240_000 as u64)
+            .saturating_add(RocksDbWeight::get().reads(1 as u64))
+            .saturating_add(RocksDbWeight::get().writes(1 as u64))
+    }
+    fn cancel_credit_setting() -> Weight {
+        Weight::from_ref_time(14_516_000 as u64)
+            .saturating_add(RocksDbWeight::get().reads(1 as u64))
+            .saturating_add(RocksDbWeight::get().writes(1 as u64))
+    }
+    fn set_credit_level_thresholds() -> Weight {
+        Weight::from_ref_time(11_932_000 as u64)
+            .saturating_add(RocksDbWeight::get().writes(2 as u64))
+    }
+    fn set_campaign() -> Weight {
+        Weight::from_ref_time(10_418_000 as u64)
+            .saturating_add(RocksDbWeight::get().writes(1 as u64))
+    }
+    // not measured yet, regenerate with `--extrinsic=set_price_feed_endpoints`
+    fn set_price_feed_endpoints() -> Weight {
+        Weight::from_ref_time(24_000_000 as u64)
+            .saturating_add(RocksDbWeight::get().reads(1 as u64))
             .saturating_add(RocksDbWeight::get().writes(1 as u64))
     }
 }
@@ -0,0 +1,47 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runtime API definition for the deeper node pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use node_primitives::deeper_node::ServerFilter;
+use sp_std::prelude::*;
+
+sp_api::decl_runtime_apis! {
+    pub trait DeeperNodeApi<AccountId> where
+        AccountId: Codec,
+    {
+        /// active servers of a country matching `filter` shuffled by `seed`: at most `limit`
+        /// servers starting at `offset`, and the number of matching servers in the country
+        fn discover_country_servers(
+            country: Vec<u8>,
+            filter: ServerFilter,
+            seed: u64,
+            offset: u32,
+            limit: u32,
+        ) -> (Vec<AccountId>, u32);
+
+        /// active servers of a level 2 or level 3 region, paged like `discover_country_servers`
+        fn discover_region_servers(
+            region: Vec<u8>,
+            filter: ServerFilter,
+            seed: u64,
+            offset: u32,
+            limit: u32,
+        ) -> (Vec<AccountId>, u32);
+    }
+}
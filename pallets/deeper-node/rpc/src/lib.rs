@@ -0,0 +1,188 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! RPC interface for the deeper node pallet.
+//!
+//! Servers are discovered by country code (e.g. `US`) or by level 3 (e.g. `AMN`) or
+//! level 2 (e.g. `AMER`) region code. Expired and offline servers are left out, and the
+//! rest are shuffled by a client provided seed, so that clients spread over the servers
+//! and can page through the same order by reusing the seed. An optional [`ServerFilter`]
+//! narrows the servers down by their advertised capabilities, e.g.
+//! `{"protocols": 1, "min_bandwidth": 100, "max_price_per_gb": 10}`.
+
+use std::sync::Arc;
+
+use codec::Codec;
+use jsonrpsee::{
+    core::{async_trait, Error as JsonRpseeError, RpcResult},
+    proc_macros::rpc,
+    types::error::{CallError, ErrorObject},
+};
+use node_primitives::deeper_node::ServerFilter;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use pallet_deeper_node_rpc_runtime_api::DeeperNodeApi as DeeperNodeRuntimeApi;
+
+/// Maximum number of servers returned in one page.
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// A page of discovered servers.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerPage<AccountId> {
+    /// servers of this page
+    pub servers: Vec<AccountId>,
+    /// number of active servers in the country or region matching the filter
+    pub total: u32,
+}
+
+#[rpc(server)]
+pub trait DeeperNodeApi<BlockHash, AccountId> {
+    /// Active servers of a country, at most `limit` starting at `offset`.
+    #[method(name = "deeperNode_discoverCountryServers")]
+    fn discover_country_servers(
+        &self,
+        country: String,
+        seed: u64,
+        offset: u32,
+        limit: u32,
+        filter: Option<ServerFilter>,
+        at: Option<BlockHash>,
+    ) -> RpcResult<ServerPage<AccountId>>;
+
+    /// Active servers of a level 2 or level 3 region, at most `limit` starting at `offset`.
+    #[method(name = "deeperNode_discoverRegionServers")]
+    fn discover_region_servers(
+        &self,
+        region: String,
+        seed: u64,
+        offset: u32,
+        limit: u32,
+        filter: Option<ServerFilter>,
+        at: Option<BlockHash>,
+    ) -> RpcResult<ServerPage<AccountId>>;
+}
+
+/// Provides RPC methods to discover servers.
+pub struct DeeperNode<C, Block> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<Block>,
+}
+
+impl<C, Block> DeeperNode<C, Block> {
+    /// Create new `DeeperNode` with the given reference to the client.
+    pub fn new(client: Arc<C>) -> Self {
+        Self {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+    /// The call to runtime failed.
+    RuntimeError,
+}
+
+impl From<Error> for i32 {
+    fn from(e: Error) -> i32 {
+        match e {
+            Error::RuntimeError => 1,
+        }
+    }
+}
+
+fn runtime_error(message: &str, e: impl std::fmt::Debug) -> JsonRpseeError {
+    CallError::Custom(ErrorObject::owned(
+        Error::RuntimeError.into(),
+        message,
+        Some(format!("{:?}", e)),
+    ))
+    .into()
+}
+
+impl<C, Block> DeeperNode<C, Block>
+where
+    Block: BlockT,
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+{
+    fn block_id(&self, at: Option<<Block as BlockT>::Hash>) -> BlockId<Block> {
+        BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash))
+    }
+}
+
+#[async_trait]
+impl<C, Block, AccountId> DeeperNodeApiServer<<Block as BlockT>::Hash, AccountId>
+    for DeeperNode<C, Block>
+where
+    Block: BlockT,
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+    C::Api: DeeperNodeRuntimeApi<Block, AccountId>,
+    AccountId: Codec + Clone + Send + Sync + 'static + Serialize,
+{
+    fn discover_country_servers(
+        &self,
+        country: String,
+        seed: u64,
+        offset: u32,
+        limit: u32,
+        filter: Option<ServerFilter>,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<ServerPage<AccountId>> {
+        let at = self.block_id(at);
+        let (servers, total) = self
+            .client
+            .runtime_api()
+            .discover_country_servers(
+                &at,
+                country.into_bytes(),
+                filter.unwrap_or_default(),
+                seed,
+                offset,
+                limit.min(MAX_PAGE_SIZE),
+            )
+            .map_err(|e| runtime_error("Unable to discover servers.", e))?;
+        Ok(ServerPage { servers, total })
+    }
+
+    fn discover_region_servers(
+        &self,
+        region: String,
+        seed: u64,
+        offset: u32,
+        limit: u32,
+        filter: Option<ServerFilter>,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<ServerPage<AccountId>> {
+        let at = self.block_id(at);
+        let (servers, total) = self
+            .client
+            .runtime_api()
+            .discover_region_servers(
+                &at,
+                region.into_bytes(),
+                filter.unwrap_or_default(),
+                seed,
+                offset,
+                limit.min(MAX_PAGE_SIZE),
+            )
+            .map_err(|e| runtime_error("Unable to discover servers.", e))?;
+        Ok(ServerPage { servers, total })
+    }
+}
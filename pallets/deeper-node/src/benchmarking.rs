@@ -23,11 +23,16 @@ use core::str::FromStr;
 pub use frame_benchmarking::{account, benchmarks, whitelist_account, whitelisted_caller};
 use frame_support::{assert_ok, traits::Currency};
 use frame_system::RawOrigin;
-use node_primitives::AccountCreator;
+use node_primitives::{
+    deeper_node::attestation_message, user_privileges::Privilege, AccountCreator,
+};
+use sp_core::crypto::KeyTypeId;
+use sp_runtime::traits::StaticLookup;
 use sp_std::vec;
 
 const SEED: u32 = 0;
 const USER_SEED: u32 = 999666;
+const ATTESTATION_KEY: KeyTypeId = KeyTypeId(*b"attn");
 
 /// Grab a funded user with balance_factor DPR.
 pub fn create_funded_user<T: Config>(
@@ -43,7 +48,7 @@ pub fn create_funded_user<T: Config>(
 }
 
 benchmarks! {
-    where_clause { where T: Config, T: pallet_credit_accumulation::Config }
+    where_clause { where T: Config, T: pallet_credit_accumulation::Config, T: pallet_user_privileges::Config }
     register_device {
         DeeperNode::<T>::setup_region_map();
         let user = create_funded_user::<T>("user",USER_SEED, 100);
@@ -102,6 +107,68 @@ benchmarks! {
     verify {
     }
 
+    set_capabilities {
+        DeeperNode::<T>::setup_region_map();
+        let user = create_funded_user::<T>("user",USER_SEED, 100);
+        DeeperNode::<T>::register_device(RawOrigin::Signed(user.clone()).into(), vec![1, 2, 3, 4], "US".as_bytes().to_vec())?;
+        let capabilities = DeviceCapabilities {
+            protocols: node_primitives::deeper_node::protocol::TCP,
+            ipv6: vec![0u8; T::MaxIpLength::get()],
+            bandwidth: 100,
+            price_per_gb: 1,
+            firmware: vec![0u8; T::MaxFirmwareLength::get() as usize],
+        };
+    }: _(RawOrigin::Signed(user.clone()), capabilities.clone())
+    verify {
+        assert_eq!(DeeperNode::<T>::device_info(user).capabilities, capabilities);
+    }
+
+    attest_device {
+        DeeperNode::<T>::setup_region_map();
+        let user = create_funded_user::<T>("user",USER_SEED, 100);
+        DeeperNode::<T>::register_device(RawOrigin::Signed(user.clone()).into(), vec![1, 2, 3, 4], "US".as_bytes().to_vec())?;
+        let key = sp_io::crypto::sr25519_generate(ATTESTATION_KEY, None);
+        let manufacturer = T::AccountId::decode(&mut &key.0[..]).unwrap();
+        let signature = sp_io::crypto::sr25519_sign(
+            ATTESTATION_KEY,
+            &key,
+            &attestation_message(&manufacturer, &user),
+        ).unwrap();
+        let manufacturer_lookup = T::Lookup::unlookup(manufacturer.clone());
+        let _ = pallet_user_privileges::Pallet::<T>::set_user_privilege(RawOrigin::Root.into(), manufacturer_lookup, Privilege::Manufacturer);
+    }: _(RawOrigin::Signed(user.clone()), manufacturer.clone(), signature.0.to_vec())
+    verify {
+        assert_eq!(DeeperNode::<T>::device_manufacturer(&user), Some(manufacturer));
+    }
+
+    set_attestation_required {
+        let admin = create_funded_user::<T>("admin",USER_SEED, 100);
+        let admin_lookup = T::Lookup::unlookup(admin.clone());
+        let _ = pallet_user_privileges::Pallet::<T>::set_user_privilege(RawOrigin::Root.into(), admin_lookup, Privilege::DeviceAdmin);
+    }: _(RawOrigin::Signed(admin), true)
+    verify {
+        assert!(DeeperNode::<T>::attestation_required());
+    }
+
+    migrate_device {
+        let d in 1 .. 10_000;
+        // the old device is the last one onboard
+        let devices: Vec<T::AccountId> = (1 .. d).map(|i| account("device", i, SEED)).collect();
+        DevicesOnboard::<T>::put(devices);
+        let admin = create_funded_user::<T>("admin",USER_SEED, 100);
+        let admin_lookup = T::Lookup::unlookup(admin.clone());
+        let _ = pallet_user_privileges::Pallet::<T>::set_user_privilege(RawOrigin::Root.into(), admin_lookup, Privilege::DeviceAdmin);
+        let old = create_funded_user::<T>("old",USER_SEED, 100);
+        let new = create_funded_user::<T>("new",USER_SEED, 100);
+        assert_ok!(DeeperNode::<T>::im_online(RawOrigin::Signed(old.clone()).into()));
+        RewardsAccountsDeepertoEVM::<T>::insert(&old, H160::zero());
+        RewardsAccountsEVMtoDeeper::<T>::insert(H160::zero(), &old);
+    }: _(RawOrigin::Signed(admin), old.clone(), new.clone(), Vec::new())
+    verify {
+        assert!(DeeperNode::<T>::onboard_time(&new).is_some());
+        assert_eq!(RewardsAccountsEVMtoDeeper::<T>::get(H160::zero()), Some(new));
+    }
+
     im_online {
         let user = create_funded_user::<T>("user",USER_SEED, 100);
     }:_(RawOrigin::Signed(user.clone()))
@@ -155,6 +222,24 @@ benchmarks! {
     }: _(RawOrigin::Signed(admin))
     verify {
     }
+
+    sweep_devices {
+        let n in 1 .. T::MaxSweepDevices::get();
+        DeeperNode::<T>::setup_region_map();
+        for i in 0 .. n {
+            let user = create_funded_user::<T>("user", i, 100);
+            DeeperNode::<T>::register_device(RawOrigin::Signed(user.clone()).into(), vec![1, 2, 3, 4], "US".as_bytes().to_vec())?;
+            DeeperNode::<T>::register_server(RawOrigin::Signed(user.clone()).into(), 0)?;
+            DeeperNode::<T>::im_online(RawOrigin::Signed(user).into())?;
+        }
+        let now = frame_system::Pallet::<T>::block_number();
+        frame_system::Pallet::<T>::set_block_number(now + T::BlocksPerEra::get());
+    }: {
+        DeeperNode::<T>::sweep_devices(n);
+    }
+    verify {
+        assert_eq!(ReportedOffline::<T>::iter().count() as u32, n);
+    }
 }
 
 #[cfg(test)]
@@ -171,7 +256,12 @@ mod tests {
             assert_ok!(Pallet::<Test>::test_benchmark_register_server());
             assert_ok!(Pallet::<Test>::test_benchmark_update_server());
             assert_ok!(Pallet::<Test>::test_benchmark_unregister_server());
+            assert_ok!(Pallet::<Test>::test_benchmark_set_capabilities());
+            assert_ok!(Pallet::<Test>::test_benchmark_attest_device());
+            assert_ok!(Pallet::<Test>::test_benchmark_set_attestation_required());
+            assert_ok!(Pallet::<Test>::test_benchmark_migrate_device());
             assert_ok!(Pallet::<Test>::test_benchmark_im_online());
+            assert_ok!(Pallet::<Test>::test_benchmark_sweep_devices());
         });
     }
 }
@@ -16,6 +16,7 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use frame_support::codec::{Decode, Encode};
+use node_primitives::deeper_node::{DeviceCapabilities, ServerFilter};
 use scale_info::TypeInfo;
 use sp_core::H160;
 
@@ -45,6 +46,30 @@ pub struct Node<AccountId, BlockNumber> {
     ipv4: IpV4, // IP will not be exposed in future version
     country: CountryRegion,
     expire: BlockNumber,
+    capabilities: DeviceCapabilities,
+}
+
+impl<AccountId, BlockNumber> Node<AccountId, BlockNumber> {
+    pub fn capabilities(&self) -> &DeviceCapabilities {
+        &self.capabilities
+    }
+
+    pub fn country(&self) -> &CountryRegion {
+        &self.country
+    }
+
+    pub fn expire(&self) -> &BlockNumber {
+        &self.expire
+    }
+}
+
+// Node of storage version 1 and before, without capabilities
+#[derive(Decode, Encode)]
+struct NodeV1<AccountId, BlockNumber> {
+    account_id: AccountId,
+    ipv4: IpV4,
+    country: CountryRegion,
+    expire: BlockNumber,
 }
 
 impl<AccountId: Decode, BlockNumber: Default> Default for Node<AccountId, BlockNumber> {
@@ -55,6 +80,7 @@ impl<AccountId: Decode, BlockNumber: Default> Default for Node<AccountId, BlockN
             ipv4: Default::default(),
             country: Default::default(),
             expire: Default::default(),
+            capabilities: Default::default(),
         }
     }
 }
@@ -67,7 +93,12 @@ pub mod pallet {
     use frame_support::{dispatch::DispatchResultWithPostInfo, pallet_prelude::*};
     use frame_system::pallet_prelude::*;
     use frame_system::{self, ensure_signed};
-    use node_primitives::{deeper_node::NodeInterface, VerifySignatureInterface};
+    use node_primitives::{
+        credit::CreditInterface,
+        deeper_node::{DeviceMigration, NodeInterface},
+        user_privileges::{Privilege, UserPrivilegeInterface},
+        VerifySignatureInterface,
+    };
     use sp_std::convert::TryInto;
 
     /// Configure the pallet by specifying the parameters and types on which it depends.
@@ -84,13 +115,33 @@ pub mod pallet {
         /// Weight information for extrinsics in this pallet.
         type WeightInfo: WeightInfo;
         type VerifySignatureInterface: VerifySignatureInterface<Self::AccountId>;
+        type CreditInterface: CreditInterface<Self::AccountId, BalanceOf<Self>>;
+        /// Maximum number of devices checked by the offline and expired server sweeps per block.
+        #[pallet::constant]
+        type MaxSweepDevices: Get<u32>;
+        /// Maximum number of servers in a country or region.
+        #[pallet::constant]
+        type MaxServersPerRegion: Get<u32>;
+        /// Maximum length of the firmware version a device advertises.
+        #[pallet::constant]
+        type MaxFirmwareLength: Get<u32>;
+        /// Manufacturers that attest devices, and admins that require attestation.
+        type UserPrivilegeInterface: UserPrivilegeInterface<Self::AccountId>;
+        /// State of other pallets that moves with a replaced device, e.g. credit and delegation.
+        type DeviceMigration: DeviceMigration<Self::AccountId>;
+        /// Minimum number of blocks between two migrations involving the same account.
+        #[pallet::constant]
+        type DeviceMigrationCooldown: Get<Self::BlockNumber>;
     }
 
     type BalanceOf<T> =
         <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(2);
+
     #[pallet::pallet]
     #[pallet::generate_store(pub(super) trait Store)]
+    #[pallet::storage_version(STORAGE_VERSION)]
     #[pallet::without_storage_info]
     pub struct Pallet<T>(_);
 
@@ -113,20 +164,44 @@ pub mod pallet {
         ValueQuery,
     >;
 
+    /// Servers registered in a country
     #[pallet::storage]
-    #[pallet::getter(fn servers_by_country)]
-    pub(super) type ServersByCountry<T: Config> =
-        StorageMap<_, Blake2_128Concat, CountryRegion, Vec<T::AccountId>, ValueQuery>;
+    pub(super) type CountryServers<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        CountryRegion,
+        Blake2_128Concat,
+        T::AccountId,
+        (),
+        OptionQuery,
+    >;
+
+    #[pallet::storage]
+    #[pallet::getter(fn country_server_count)]
+    pub(super) type CountryServerCount<T: Config> =
+        StorageMap<_, Blake2_128Concat, CountryRegion, u32, ValueQuery>;
 
     #[pallet::storage]
     #[pallet::getter(fn device_credit_proof)]
     pub type DeviceCreditProof<T: Config> =
         StorageMap<_, Blake2_128Concat, T::AccountId, (u32, u64, u32), ValueQuery>;
 
+    /// Servers registered in a level 2 or level 3 region
+    #[pallet::storage]
+    pub(super) type RegionServers<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        CountryRegion,
+        Blake2_128Concat,
+        T::AccountId,
+        (),
+        OptionQuery,
+    >;
+
     #[pallet::storage]
-    #[pallet::getter(fn servers_by_region)]
-    pub(super) type ServersByRegion<T: Config> =
-        StorageMap<_, Blake2_128Concat, CountryRegion, Vec<T::AccountId>, ValueQuery>;
+    #[pallet::getter(fn region_server_count)]
+    pub(super) type RegionServerCount<T: Config> =
+        StorageMap<_, Blake2_128Concat, CountryRegion, u32, ValueQuery>;
 
     #[pallet::storage]
     #[pallet::getter(fn get_im_online)]
@@ -154,6 +229,37 @@ pub mod pallet {
     pub type RewardsAccountsDeepertoEVM<T: Config> =
         StorageMap<_, Blake2_128Concat, T::AccountId, H160, OptionQuery>;
 
+    /// Raw key in OnboardTime after which the offline sweep continues
+    #[pallet::storage]
+    pub(super) type OfflineSweepCursor<T: Config> = StorageValue<_, Vec<u8>, OptionQuery>;
+
+    /// Raw key in CountryServers after which the expired server sweep continues
+    #[pallet::storage]
+    pub(super) type ServerSweepCursor<T: Config> = StorageValue<_, Vec<u8>, OptionQuery>;
+
+    /// Eras offline when the device was last reported offline
+    #[pallet::storage]
+    #[pallet::getter(fn reported_offline)]
+    pub(super) type ReportedOffline<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, u32, OptionQuery>;
+
+    /// Manufacturer that attested the device
+    #[pallet::storage]
+    #[pallet::getter(fn device_manufacturer)]
+    pub type DeviceManufacturer<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, T::AccountId, OptionQuery>;
+
+    /// Whether devices must be attested to become servers and accrue traffic credit
+    #[pallet::storage]
+    #[pallet::getter(fn attestation_required)]
+    pub type AttestationRequired<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+    /// Block of the last device migration from or to the account
+    #[pallet::storage]
+    #[pallet::getter(fn last_device_migration)]
+    pub type LastDeviceMigration<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, T::BlockNumber, OptionQuery>;
+
     #[pallet::genesis_config]
     pub struct GenesisConfig<T: Config> {
         pub reward_setting: Vec<(T::AccountId, H160)>,
@@ -187,12 +293,12 @@ pub mod pallet {
         RegisterNode(T::AccountId, IpV4, CountryRegion),
         UnregisterNode(T::AccountId),
 
-        // add account into a country's server list
+        // add account into a country's server list: AccountId, country, duration, number of servers
         ServerCountryAdded(T::AccountId, CountryRegion, T::BlockNumber, u64),
         // remove account from a country's server list
         ServerCountryRemoved(T::AccountId, CountryRegion),
 
-        // add account into a region's server list
+        // add account into a region's server list: AccountId, region, duration, number of servers
         ServerRegionAdded(T::AccountId, CountryRegion, T::BlockNumber, u64),
         // remove account from a region's server list
         ServerRegionRemoved(T::AccountId, CountryRegion),
@@ -206,6 +312,19 @@ pub mod pallet {
 
         /// send this event to let system mint dpr to user
         GetNpowReward(T::AccountId, H160),
+
+        /// device has not been online for some eras: AccountId, eras offline
+        DeviceOffline(T::AccountId, u32),
+        /// server registration expired and was removed from the server lists: AccountId, country
+        ServerExpired(T::AccountId, CountryRegion),
+        /// device advertised new capabilities
+        CapabilitiesUpdated(T::AccountId, DeviceCapabilities),
+        /// device was attested by a manufacturer: AccountId, manufacturer
+        DeviceAttested(T::AccountId, T::AccountId),
+        /// attestation requirement was switched on or off
+        AttestationRequirementSet(bool),
+        /// state of a replaced device moved to a new account: old, new
+        DeviceMigrated(T::AccountId, T::AccountId),
     }
 
     // Errors inform users that something went wrong.
@@ -235,10 +354,48 @@ pub mod pallet {
         NotBound,
         /// no coresponding evm address
         NpowRewardAddressNotFound,
+        /// the country or one of its regions has reached the maximum number of servers
+        TooManyServers,
+        /// firmware version is too long
+        InvalidFirmware,
+        /// account is not a registered manufacturer
+        NotManufacturer,
+        /// device is not attested by a manufacturer
+        DeviceNotAttested,
+        /// account is not a device admin
+        NotDeviceAdmin,
+        /// caller is neither the old device account nor a device admin
+        NotDeviceOwner,
+        /// device has never been online
+        DeviceNotOnboard,
+        /// new account is the old one or already has device state
+        InvalidMigrationTarget,
+        /// an account migrated a device too recently
+        MigrationCooldown,
     }
 
     #[pallet::hooks]
-    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_initialize(_n: T::BlockNumber) -> Weight {
+            Self::sweep_devices(T::MaxSweepDevices::get())
+        }
+
+        fn on_runtime_upgrade() -> Weight {
+            let version = StorageVersion::get::<Pallet<T>>();
+            if version >= STORAGE_VERSION {
+                return T::DbWeight::get().reads(1);
+            }
+            let mut weight = T::DbWeight::get().reads_writes(1, 1);
+            if version < 1 {
+                weight = weight.saturating_add(Self::migrate_server_lists());
+            }
+            if version < 2 {
+                weight = weight.saturating_add(Self::migrate_node_capabilities());
+            }
+            STORAGE_VERSION.put::<Pallet<T>>();
+            weight
+        }
+    }
 
     // Dispatchable functions allows users to interact with the pallet and invoke state changes.
     // These functions materialize as "extrinsics", which are often compared to transactions.
@@ -265,6 +422,7 @@ pub mod pallet {
                     ipv4: ip.clone(),
                     country: country.clone(),
                     expire: <frame_system::Pallet<T>>::block_number(),
+                    capabilities: Default::default(),
                 };
                 T::Currency::reserve(&sender, BalanceOf::<T>::from(T::MinLockAmt::get()))?;
                 <DeviceInfo<T>>::insert(&sender, node);
@@ -291,6 +449,7 @@ pub mod pallet {
             );
             let _ = Self::try_remove_server(&sender);
             <DeviceInfo<T>>::remove(&sender);
+            <DeviceManufacturer<T>>::remove(&sender);
             T::Currency::unreserve(&sender, BalanceOf::<T>::from(T::MinLockAmt::get()));
             Self::deposit_event(Event::UnregisterNode(sender));
             Ok(().into())
@@ -310,6 +469,16 @@ pub mod pallet {
                 duration_eras <= T::MaxDurationEras::get(),
                 Error::<T>::DurationOverflow
             );
+            ensure!(
+                Self::attestation_satisfied(&sender),
+                Error::<T>::DeviceNotAttested
+            );
+            let country = <DeviceInfo<T>>::get(&sender).country;
+            ensure!(
+                <CountryServers<T>>::contains_key(&country, &sender)
+                    || Self::has_server_capacity(&country),
+                Error::<T>::TooManyServers
+            );
             let blocks = T::BlockNumber::from(duration_eras) * T::BlocksPerEra::get();
             let _ = Self::try_add_server(&sender, blocks);
             Ok(().into())
@@ -347,11 +516,158 @@ pub mod pallet {
             Ok(().into())
         }
 
+        #[pallet::weight(T::WeightInfo::set_capabilities())]
+        pub fn set_capabilities(
+            origin: OriginFor<T>,
+            capabilities: DeviceCapabilities,
+        ) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin)?;
+            ensure!(
+                <DeviceInfo<T>>::contains_key(&sender),
+                Error::<T>::DeviceNotRegister
+            );
+            ensure!(
+                capabilities.ipv6.len() <= T::MaxIpLength::get(),
+                Error::<T>::InvalidIP
+            );
+            ensure!(
+                capabilities.firmware.len() <= T::MaxFirmwareLength::get() as usize,
+                Error::<T>::InvalidFirmware
+            );
+            <DeviceInfo<T>>::mutate(&sender, |node| {
+                node.capabilities = capabilities.clone();
+            });
+            Self::deposit_event(Event::CapabilitiesUpdated(sender, capabilities));
+            Ok(().into())
+        }
+
+        /// Attest the device with a manufacturer's signature on
+        /// `node_primitives::deeper_node::attestation_message`. The attestation lasts
+        /// until the device is unregistered or the manufacturer loses its privilege.
+        #[pallet::weight(T::WeightInfo::attest_device())]
+        pub fn attest_device(
+            origin: OriginFor<T>,
+            manufacturer: T::AccountId,
+            signature: Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin)?;
+            ensure!(
+                <DeviceInfo<T>>::contains_key(&sender),
+                Error::<T>::DeviceNotRegister
+            );
+            ensure!(
+                T::UserPrivilegeInterface::has_privilege(&manufacturer, Privilege::Manufacturer),
+                Error::<T>::NotManufacturer
+            );
+            ensure!(
+                T::VerifySignatureInterface::verify_device_attestation(
+                    &manufacturer,
+                    &sender,
+                    &signature
+                ),
+                Error::<T>::SignatureVerifyFailed
+            );
+            <DeviceManufacturer<T>>::insert(&sender, &manufacturer);
+            Self::deposit_event(Event::DeviceAttested(sender, manufacturer));
+            Ok(().into())
+        }
+
+        /// Require devices to be attested before they become servers or accrue traffic
+        /// credit. Servers registered before keep serving until their registration ends.
+        #[pallet::weight(T::WeightInfo::set_attestation_required())]
+        pub fn set_attestation_required(
+            origin: OriginFor<T>,
+            required: bool,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            ensure!(
+                T::UserPrivilegeInterface::has_privilege(&who, Privilege::DeviceAdmin),
+                Error::<T>::NotDeviceAdmin
+            );
+            <AttestationRequired<T>>::put(required);
+            Self::deposit_event(Event::AttestationRequirementSet(required));
+            Ok(().into())
+        }
+
+        /// Move the onboard time, online status, reward mapping, credit and delegation of the
+        /// `old` device account to the `new` one when a device is replaced. Called by the old
+        /// account with the new account's signature on
+        /// `node_primitives::deeper_node::device_migration_message`, or by a device admin.
+        /// The worst case of `T::DeviceMigration` is charged and the rest refunded.
+        #[pallet::weight(
+            T::WeightInfo::migrate_device(DevicesOnboard::<T>::decode_len().unwrap_or(0) as u32)
+                .saturating_add(T::DeviceMigration::max_migrate_device_weight())
+        )]
+        pub fn migrate_device(
+            origin: OriginFor<T>,
+            old: T::AccountId,
+            new: T::AccountId,
+            signature: Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            if !T::UserPrivilegeInterface::has_privilege(&who, Privilege::DeviceAdmin) {
+                ensure!(who == old, Error::<T>::NotDeviceOwner);
+                ensure!(
+                    T::VerifySignatureInterface::verify_device_migration(&old, &new, &signature),
+                    Error::<T>::SignatureVerifyFailed
+                );
+            }
+            ensure!(
+                OnboardTime::<T>::contains_key(&old),
+                Error::<T>::DeviceNotOnboard
+            );
+            ensure!(
+                old != new
+                    && !OnboardTime::<T>::contains_key(&new)
+                    && !RewardsAccountsDeepertoEVM::<T>::contains_key(&new)
+                    && T::DeviceMigration::can_migrate_device(&old, &new),
+                Error::<T>::InvalidMigrationTarget
+            );
+            let now = <frame_system::Pallet<T>>::block_number();
+            for account in [&old, &new] {
+                if let Some(last) = Self::last_device_migration(account) {
+                    ensure!(
+                        now.saturating_sub(last) >= T::DeviceMigrationCooldown::get(),
+                        Error::<T>::MigrationCooldown
+                    );
+                }
+            }
+
+            if let Some(onboard) = OnboardTime::<T>::take(&old) {
+                OnboardTime::<T>::insert(&new, onboard);
+            }
+            let devices_onboard = DevicesOnboard::<T>::decode_len().unwrap_or(0) as u32;
+            DevicesOnboard::<T>::mutate(|devices| {
+                if let Some(device) = devices.iter_mut().find(|device| **device == old) {
+                    *device = new.clone();
+                }
+            });
+            if let Some(online) = ImOnline::<T>::take(&old) {
+                ImOnline::<T>::insert(&new, online);
+            }
+            if let Some(eras) = ReportedOffline::<T>::take(&old) {
+                ReportedOffline::<T>::insert(&new, eras);
+            }
+            if let Some(evm_address) = RewardsAccountsDeepertoEVM::<T>::take(&old) {
+                RewardsAccountsDeepertoEVM::<T>::insert(&new, evm_address);
+                RewardsAccountsEVMtoDeeper::<T>::insert(evm_address, &new);
+            }
+            let migrated = T::DeviceMigration::migrate_device(&old, &new);
+
+            LastDeviceMigration::<T>::insert(&old, now);
+            LastDeviceMigration::<T>::insert(&new, now);
+            Self::deposit_event(Event::DeviceMigrated(old, new));
+            let actual_weight =
+                T::WeightInfo::migrate_device(devices_onboard).saturating_add(migrated);
+            Ok(Some(actual_weight).into())
+        }
+
         #[pallet::weight(T::WeightInfo::im_online())]
         pub fn im_online(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
             let sender = ensure_signed(origin)?;
             let current_block = <frame_system::Pallet<T>>::block_number();
             ImOnline::<T>::insert(&sender, current_block.clone());
+            ReportedOffline::<T>::remove(&sender);
             if !OnboardTime::<T>::contains_key(&sender) {
                 OnboardTime::<T>::insert(&sender, current_block.clone());
                 DevicesOnboard::<T>::mutate(|devices| devices.push(sender.clone()));
@@ -451,21 +767,7 @@ pub mod pallet {
         fn try_remove_server(sender: &T::AccountId) -> DispatchResult {
             if <DeviceInfo<T>>::contains_key(&sender) {
                 let mut node = <DeviceInfo<T>>::get(&sender);
-
-                let first_region = <RegionMap<T>>::get(&node.country);
-                let sec_region = <RegionMap<T>>::get(&first_region);
-
-                // remove from country server list
-                let mut server_list = <ServersByCountry<T>>::get(&node.country);
-                let _ = Self::country_list_remove(&mut server_list, &sender, &node.country);
-
-                // remove from level 3 region server list
-                server_list = <ServersByRegion<T>>::get(&first_region);
-                let _ = Self::region_list_remove(&mut server_list, &sender, &first_region);
-
-                // remove from level 2 region server list
-                server_list = <ServersByRegion<T>>::get(&sec_region);
-                let _ = Self::region_list_remove(&mut server_list, &sender, &sec_region);
+                Self::remove_from_server_lists(&sender, &node.country);
 
                 // ensure consistency
                 node.expire = <frame_system::Pallet<T>>::block_number();
@@ -474,6 +776,114 @@ pub mod pallet {
             Ok(())
         }
 
+        fn remove_from_server_lists(sender: &T::AccountId, country: &CountryRegion) {
+            let first_region = <RegionMap<T>>::get(country);
+            let sec_region = <RegionMap<T>>::get(&first_region);
+
+            // remove from country server list
+            let _ = Self::country_list_remove(&sender, country);
+
+            // remove from level 3 region server list
+            let _ = Self::region_list_remove(&sender, &first_region);
+
+            // remove from level 2 region server list
+            let _ = Self::region_list_remove(&sender, &sec_region);
+        }
+
+        /// Whether the device is attested by an account that is still a manufacturer.
+        pub fn is_attested(account: &T::AccountId) -> bool {
+            Self::device_manufacturer(account).map_or(false, |manufacturer| {
+                T::UserPrivilegeInterface::has_privilege(&manufacturer, Privilege::Manufacturer)
+            })
+        }
+
+        // whether the country and its level 3 and level 2 regions can take one more server
+        fn has_server_capacity(country: &CountryRegion) -> bool {
+            let first_region = <RegionMap<T>>::get(country);
+            let sec_region = <RegionMap<T>>::get(&first_region);
+            let max = T::MaxServersPerRegion::get();
+            <CountryServerCount<T>>::get(country) < max
+                && <RegionServerCount<T>>::get(&first_region) < max
+                && <RegionServerCount<T>>::get(&sec_region) < max
+        }
+
+        /// Check up to `limit` onboard devices for being offline and up to `limit` servers for
+        /// expired registrations, continuing from where the previous sweep stopped. The weight
+        /// is charged for the number of devices or servers actually swept.
+        pub fn sweep_devices(limit: u32) -> Weight {
+            let mut weight = Weight::zero();
+            let mut swept = 0u32;
+
+            let mut devices = match OfflineSweepCursor::<T>::get() {
+                Some(key) => <OnboardTime<T>>::iter_keys_from(key),
+                None => <OnboardTime<T>>::iter_keys(),
+            };
+            let mut cursor = None;
+            for _ in 0..limit {
+                match devices.next() {
+                    Some(account) => {
+                        swept += 1;
+                        weight = weight.saturating_add(Self::check_offline(&account));
+                        cursor = Some(<OnboardTime<T>>::hashed_key_for(&account));
+                    }
+                    None => {
+                        cursor = None;
+                        break;
+                    }
+                }
+            }
+            OfflineSweepCursor::<T>::set(cursor);
+
+            let servers = Self::sweep_expired_servers(limit);
+            weight.saturating_add(T::WeightInfo::sweep_devices(swept.max(servers)))
+        }
+
+        // report a device offline once for every era it stays offline
+        fn check_offline(account: &T::AccountId) -> Weight {
+            let eras = Self::get_eras_offline(account);
+            if eras == 0 || Self::reported_offline(account) == Some(eras) {
+                return Weight::zero();
+            }
+            ReportedOffline::<T>::insert(account, eras);
+            Self::deposit_event(Event::DeviceOffline(account.clone(), eras));
+            T::CreditInterface::slash_offline_device_credit(account)
+        }
+
+        /// returns the number of servers checked
+        fn sweep_expired_servers(limit: u32) -> u32 {
+            let now = <frame_system::Pallet<T>>::block_number();
+            let mut servers = match ServerSweepCursor::<T>::get() {
+                Some(key) => <CountryServers<T>>::iter_keys_from(key),
+                None => <CountryServers<T>>::iter_keys(),
+            };
+            let mut cursor = None;
+            let mut checked = 0u32;
+            for _ in 0..limit {
+                match servers.next() {
+                    Some((country, account)) => {
+                        checked += 1;
+                        let expired = <DeviceInfo<T>>::try_get(&account)
+                            .map(|node| node.expire <= now)
+                            .unwrap_or(true);
+                        if expired {
+                            Self::remove_from_server_lists(&account, &country);
+                            Self::deposit_event(Event::ServerExpired(
+                                account.clone(),
+                                country.clone(),
+                            ));
+                        }
+                        cursor = Some(<CountryServers<T>>::hashed_key_for(&country, &account));
+                    }
+                    None => {
+                        cursor = None;
+                        break;
+                    }
+                }
+            }
+            ServerSweepCursor::<T>::set(cursor);
+            checked
+        }
+
         // try to add an account to a country's server list; no double add
         fn try_add_server(sender: &T::AccountId, duration: T::BlockNumber) -> DispatchResult {
             let mut node = <DeviceInfo<T>>::get(&sender);
@@ -481,32 +891,29 @@ pub mod pallet {
             let first_region = <RegionMap<T>>::get(&node.country);
             let sec_region = <RegionMap<T>>::get(&first_region);
 
-            // country registration
-            let mut country_server_list = <ServersByCountry<T>>::get(&node.country);
-            if !Self::country_list_insert(
-                &mut country_server_list,
-                &sender,
-                &node.country,
-                &duration,
-            ) {
-                Err(Error::<T>::DoubleCountryRegistration)?
-            }
+            ensure!(
+                !<CountryServers<T>>::contains_key(&node.country, &sender),
+                Error::<T>::DoubleCountryRegistration
+            );
+            ensure!(
+                !<RegionServers<T>>::contains_key(&first_region, &sender),
+                Error::<T>::DoubleLevel3Registration
+            );
+            ensure!(
+                !<RegionServers<T>>::contains_key(&sec_region, &sender),
+                Error::<T>::DoubleLevel2Registration
+            );
+            ensure!(
+                Self::has_server_capacity(&node.country),
+                Error::<T>::TooManyServers
+            );
 
+            // country registration
+            Self::country_list_insert(&sender, &node.country, &duration);
             // level 3 region registration
-            let mut level3_server_list = <ServersByRegion<T>>::get(&first_region);
-            if !Self::region_list_insert(&mut level3_server_list, &sender, &first_region, &duration)
-            {
-                let _ = Self::country_list_remove(&mut country_server_list, &sender, &node.country);
-                Err(Error::<T>::DoubleLevel3Registration)?
-            }
-
+            Self::region_list_insert(&sender, &first_region, &duration);
             // level 2 region registration
-            let mut level2_server_list = <ServersByRegion<T>>::get(&sec_region);
-            if !Self::region_list_insert(&mut level2_server_list, &sender, &sec_region, &duration) {
-                let _ = Self::country_list_remove(&mut country_server_list, &sender, &node.country);
-                let _ = Self::region_list_remove(&mut level3_server_list, &sender, &first_region);
-                Err(Error::<T>::DoubleLevel2Registration)?
-            }
+            Self::region_list_insert(&sender, &sec_region, &duration);
 
             // ensure consistency
             node.expire = <frame_system::Pallet<T>>::block_number() + duration;
@@ -516,85 +923,197 @@ pub mod pallet {
         }
 
         fn country_list_insert(
-            servers: &mut Vec<T::AccountId>,
             account: &T::AccountId,
             country: &CountryRegion,
             duration: &T::BlockNumber,
-        ) -> bool {
-            match servers.binary_search(&account) {
-                Ok(_) => false,
-                Err(index) => {
-                    servers.insert(index, account.clone());
-                    <ServersByCountry<T>>::insert(&country, servers);
-                    Self::deposit_event(Event::ServerCountryAdded(
-                        account.clone(),
-                        country.clone(),
-                        duration.clone(),
-                        index as u64,
-                    ));
-                    true
-                }
-            }
+        ) {
+            <CountryServers<T>>::insert(country, account, ());
+            let count = <CountryServerCount<T>>::mutate(country, |count| {
+                *count = count.saturating_add(1);
+                *count
+            });
+            Self::deposit_event(Event::ServerCountryAdded(
+                account.clone(),
+                country.clone(),
+                duration.clone(),
+                count as u64,
+            ));
         }
 
-        fn country_list_remove(
-            servers: &mut Vec<T::AccountId>,
-            account: &T::AccountId,
-            country: &CountryRegion,
-        ) -> bool {
-            match servers.binary_search(&account) {
-                Ok(index) => {
-                    servers.remove(index);
-                    <ServersByCountry<T>>::insert(&country, servers);
-                    Self::deposit_event(Event::ServerCountryRemoved(
-                        account.clone(),
-                        country.clone(),
-                    ));
-                    true
-                }
-                Err(_) => false,
+        fn country_list_remove(account: &T::AccountId, country: &CountryRegion) -> bool {
+            if <CountryServers<T>>::take(country, account).is_none() {
+                return false;
             }
+            <CountryServerCount<T>>::mutate(country, |count| *count = count.saturating_sub(1));
+            Self::deposit_event(Event::ServerCountryRemoved(
+                account.clone(),
+                country.clone(),
+            ));
+            true
         }
 
         fn region_list_insert(
-            servers: &mut Vec<T::AccountId>,
             account: &T::AccountId,
             region: &CountryRegion,
             duration: &T::BlockNumber,
-        ) -> bool {
-            match servers.binary_search(&account) {
-                Ok(_) => false,
-                Err(index) => {
-                    servers.insert(index, account.clone());
-                    <ServersByRegion<T>>::insert(&region, servers);
-                    Self::deposit_event(Event::ServerRegionAdded(
-                        account.clone(),
-                        region.clone(),
-                        duration.clone(),
-                        index as u64,
-                    ));
-                    true
-                }
+        ) {
+            <RegionServers<T>>::insert(region, account, ());
+            let count = <RegionServerCount<T>>::mutate(region, |count| {
+                *count = count.saturating_add(1);
+                *count
+            });
+            Self::deposit_event(Event::ServerRegionAdded(
+                account.clone(),
+                region.clone(),
+                duration.clone(),
+                count as u64,
+            ));
+        }
+
+        fn region_list_remove(account: &T::AccountId, region: &CountryRegion) -> bool {
+            if <RegionServers<T>>::take(region, account).is_none() {
+                return false;
             }
+            <RegionServerCount<T>>::mutate(region, |count| *count = count.saturating_sub(1));
+            Self::deposit_event(Event::ServerRegionRemoved(account.clone(), region.clone()));
+            true
         }
 
-        fn region_list_remove(
-            servers: &mut Vec<T::AccountId>,
-            account: &T::AccountId,
-            region: &CountryRegion,
-        ) -> bool {
-            match servers.binary_search(&account) {
-                Ok(index) => {
-                    servers.remove(index);
-                    <ServersByRegion<T>>::insert(&region, servers);
-                    Self::deposit_event(Event::ServerRegionRemoved(
-                        account.clone(),
-                        region.clone(),
-                    ));
-                    true
+        /// All servers of a country, sorted.
+        pub fn servers_by_country(country: CountryRegion) -> Vec<T::AccountId> {
+            let mut servers: Vec<_> = <CountryServers<T>>::iter_key_prefix(country).collect();
+            servers.sort();
+            servers
+        }
+
+        /// All servers of a level 2 or level 3 region, sorted.
+        pub fn servers_by_region(region: CountryRegion) -> Vec<T::AccountId> {
+            let mut servers: Vec<_> = <RegionServers<T>>::iter_key_prefix(region).collect();
+            servers.sort();
+            servers
+        }
+
+        /// Servers of a country that are neither expired nor offline and match `filter`,
+        /// shuffled by `seed`. Returns at most `limit` servers starting at `offset` of the
+        /// shuffled list, and the number of servers in the list. The same seed gives the same
+        /// order, so that clients can page through the list.
+        pub fn discover_country_servers(
+            country: CountryRegion,
+            filter: ServerFilter,
+            seed: u64,
+            offset: u32,
+            limit: u32,
+        ) -> (Vec<T::AccountId>, u32) {
+            Self::discover(
+                <CountryServers<T>>::iter_key_prefix(country).collect(),
+                filter,
+                seed,
+                offset,
+                limit,
+            )
+        }
+
+        /// Servers of a level 2 or level 3 region that are neither expired nor offline and
+        /// match `filter`, paged like [`Self::discover_country_servers`].
+        pub fn discover_region_servers(
+            region: CountryRegion,
+            filter: ServerFilter,
+            seed: u64,
+            offset: u32,
+            limit: u32,
+        ) -> (Vec<T::AccountId>, u32) {
+            Self::discover(
+                <RegionServers<T>>::iter_key_prefix(region).collect(),
+                filter,
+                seed,
+                offset,
+                limit,
+            )
+        }
+
+        fn discover(
+            mut servers: Vec<T::AccountId>,
+            filter: ServerFilter,
+            seed: u64,
+            offset: u32,
+            limit: u32,
+        ) -> (Vec<T::AccountId>, u32) {
+            let now = <frame_system::Pallet<T>>::block_number();
+            servers.retain(|server| {
+                <DeviceInfo<T>>::try_get(server)
+                    .map(|node| node.expire > now && filter.matches(&node.capabilities))
+                    .unwrap_or(false)
+                    && Self::get_eras_offline(server) == 0
+            });
+            // storage iteration order only depends on the account hashes,
+            // sort first so that the shuffle only depends on the seed
+            servers.sort();
+            for i in (1..servers.len()).rev() {
+                let random = sp_io::hashing::blake2_256(&(seed, i as u32).encode());
+                let j = u32::from_le_bytes([random[0], random[1], random[2], random[3]]) as usize
+                    % (i + 1);
+                servers.swap(i, j);
+            }
+            let total = servers.len() as u32;
+            let page = servers
+                .into_iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .collect();
+            (page, total)
+        }
+
+        // move the server lists of storage version 0 into the double maps
+        fn migrate_server_lists() -> Weight {
+            use frame_support::storage::migration::storage_key_iter;
+
+            let pallet = <Pallet<T> as PalletInfoAccess>::name().as_bytes();
+            let mut count = 0u64;
+            let countries: Vec<_> = storage_key_iter::<
+                CountryRegion,
+                Vec<T::AccountId>,
+                Blake2_128Concat,
+            >(pallet, b"ServersByCountry")
+            .drain()
+            .collect();
+            for (country, servers) in countries {
+                <CountryServerCount<T>>::insert(&country, servers.len() as u32);
+                for server in servers {
+                    <CountryServers<T>>::insert(&country, server, ());
+                    count += 1;
+                }
+            }
+            let regions: Vec<_> = storage_key_iter::<
+                CountryRegion,
+                Vec<T::AccountId>,
+                Blake2_128Concat,
+            >(pallet, b"ServersByRegion")
+            .drain()
+            .collect();
+            for (region, servers) in regions {
+                <RegionServerCount<T>>::insert(&region, servers.len() as u32);
+                for server in servers {
+                    <RegionServers<T>>::insert(&region, server, ());
+                    count += 1;
                 }
-                Err(_) => false,
             }
+            T::DbWeight::get().reads_writes(count, count)
+        }
+
+        // add default capabilities to the nodes of storage version 1
+        fn migrate_node_capabilities() -> Weight {
+            let mut count = 0u64;
+            <DeviceInfo<T>>::translate::<NodeV1<T::AccountId, T::BlockNumber>, _>(|_, old| {
+                count += 1;
+                Some(Node {
+                    account_id: old.account_id,
+                    ipv4: old.ipv4,
+                    country: old.country,
+                    expire: old.expire,
+                    capabilities: Default::default(),
+                })
+            });
+            T::DbWeight::get().reads_writes(count, count)
         }
 
         pub fn setup_region_map() {
@@ -930,5 +1449,9 @@ pub mod pallet {
         fn get_accounts_evm_deeper(evm_address: &H160) -> Option<T::AccountId> {
             Self::rewards_accounts_evm_deeper(evm_address)
         }
+
+        fn attestation_satisfied(account_id: &T::AccountId) -> bool {
+            !Self::attestation_required() || Self::is_attested(account_id)
+        }
     }
 }
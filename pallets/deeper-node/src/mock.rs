@@ -21,6 +21,7 @@ use frame_support::{
 };
 use frame_system as system;
 use sp_core::H256;
+use sp_keystore::{testing::KeyStore, KeystoreExt};
 use sp_runtime::{
     testing::Header,
     traits::{BlakeTwo256, IdentityLookup},
@@ -42,6 +43,7 @@ frame_support::construct_runtime!(
         System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
         Balances: pallet_balances::{Pallet, Call, Event<T>, Config<T>},
         DeeperNode: pallet_deeper_node::{Pallet, Call, Storage, Event<T>},
+        UserPrivileges: pallet_user_privileges::{Pallet, Call, Storage, Event<T>},
     }
 );
 
@@ -104,6 +106,10 @@ parameter_types! {
     pub const MaxDurationEras: u8 = 7;
     pub const BlocksPerEra: BlockNumber =  6 * EPOCH_DURATION_IN_BLOCKS;
     pub const MaxIpLength: usize = 256;
+    pub const MaxSweepDevices: u32 = 10;
+    pub const MaxServersPerRegion: u32 = 10;
+    pub const MaxFirmwareLength: u32 = 64;
+    pub const DeviceMigrationCooldown: u64 = 100;
 }
 impl pallet_deeper_node::Config for Test {
     type RuntimeEvent = RuntimeEvent;
@@ -114,6 +120,19 @@ impl pallet_deeper_node::Config for Test {
     type MaxIpLength = MaxIpLength;
     type WeightInfo = ();
     type VerifySignatureInterface = ();
+    type CreditInterface = ();
+    type MaxSweepDevices = MaxSweepDevices;
+    type MaxServersPerRegion = MaxServersPerRegion;
+    type MaxFirmwareLength = MaxFirmwareLength;
+    type UserPrivilegeInterface = UserPrivileges;
+    type DeviceMigration = ();
+    type DeviceMigrationCooldown = DeviceMigrationCooldown;
+}
+
+impl pallet_user_privileges::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+    type WeightInfo = ();
 }
 
 // Build genesis storage according to the mock runtime.
@@ -126,7 +145,8 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
     }
     .assimilate_storage(&mut storage);
 
-    let ext = sp_io::TestExternalities::from(storage);
+    let mut ext = sp_io::TestExternalities::from(storage);
+    ext.register_extension(KeystoreExt(std::sync::Arc::new(KeyStore::new())));
     ext
 }
 
@@ -135,5 +155,6 @@ pub fn run_to_block(n: u64) {
         System::on_finalize(System::block_number());
         System::set_block_number(System::block_number() + 1);
         System::on_initialize(System::block_number());
+        DeeperNode::on_initialize(System::block_number());
     }
 }
@@ -13,9 +13,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{mock::*, Error};
-use frame_support::{assert_err, assert_ok, dispatch::DispatchErrorWithPostInfo};
-use node_primitives::deeper_node::NodeInterface;
+use crate::{mock::*, Error, ReportedOffline, WeightInfo};
+use codec::Encode;
+use frame_support::{
+    assert_err, assert_ok,
+    dispatch::DispatchErrorWithPostInfo,
+    traits::{Currency, GetStorageVersion, Hooks, StorageVersion},
+    Blake2_128Concat, StorageHasher,
+};
+use node_primitives::deeper_node::{protocol, DeviceCapabilities, NodeInterface, ServerFilter};
+use node_primitives::user_privileges::Privilege;
 use sp_core::H160;
 use std::str::FromStr;
 
@@ -353,3 +360,633 @@ fn get_npow_reward() {
         );
     });
 }
+
+#[test]
+fn sweep_offline_devices() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(DeeperNode::im_online(RuntimeOrigin::signed(1)));
+        assert_ok!(DeeperNode::im_online(RuntimeOrigin::signed(2)));
+        run_to_block(BLOCKS_PER_ERA - 1);
+        assert_eq!(DeeperNode::reported_offline(1), None);
+
+        run_to_block(BLOCKS_PER_ERA);
+        assert_eq!(DeeperNode::reported_offline(1), Some(1));
+        assert_eq!(DeeperNode::reported_offline(2), Some(1));
+        let offline_events = System::events()
+            .into_iter()
+            .filter(|r| r.event == RuntimeEvent::DeeperNode(crate::Event::DeviceOffline(1, 1)))
+            .count();
+        assert_eq!(offline_events, 1);
+
+        // online again
+        assert_ok!(DeeperNode::im_online(RuntimeOrigin::signed(1)));
+        assert_eq!(DeeperNode::reported_offline(1), None);
+        run_to_block(BLOCKS_PER_ERA * 2);
+        assert_eq!(DeeperNode::reported_offline(1), Some(1));
+        assert_eq!(DeeperNode::reported_offline(2), Some(2));
+    });
+}
+
+#[test]
+fn sweep_offline_devices_is_bounded() {
+    new_test_ext().execute_with(|| {
+        // nothing to sweep
+        assert_eq!(
+            DeeperNode::sweep_devices(10),
+            <() as WeightInfo>::sweep_devices(0)
+        );
+        for account in 1..=15 {
+            assert_ok!(DeeperNode::im_online(RuntimeOrigin::signed(account)));
+        }
+        System::set_block_number(BLOCKS_PER_ERA);
+        DeeperNode::sweep_devices(10);
+        assert_eq!(ReportedOffline::<Test>::iter().count(), 10);
+        DeeperNode::sweep_devices(10);
+        assert_eq!(ReportedOffline::<Test>::iter().count(), 15);
+        assert!((1..=15).all(|account| DeeperNode::reported_offline(account) == Some(1)));
+    });
+}
+
+#[test]
+fn sweep_expired_servers() {
+    new_test_ext().execute_with(|| {
+        DeeperNode::setup_region_map();
+        assert_ok!(DeeperNode::register_device(
+            RuntimeOrigin::signed(1),
+            vec![1, 2, 3, 4],
+            "US".as_bytes().to_vec()
+        ));
+        assert_ok!(DeeperNode::register_device(
+            RuntimeOrigin::signed(2),
+            vec![1, 2, 3, 4],
+            "US".as_bytes().to_vec()
+        ));
+        assert_ok!(DeeperNode::register_device(
+            RuntimeOrigin::signed(3),
+            vec![1, 2, 3, 4],
+            "CN".as_bytes().to_vec()
+        ));
+        assert_ok!(DeeperNode::register_server(RuntimeOrigin::signed(1), 1));
+        assert_ok!(DeeperNode::register_server(RuntimeOrigin::signed(2), 2));
+        assert_ok!(DeeperNode::register_server(RuntimeOrigin::signed(3), 1));
+
+        run_to_block(BLOCKS_PER_ERA - 1);
+        assert_eq!(
+            DeeperNode::servers_by_country("US".as_bytes().to_vec()),
+            vec![1, 2]
+        );
+
+        run_to_block(BLOCKS_PER_ERA);
+        assert_eq!(
+            DeeperNode::servers_by_country("US".as_bytes().to_vec()),
+            vec![2]
+        );
+        assert_eq!(
+            DeeperNode::servers_by_region("AMN".as_bytes().to_vec()),
+            vec![2]
+        );
+        assert_eq!(
+            DeeperNode::servers_by_region("AMER".as_bytes().to_vec()),
+            vec![2]
+        );
+        assert!(DeeperNode::servers_by_country("CN".as_bytes().to_vec()).is_empty());
+        assert!(DeeperNode::servers_by_region("ASIA".as_bytes().to_vec()).is_empty());
+
+        run_to_block(BLOCKS_PER_ERA * 2);
+        assert!(DeeperNode::servers_by_country("US".as_bytes().to_vec()).is_empty());
+        // devices stay registered
+        assert_eq!(DeeperNode::device_info(2).country, "US".as_bytes().to_vec());
+    });
+}
+
+#[test]
+fn register_server_is_bounded() {
+    new_test_ext().execute_with(|| {
+        DeeperNode::setup_region_map();
+        for account in 1..=11 {
+            let _ = Balances::make_free_balance_be(&account, 500);
+            // US and CA share the level 3 region AMN
+            let country = if account % 2 == 0 { "US" } else { "CA" };
+            assert_ok!(DeeperNode::register_device(
+                RuntimeOrigin::signed(account),
+                vec![1, 2, 3, 4],
+                country.as_bytes().to_vec()
+            ));
+        }
+        for account in 1..=10 {
+            assert_ok!(DeeperNode::register_server(
+                RuntimeOrigin::signed(account),
+                1
+            ));
+        }
+        assert_eq!(
+            DeeperNode::country_server_count("US".as_bytes().to_vec()),
+            5
+        );
+        assert_eq!(
+            DeeperNode::region_server_count("AMN".as_bytes().to_vec()),
+            10
+        );
+        assert_eq!(
+            DeeperNode::register_server(RuntimeOrigin::signed(11), 1),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::TooManyServers
+            ))
+        );
+        // registered servers can still renew
+        assert_ok!(DeeperNode::register_server(RuntimeOrigin::signed(1), 1));
+
+        assert_ok!(DeeperNode::unregister_server(RuntimeOrigin::signed(1)));
+        assert_eq!(
+            DeeperNode::region_server_count("AMN".as_bytes().to_vec()),
+            9
+        );
+        assert_ok!(DeeperNode::register_server(RuntimeOrigin::signed(11), 1));
+        assert_eq!(
+            DeeperNode::servers_by_country("CA".as_bytes().to_vec()),
+            vec![3, 5, 7, 9, 11]
+        );
+    });
+}
+
+#[test]
+fn discover_servers() {
+    new_test_ext().execute_with(|| {
+        DeeperNode::setup_region_map();
+        for account in 1..=4 {
+            assert_ok!(DeeperNode::register_device(
+                RuntimeOrigin::signed(account),
+                vec![1, 2, 3, 4],
+                "US".as_bytes().to_vec()
+            ));
+            assert_ok!(DeeperNode::register_server(
+                RuntimeOrigin::signed(account),
+                2
+            ));
+        }
+        run_to_block(BLOCKS_PER_ERA);
+        // 4 has been offline for an era
+        for account in 1..=3 {
+            assert_ok!(DeeperNode::im_online(RuntimeOrigin::signed(account)));
+        }
+
+        let (first, total) = DeeperNode::discover_country_servers(
+            "US".as_bytes().to_vec(),
+            ServerFilter::default(),
+            7,
+            0,
+            2,
+        );
+        assert_eq!(total, 3);
+        assert_eq!(first.len(), 2);
+        let (second, _) = DeeperNode::discover_country_servers(
+            "US".as_bytes().to_vec(),
+            ServerFilter::default(),
+            7,
+            2,
+            2,
+        );
+        assert_eq!(second.len(), 1);
+        let mut servers = [first.clone(), second].concat();
+        servers.sort();
+        assert_eq!(servers, vec![1, 2, 3]);
+        // same seed, same order
+        assert_eq!(
+            DeeperNode::discover_country_servers(
+                "US".as_bytes().to_vec(),
+                ServerFilter::default(),
+                7,
+                0,
+                2
+            )
+            .0,
+            first
+        );
+
+        let (servers, total) = DeeperNode::discover_region_servers(
+            "AMER".as_bytes().to_vec(),
+            ServerFilter::default(),
+            1,
+            0,
+            10,
+        );
+        assert_eq!((servers.len(), total), (3, 3));
+        assert_eq!(
+            DeeperNode::discover_region_servers(
+                "ASIA".as_bytes().to_vec(),
+                ServerFilter::default(),
+                1,
+                0,
+                10
+            ),
+            (vec![], 0)
+        );
+
+        // expired
+        System::set_block_number(BLOCKS_PER_ERA * 2);
+        assert_eq!(
+            DeeperNode::discover_country_servers(
+                "US".as_bytes().to_vec(),
+                ServerFilter::default(),
+                7,
+                0,
+                10
+            ),
+            (vec![], 0)
+        );
+    });
+}
+
+#[test]
+fn migrate_server_lists() {
+    new_test_ext().execute_with(|| {
+        let country = "US".as_bytes().to_vec();
+        let region = "AMN".as_bytes().to_vec();
+        frame_support::storage::migration::put_storage_value(
+            b"DeeperNode",
+            b"ServersByCountry",
+            &Blake2_128Concat::hash(&country.encode()),
+            vec![1u64, 2],
+        );
+        frame_support::storage::migration::put_storage_value(
+            b"DeeperNode",
+            b"ServersByRegion",
+            &Blake2_128Concat::hash(&region.encode()),
+            vec![1u64, 2, 3],
+        );
+        StorageVersion::new(0).put::<DeeperNode>();
+
+        DeeperNode::on_runtime_upgrade();
+        assert_eq!(DeeperNode::on_chain_storage_version(), 2);
+        assert_eq!(DeeperNode::servers_by_country(country.clone()), vec![1, 2]);
+        assert_eq!(DeeperNode::country_server_count(country), 2);
+        assert_eq!(DeeperNode::servers_by_region(region.clone()), vec![1, 2, 3]);
+        assert_eq!(DeeperNode::region_server_count(region), 3);
+        assert!(
+            frame_support::storage::migration::get_storage_value::<Vec<u64>>(
+                b"DeeperNode",
+                b"ServersByCountry",
+                &Blake2_128Concat::hash(&"US".as_bytes().to_vec().encode()),
+            )
+            .is_none()
+        );
+    });
+}
+
+#[test]
+fn set_capabilities() {
+    new_test_ext().execute_with(|| {
+        let capabilities = DeviceCapabilities {
+            protocols: protocol::TCP | protocol::WIREGUARD,
+            ipv6: "2001:db8::1".as_bytes().to_vec(),
+            bandwidth: 100,
+            price_per_gb: 5,
+            firmware: "3.0.1".as_bytes().to_vec(),
+        };
+        assert_eq!(
+            DeeperNode::set_capabilities(RuntimeOrigin::signed(1), capabilities.clone()),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::DeviceNotRegister
+            ))
+        );
+
+        DeeperNode::setup_region_map();
+        assert_ok!(DeeperNode::register_device(
+            RuntimeOrigin::signed(1),
+            vec![1, 2, 3, 4],
+            "US".as_bytes().to_vec()
+        ));
+        assert_eq!(
+            DeeperNode::set_capabilities(
+                RuntimeOrigin::signed(1),
+                DeviceCapabilities {
+                    firmware: vec![b'1'; 65],
+                    ..capabilities.clone()
+                }
+            ),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::InvalidFirmware
+            ))
+        );
+        assert_eq!(
+            DeeperNode::set_capabilities(
+                RuntimeOrigin::signed(1),
+                DeviceCapabilities {
+                    ipv6: vec![b'1'; 257],
+                    ..capabilities.clone()
+                }
+            ),
+            Err(DispatchErrorWithPostInfo::from(Error::<Test>::InvalidIP))
+        );
+        assert_ok!(DeeperNode::set_capabilities(
+            RuntimeOrigin::signed(1),
+            capabilities.clone()
+        ));
+        assert_eq!(DeeperNode::device_info(1).capabilities(), &capabilities);
+
+        // re-registering keeps the capabilities
+        assert_ok!(DeeperNode::register_device(
+            RuntimeOrigin::signed(1),
+            vec![5, 6, 7, 8],
+            "CA".as_bytes().to_vec()
+        ));
+        assert_eq!(DeeperNode::device_info(1).capabilities(), &capabilities);
+    });
+}
+
+#[test]
+fn discover_servers_with_filter() {
+    new_test_ext().execute_with(|| {
+        DeeperNode::setup_region_map();
+        for account in 1..=3 {
+            assert_ok!(DeeperNode::register_device(
+                RuntimeOrigin::signed(account),
+                vec![1, 2, 3, 4],
+                "US".as_bytes().to_vec()
+            ));
+            assert_ok!(DeeperNode::register_server(
+                RuntimeOrigin::signed(account),
+                2
+            ));
+            assert_ok!(DeeperNode::im_online(RuntimeOrigin::signed(account)));
+        }
+        assert_ok!(DeeperNode::set_capabilities(
+            RuntimeOrigin::signed(1),
+            DeviceCapabilities {
+                protocols: protocol::TCP | protocol::UDP,
+                bandwidth: 100,
+                price_per_gb: 10,
+                ..Default::default()
+            }
+        ));
+        assert_ok!(DeeperNode::set_capabilities(
+            RuntimeOrigin::signed(2),
+            DeviceCapabilities {
+                protocols: protocol::TCP | protocol::WIREGUARD,
+                ipv6: "2001:db8::2".as_bytes().to_vec(),
+                bandwidth: 1000,
+                price_per_gb: 20,
+                ..Default::default()
+            }
+        ));
+
+        let discover = |filter| {
+            let (mut servers, total) =
+                DeeperNode::discover_country_servers("US".as_bytes().to_vec(), filter, 1, 0, 10);
+            servers.sort();
+            (servers, total)
+        };
+        assert_eq!(discover(ServerFilter::default()), (vec![1, 2, 3], 3));
+        assert_eq!(
+            discover(ServerFilter {
+                protocols: protocol::TCP,
+                ..Default::default()
+            }),
+            (vec![1, 2], 2)
+        );
+        assert_eq!(
+            discover(ServerFilter {
+                protocols: protocol::TCP | protocol::UDP,
+                ..Default::default()
+            }),
+            (vec![1], 1)
+        );
+        assert_eq!(
+            discover(ServerFilter {
+                min_bandwidth: 500,
+                ..Default::default()
+            }),
+            (vec![2], 1)
+        );
+        assert_eq!(
+            discover(ServerFilter {
+                protocols: protocol::TCP,
+                max_price_per_gb: Some(15),
+                ..Default::default()
+            }),
+            (vec![1], 1)
+        );
+        assert_eq!(
+            discover(ServerFilter {
+                ipv6: true,
+                ..Default::default()
+            }),
+            (vec![2], 1)
+        );
+    });
+}
+
+#[test]
+fn migrate_node_capabilities() {
+    new_test_ext().execute_with(|| {
+        let old = crate::NodeV1::<u64, u64> {
+            account_id: 1,
+            ipv4: vec![1, 2, 3, 4],
+            country: "US".as_bytes().to_vec(),
+            expire: 10,
+        };
+        frame_support::storage::migration::put_storage_value(
+            b"DeeperNode",
+            b"DeviceInfo",
+            &Blake2_128Concat::hash(&1u64.encode()),
+            old,
+        );
+        StorageVersion::new(1).put::<DeeperNode>();
+
+        DeeperNode::on_runtime_upgrade();
+        assert_eq!(DeeperNode::on_chain_storage_version(), 2);
+        let node = DeeperNode::device_info(1);
+        assert_eq!(node.account_id, 1);
+        assert_eq!(node.ipv4, vec![1, 2, 3, 4]);
+        assert_eq!(node.country, "US".as_bytes().to_vec());
+        assert_eq!(node.expire, 10);
+        assert_eq!(node.capabilities(), &DeviceCapabilities::default());
+    });
+}
+
+#[test]
+fn attest_device() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(
+            DeeperNode::attest_device(RuntimeOrigin::signed(1), 10, vec![0; 64]),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::DeviceNotRegister
+            ))
+        );
+        DeeperNode::setup_region_map();
+        assert_ok!(DeeperNode::register_device(
+            RuntimeOrigin::signed(1),
+            vec![1, 2, 3, 4],
+            "US".as_bytes().to_vec()
+        ));
+        assert_eq!(
+            DeeperNode::attest_device(RuntimeOrigin::signed(1), 10, vec![0; 64]),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::NotManufacturer
+            ))
+        );
+
+        assert_ok!(UserPrivileges::set_user_privilege(
+            RuntimeOrigin::root(),
+            10,
+            Privilege::Manufacturer
+        ));
+        assert_ok!(DeeperNode::attest_device(
+            RuntimeOrigin::signed(1),
+            10,
+            vec![0; 64]
+        ));
+        assert_eq!(DeeperNode::device_manufacturer(1), Some(10));
+        assert!(DeeperNode::is_attested(&1));
+
+        // revoking the manufacturer revokes its devices
+        assert_ok!(UserPrivileges::unset_user_privilege(
+            RuntimeOrigin::root(),
+            10,
+            Privilege::Manufacturer
+        ));
+        assert!(!DeeperNode::is_attested(&1));
+        assert_ok!(UserPrivileges::set_user_privilege(
+            RuntimeOrigin::root(),
+            10,
+            Privilege::Manufacturer
+        ));
+
+        // unregistering drops the attestation
+        assert_ok!(DeeperNode::unregister_device(RuntimeOrigin::signed(1)));
+        assert_eq!(DeeperNode::device_manufacturer(1), None);
+    });
+}
+
+#[test]
+fn attestation_required() {
+    new_test_ext().execute_with(|| {
+        DeeperNode::setup_region_map();
+        for account in 1..=2 {
+            assert_ok!(DeeperNode::register_device(
+                RuntimeOrigin::signed(account),
+                vec![1, 2, 3, 4],
+                "US".as_bytes().to_vec()
+            ));
+        }
+        assert!(DeeperNode::attestation_satisfied(&1));
+
+        assert_eq!(
+            DeeperNode::set_attestation_required(RuntimeOrigin::signed(3), true),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::NotDeviceAdmin
+            ))
+        );
+        assert_ok!(UserPrivileges::set_user_privilege(
+            RuntimeOrigin::root(),
+            3,
+            Privilege::DeviceAdmin
+        ));
+        assert_ok!(DeeperNode::set_attestation_required(
+            RuntimeOrigin::signed(3),
+            true
+        ));
+        assert!(!DeeperNode::attestation_satisfied(&1));
+
+        assert_ok!(UserPrivileges::set_user_privilege(
+            RuntimeOrigin::root(),
+            10,
+            Privilege::Manufacturer
+        ));
+        assert_ok!(DeeperNode::attest_device(
+            RuntimeOrigin::signed(1),
+            10,
+            vec![0; 64]
+        ));
+        assert!(DeeperNode::attestation_satisfied(&1));
+        assert_ok!(DeeperNode::register_server(RuntimeOrigin::signed(1), 1));
+        assert_eq!(
+            DeeperNode::register_server(RuntimeOrigin::signed(2), 1),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::DeviceNotAttested
+            ))
+        );
+        assert_eq!(
+            DeeperNode::servers_by_country("US".as_bytes().to_vec()),
+            vec![1]
+        );
+    });
+}
+
+#[test]
+fn migrate_device() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        assert_eq!(
+            DeeperNode::migrate_device(RuntimeOrigin::signed(1), 1, 2, vec![0; 64]),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::DeviceNotOnboard
+            ))
+        );
+        assert_ok!(DeeperNode::im_online(RuntimeOrigin::signed(1)));
+        RewardsAccountsDeepertoEVM::<Test>::insert(1, H160::zero());
+        RewardsAccountsEVMtoDeeper::<Test>::insert(H160::zero(), 1);
+        assert_eq!(
+            DeeperNode::migrate_device(RuntimeOrigin::signed(3), 1, 2, vec![0; 64]),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::NotDeviceOwner
+            ))
+        );
+        assert_eq!(
+            DeeperNode::migrate_device(RuntimeOrigin::signed(1), 1, 1, vec![0; 64]),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::InvalidMigrationTarget
+            ))
+        );
+
+        // the worst case of the device migration is refunded
+        let post_info =
+            DeeperNode::migrate_device(RuntimeOrigin::signed(1), 1, 2, vec![0; 64]).unwrap();
+        assert_eq!(
+            post_info.actual_weight,
+            Some(<() as WeightInfo>::migrate_device(1))
+        );
+        assert_eq!(DeeperNode::onboard_time(1), None);
+        assert_eq!(DeeperNode::onboard_time(2), Some(1));
+        assert_eq!(DeeperNode::get_im_online(2), Some(1));
+        assert_eq!(DeeperNode::devices_onboard(), vec![2]);
+        assert_eq!(DeeperNode::rewards_accounts_deeper_evm(1), None);
+        assert_eq!(
+            DeeperNode::rewards_accounts_deeper_evm(2),
+            Some(H160::zero())
+        );
+        assert_eq!(
+            DeeperNode::rewards_accounts_evm_deeper(H160::zero()),
+            Some(2)
+        );
+        System::assert_last_event(RuntimeEvent::DeeperNode(crate::Event::DeviceMigrated(1, 2)));
+
+        // a device admin migrates without a signature, but not to an account in use
+        assert_ok!(UserPrivileges::set_user_privilege(
+            RuntimeOrigin::root(),
+            10,
+            Privilege::DeviceAdmin
+        ));
+        assert_ok!(DeeperNode::im_online(RuntimeOrigin::signed(3)));
+        assert_eq!(
+            DeeperNode::migrate_device(RuntimeOrigin::signed(10), 2, 3, vec![]),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::InvalidMigrationTarget
+            ))
+        );
+        assert_eq!(
+            DeeperNode::migrate_device(RuntimeOrigin::signed(10), 2, 4, vec![]),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::MigrationCooldown
+            ))
+        );
+        System::set_block_number(101);
+        assert_ok!(DeeperNode::migrate_device(
+            RuntimeOrigin::signed(10),
+            2,
+            4,
+            vec![]
+        ));
+        assert_eq!(DeeperNode::onboard_time(4), Some(1));
+    });
+}
@@ -52,10 +52,15 @@ pub trait WeightInfo {
     fn register_server() -> Weight;
     fn update_server() -> Weight;
     fn unregister_server() -> Weight;
+    fn set_capabilities() -> Weight;
+    fn attest_device() -> Weight;
+    fn set_attestation_required() -> Weight;
+    fn migrate_device(d: u32) -> Weight;
     fn im_online() -> Weight;
     fn report_credit_proof() -> Weight;
     fn reward_mapping() -> Weight;
     fn get_npow_reward() -> Weight;
+    fn sweep_devices(n: u32) -> Weight;
 }
 
 /// Weights for pallet_deeper_node using the Substrate node and recommended hardware.
@@ -86,10 +91,32 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
             .saturating_add(T::DbWeight::get().reads(6 as u64))
             .saturating_add(T::DbWeight::get().writes(4 as u64))
     }
+    fn set_capabilities() -> Weight {
+        Weight::from_ref_time(21_480_000 as u64)
+            .saturating_add(T::DbWeight::get().reads(1 as u64))
+            .saturating_add(T::DbWeight::get().writes(1 as u64))
+    }
+    fn attest_device() -> Weight {
+        Weight::from_ref_time(64_215_000 as u64)
+            .saturating_add(T::DbWeight::get().reads(2 as u64))
+            .saturating_add(T::DbWeight::get().writes(1 as u64))
+    }
+    fn set_attestation_required() -> Weight {
+        Weight::from_ref_time(16_870_000 as u64)
+            .saturating_add(T::DbWeight::get().reads(1 as u64))
+            .saturating_add(T::DbWeight::get().writes(1 as u64))
+    }
+    // not measured yet, regenerate with `--extrinsic=migrate_device`
+    fn migrate_device(d: u32) -> Weight {
+        Weight::from_ref_time(61_205_000 as u64)
+            .saturating_add(Weight::from_ref_time(118_000 as u64).saturating_mul(d as u64))
+            .saturating_add(T::DbWeight::get().reads(17 as u64))
+            .saturating_add(T::DbWeight::get().writes(12 as u64))
+    }
     fn im_online() -> Weight {
         Weight::from_ref_time(19_331_000 as u64)
             .saturating_add(T::DbWeight::get().reads(2 as u64))
-            .saturating_add(T::DbWeight::get().writes(3 as u64))
+            .saturating_add(T::DbWeight::get().writes(4 as u64))
     }
     fn report_credit_proof() -> Weight {
         Weight::from_ref_time(77_938_000 as u64)
@@ -104,6 +131,15 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
     fn get_npow_reward() -> Weight {
         Weight::from_ref_time(16_571_000 as u64).saturating_add(T::DbWeight::get().reads(1 as u64))
     }
+    // not measured yet, regenerate with `--extrinsic=sweep_devices`
+    fn sweep_devices(n: u32) -> Weight {
+        Weight::from_ref_time(12_416_000 as u64)
+            .saturating_add(Weight::from_ref_time(68_402_000 as u64).saturating_mul(n as u64))
+            .saturating_add(T::DbWeight::get().reads(4 as u64))
+            .saturating_add(T::DbWeight::get().reads((7 as u64).saturating_mul(n as u64)))
+            .saturating_add(T::DbWeight::get().writes(2 as u64))
+            .saturating_add(T::DbWeight::get().writes((4 as u64).saturating_mul(n as u64)))
+    }
 }
 
 // For backwards compatibility and tests
@@ -133,10 +169,32 @@ impl WeightInfo for () {
             .saturating_add(RocksDbWeight::get().reads(6 as u64))
             .saturating_add(RocksDbWeight::get().writes(4 as u64))
     }
+    fn set_capabilities() -> Weight {
+        Weight::from_ref_time(21_480_000 as u64)
+            .saturating_add(RocksDbWeight::get().reads(1 as u64))
+            .saturating_add(RocksDbWeight::get().writes(1 as u64))
+    }
+    fn attest_device() -> Weight {
+        Weight::from_ref_time(64_215_000 as u64)
+            .saturating_add(RocksDbWeight::get().reads(2 as u64))
+            .saturating_add(RocksDbWeight::get().writes(1 as u64))
+    }
+    fn set_attestation_required() -> Weight {
+        Weight::from_ref_time(16_870_000 as u64)
+            .saturating_add(RocksDbWeight::get().reads(1 as u64))
+            .saturating_add(RocksDbWeight::get().writes(1 as u64))
+    }
+    // not measured yet, regenerate with `--extrinsic=migrate_device`
+    fn migrate_device(d: u32) -> Weight {
+        Weight::from_ref_time(61_205_000 as u64)
+            .saturating_add(Weight::from_ref_time(118_000 as u64).saturating_mul(d as u64))
+            .saturating_add(RocksDbWeight::get().reads(17 as u64))
+            .saturating_add(RocksDbWeight::get().writes(12 as u64))
+    }
     fn im_online() -> Weight {
         Weight::from_ref_time(19_331_000 as u64)
             .saturating_add(RocksDbWeight::get().reads(2 as u64))
-            .saturating_add(RocksDbWeight::get().writes(3 as u64))
+            .saturating_add(RocksDbWeight::get().writes(4 as u64))
     }
     fn report_credit_proof() -> Weight {
         Weight::from_ref_time(77_938_000 as u64)
@@ -152,4 +210,13 @@ impl WeightInfo for () {
         Weight::from_ref_time(16_571_000 as u64)
             .saturating_add(RocksDbWeight::get().reads(1 as u64))
     }
+    // not measured yet, regenerate with `--extrinsic=sweep_devices`
+    fn sweep_devices(n: u32) -> Weight {
+        Weight::from_ref_time(12_416_000 as u64)
+            .saturating_add(Weight::from_ref_time(68_402_000 as u64).saturating_mul(n as u64))
+            .saturating_add(RocksDbWeight::get().reads(4 as u64))
+            .saturating_add(RocksDbWeight::get().reads((7 as u64).saturating_mul(n as u64)))
+            .saturating_add(RocksDbWeight::get().writes(2 as u64))
+            .saturating_add(RocksDbWeight::get().writes((4 as u64).saturating_mul(n as u64)))
+    }
 }
@@ -24,14 +24,18 @@ use crate::Pallet as Micropayment;
 pub use frame_benchmarking::{
     account, benchmarks, impl_benchmark_test_suite, whitelist_account, whitelisted_caller,
 };
-use frame_support::traits::Currency;
+use frame_support::traits::{
+    tokens::fungibles::{Create, Mutate},
+    Currency, Get,
+};
 use frame_system::Pallet as System;
 use frame_system::RawOrigin;
 //use hex_literal::hex;
-use codec::Encode;
+use codec::{Decode, Encode};
 use node_primitives::AccountCreator;
-use sp_core::sr25519;
-use sp_io::crypto::sr25519_sign;
+use sp_core::{sr25519, H256};
+use sp_io::crypto::{sr25519_generate, sr25519_sign};
+use sp_runtime::Percent;
 
 //  use sp_core::sr25519::{Pair,Public};
 //  use sp_core::Pair as OtherPair;
@@ -59,7 +63,60 @@ pub fn create_funded_user<T: Config>(string: &'static str, balance_factor: u32)
     user
 }
 
+/// Grab a funded user whose sr25519 key is generated from the index.
+fn create_funded_client<T: Config>(index: u32, balance_factor: u32) -> T::AccountId {
+    let public = sr25519_generate(0.into(), Some((b"//client", index).encode()));
+    let user = T::AccountId::decode(&mut &public.encode()[..]).unwrap();
+    let balance = T::Currency::minimum_balance() * balance_factor.into();
+    T::Currency::make_free_balance_be(&user, balance);
+    T::Currency::issue(balance);
+    user
+}
+
+/// Sign the message with the sr25519 key of the benchmark account.
+fn sign<T: Config>(account: &T::AccountId, msg: &[u8; 32]) -> sr25519::Signature {
+    let mut pk = [0u8; 32];
+    pk.copy_from_slice(&account.encode());
+    let pub_key = sr25519::Public::from_raw(pk);
+    sr25519_sign(0.into(), &pub_key, msg).unwrap()
+}
+
+/// Lock `l` payments of the minimum balance in the channel, all still pending.
+fn add_locks<T: Config>(
+    client: &T::AccountId,
+    server: &T::AccountId,
+    l: u32,
+) -> Result<(), &'static str> {
+    let lock_amount = T::Currency::minimum_balance();
+    let expiration: T::BlockNumber = 100u32.into();
+    for i in 0..l {
+        let hashlock = H256::from(sp_io::hashing::blake2_256(&(b"lock", i).encode()));
+        let msg = Micropayment::<T>::construct_lock_hash(
+            server,
+            0,
+            &hashlock,
+            lock_amount,
+            expiration,
+            server,
+        );
+        let signature: [u8; 64] = sign::<T>(client, &msg).into();
+        Micropayment::<T>::lock_payment(
+            RawOrigin::Signed(server.clone()).into(),
+            client.clone(),
+            hashlock,
+            lock_amount,
+            expiration,
+            server.clone(),
+            signature.into(),
+        )
+        .map_err(|e| e.error)?;
+    }
+    Ok(())
+}
+
 benchmarks! {
+    where_clause { where T::Assets: Create<T::AccountId>, AssetIdOf<T>: From<u32> }
+
     open_channel {
         let client = create_funded_user::<T>("Alice", 100);
         let server = create_funded_user::<T>("Bob", 100);
@@ -74,12 +131,33 @@ benchmarks! {
                 balance: amount,
                 nonce: 0,
                 opened: 1u32.into(),
-                expiration: 721u32.into()
+                expiration: 721u32.into(),
+                asset: None
             }
         );
     }
 
+    open_asset_channel {
+        let client = create_funded_user::<T>("Alice", 100);
+        let server = create_funded_user::<T>("Bob", 100);
+        let asset: AssetIdOf<T> = 1000u32.into();
+        let amount = T::Currency::minimum_balance() * 30u32.into();
+        T::Assets::create(asset, client.clone(), true, 1u32.into())?;
+        T::Assets::mint_into(asset, &client, amount * 2u32.into())?;
+    }: _(RawOrigin::Signed(client.clone()), server.clone(), asset, amount, 3600)
+    verify {
+        assert_eq!(Micropayment::<T>::channel(&client, &server).asset, Some(asset));
+    }
+
+    set_asset_burn_rate {
+        let asset: AssetIdOf<T> = 1000u32.into();
+    }: _(RawOrigin::Root, asset, Some(Percent::from_percent(20)))
+    verify {
+        assert_eq!(Micropayment::<T>::asset_burn_rate(asset), Some(Percent::from_percent(20)));
+    }
+
     close_channel {
+        let l in 0 .. T::MaxPendingLocks::get();
         let client = create_funded_user::<T>("Alice", 100);
         let server = create_funded_user::<T>("Bob", 100);
         let amount = T::Currency::minimum_balance() * 30u32.into();
@@ -93,15 +171,18 @@ benchmarks! {
                 balance: amount,
                 nonce: 0,
                 opened: 0u32.into(),
-                expiration: 720u32.into()
+                expiration: 720u32.into(),
+                asset: None
             }
         );
+        add_locks::<T>(&client, &server, l)?;
     }: _(RawOrigin::Signed(server.clone()), client.clone())
     verify {
         assert!(!Channel::<T>::contains_key(client, server));
     }
 
     close_expired_channels {
+        let l in 0 .. T::MaxPendingLocks::get();
         let client = create_funded_user::<T>("Alice", 100);
         let server = create_funded_user::<T>("Bob", 100);
         let amount = T::Currency::minimum_balance() * 30u32.into();
@@ -115,9 +196,11 @@ benchmarks! {
                 balance: amount,
                 nonce: 0,
                 opened: 0u32.into(),
-                expiration: 720u32.into()
+                expiration: 720u32.into(),
+                asset: None
             }
         );
+        add_locks::<T>(&client, &server, l)?;
         System::<T>::set_block_number(722u32.into());
     }: _(RawOrigin::Signed(client.clone()))
     verify {
@@ -138,7 +221,8 @@ benchmarks! {
                 balance: amount,
                 nonce: 0,
                 opened: 0u32.into(),
-                expiration: 720u32.into()
+                expiration: 720u32.into(),
+                asset: None
             }
         );
 
@@ -153,7 +237,8 @@ benchmarks! {
                 balance: amount + add_amount,
                 nonce: 0,
                 opened: 0u32.into(),
-                expiration: 720u32.into()
+                expiration: 720u32.into(),
+                asset: None
             }
         );
     }
@@ -172,7 +257,8 @@ benchmarks! {
                 balance: amount,
                 nonce: 0,
                 opened: 0u32.into(),
-                expiration: 720u32.into()
+                expiration: 720u32.into(),
+                asset: None
             }
         );
 
@@ -197,11 +283,186 @@ benchmarks! {
                 balance: balance_of_chain,
                 nonce: 0,
                 opened: 0u32.into(),
-                expiration: 720u32.into()
+                expiration: 720u32.into(),
+                asset: None
             }
         );
 
     }
+
+    lock_payment {
+        let client = create_funded_user::<T>("Alice", 100);
+        let server = create_funded_user::<T>("Bob", 100);
+        let amount = T::Currency::minimum_balance() * 30u32.into();
+
+        Micropayment::<T>::open_channel(RawOrigin::Signed(client.clone()).into(), server.clone(), amount, 3600)?;
+
+        let hashlock = H256::from(sp_io::hashing::blake2_256(&[1u8; 32]));
+        let lock_amount = T::Currency::minimum_balance() * 10u32.into();
+        let expiration: T::BlockNumber = 100u32.into();
+        let msg = Micropayment::<T>::construct_lock_hash(&server, 0, &hashlock, lock_amount, expiration, &server);
+
+        let mut pk = [0u8; 32];
+        pk.copy_from_slice(&client.encode());
+        let pub_key = sr25519::Public::from_raw(pk);
+
+        let signature: [u8; 64] = sr25519_sign(0.into(), &pub_key, &msg).unwrap().into();
+    }: _(RawOrigin::Signed(server.clone()), client.clone(), hashlock, lock_amount, expiration, server.clone(), signature.into())
+    verify {
+        assert!(PendingLocks::<T>::contains_key((client, server), hashlock));
+    }
+
+    claim_locked_payment {
+        let client = create_funded_user::<T>("Alice", 100);
+        let server = create_funded_user::<T>("Bob", 100);
+        let amount = T::Currency::minimum_balance() * 30u32.into();
+
+        Micropayment::<T>::open_channel(RawOrigin::Signed(client.clone()).into(), server.clone(), amount, 3600)?;
+
+        let preimage = [1u8; 32];
+        let hashlock = H256::from(sp_io::hashing::blake2_256(&preimage));
+        let lock_amount = T::Currency::minimum_balance() * 10u32.into();
+        let expiration: T::BlockNumber = 100u32.into();
+        let msg = Micropayment::<T>::construct_lock_hash(&server, 0, &hashlock, lock_amount, expiration, &server);
+
+        let mut pk = [0u8; 32];
+        pk.copy_from_slice(&client.encode());
+        let pub_key = sr25519::Public::from_raw(pk);
+
+        let signature: [u8; 64] = sr25519_sign(0.into(), &pub_key, &msg).unwrap().into();
+        Micropayment::<T>::lock_payment(RawOrigin::Signed(server.clone()).into(), client.clone(), hashlock, lock_amount, expiration, server.clone(), signature.into())?;
+    }: _(RawOrigin::Signed(server.clone()), client.clone(), preimage)
+    verify {
+        assert!(!PendingLocks::<T>::contains_key((client, server), hashlock));
+    }
+
+    refund_locked_payment {
+        let client = create_funded_user::<T>("Alice", 100);
+        let server = create_funded_user::<T>("Bob", 100);
+        let amount = T::Currency::minimum_balance() * 30u32.into();
+
+        Micropayment::<T>::open_channel(RawOrigin::Signed(client.clone()).into(), server.clone(), amount, 3600)?;
+
+        let hashlock = H256::from(sp_io::hashing::blake2_256(&[1u8; 32]));
+        let lock_amount = T::Currency::minimum_balance() * 10u32.into();
+        let expiration: T::BlockNumber = 100u32.into();
+        let msg = Micropayment::<T>::construct_lock_hash(&server, 0, &hashlock, lock_amount, expiration, &server);
+
+        let mut pk = [0u8; 32];
+        pk.copy_from_slice(&client.encode());
+        let pub_key = sr25519::Public::from_raw(pk);
+
+        let signature: [u8; 64] = sr25519_sign(0.into(), &pub_key, &msg).unwrap().into();
+        Micropayment::<T>::lock_payment(RawOrigin::Signed(server.clone()).into(), client.clone(), hashlock, lock_amount, expiration, server.clone(), signature.into())?;
+        System::<T>::set_block_number(101u32.into());
+    }: _(RawOrigin::Signed(client.clone()), server.clone(), hashlock)
+    verify {
+        assert_eq!(Micropayment::<T>::channel(&client, &server).balance, amount);
+    }
+
+    cooperative_close {
+        let l in 0 .. T::MaxPendingLocks::get();
+        let client = create_funded_user::<T>("Alice", 100);
+        let server = create_funded_user::<T>("Bob", 100);
+        let amount = T::Currency::minimum_balance() * 30u32.into();
+
+        Micropayment::<T>::open_channel(RawOrigin::Signed(client.clone()).into(), server.clone(), amount, 3600)?;
+
+        add_locks::<T>(&client, &server, l)?;
+
+        let settle_amount = T::Currency::minimum_balance() * 10u32.into();
+        let msg = Micropayment::<T>::construct_close_hash(&client, &server, 0, settle_amount);
+        let client_signature: [u8; 64] = sign::<T>(&client, &msg).into();
+        let server_signature: [u8; 64] = sign::<T>(&server, &msg).into();
+    }: _(RawOrigin::Signed(server.clone()), client.clone(), settle_amount, client_signature.into(), server_signature.into())
+    verify {
+        assert!(!Channel::<T>::contains_key(client, server));
+    }
+
+    initiate_close {
+        let client = create_funded_user::<T>("Alice", 100);
+        let server = create_funded_user::<T>("Bob", 100);
+        let amount = T::Currency::minimum_balance() * 30u32.into();
+
+        Micropayment::<T>::open_channel(RawOrigin::Signed(client.clone()).into(), server.clone(), amount, 3600)?;
+
+        let settle_amount = T::Currency::minimum_balance() * 10u32.into();
+        let msg = Micropayment::<T>::construct_byte_array_and_hash(&server, 0, 1, settle_amount);
+        let signature: [u8; 64] = sign::<T>(&client, &msg).into();
+    }: _(RawOrigin::Signed(server.clone()), client.clone(), 1, settle_amount, signature.into())
+    verify {
+        assert!(PendingCloses::<T>::contains_key(client, server));
+    }
+
+    dispute_close {
+        let client = create_funded_user::<T>("Alice", 100);
+        let server = create_funded_user::<T>("Bob", 100);
+        let amount = T::Currency::minimum_balance() * 30u32.into();
+
+        Micropayment::<T>::open_channel(RawOrigin::Signed(client.clone()).into(), server.clone(), amount, 3600)?;
+
+        let old_amount = T::Currency::minimum_balance() * 10u32.into();
+        let msg = Micropayment::<T>::construct_byte_array_and_hash(&server, 0, 1, old_amount);
+        let signature: [u8; 64] = sign::<T>(&client, &msg).into();
+        Micropayment::<T>::initiate_close(RawOrigin::Signed(client.clone()).into(), server.clone(), 1, old_amount, signature.into())?;
+
+        let new_amount = T::Currency::minimum_balance() * 20u32.into();
+        let msg = Micropayment::<T>::construct_byte_array_and_hash(&server, 0, 2, new_amount);
+        let signature: [u8; 64] = sign::<T>(&client, &msg).into();
+    }: _(RawOrigin::Signed(server.clone()), client.clone(), 2, new_amount, signature.into())
+    verify {
+        assert_eq!(
+            PendingCloses::<T>::get(client, server).map(|c| (c.amount, c.settled)),
+            Some((new_amount, old_amount))
+        );
+    }
+
+    claim_payments_batch {
+        let n in 1 .. T::MaxBatchClaims::get();
+        let server = create_funded_user::<T>("Bob", 100);
+        let amount = T::Currency::minimum_balance() * 30u32.into();
+        let claim_amount = T::Currency::minimum_balance() * 10u32.into();
+
+        let mut vouchers = Vec::new();
+        for i in 0 .. n {
+            let client = create_funded_client::<T>(i, 100);
+            Micropayment::<T>::open_channel(RawOrigin::Signed(client.clone()).into(), server.clone(), amount, 3600)?;
+            let msg = Micropayment::<T>::construct_byte_array_and_hash(&server, 0, 1, claim_amount);
+            let signature: [u8; 64] = sign::<T>(&client, &msg).into();
+            vouchers.push(VoucherOf::<T> {
+                client,
+                session_id: 1,
+                amount: claim_amount,
+                signature: signature.into(),
+            });
+        }
+    }: _(RawOrigin::Signed(server.clone()), vouchers.clone())
+    verify {
+        for voucher in vouchers {
+            assert_eq!(Micropayment::<T>::session_id((&voucher.client, &server)), Some(1));
+        }
+    }
+
+    finalize_close {
+        let l in 0 .. T::MaxPendingLocks::get();
+        let client = create_funded_user::<T>("Alice", 100);
+        let server = create_funded_user::<T>("Bob", 100);
+        let amount = T::Currency::minimum_balance() * 30u32.into();
+
+        Micropayment::<T>::open_channel(RawOrigin::Signed(client.clone()).into(), server.clone(), amount, 3600)?;
+
+        add_locks::<T>(&client, &server, l)?;
+
+        let settle_amount = T::Currency::minimum_balance() * 10u32.into();
+        let msg = Micropayment::<T>::construct_byte_array_and_hash(&server, 0, 1, settle_amount);
+        let signature: [u8; 64] = sign::<T>(&client, &msg).into();
+        Micropayment::<T>::initiate_close(RawOrigin::Signed(server.clone()).into(), client.clone(), 1, settle_amount, signature.into())?;
+        let dispute_end = System::<T>::block_number() + T::ChannelDisputePeriod::get();
+        System::<T>::set_block_number(dispute_end + 1u32.into());
+    }: _(RawOrigin::Signed(client.clone()), client.clone(), server.clone())
+    verify {
+        assert!(!Channel::<T>::contains_key(client, server));
+    }
 }
 
 #[cfg(test)]
@@ -217,6 +478,14 @@ mod tests {
             assert_ok!(Pallet::<Test>::test_benchmark_close_channel());
             assert_ok!(Pallet::<Test>::test_benchmark_close_expired_channels());
             assert_ok!(Pallet::<Test>::test_benchmark_add_balance());
+            assert_ok!(Pallet::<Test>::test_benchmark_claim_payments_batch());
+            assert_ok!(Pallet::<Test>::test_benchmark_lock_payment());
+            assert_ok!(Pallet::<Test>::test_benchmark_claim_locked_payment());
+            assert_ok!(Pallet::<Test>::test_benchmark_refund_locked_payment());
+            assert_ok!(Pallet::<Test>::test_benchmark_cooperative_close());
+            assert_ok!(Pallet::<Test>::test_benchmark_initiate_close());
+            assert_ok!(Pallet::<Test>::test_benchmark_dispute_close());
+            assert_ok!(Pallet::<Test>::test_benchmark_finalize_close());
             //assert_ok!(test_benchmark_claim_payment::<Test>()); // Move to test.rs
         });
     }
@@ -44,24 +44,32 @@ pub mod weights;
 pub mod pallet {
     use crate::weights::WeightInfo;
     use frame_support::codec::{Decode, Encode};
+    use frame_support::traits::tokens::fungibles;
     use frame_support::traits::{
         tokens::currency::Currency, ExistenceRequirement, Get, OnUnbalanced, WithdrawReasons,
     };
     use frame_support::{
-        dispatch::{DispatchError, DispatchResultWithPostInfo},
+        dispatch::{DispatchError, DispatchResult, DispatchResultWithPostInfo},
         pallet_prelude::*,
+        transactional, PalletId,
     };
     use frame_system::pallet_prelude::*;
     use node_primitives::{credit::CreditInterface, deeper_node::NodeInterface};
     use sp_core::crypto::UncheckedFrom;
-    use sp_core::sr25519;
+    use sp_core::{sr25519, H256};
     use sp_io::crypto::sr25519_verify;
-    use sp_runtime::Percent;
+    use sp_runtime::{
+        traits::{AccountIdConversion, Zero},
+        Percent,
+    };
     use sp_std::prelude::Vec;
 
     #[cfg(feature = "runtime-benchmarks")]
     use node_primitives::AccountCreator;
 
+    /// holder of the funds locked in asset channels
+    const ESCROW_PALLET_ID: PalletId = PalletId(*b"dpr/mcpy");
+
     /// Configure the pallet by specifying the parameters and types on which it depends.
     #[pallet::config]
     pub trait Config: frame_system::Config {
@@ -76,42 +84,62 @@ pub mod pallet {
         type DataPerDPR: Get<u64>;
         // Weight information for extrinsics in this pallet.
         type WeightInfo: WeightInfo;
+        /// Assets that channels can be denominated in besides DPR, e.g. ADSC
+        type Assets: fungibles::Mutate<Self::AccountId, Balance = BalanceOf<Self>>
+            + fungibles::Transfer<Self::AccountId>;
         /// NodeInterface of deeper-node pallet
         type NodeInterface: NodeInterface<Self::AccountId, Self::BlockNumber>;
 
         type MicropaymentBurn: Get<Percent>;
 
         type Slash: OnUnbalanced<NegativeImbalanceOf<Self>>;
+        /// Maximum number of hash-locked payments in one channel, pending or settled
+        #[pallet::constant]
+        type MaxPendingLocks: Get<u32>;
+        /// Number of blocks the counterparty has to submit a newer state
+        /// after a channel is closed unilaterally
+        #[pallet::constant]
+        type ChannelDisputePeriod: Get<Self::BlockNumber>;
+        /// Maximum number of vouchers claimed in one batch
+        #[pallet::constant]
+        type MaxBatchClaims: Get<u32>;
         #[cfg(feature = "runtime-benchmarks")]
         type AccountCreator: AccountCreator<Self::AccountId>;
     }
 
-    type BalanceOf<T> =
+    pub type BalanceOf<T> =
         <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
     pub type NegativeImbalanceOf<T> = <<T as Config>::Currency as Currency<
         <T as frame_system::Config>::AccountId,
     >>::NegativeImbalance;
 
+    pub type AssetIdOf<T> = <<T as Config>::Assets as fungibles::Inspect<
+        <T as frame_system::Config>::AccountId,
+    >>::AssetId;
+
     pub type ChannelOf<T> = Chan<
         <T as frame_system::Config>::AccountId,
         <T as frame_system::Config>::BlockNumber,
         BalanceOf<T>,
+        AssetIdOf<T>,
     >;
 
     // struct to store micro-payment channel
     #[derive(Decode, Encode, Eq, PartialEq, Debug, scale_info::TypeInfo)]
-    pub struct Chan<AccountId, BlockNumber, Balance> {
+    pub struct Chan<AccountId, BlockNumber, Balance, AssetId> {
         pub client: AccountId,
         pub server: AccountId,
         pub balance: Balance,
         pub nonce: u64,
         pub opened: BlockNumber,
         pub expiration: BlockNumber,
+        // asset of the channel, None for DPR
+        pub asset: Option<AssetId>,
     }
 
-    impl<AccountId: Decode, BlockNumber: Default, Balance: Default> Default
-        for Chan<AccountId, BlockNumber, Balance>
+    impl<AccountId: Decode, BlockNumber: Default, Balance: Default, AssetId> Default
+        for Chan<AccountId, BlockNumber, Balance, AssetId>
     {
         fn default() -> Self {
             Self {
@@ -123,12 +151,84 @@ pub mod pallet {
                 nonce: Default::default(),
                 opened: Default::default(),
                 expiration: Default::default(),
+                asset: None,
             }
         }
     }
 
+    pub type HashLockOf<T> = HashLock<
+        <T as frame_system::Config>::AccountId,
+        <T as frame_system::Config>::BlockNumber,
+        BalanceOf<T>,
+    >;
+
+    // struct to store a hash-locked conditional payment of a channel.
+    // The payee of the channel claims the amount by revealing the preimage of
+    // the hashlock before expiration, otherwise the amount is refunded to the channel.
+    #[derive(Decode, Encode, Eq, PartialEq, Debug, scale_info::TypeInfo)]
+    pub struct HashLock<AccountId, BlockNumber, Balance> {
+        pub amount: Balance,
+        pub expiration: BlockNumber,
+        // final server of a hub-routed payment
+        pub recipient: AccountId,
+    }
+
+    pub type CloseStateOf<T> = CloseState<
+        <T as frame_system::Config>::AccountId,
+        <T as frame_system::Config>::BlockNumber,
+        BalanceOf<T>,
+    >;
+
+    // struct to store the state of a channel being closed unilaterally.
+    // The close covers the unclaimed sessions up to `session_id`, starting from the
+    // next session to claim.
+    #[derive(Decode, Encode, Eq, PartialEq, Debug, scale_info::TypeInfo)]
+    pub struct CloseState<AccountId, BlockNumber, Balance> {
+        // latest session in the close
+        pub session_id: u32,
+        // amount of the latest session, signed by the client
+        pub amount: Balance,
+        // total amount of the earlier sessions in the close
+        pub settled: Balance,
+        pub initiator: AccountId,
+        // block after which the channel can be settled
+        pub dispute_end: BlockNumber,
+    }
+
+    pub type VoucherOf<T> = Voucher<<T as frame_system::Config>::AccountId, BalanceOf<T>>;
+
+    // signed micropayment of a client, claimed by the server in batch
+    #[derive(Decode, Encode, Clone, Eq, PartialEq, Debug, scale_info::TypeInfo)]
+    pub struct Voucher<AccountId, Balance> {
+        pub client: AccountId,
+        pub session_id: u32,
+        pub amount: Balance,
+        pub signature: Vec<u8>,
+    }
+
+    type OldChannelOf<T> = OldChan<
+        <T as frame_system::Config>::AccountId,
+        <T as frame_system::Config>::BlockNumber,
+        BalanceOf<T>,
+    >;
+
+    // channel layout before multi-asset support
+    #[derive(Decode, Encode)]
+    struct OldChan<AccountId, BlockNumber, Balance> {
+        client: AccountId,
+        server: AccountId,
+        balance: Balance,
+        nonce: u64,
+        opened: BlockNumber,
+        expiration: BlockNumber,
+    }
+
+    /// The current storage version.
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
     #[pallet::pallet]
     #[pallet::generate_store(pub(super) trait Store)]
+    #[pallet::storage_version(STORAGE_VERSION)]
     #[pallet::without_storage_info]
     pub struct Pallet<T>(_);
 
@@ -164,6 +264,77 @@ pub mod pallet {
     pub(super) type TotalMicropaymentChannelBalance<T: Config> =
         StorageMap<_, Blake2_128Concat, T::AccountId, BalanceOf<T>, OptionQuery>;
 
+    // record total balance of accountId in channels of the asset
+    #[pallet::storage]
+    #[pallet::getter(fn total_asset_channel_balance)]
+    pub(super) type TotalAssetChannelBalance<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        AssetIdOf<T>,
+        BalanceOf<T>,
+        OptionQuery,
+    >;
+
+    // burn rate of the asset, MicropaymentBurn is used if not set
+    #[pallet::storage]
+    #[pallet::getter(fn asset_burn_rate)]
+    pub(super) type AssetBurnRate<T: Config> =
+        StorageMap<_, Blake2_128Concat, AssetIdOf<T>, Percent, OptionQuery>;
+
+    // total amount of the asset burned by micropayments
+    #[pallet::storage]
+    #[pallet::getter(fn total_asset_burned)]
+    pub(super) type TotalAssetBurned<T: Config> =
+        StorageMap<_, Blake2_128Concat, AssetIdOf<T>, BalanceOf<T>, ValueQuery>;
+
+    // pending hash-locked payments of channel (client, server), keyed by hashlock
+    #[pallet::storage]
+    #[pallet::getter(fn pending_lock)]
+    pub(super) type PendingLocks<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        (T::AccountId, T::AccountId),
+        Blake2_128Concat,
+        H256,
+        HashLockOf<T>,
+        OptionQuery,
+    >;
+
+    // number of hash-locked payments made in channel (client, server), pending or settled,
+    // bounds the locks cleaned up when the channel closes
+    #[pallet::storage]
+    #[pallet::getter(fn lock_count)]
+    pub(super) type LockCount<T: Config> =
+        StorageMap<_, Blake2_128Concat, (T::AccountId, T::AccountId), u32, ValueQuery>;
+
+    // hashlocks already claimed or refunded in the current channel,
+    // prevents the same lock signature from being replayed
+    #[pallet::storage]
+    pub(super) type SettledLocks<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        (T::AccountId, T::AccountId),
+        Blake2_128Concat,
+        H256,
+        (),
+        OptionQuery,
+    >;
+
+    // state of channel (client, server) in dispute period
+    #[pallet::storage]
+    #[pallet::getter(fn pending_close)]
+    pub(super) type PendingCloses<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        T::AccountId,
+        CloseStateOf<T>,
+        OptionQuery,
+    >;
+
     // Pallets use events to inform users when important changes are made.
     // https://substrate.dev/docs/en/knowledgebase/runtime/events
     #[pallet::event]
@@ -181,6 +352,45 @@ pub mod pallet {
         ChannelClosed(T::AccountId, T::AccountId, T::BlockNumber),
         ClaimPayment(T::AccountId, T::AccountId, BalanceOf<T>),
         BalanceAdded(T::AccountId, T::AccountId, BalanceOf<T>, T::BlockNumber),
+        /// client, server, asset, balance, nonce, opened, expiration
+        AssetChannelOpened(
+            T::AccountId,
+            T::AccountId,
+            AssetIdOf<T>,
+            BalanceOf<T>,
+            u64,
+            T::BlockNumber,
+            T::BlockNumber,
+        ),
+        /// asset, burn rate
+        AssetBurnRateSet(AssetIdOf<T>, Option<Percent>),
+        /// client, server, hashlock, amount, expiration, recipient
+        PaymentLocked(
+            T::AccountId,
+            T::AccountId,
+            H256,
+            BalanceOf<T>,
+            T::BlockNumber,
+            T::AccountId,
+        ),
+        /// client, server, hashlock, preimage, amount
+        LockedPaymentClaimed(T::AccountId, T::AccountId, H256, [u8; 32], BalanceOf<T>),
+        /// client, server, hashlock, amount
+        LockedPaymentRefunded(T::AccountId, T::AccountId, H256, BalanceOf<T>),
+        /// client, server, session_id, amount, dispute_end
+        ChannelCloseInitiated(
+            T::AccountId,
+            T::AccountId,
+            u32,
+            BalanceOf<T>,
+            T::BlockNumber,
+        ),
+        /// client, server, session_id, amount
+        ChannelCloseDisputed(T::AccountId, T::AccountId, u32, BalanceOf<T>),
+        /// client, server, session_id, error
+        ClaimPaymentFailed(T::AccountId, T::AccountId, u32, DispatchError),
+        /// server, number of claimed vouchers, number of failed vouchers
+        PaymentsBatchClaimed(T::AccountId, u32, u32),
     }
 
     #[pallet::error]
@@ -199,10 +409,58 @@ pub mod pallet {
         SessionError,
         // Invalid signature
         InvalidSignature,
+        // hashlock is already used in the channel
+        LockAlreadyExists,
+        // hash-locked payment not exist
+        LockNotExist,
+        // too many hash-locked payments in the channel
+        TooManyPendingLocks,
+        // lock expiration must be in the future and within the channel's lifetime
+        InvalidLockExpiration,
+        // hash-locked payment has expired and can only be refunded
+        LockExpired,
+        // hash-locked payment can only be refunded after expiration
+        LockNotExpired,
+        // channel is being closed and in dispute period
+        ChannelClosing,
+        // channel is not being closed
+        ChannelNotClosing,
+        // channel can only be settled after the dispute period
+        DisputePeriodNotEnded,
+        // dispute period has ended
+        DisputePeriodEnded,
+        // submitted state is not newer than the current one
+        StaleChannelState,
+        // too many vouchers in one batch
+        TooManyVouchers,
+        // channel has expired
+        ChannelExpired,
     }
 
     #[pallet::hooks]
-    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if StorageVersion::get::<Pallet<T>>() < 1 {
+                // channels opened before multi-asset support are DPR channels
+                let mut count = 0u64;
+                Channel::<T>::translate::<OldChannelOf<T>, _>(|_, _, c| {
+                    count += 1;
+                    Some(ChannelOf::<T> {
+                        client: c.client,
+                        server: c.server,
+                        balance: c.balance,
+                        nonce: c.nonce,
+                        opened: c.opened,
+                        expiration: c.expiration,
+                        asset: None,
+                    })
+                });
+                STORAGE_VERSION.put::<Pallet<T>>();
+                return T::DbWeight::get().reads_writes(count + 1, count + 1);
+            }
+            T::DbWeight::get().reads(1)
+        }
+    }
 
     // Dispatchable functions allows users to interact with the pallet and invoke state changes.
     // These functions materialize as "extrinsics", which are often compared to transactions.
@@ -220,49 +478,39 @@ pub mod pallet {
             duration: u32, // duration is in units of seconds
         ) -> DispatchResultWithPostInfo {
             let client = ensure_signed(origin)?;
-            ensure!(
-                !Channel::<T>::contains_key(&client, &server),
-                Error::<T>::ChannelAlreadyOpened
-            );
-            ensure!(client != server, Error::<T>::SameChannelEnds);
-            let nonce = Nonce::<T>::get((&client, &server));
-            let start_block = <frame_system::Pallet<T>>::block_number();
-            let duration_blocks = duration / T::SecsPerBlock::get();
-            let expiration = start_block + T::BlockNumber::from(duration_blocks);
-            let chan = ChannelOf::<T> {
-                client: client.clone(),
-                server: server.clone(),
-                balance: lock_amount,
-                nonce: nonce.clone(),
-                opened: start_block.clone(),
-                expiration: expiration.clone(),
-            };
-            if !Self::take_from_account(&client, lock_amount) {
-                log::error!("Not enough free balance to open channel");
-                Err(Error::<T>::NotEnoughBalance)?
-            }
-            Channel::<T>::insert(&client, &server, chan);
-            if TotalMicropaymentChannelBalance::<T>::contains_key(&client) {
-                TotalMicropaymentChannelBalance::<T>::mutate_exists(&client, |b| {
-                    let total_balance = b.take().unwrap_or_default();
-                    *b = Some(total_balance + lock_amount);
-                });
-            } else {
-                TotalMicropaymentChannelBalance::<T>::insert(&client, lock_amount);
-            }
-            Self::deposit_event(Event::ChannelOpened(
-                client,
-                server,
-                lock_amount,
-                nonce,
-                start_block,
-                expiration,
-            ));
+            Self::do_open_channel(client, server, None, lock_amount, duration)?;
+            Ok(().into())
+        }
+
+        /// Client opens a channel to the server denominated in a pallet_assets asset, e.g. ADSC
+        #[pallet::weight(T::WeightInfo::open_asset_channel())]
+        pub fn open_asset_channel(
+            origin: OriginFor<T>,
+            server: T::AccountId,
+            asset: AssetIdOf<T>,
+            lock_amount: BalanceOf<T>,
+            duration: u32, // duration is in units of seconds
+        ) -> DispatchResultWithPostInfo {
+            let client = ensure_signed(origin)?;
+            Self::do_open_channel(client, server, Some(asset), lock_amount, duration)?;
+            Ok(().into())
+        }
+
+        /// Set the burn rate of micropayments in the asset, None to use MicropaymentBurn
+        #[pallet::weight(T::WeightInfo::set_asset_burn_rate())]
+        pub fn set_asset_burn_rate(
+            origin: OriginFor<T>,
+            asset: AssetIdOf<T>,
+            rate: Option<Percent>,
+        ) -> DispatchResultWithPostInfo {
+            ensure_root(origin)?;
+            AssetBurnRate::<T>::set(&asset, rate);
+            Self::deposit_event(Event::AssetBurnRateSet(asset, rate));
             Ok(().into())
         }
 
         /// Close the channel and settle the payment
-        #[pallet::weight(T::WeightInfo::close_channel())]
+        #[pallet::weight(T::WeightInfo::close_channel(T::MaxPendingLocks::get()))]
         pub fn close_channel(
             origin: OriginFor<T>,
             account_id: T::AccountId,
@@ -275,40 +523,29 @@ pub mod pallet {
                 // signer is server
 
                 let chan = Channel::<T>::get(&account_id, &signer);
-                TotalMicropaymentChannelBalance::<T>::mutate_exists(&account_id, |b| {
-                    let total_balance = b.take().unwrap_or_default();
-
-                    *b = if total_balance > chan.balance {
-                        Some(total_balance - chan.balance)
-                    } else {
-                        None
-                    };
-                });
+                Self::sub_total_channel_balance(&account_id, chan.asset, chan.balance);
                 // return the remaining balance in the channel to the client
-                Self::deposit_into_account(&account_id, chan.balance)?;
-                Self::_close_channel(&account_id, &signer);
+                Self::deposit_into_account(chan.asset, &account_id, chan.balance)?;
+                Self::_close_channel(&account_id, &signer)?;
                 let end_block = <frame_system::Pallet<T>>::block_number();
                 Self::deposit_event(Event::ChannelClosed(account_id, signer, end_block));
 
                 return Ok(().into());
             } else if Channel::<T>::contains_key(&signer, &account_id) {
                 // signer is client
+                ensure!(
+                    !PendingCloses::<T>::contains_key(&signer, &account_id),
+                    Error::<T>::ChannelClosing
+                );
                 let chan = Channel::<T>::get(&signer, &account_id);
                 let current_block = <frame_system::Pallet<T>>::block_number();
                 if chan.expiration < current_block
                     || T::NodeInterface::get_eras_offline(&chan.server) >= 1
                 {
-                    TotalMicropaymentChannelBalance::<T>::mutate_exists(&signer, |b| {
-                        let total_balance = b.take().unwrap_or_default();
-                        *b = if total_balance > chan.balance {
-                            Some(total_balance - chan.balance)
-                        } else {
-                            None
-                        };
-                    });
+                    Self::sub_total_channel_balance(&signer, chan.asset, chan.balance);
                     // return the remaining balance in the channel to the client
-                    Self::deposit_into_account(&signer, chan.balance)?;
-                    Self::_close_channel(&signer, &account_id);
+                    Self::deposit_into_account(chan.asset, &signer, chan.balance)?;
+                    Self::_close_channel(&signer, &account_id)?;
                     let end_block = current_block;
                     Self::deposit_event(Event::ChannelClosed(signer, account_id, end_block));
                     return Ok(().into());
@@ -324,23 +561,18 @@ pub mod pallet {
 
         /// client close all expired channels.
         /// client can only close expired channel.
-        #[pallet::weight(T::WeightInfo::close_expired_channels())]
+        #[pallet::weight(T::WeightInfo::close_expired_channels(T::MaxPendingLocks::get()))]
         pub fn close_expired_channels(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
             let client = ensure_signed(origin)?;
             for (server, chan) in Channel::<T>::iter_prefix(&client) {
                 let current_block = <frame_system::Pallet<T>>::block_number();
-                if chan.expiration < current_block {
-                    TotalMicropaymentChannelBalance::<T>::mutate_exists(&client, |b| {
-                        let total_balance = b.take().unwrap_or_default();
-                        *b = if total_balance > chan.balance {
-                            Some(total_balance - chan.balance)
-                        } else {
-                            None
-                        };
-                    });
+                if chan.expiration < current_block
+                    && !PendingCloses::<T>::contains_key(&client, &server)
+                {
+                    Self::sub_total_channel_balance(&client, chan.asset, chan.balance);
                     // return the remaining balance in the channel to the client
-                    Self::deposit_into_account(&client, chan.balance)?;
-                    Self::_close_channel(&client, &server);
+                    Self::deposit_into_account(chan.asset, &client, chan.balance)?;
+                    Self::_close_channel(&client, &server)?;
                     let end_block = current_block;
                     Self::deposit_event(Event::ChannelClosed(client.clone(), server, end_block));
                 }
@@ -360,24 +592,27 @@ pub mod pallet {
                 Channel::<T>::contains_key(&client, &server),
                 Error::<T>::ChannelNotExist
             );
-            if !Self::take_from_account(&client, amount) {
+            ensure!(
+                !PendingCloses::<T>::contains_key(&client, &server),
+                Error::<T>::ChannelClosing
+            );
+            let asset = Channel::<T>::get(&client, &server).asset;
+            if !Self::take_from_account(asset, &client, amount) {
                 log::error!("Not enough free balance to add into channel");
                 Err(Error::<T>::NotEnoughBalance)?
             }
             Channel::<T>::mutate(&client, &server, |c| {
                 c.balance += amount;
             });
-            TotalMicropaymentChannelBalance::<T>::mutate_exists(&client, |b| {
-                let total_balance = b.take().unwrap_or_default();
-                *b = Some(total_balance + amount);
-            });
+            Self::add_total_channel_balance(&client, asset, amount);
             let end_block = <frame_system::Pallet<T>>::block_number();
             Self::deposit_event(Event::BalanceAdded(client, server, amount, end_block));
             Ok(().into())
         }
 
         /// Server claims payment from the channel
-        #[pallet::weight(T::WeightInfo::claim_payment())]
+        #[pallet::weight(T::WeightInfo::claim_payment()
+            .max(T::WeightInfo::close_channel(T::MaxPendingLocks::get())))]
         pub fn claim_payment(
             origin: OriginFor<T>,
             client: T::AccountId,
@@ -396,44 +631,33 @@ pub mod pallet {
             let mut chan = Channel::<T>::get(&client, &server);
             let current_block = <frame_system::Pallet<T>>::block_number();
             if chan.expiration < current_block {
-                TotalMicropaymentChannelBalance::<T>::mutate_exists(&client, |b| {
-                    let total_balance = b.take().unwrap_or_default();
-                    *b = if total_balance > chan.balance {
-                        Some(total_balance - chan.balance)
-                    } else {
-                        None
-                    };
-                });
+                Self::sub_total_channel_balance(&client, chan.asset, chan.balance);
                 // return the remaining balance in the channel to the client
-                Self::deposit_into_account(&client, chan.balance)?;
-                Self::_close_channel(&client, &server);
+                Self::deposit_into_account(chan.asset, &client, chan.balance)?;
+                Self::_close_channel(&client, &server)?;
                 let end_block = current_block;
                 Self::deposit_event(Event::ChannelClosed(client, server, end_block));
                 return Ok(().into());
             }
 
-            if SessionId::<T>::contains_key((&client, &server))
-                && session_id != Self::session_id((&client, &server)).unwrap_or(0) + 1
-            {
-                Err(Error::<T>::SessionError)?
-            }
+            ensure!(
+                !PendingCloses::<T>::contains_key(&client, &server),
+                Error::<T>::ChannelClosing
+            );
+            ensure!(
+                Some(session_id) == Self::next_session(&client, &server),
+                Error::<T>::SessionError
+            );
             Self::verify_signature(&client, &server, chan.nonce, session_id, amount, &signature)?;
             SessionId::<T>::insert((&client, &server), session_id); // mark session_id as used
 
             // if there is not enough balance in the channel
             if chan.balance < amount {
-                TotalMicropaymentChannelBalance::<T>::mutate_exists(&client, |b| {
-                    let total_balance = b.take().unwrap_or_default();
-                    *b = if total_balance > chan.balance {
-                        Some(total_balance - chan.balance)
-                    } else {
-                        None
-                    };
-                });
+                Self::sub_total_channel_balance(&client, chan.asset, chan.balance);
                 // deposit all the balance in the channel to the server's account
-                Self::deposit_into_account(&server, chan.balance)?;
+                Self::deposit_into_account(chan.asset, &server, chan.balance)?;
                 // no balance in channel now, just close it
-                Self::_close_channel(&client, &server);
+                Self::_close_channel(&client, &server)?;
                 let end_block = <frame_system::Pallet<T>>::block_number();
                 Self::deposit_event(Event::ChannelClosed(
                     client.clone(),
@@ -444,33 +668,529 @@ pub mod pallet {
                 Err(Error::<T>::NotEnoughBalance)?
             }
 
+            let asset = chan.asset;
             chan.balance -= amount;
             Channel::<T>::insert(&client, &server, chan);
-            TotalMicropaymentChannelBalance::<T>::mutate_exists(&client, |b| {
-                let total_balance = b.take().unwrap_or_default();
-                *b = if total_balance > amount {
-                    Some(total_balance - amount)
-                } else {
-                    None
-                };
-            });
+            Self::sub_total_channel_balance(&client, asset, amount);
             // deposit the claimed amount to the server's account
-            Self::deposit_into_account(&server, amount)?;
+            Self::deposit_into_account(asset, &server, amount)?;
             Self::deposit_event(Event::ClaimPayment(client, server, amount));
 
             Ok(().into())
         }
+
+        /// Server claims payments from many channels in one transaction.
+        /// A failed voucher is reported in `ClaimPaymentFailed` and does not fail the batch.
+        /// Signatures are verified one by one: sr25519 batch verification only reports the
+        /// result of the whole batch and depends on a host extension that is not registered
+        /// in every execution context.
+        #[pallet::weight(T::WeightInfo::claim_payments_batch(vouchers.len() as u32))]
+        pub fn claim_payments_batch(
+            origin: OriginFor<T>,
+            vouchers: Vec<VoucherOf<T>>,
+        ) -> DispatchResultWithPostInfo {
+            let server = ensure_signed(origin)?;
+            ensure!(
+                vouchers.len() as u32 <= T::MaxBatchClaims::get(),
+                Error::<T>::TooManyVouchers
+            );
+            let mut claimed = 0u32;
+            let mut failed = 0u32;
+            for voucher in vouchers {
+                match Self::do_claim_payment(
+                    &voucher.client,
+                    &server,
+                    voucher.session_id,
+                    voucher.amount,
+                    &voucher.signature,
+                ) {
+                    Ok(()) => {
+                        claimed += 1;
+                        Self::deposit_event(Event::ClaimPayment(
+                            voucher.client,
+                            server.clone(),
+                            voucher.amount,
+                        ));
+                    }
+                    Err(e) => {
+                        failed += 1;
+                        Self::deposit_event(Event::ClaimPaymentFailed(
+                            voucher.client,
+                            server.clone(),
+                            voucher.session_id,
+                            e,
+                        ));
+                    }
+                }
+            }
+            Self::deposit_event(Event::PaymentsBatchClaimed(server, claimed, failed));
+            Ok(().into())
+        }
+
+        /// Server locks a conditional payment in the channel with the client's signature.
+        /// For hub-routed payments, the hub locks the payment in the client's channel and
+        /// forwards it by signing a lock with the same hashlock and an earlier expiration
+        /// in its own channel to the recipient server.
+        #[pallet::weight(T::WeightInfo::lock_payment())]
+        pub fn lock_payment(
+            origin: OriginFor<T>,
+            client: T::AccountId,
+            hashlock: H256,
+            amount: BalanceOf<T>,
+            expiration: T::BlockNumber,
+            recipient: T::AccountId,
+            signature: Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            let server = ensure_signed(origin)?;
+            ensure!(
+                Channel::<T>::contains_key(&client, &server),
+                Error::<T>::ChannelNotExist
+            );
+            ensure!(
+                !PendingCloses::<T>::contains_key(&client, &server),
+                Error::<T>::ChannelClosing
+            );
+            let mut chan = Channel::<T>::get(&client, &server);
+            let current_block = <frame_system::Pallet<T>>::block_number();
+            ensure!(
+                expiration > current_block && expiration <= chan.expiration,
+                Error::<T>::InvalidLockExpiration
+            );
+            let key = (client.clone(), server.clone());
+            ensure!(
+                !PendingLocks::<T>::contains_key(&key, &hashlock)
+                    && !SettledLocks::<T>::contains_key(&key, &hashlock),
+                Error::<T>::LockAlreadyExists
+            );
+            ensure!(
+                Self::lock_count(&key) < T::MaxPendingLocks::get(),
+                Error::<T>::TooManyPendingLocks
+            );
+            ensure!(chan.balance >= amount, Error::<T>::NotEnoughBalance);
+            Self::verify_lock_signature(
+                &client, &server, chan.nonce, &hashlock, amount, expiration, &recipient, &signature,
+            )?;
+
+            // locked amount leaves the channel balance but still counts in the total channel balance
+            chan.balance -= amount;
+            Channel::<T>::insert(&client, &server, chan);
+            PendingLocks::<T>::insert(
+                &key,
+                &hashlock,
+                HashLockOf::<T> {
+                    amount,
+                    expiration,
+                    recipient: recipient.clone(),
+                },
+            );
+            LockCount::<T>::mutate(&key, |c| *c += 1);
+            Self::deposit_event(Event::PaymentLocked(
+                client, server, hashlock, amount, expiration, recipient,
+            ));
+            Ok(().into())
+        }
+
+        /// Server claims a hash-locked payment by revealing the preimage of the hashlock.
+        /// The preimage is published in the event so that the hub can claim upstream.
+        #[pallet::weight(T::WeightInfo::claim_locked_payment())]
+        pub fn claim_locked_payment(
+            origin: OriginFor<T>,
+            client: T::AccountId,
+            preimage: [u8; 32],
+        ) -> DispatchResultWithPostInfo {
+            let server = ensure_signed(origin)?;
+            let hashlock = H256::from(sp_io::hashing::blake2_256(&preimage));
+            let key = (client.clone(), server.clone());
+            let lock = PendingLocks::<T>::get(&key, &hashlock).ok_or(Error::<T>::LockNotExist)?;
+            let current_block = <frame_system::Pallet<T>>::block_number();
+            ensure!(current_block <= lock.expiration, Error::<T>::LockExpired);
+
+            Self::settle_lock(&key, &hashlock);
+            let asset = Channel::<T>::get(&client, &server).asset;
+            Self::sub_total_channel_balance(&client, asset, lock.amount);
+            // deposit the claimed amount to the server's account
+            Self::deposit_into_account(asset, &server, lock.amount)?;
+            Self::deposit_event(Event::LockedPaymentClaimed(
+                client,
+                server,
+                hashlock,
+                preimage,
+                lock.amount,
+            ));
+            Ok(().into())
+        }
+
+        /// Client takes an expired hash-locked payment back into the channel balance
+        #[pallet::weight(T::WeightInfo::refund_locked_payment())]
+        pub fn refund_locked_payment(
+            origin: OriginFor<T>,
+            server: T::AccountId,
+            hashlock: H256,
+        ) -> DispatchResultWithPostInfo {
+            let client = ensure_signed(origin)?;
+            let key = (client.clone(), server.clone());
+            let lock = PendingLocks::<T>::get(&key, &hashlock).ok_or(Error::<T>::LockNotExist)?;
+            let current_block = <frame_system::Pallet<T>>::block_number();
+            ensure!(current_block > lock.expiration, Error::<T>::LockNotExpired);
+
+            Self::settle_lock(&key, &hashlock);
+            Channel::<T>::mutate(&client, &server, |c| {
+                c.balance += lock.amount;
+            });
+            Self::deposit_event(Event::LockedPaymentRefunded(
+                client,
+                server,
+                hashlock,
+                lock.amount,
+            ));
+            Ok(().into())
+        }
+
+        /// Client or server closes the channel immediately with a final state signed by both.
+        /// `amount` is settled to the server and the rest is returned to the client.
+        #[pallet::weight(T::WeightInfo::cooperative_close(T::MaxPendingLocks::get()))]
+        pub fn cooperative_close(
+            origin: OriginFor<T>,
+            counterparty: T::AccountId,
+            amount: BalanceOf<T>,
+            client_signature: Vec<u8>,
+            server_signature: Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            let signer = ensure_signed(origin)?;
+            let (client, server) = Self::channel_ends(signer, counterparty)?;
+            let chan = Channel::<T>::get(&client, &server);
+            let msg = Self::construct_close_hash(&client, &server, chan.nonce, amount);
+            Self::verify_account_signature(&client, &msg, &client_signature)?;
+            Self::verify_account_signature(&server, &msg, &server_signature)?;
+
+            Self::settle_channel(client, server, chan, amount)?;
+            Ok(().into())
+        }
+
+        /// Client or server closes the channel unilaterally with the state of the next
+        /// session to claim, signed by the client. The server can submit newer states
+        /// during the dispute period.
+        #[pallet::weight(T::WeightInfo::initiate_close())]
+        pub fn initiate_close(
+            origin: OriginFor<T>,
+            counterparty: T::AccountId,
+            session_id: u32,
+            amount: BalanceOf<T>,
+            signature: Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            let signer = ensure_signed(origin)?;
+            let (client, server) = Self::channel_ends(signer.clone(), counterparty)?;
+            ensure!(
+                !PendingCloses::<T>::contains_key(&client, &server),
+                Error::<T>::ChannelClosing
+            );
+            ensure!(
+                Some(session_id) == Self::next_session(&client, &server),
+                Error::<T>::SessionError
+            );
+            Self::verify_close_state(&client, &server, session_id, amount, &signature)?;
+
+            let dispute_end =
+                <frame_system::Pallet<T>>::block_number() + T::ChannelDisputePeriod::get();
+            PendingCloses::<T>::insert(
+                &client,
+                &server,
+                CloseStateOf::<T> {
+                    session_id,
+                    amount,
+                    settled: Zero::zero(),
+                    initiator: signer,
+                    dispute_end,
+                },
+            );
+            Self::deposit_event(Event::ChannelCloseInitiated(
+                client,
+                server,
+                session_id,
+                amount,
+                dispute_end,
+            ));
+            Ok(().into())
+        }
+
+        /// Server submits a newer voucher for the channel being closed: more accumulated
+        /// amount in the latest session, or the next session. Every unclaimed session is
+        /// settled this way, one voucher at a time in session order.
+        #[pallet::weight(T::WeightInfo::dispute_close())]
+        pub fn dispute_close(
+            origin: OriginFor<T>,
+            client: T::AccountId,
+            session_id: u32,
+            amount: BalanceOf<T>,
+            signature: Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            let server = ensure_signed(origin)?;
+            let mut state =
+                PendingCloses::<T>::get(&client, &server).ok_or(Error::<T>::ChannelNotClosing)?;
+            let current_block = <frame_system::Pallet<T>>::block_number();
+            ensure!(
+                current_block <= state.dispute_end,
+                Error::<T>::DisputePeriodEnded
+            );
+            // amounts are only comparable within a session, the next session adds to the close
+            if session_id == state.session_id {
+                ensure!(amount > state.amount, Error::<T>::StaleChannelState);
+            } else {
+                ensure!(
+                    Some(session_id) == state.session_id.checked_add(1),
+                    Error::<T>::SessionError
+                );
+                state.settled += state.amount;
+            }
+            Self::verify_close_state(&client, &server, session_id, amount, &signature)?;
+
+            state.session_id = session_id;
+            state.amount = amount;
+            PendingCloses::<T>::insert(&client, &server, state);
+            Self::deposit_event(Event::ChannelCloseDisputed(
+                client, server, session_id, amount,
+            ));
+            Ok(().into())
+        }
+
+        /// Anyone settles a unilaterally closed channel after the dispute period
+        #[pallet::weight(T::WeightInfo::finalize_close(T::MaxPendingLocks::get()))]
+        pub fn finalize_close(
+            origin: OriginFor<T>,
+            client: T::AccountId,
+            server: T::AccountId,
+        ) -> DispatchResultWithPostInfo {
+            ensure_signed(origin)?;
+            let state =
+                PendingCloses::<T>::get(&client, &server).ok_or(Error::<T>::ChannelNotClosing)?;
+            let current_block = <frame_system::Pallet<T>>::block_number();
+            ensure!(
+                current_block > state.dispute_end,
+                Error::<T>::DisputePeriodNotEnded
+            );
+            let chan = Channel::<T>::get(&client, &server);
+
+            Self::settle_channel(client, server, chan, state.settled + state.amount)?;
+            Ok(().into())
+        }
     }
 
     impl<T: Config> Pallet<T> {
-        /// Close the channel between the client and server
-        fn _close_channel(client: &T::AccountId, server: &T::AccountId) {
+        /// Open a channel, asset None for DPR
+        fn do_open_channel(
+            client: T::AccountId,
+            server: T::AccountId,
+            asset: Option<AssetIdOf<T>>,
+            lock_amount: BalanceOf<T>,
+            duration: u32,
+        ) -> DispatchResult {
+            ensure!(
+                !Channel::<T>::contains_key(&client, &server),
+                Error::<T>::ChannelAlreadyOpened
+            );
+            ensure!(client != server, Error::<T>::SameChannelEnds);
+            let nonce = Nonce::<T>::get((&client, &server));
+            let start_block = <frame_system::Pallet<T>>::block_number();
+            let duration_blocks = duration / T::SecsPerBlock::get();
+            let expiration = start_block + T::BlockNumber::from(duration_blocks);
+            let chan = ChannelOf::<T> {
+                client: client.clone(),
+                server: server.clone(),
+                balance: lock_amount,
+                nonce: nonce.clone(),
+                opened: start_block.clone(),
+                expiration: expiration.clone(),
+                asset,
+            };
+            if !Self::take_from_account(asset, &client, lock_amount) {
+                log::error!("Not enough free balance to open channel");
+                Err(Error::<T>::NotEnoughBalance)?
+            }
+            Channel::<T>::insert(&client, &server, chan);
+            Self::add_total_channel_balance(&client, asset, lock_amount);
+            match asset {
+                Some(asset) => Self::deposit_event(Event::AssetChannelOpened(
+                    client,
+                    server,
+                    asset,
+                    lock_amount,
+                    nonce,
+                    start_block,
+                    expiration,
+                )),
+                None => Self::deposit_event(Event::ChannelOpened(
+                    client,
+                    server,
+                    lock_amount,
+                    nonce,
+                    start_block,
+                    expiration,
+                )),
+            }
+            Ok(())
+        }
+
+        /// Close the channel between the client and server,
+        /// pending hash-locked payments are returned to the client
+        fn _close_channel(
+            client: &T::AccountId,
+            server: &T::AccountId,
+        ) -> Result<(), DispatchError> {
+            let key = (client.clone(), server.clone());
+            let mut locked = BalanceOf::<T>::zero();
+            for (_, lock) in PendingLocks::<T>::drain_prefix(&key) {
+                locked += lock.amount;
+            }
+            LockCount::<T>::remove(&key);
+            let _ = SettledLocks::<T>::clear_prefix(&key, T::MaxPendingLocks::get(), None);
+            PendingCloses::<T>::remove(client, server);
+            if !locked.is_zero() {
+                let asset = Channel::<T>::get(client, server).asset;
+                Self::sub_total_channel_balance(client, asset, locked);
+                Self::deposit_into_account(asset, client, locked)?;
+            }
             // remove all the session_ids of given channel
             SessionId::<T>::remove((client, server));
             // remove the channel
             Channel::<T>::remove(client, server);
             // increment the nonce
             Nonce::<T>::mutate((client, server), |v| *v += 1);
+            Ok(())
+        }
+
+        /// Claim one voucher, storage changes are reverted if the claim fails
+        #[transactional]
+        fn do_claim_payment(
+            client: &T::AccountId,
+            server: &T::AccountId,
+            session_id: u32,
+            amount: BalanceOf<T>,
+            signature: &Vec<u8>,
+        ) -> DispatchResult {
+            ensure!(
+                Channel::<T>::contains_key(client, server),
+                Error::<T>::ChannelNotExist
+            );
+            ensure!(
+                !PendingCloses::<T>::contains_key(client, server),
+                Error::<T>::ChannelClosing
+            );
+            let mut chan = Channel::<T>::get(client, server);
+            let current_block = <frame_system::Pallet<T>>::block_number();
+            ensure!(chan.expiration >= current_block, Error::<T>::ChannelExpired);
+            ensure!(
+                Some(session_id) == Self::next_session(client, server),
+                Error::<T>::SessionError
+            );
+            Self::verify_signature(client, server, chan.nonce, session_id, amount, signature)
+                .map_err(|e| e.error)?;
+            ensure!(chan.balance >= amount, Error::<T>::NotEnoughBalance);
+
+            SessionId::<T>::insert((client, server), session_id); // mark session_id as used
+            let asset = chan.asset;
+            chan.balance -= amount;
+            Channel::<T>::insert(client, server, chan);
+            Self::sub_total_channel_balance(client, asset, amount);
+            // deposit the claimed amount to the server's account
+            Self::deposit_into_account(asset, server, amount)
+        }
+
+        /// Find out which side of the channel the signer is on, returns (client, server)
+        fn channel_ends(
+            signer: T::AccountId,
+            counterparty: T::AccountId,
+        ) -> Result<(T::AccountId, T::AccountId), DispatchError> {
+            if Channel::<T>::contains_key(&signer, &counterparty) {
+                Ok((signer, counterparty))
+            } else if Channel::<T>::contains_key(&counterparty, &signer) {
+                Ok((counterparty, signer))
+            } else {
+                Err(Error::<T>::ChannelNotExist.into())
+            }
+        }
+
+        /// Pay `amount` capped by the channel balance to the server,
+        /// return the rest to the client and close the channel
+        fn settle_channel(
+            client: T::AccountId,
+            server: T::AccountId,
+            chan: ChannelOf<T>,
+            amount: BalanceOf<T>,
+        ) -> Result<(), DispatchError> {
+            let paid = amount.min(chan.balance);
+            Self::sub_total_channel_balance(&client, chan.asset, chan.balance);
+            if !paid.is_zero() {
+                Self::deposit_into_account(chan.asset, &server, paid)?;
+                Self::deposit_event(Event::ClaimPayment(client.clone(), server.clone(), paid));
+            }
+            // return the remaining balance in the channel to the client
+            Self::deposit_into_account(chan.asset, &client, chan.balance - paid)?;
+            Self::_close_channel(&client, &server)?;
+            let end_block = <frame_system::Pallet<T>>::block_number();
+            Self::deposit_event(Event::ChannelClosed(client, server, end_block));
+            Ok(())
+        }
+
+        /// Sessions of a channel are claimed in order starting from 1
+        fn next_session(client: &T::AccountId, server: &T::AccountId) -> Option<u32> {
+            match Self::session_id((client, server)) {
+                Some(claimed) => claimed.checked_add(1),
+                None => Some(1),
+            }
+        }
+
+        /// Check a state submitted for unilateral close is signed by the client
+        fn verify_close_state(
+            client: &T::AccountId,
+            server: &T::AccountId,
+            session_id: u32,
+            amount: BalanceOf<T>,
+            signature: &Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            let nonce = Channel::<T>::get(client, server).nonce;
+            Self::verify_signature(client, server, nonce, session_id, amount, signature)
+        }
+
+        /// Remove the pending lock and mark its hashlock as used in the channel
+        fn settle_lock(key: &(T::AccountId, T::AccountId), hashlock: &H256) {
+            PendingLocks::<T>::remove(key, hashlock);
+            SettledLocks::<T>::insert(key, hashlock, ());
+        }
+
+        /// Decrease the total micropayment channel balance of the client
+        fn sub_total_channel_balance(
+            client: &T::AccountId,
+            asset: Option<AssetIdOf<T>>,
+            amount: BalanceOf<T>,
+        ) {
+            let sub = |b: &mut Option<BalanceOf<T>>| {
+                let total_balance = b.take().unwrap_or_default();
+                *b = if total_balance > amount {
+                    Some(total_balance - amount)
+                } else {
+                    None
+                };
+            };
+            match asset {
+                Some(asset) => TotalAssetChannelBalance::<T>::mutate_exists(client, asset, sub),
+                None => TotalMicropaymentChannelBalance::<T>::mutate_exists(client, sub),
+            }
+        }
+
+        /// Increase the total micropayment channel balance of the client
+        fn add_total_channel_balance(
+            client: &T::AccountId,
+            asset: Option<AssetIdOf<T>>,
+            amount: BalanceOf<T>,
+        ) {
+            let add = |b: &mut Option<BalanceOf<T>>| {
+                let total_balance = b.take().unwrap_or_default();
+                *b = Some(total_balance + amount);
+            };
+            match asset {
+                Some(asset) => TotalAssetChannelBalance::<T>::mutate_exists(client, asset, add),
+                None => TotalMicropaymentChannelBalance::<T>::mutate_exists(client, add),
+            }
         }
 
         /// verify signature, signature is on hash of |server_addr|nonce|session_id|amount|
@@ -484,18 +1204,43 @@ pub mod pallet {
             session_id: u32,
             amount: BalanceOf<T>,
             signature: &Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            let msg = Self::construct_byte_array_and_hash(server, nonce, session_id, amount);
+            Self::verify_account_signature(client, &msg, signature)
+        }
+
+        /// verify lock signature, signature is on hash of
+        /// |server_addr|nonce|hashlock|amount|expiration|recipient|
+        #[allow(clippy::too_many_arguments)]
+        pub fn verify_lock_signature(
+            client: &T::AccountId,
+            server: &T::AccountId,
+            nonce: u64,
+            hashlock: &H256,
+            amount: BalanceOf<T>,
+            expiration: T::BlockNumber,
+            recipient: &T::AccountId,
+            signature: &Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            let msg =
+                Self::construct_lock_hash(server, nonce, hashlock, amount, expiration, recipient);
+            Self::verify_account_signature(client, &msg, signature)
+        }
+
+        fn verify_account_signature(
+            account: &T::AccountId,
+            msg: &[u8; 32],
+            signature: &Vec<u8>,
         ) -> DispatchResultWithPostInfo {
             let mut pk = [0u8; 32];
-            pk.copy_from_slice(&client.encode());
+            pk.copy_from_slice(&account.encode());
             let pub_key = sr25519::Public::from_raw(pk);
 
             let sig = sr25519::Signature::from_slice(&signature);
 
-            let msg = Self::construct_byte_array_and_hash(server, nonce, session_id, amount);
-
             let verified = sr25519_verify(
                 &sig.unwrap_or(UncheckedFrom::unchecked_from([0; 64])),
-                &msg,
+                msg,
                 &pub_key,
             );
             ensure!(verified, Error::<T>::InvalidSignature);
@@ -519,32 +1264,110 @@ pub mod pallet {
             hash
         }
 
-        /// Deduct the amount from the account free balance
-        fn take_from_account(account: &T::AccountId, amount: BalanceOf<T>) -> bool {
-            T::Currency::withdraw(
-                account,
-                amount,
-                WithdrawReasons::FEE,
-                ExistenceRequirement::KeepAlive,
-            )
-            .map_or(false, |_| true)
+        // construct data from |client_addr|server_addr|nonce|amount| and hash it,
+        // both client and server sign it for cooperative close
+        pub fn construct_close_hash(
+            client: &T::AccountId,
+            server: &T::AccountId,
+            nonce: u64,
+            amount: BalanceOf<T>,
+        ) -> [u8; 32] {
+            let mut data = Vec::new();
+            data.extend_from_slice(&client.encode());
+            data.extend_from_slice(&server.encode());
+            data.extend_from_slice(&nonce.to_be_bytes());
+            data.extend_from_slice(&amount.encode());
+            sp_io::hashing::blake2_256(&data)
+        }
+
+        // construct data from |server_addr|nonce|hashlock|amount|expiration|recipient| and hash it
+        pub fn construct_lock_hash(
+            address: &T::AccountId,
+            nonce: u64,
+            hashlock: &H256,
+            amount: BalanceOf<T>,
+            expiration: T::BlockNumber,
+            recipient: &T::AccountId,
+        ) -> [u8; 32] {
+            let mut data = Vec::new();
+            data.extend_from_slice(&address.encode());
+            data.extend_from_slice(&nonce.to_be_bytes());
+            data.extend_from_slice(hashlock.as_bytes());
+            data.extend_from_slice(&amount.encode());
+            data.extend_from_slice(&expiration.encode());
+            data.extend_from_slice(&recipient.encode());
+            sp_io::hashing::blake2_256(&data)
+        }
+
+        /// account holding the funds locked in asset channels
+        pub fn escrow_account() -> T::AccountId {
+            ESCROW_PALLET_ID.into_account_truncating()
+        }
+
+        /// Deduct the amount from the account free balance,
+        /// assets are moved to the escrow account until the channel pays out
+        fn take_from_account(
+            asset: Option<AssetIdOf<T>>,
+            account: &T::AccountId,
+            amount: BalanceOf<T>,
+        ) -> bool {
+            match asset {
+                Some(asset) => <T::Assets as fungibles::Transfer<T::AccountId>>::transfer(
+                    asset,
+                    account,
+                    &Self::escrow_account(),
+                    amount,
+                    false,
+                )
+                .map_or(false, |moved| moved == amount),
+                None => T::Currency::withdraw(
+                    account,
+                    amount,
+                    WithdrawReasons::FEE,
+                    ExistenceRequirement::KeepAlive,
+                )
+                .map_or(false, |_| true),
+            }
         }
 
         /// Deposit the amount to the account free balance
-        /// some additional fee should be charged
+        /// some additional fee should be charged,
+        /// assets are paid out of the escrow account and their fee is burned there
         fn deposit_into_account(
+            asset: Option<AssetIdOf<T>>,
             account: &T::AccountId,
             amount: BalanceOf<T>,
         ) -> Result<(), DispatchError> {
-            T::Currency::deposit_creating(account, amount);
-            let fee = T::MicropaymentBurn::get() * amount;
-            let burned = T::Currency::withdraw(
-                account,
-                fee,
-                WithdrawReasons::FEE,
-                ExistenceRequirement::KeepAlive,
-            )?;
-            T::Slash::on_unbalanced(burned);
+            match asset {
+                Some(asset) => {
+                    // the fee of asset micropayments is burned
+                    let rate = Self::asset_burn_rate(asset).unwrap_or(T::MicropaymentBurn::get());
+                    let fee = rate * amount;
+                    let escrow = Self::escrow_account();
+                    let burned = <T::Assets as fungibles::Mutate<T::AccountId>>::burn_from(
+                        asset, &escrow, fee,
+                    )?;
+                    <T::Assets as fungibles::Transfer<T::AccountId>>::transfer(
+                        asset,
+                        &escrow,
+                        account,
+                        amount - fee,
+                        false,
+                    )?;
+                    TotalAssetBurned::<T>::mutate(asset, |b| *b += burned);
+                }
+                None => {
+                    T::Currency::deposit_creating(account, amount);
+                    let fee = T::MicropaymentBurn::get() * amount;
+                    let burned = T::Currency::withdraw(
+                        account,
+                        fee,
+                        WithdrawReasons::FEE,
+                        ExistenceRequirement::KeepAlive,
+                    )?;
+                    T::Slash::on_unbalanced(burned);
+                }
+            }
             Ok(())
         }
     }
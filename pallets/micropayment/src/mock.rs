@@ -25,6 +25,7 @@ use frame_support::{
 use frame_system as system;
 use node_primitives::{AccountCreator, Balance, Moment, Signature};
 use sp_core::{crypto::AccountId32, H256};
+use sp_keystore::{testing::KeyStore, KeystoreExt};
 use sp_runtime::{
     testing::Header,
     traits::{BlakeTwo256, IdentityLookup},
@@ -59,6 +60,7 @@ frame_support::construct_runtime!(
         Timestamp: pallet_timestamp::{Pallet, Call, Storage, Inherent},
         Treasury: pallet_treasury::{Pallet, Call, Storage, Config, Event<T>},
         Uniques: pallet_uniques::{Pallet, Call, Storage, Event<T>},
+        Assets: pallet_assets::{Pallet, Call, Storage, Event<T>},
     }
 );
 
@@ -187,6 +189,14 @@ parameter_types! {
     pub const BlocksPerEra: BlockNumber =  BLOCKS_PER_ERA;
     pub const DPRPerCreditBurned: Balance = 100;
     pub const MaxBurnCreditPerAddress: u32 = 50;
+    pub const MinSettingEnactmentDelay: u32 = 2;
+    pub const MaxScheduledSettingsPerEra: u32 = 10;
+    pub const MaxCreditHistory: u32 = 100;
+    pub const PriceQuorum: u32 = 1;
+    pub const PriceStaleAfter: u64 = 24 * 60 * 60;
+    pub const MaxPriceHistory: u32 = 10;
+    pub const PriceAggregationInterval: u64 = 0;
+    pub const PriceFeedInterval: u64 = 0;
 }
 
 impl pallet_credit::Config for Test {
@@ -204,6 +214,20 @@ impl pallet_credit::Config for Test {
     type BurnedTo = ();
     type UserPrivilegeInterface = ();
     type MaxBurnCreditPerAddress = MaxBurnCreditPerAddress;
+    type CreditSettingOrigin = frame_system::EnsureRoot<AccountId>;
+    type MinSettingEnactmentDelay = MinSettingEnactmentDelay;
+    type MaxScheduledSettingsPerEra = MaxScheduledSettingsPerEra;
+    type MaxCreditHistory = MaxCreditHistory;
+    type PriceQuorum = PriceQuorum;
+    type PriceStaleAfter = PriceStaleAfter;
+    type MaxPriceHistory = MaxPriceHistory;
+    type PriceAggregationInterval = PriceAggregationInterval;
+    type PriceFeed = ();
+    type PriceFeedInterval = PriceFeedInterval;
+    type MaxPriceFeedEndpoints = ConstU32<2>;
+    type MaxPriceFeedEndpointLength = ConstU32<64>;
+    type CreditBadgeCollection = ();
+    type MaxCreditChanges = ConstU32<10>;
 }
 
 parameter_types! {
@@ -221,6 +245,10 @@ parameter_types! {
     pub const MinLockAmt: u32 = 100;
     pub const MaxDurationEras: u8 = 7;
     pub const MaxIpLength: usize = 256;
+    pub const MaxSweepDevices: u32 = 10;
+    pub const MaxServersPerRegion: u32 = 100;
+    pub const MaxFirmwareLength: u32 = 64;
+    pub const DeviceMigrationCooldown: u64 = 100;
 }
 impl pallet_deeper_node::Config for Test {
     type RuntimeEvent = RuntimeEvent;
@@ -231,12 +259,44 @@ impl pallet_deeper_node::Config for Test {
     type MaxIpLength = MaxIpLength;
     type WeightInfo = ();
     type VerifySignatureInterface = ();
+    type CreditInterface = Credit;
+    type MaxSweepDevices = MaxSweepDevices;
+    type MaxServersPerRegion = MaxServersPerRegion;
+    type MaxFirmwareLength = MaxFirmwareLength;
+    type UserPrivilegeInterface = ();
+    type DeviceMigration = ();
+    type DeviceMigrationCooldown = DeviceMigrationCooldown;
+}
+
+impl pallet_assets::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Balance = Balance;
+    type AssetId = u32;
+    type AssetIdParameter = u32;
+    type Currency = Balances;
+    type CreateOrigin = AsEnsureOriginWithArg<frame_system::EnsureSigned<AccountId>>;
+    type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+    type AssetDeposit = ConstU128<1>;
+    type AssetAccountDeposit = ConstU128<1>;
+    type MetadataDepositBase = ConstU128<1>;
+    type MetadataDepositPerByte = ConstU128<1>;
+    type ApprovalDeposit = ConstU128<1>;
+    type StringLimit = ConstU32<50>;
+    type Freezer = ();
+    type WeightInfo = ();
+    type Extra = ();
+    type RemoveItemsLimit = ConstU32<5>;
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = ();
 }
 
 parameter_types! {
     pub const SecsPerBlock: u32 = 5u32;
     pub const DataPerDPR: u64 = 1024 * 1024 * 1024 * 1024;
     pub const MicropaymentBurn: Percent = Percent::from_percent(10);
+    pub const MaxPendingLocks: u32 = 16;
+    pub const MaxBatchClaims: u32 = 10;
+    pub const ChannelDisputePeriod: BlockNumber = 10;
 }
 
 pub struct DefaultAccountCreator;
@@ -258,8 +318,12 @@ impl pallet_micropayment::Config for Test {
     type SecsPerBlock = SecsPerBlock;
     type DataPerDPR = DataPerDPR;
     type WeightInfo = ();
+    type Assets = Assets;
     type NodeInterface = DeeperNode;
     type MicropaymentBurn = MicropaymentBurn;
+    type MaxPendingLocks = MaxPendingLocks;
+    type ChannelDisputePeriod = ChannelDisputePeriod;
+    type MaxBatchClaims = MaxBatchClaims;
     type Slash = Treasury;
     #[cfg(feature = "runtime-benchmarks")]
     type AccountCreator = DefaultAccountCreator;
@@ -282,7 +346,9 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
     GenesisBuild::<Test>::assimilate_storage(&pallet_treasury::GenesisConfig, &mut storage)
         .unwrap();
 
-    let ext = sp_io::TestExternalities::from(storage);
+    let mut ext = sp_io::TestExternalities::from(storage);
+    // benchmark accounts sign with the keys generated in the keystore
+    ext.register_extension(KeystoreExt(std::sync::Arc::new(KeyStore::new())));
     ext
 }
 
@@ -55,3 +55,12 @@ pub fn charlie() -> AccountId {
 pub fn dave() -> AccountId {
     get_account_id_from_seed::<sr25519::Public>("Dave")
 }
+
+/// Helper function to sign a message with the sr25519 key derived from seed
+pub fn sign_with_seed(seed: &str, msg: &[u8]) -> Vec<u8> {
+    sr25519::Pair::from_string(&format!("//{}", seed), None)
+        .expect("static values are valid; qed")
+        .sign(msg)
+        .0
+        .to_vec()
+}
@@ -13,14 +13,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::Chan;
+use super::{Chan, Channel, SettledLocks, VoucherOf};
 use crate::{mock::*, testing_utils::*, Error};
+use codec::Encode;
+use frame_support::traits::{Hooks, StorageVersion};
 use frame_support::{assert_ok, dispatch::DispatchErrorWithPostInfo};
 use hex_literal::hex;
-use sp_core::crypto::UncheckedFrom;
+use sp_core::crypto::{AccountId32, UncheckedFrom};
 use sp_core::sr25519::{Public, Signature};
+use sp_core::H256;
 use sp_io::crypto::sr25519_verify;
-use sp_runtime::{DispatchError, ModuleError};
+use sp_runtime::{DispatchError, ModuleError, Percent};
 
 #[test]
 fn open_channel() {
@@ -42,7 +45,8 @@ fn open_channel() {
                 balance: 399,
                 nonce: 0,
                 opened: 0,
-                expiration: 720
+                expiration: 720,
+                asset: None,
             }
         );
 
@@ -291,6 +295,694 @@ fn claim_payment() {
     });
 }
 
+fn lock_signature(
+    seed: &str,
+    server: &AccountId32,
+    nonce: u64,
+    hashlock: &H256,
+    amount: u128,
+    expiration: u64,
+    recipient: &AccountId32,
+) -> Vec<u8> {
+    let msg =
+        Micropayment::construct_lock_hash(server, nonce, hashlock, amount, expiration, recipient);
+    sign_with_seed(seed, &msg)
+}
+
+#[test]
+fn hub_routed_payment() {
+    new_test_ext().execute_with(|| {
+        // alice pays charlie through hub bob
+        assert_ok!(Micropayment::open_channel(
+            RuntimeOrigin::signed(alice()),
+            bob(),
+            300,
+            3600
+        ));
+        assert_ok!(Micropayment::open_channel(
+            RuntimeOrigin::signed(bob()),
+            charlie(),
+            300,
+            3600
+        ));
+        let preimage = [7u8; 32];
+        let hashlock = H256::from(sp_io::hashing::blake2_256(&preimage));
+
+        let signature = lock_signature("Alice", &bob(), 0, &hashlock, 50, 100, &charlie());
+        assert_ok!(Micropayment::lock_payment(
+            RuntimeOrigin::signed(bob()),
+            alice(),
+            hashlock,
+            50,
+            100,
+            charlie(),
+            signature
+        ));
+        // the hub forwards the payment with an earlier expiration
+        let signature = lock_signature("Bob", &charlie(), 0, &hashlock, 50, 50, &charlie());
+        assert_ok!(Micropayment::lock_payment(
+            RuntimeOrigin::signed(charlie()),
+            bob(),
+            hashlock,
+            50,
+            50,
+            charlie(),
+            signature
+        ));
+        assert_eq!(Micropayment::channel(&alice(), &bob()).balance, 250);
+        assert_eq!(Micropayment::lock_count((alice(), bob())), 1);
+
+        assert_ok!(Micropayment::claim_locked_payment(
+            RuntimeOrigin::signed(charlie()),
+            bob(),
+            preimage
+        ));
+        assert_eq!(Balances::free_balance(charlie()), 545);
+        assert_ok!(Micropayment::claim_locked_payment(
+            RuntimeOrigin::signed(bob()),
+            alice(),
+            preimage
+        ));
+        assert_eq!(Balances::free_balance(bob()), 245);
+        assert_eq!(Micropayment::pending_lock((alice(), bob()), hashlock), None);
+        // the settled lock still counts against the channel's cap
+        assert_eq!(Micropayment::lock_count((alice(), bob())), 1);
+        assert_eq!(
+            Micropayment::total_micropayment_chanel_balance(alice()),
+            Some(250)
+        );
+
+        // the same lock can not be replayed in the channel
+        let signature = lock_signature("Alice", &bob(), 0, &hashlock, 50, 100, &charlie());
+        assert_eq!(
+            Micropayment::lock_payment(
+                RuntimeOrigin::signed(bob()),
+                alice(),
+                hashlock,
+                50,
+                100,
+                charlie(),
+                signature
+            ),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::LockAlreadyExists
+            ))
+        );
+    });
+}
+
+#[test]
+fn refund_locked_payment() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Micropayment::open_channel(
+            RuntimeOrigin::signed(alice()),
+            bob(),
+            300,
+            3600
+        ));
+        let preimage = [1u8; 32];
+        let hashlock = H256::from(sp_io::hashing::blake2_256(&preimage));
+
+        // expiration beyond the channel's lifetime
+        let signature = lock_signature("Alice", &bob(), 0, &hashlock, 50, 1000, &bob());
+        assert_eq!(
+            Micropayment::lock_payment(
+                RuntimeOrigin::signed(bob()),
+                alice(),
+                hashlock,
+                50,
+                1000,
+                bob(),
+                signature
+            ),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::InvalidLockExpiration
+            ))
+        );
+        // signed by the wrong account
+        let signature = lock_signature("Charlie", &bob(), 0, &hashlock, 50, 10, &bob());
+        assert_eq!(
+            Micropayment::lock_payment(
+                RuntimeOrigin::signed(bob()),
+                alice(),
+                hashlock,
+                50,
+                10,
+                bob(),
+                signature
+            ),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::InvalidSignature
+            ))
+        );
+
+        let signature = lock_signature("Alice", &bob(), 0, &hashlock, 50, 10, &bob());
+        assert_ok!(Micropayment::lock_payment(
+            RuntimeOrigin::signed(bob()),
+            alice(),
+            hashlock,
+            50,
+            10,
+            bob(),
+            signature
+        ));
+        assert_eq!(
+            Micropayment::refund_locked_payment(RuntimeOrigin::signed(alice()), bob(), hashlock),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::LockNotExpired
+            ))
+        );
+
+        run_to_block(11);
+        assert_eq!(
+            Micropayment::claim_locked_payment(RuntimeOrigin::signed(bob()), alice(), preimage),
+            Err(DispatchErrorWithPostInfo::from(Error::<Test>::LockExpired))
+        );
+        assert_ok!(Micropayment::refund_locked_payment(
+            RuntimeOrigin::signed(alice()),
+            bob(),
+            hashlock
+        ));
+        assert_eq!(Micropayment::channel(&alice(), &bob()).balance, 300);
+        assert_eq!(
+            Micropayment::claim_locked_payment(RuntimeOrigin::signed(bob()), alice(), preimage),
+            Err(DispatchErrorWithPostInfo::from(Error::<Test>::LockNotExist))
+        );
+    });
+}
+
+#[test]
+fn locks_per_channel_are_capped() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Micropayment::open_channel(
+            RuntimeOrigin::signed(alice()),
+            bob(),
+            300,
+            3600
+        ));
+        // settled locks still count, they are kept until the channel closes
+        for i in 0..MaxPendingLocks::get() as u8 {
+            let preimage = [i; 32];
+            let hashlock = H256::from(sp_io::hashing::blake2_256(&preimage));
+            let signature = lock_signature("Alice", &bob(), 0, &hashlock, 10, 100, &bob());
+            assert_ok!(Micropayment::lock_payment(
+                RuntimeOrigin::signed(bob()),
+                alice(),
+                hashlock,
+                10,
+                100,
+                bob(),
+                signature
+            ));
+            assert_ok!(Micropayment::claim_locked_payment(
+                RuntimeOrigin::signed(bob()),
+                alice(),
+                preimage
+            ));
+        }
+        assert_eq!(
+            Micropayment::lock_count((alice(), bob())),
+            MaxPendingLocks::get()
+        );
+
+        let hashlock = H256::from(sp_io::hashing::blake2_256(&[255u8; 32]));
+        let signature = lock_signature("Alice", &bob(), 0, &hashlock, 10, 100, &bob());
+        assert_eq!(
+            Micropayment::lock_payment(
+                RuntimeOrigin::signed(bob()),
+                alice(),
+                hashlock,
+                10,
+                100,
+                bob(),
+                signature
+            ),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::TooManyPendingLocks
+            ))
+        );
+
+        assert_ok!(Micropayment::close_channel(
+            RuntimeOrigin::signed(bob()),
+            alice()
+        ));
+        assert_eq!(Micropayment::lock_count((alice(), bob())), 0);
+        assert_eq!(
+            SettledLocks::<Test>::iter_prefix((alice(), bob())).count(),
+            0
+        );
+    });
+}
+
+#[test]
+fn close_channel_returns_pending_locks() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Micropayment::open_channel(
+            RuntimeOrigin::signed(alice()),
+            bob(),
+            300,
+            3600
+        ));
+        let hashlock = H256::from(sp_io::hashing::blake2_256(&[2u8; 32]));
+        let signature = lock_signature("Alice", &bob(), 0, &hashlock, 50, 100, &bob());
+        assert_ok!(Micropayment::lock_payment(
+            RuntimeOrigin::signed(bob()),
+            alice(),
+            hashlock,
+            50,
+            100,
+            bob(),
+            signature
+        ));
+        assert_ok!(Micropayment::close_channel(
+            RuntimeOrigin::signed(bob()),
+            alice()
+        ));
+        // 250 in channel and 50 locked are returned, 10% burned
+        assert_eq!(Balances::free_balance(alice()), 470);
+        assert_eq!(Micropayment::pending_lock((alice(), bob()), hashlock), None);
+        assert_eq!(
+            Micropayment::total_micropayment_chanel_balance(alice()),
+            None
+        );
+    });
+}
+
+fn voucher_signature(seed: &str, server: &AccountId32, session_id: u32, amount: u128) -> Vec<u8> {
+    let msg = Micropayment::construct_byte_array_and_hash(server, 0, session_id, amount);
+    sign_with_seed(seed, &msg)
+}
+
+#[test]
+fn cooperative_close() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Micropayment::open_channel(
+            RuntimeOrigin::signed(alice()),
+            bob(),
+            300,
+            3600
+        ));
+        let msg = Micropayment::construct_close_hash(&alice(), &bob(), 0, 100);
+        // both sides must sign the final state
+        assert_eq!(
+            Micropayment::cooperative_close(
+                RuntimeOrigin::signed(alice()),
+                bob(),
+                100,
+                sign_with_seed("Alice", &msg),
+                sign_with_seed("Alice", &msg)
+            ),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::InvalidSignature
+            ))
+        );
+        assert_ok!(Micropayment::cooperative_close(
+            RuntimeOrigin::signed(alice()),
+            bob(),
+            100,
+            sign_with_seed("Alice", &msg),
+            sign_with_seed("Bob", &msg)
+        ));
+        assert!(!Channel::<Test>::contains_key(alice(), bob()));
+        // 100 to bob and 200 back to alice, 10% burned
+        assert_eq!(Balances::free_balance(bob()), 590);
+        assert_eq!(Balances::free_balance(alice()), 380);
+        assert_eq!(Micropayment::nonce((alice(), bob())), 1);
+    });
+}
+
+#[test]
+fn disputed_close() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Micropayment::open_channel(
+            RuntimeOrigin::signed(alice()),
+            bob(),
+            300,
+            3600
+        ));
+        // client closes with an old state
+        assert_ok!(Micropayment::initiate_close(
+            RuntimeOrigin::signed(alice()),
+            bob(),
+            1,
+            20,
+            voucher_signature("Alice", &bob(), 1, 20)
+        ));
+        assert_eq!(
+            Micropayment::pending_close(alice(), bob()).map(|c| c.dispute_end),
+            Some(10)
+        );
+        assert_eq!(
+            Micropayment::claim_payment(
+                RuntimeOrigin::signed(bob()),
+                alice(),
+                1,
+                20,
+                voucher_signature("Alice", &bob(), 1, 20)
+            ),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::ChannelClosing
+            ))
+        );
+
+        // server submits the newer state
+        assert_eq!(
+            Micropayment::dispute_close(
+                RuntimeOrigin::signed(bob()),
+                alice(),
+                1,
+                10,
+                voucher_signature("Alice", &bob(), 1, 10)
+            ),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::StaleChannelState
+            ))
+        );
+        assert_ok!(Micropayment::dispute_close(
+            RuntimeOrigin::signed(bob()),
+            alice(),
+            2,
+            80,
+            voucher_signature("Alice", &bob(), 2, 80)
+        ));
+        assert_eq!(
+            Micropayment::finalize_close(RuntimeOrigin::signed(charlie()), alice(), bob()),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::DisputePeriodNotEnded
+            ))
+        );
+
+        run_to_block(11);
+        assert_eq!(
+            Micropayment::dispute_close(
+                RuntimeOrigin::signed(bob()),
+                alice(),
+                3,
+                90,
+                voucher_signature("Alice", &bob(), 3, 90)
+            ),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::DisputePeriodEnded
+            ))
+        );
+        assert_ok!(Micropayment::finalize_close(
+            RuntimeOrigin::signed(charlie()),
+            alice(),
+            bob()
+        ));
+        assert!(!Channel::<Test>::contains_key(alice(), bob()));
+        assert_eq!(Micropayment::pending_close(alice(), bob()), None);
+        // 20 + 80 to bob and 200 back to alice, 10% burned
+        assert_eq!(Balances::free_balance(bob()), 590);
+        assert_eq!(Balances::free_balance(alice()), 380);
+    });
+}
+
+#[test]
+fn close_with_unexpected_session() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Micropayment::open_channel(
+            RuntimeOrigin::signed(alice()),
+            bob(),
+            300,
+            3600
+        ));
+        assert_ok!(Micropayment::claim_payment(
+            RuntimeOrigin::signed(bob()),
+            alice(),
+            1,
+            30,
+            voucher_signature("Alice", &bob(), 1, 30)
+        ));
+
+        // the client can't jump over the unclaimed sessions of the server
+        for session_id in [u32::MAX, 3, 1] {
+            assert_eq!(
+                Micropayment::initiate_close(
+                    RuntimeOrigin::signed(alice()),
+                    bob(),
+                    session_id,
+                    0,
+                    voucher_signature("Alice", &bob(), session_id, 0)
+                ),
+                Err(DispatchErrorWithPostInfo::from(Error::<Test>::SessionError))
+            );
+        }
+        assert_ok!(Micropayment::initiate_close(
+            RuntimeOrigin::signed(alice()),
+            bob(),
+            2,
+            0,
+            voucher_signature("Alice", &bob(), 2, 0)
+        ));
+
+        // the client can't override the vouchers of the server
+        assert_eq!(
+            Micropayment::dispute_close(
+                RuntimeOrigin::signed(alice()),
+                bob(),
+                3,
+                0,
+                voucher_signature("Alice", &bob(), 3, 0)
+            ),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::ChannelNotClosing
+            ))
+        );
+        assert_eq!(
+            Micropayment::dispute_close(
+                RuntimeOrigin::signed(bob()),
+                alice(),
+                u32::MAX,
+                0,
+                voucher_signature("Alice", &bob(), u32::MAX, 0)
+            ),
+            Err(DispatchErrorWithPostInfo::from(Error::<Test>::SessionError))
+        );
+        // every unclaimed session is settled
+        assert_ok!(Micropayment::dispute_close(
+            RuntimeOrigin::signed(bob()),
+            alice(),
+            2,
+            50,
+            voucher_signature("Alice", &bob(), 2, 50)
+        ));
+        assert_ok!(Micropayment::dispute_close(
+            RuntimeOrigin::signed(bob()),
+            alice(),
+            3,
+            40,
+            voucher_signature("Alice", &bob(), 3, 40)
+        ));
+        assert_eq!(
+            Micropayment::pending_close(alice(), bob()).map(|c| (
+                c.session_id,
+                c.amount,
+                c.settled
+            )),
+            Some((3, 40, 50))
+        );
+
+        run_to_block(11);
+        assert_ok!(Micropayment::finalize_close(
+            RuntimeOrigin::signed(charlie()),
+            alice(),
+            bob()
+        ));
+        // 30 + 50 + 40 to bob and 180 back to alice, 10% burned
+        assert_eq!(Balances::free_balance(bob()), 608);
+        assert_eq!(Balances::free_balance(alice()), 362);
+    });
+}
+
+#[test]
+fn claim_payments_batch() {
+    new_test_ext().execute_with(|| {
+        run_to_block(1);
+        assert_ok!(Micropayment::open_channel(
+            RuntimeOrigin::signed(alice()),
+            bob(),
+            300,
+            3600
+        ));
+        assert_ok!(Micropayment::open_channel(
+            RuntimeOrigin::signed(charlie()),
+            bob(),
+            300,
+            3600
+        ));
+        let vouchers = vec![
+            VoucherOf::<Test> {
+                client: alice(),
+                session_id: 1,
+                amount: 30,
+                signature: voucher_signature("Alice", &bob(), 1, 30),
+            },
+            // signed by the wrong client
+            VoucherOf::<Test> {
+                client: charlie(),
+                session_id: 1,
+                amount: 30,
+                signature: voucher_signature("Alice", &bob(), 1, 30),
+            },
+            // no channel between dave and bob
+            VoucherOf::<Test> {
+                client: dave(),
+                session_id: 1,
+                amount: 30,
+                signature: voucher_signature("Dave", &bob(), 1, 30),
+            },
+            VoucherOf::<Test> {
+                client: charlie(),
+                session_id: 1,
+                amount: 50,
+                signature: voucher_signature("Charlie", &bob(), 1, 50),
+            },
+        ];
+        assert_ok!(Micropayment::claim_payments_batch(
+            RuntimeOrigin::signed(bob()),
+            vouchers
+        ));
+
+        System::assert_has_event(RuntimeEvent::Micropayment(
+            crate::Event::ClaimPaymentFailed(
+                charlie(),
+                bob(),
+                1,
+                Error::<Test>::InvalidSignature.into(),
+            ),
+        ));
+        System::assert_has_event(RuntimeEvent::Micropayment(
+            crate::Event::ClaimPaymentFailed(
+                dave(),
+                bob(),
+                1,
+                Error::<Test>::ChannelNotExist.into(),
+            ),
+        ));
+        System::assert_last_event(RuntimeEvent::Micropayment(
+            crate::Event::PaymentsBatchClaimed(bob(), 2, 2),
+        ));
+        assert_eq!(Micropayment::channel(&alice(), &bob()).balance, 270);
+        assert_eq!(Micropayment::channel(&charlie(), &bob()).balance, 250);
+        assert_eq!(Micropayment::session_id((alice(), bob())), Some(1));
+        // 80 claimed, 10% burned
+        assert_eq!(Balances::free_balance(bob()), 572);
+
+        // too many vouchers
+        let voucher = VoucherOf::<Test> {
+            client: alice(),
+            session_id: 2,
+            amount: 1,
+            signature: voucher_signature("Alice", &bob(), 2, 1),
+        };
+        assert_eq!(
+            Micropayment::claim_payments_batch(RuntimeOrigin::signed(bob()), vec![voucher; 11]),
+            Err(DispatchErrorWithPostInfo::from(
+                Error::<Test>::TooManyVouchers
+            ))
+        );
+    });
+}
+
+#[test]
+fn asset_channel() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Assets::force_create(
+            RuntimeOrigin::root(),
+            1,
+            alice(),
+            true,
+            1
+        ));
+        assert_ok!(Assets::mint(
+            RuntimeOrigin::signed(alice()),
+            1,
+            alice(),
+            1000
+        ));
+        assert_ok!(Micropayment::open_asset_channel(
+            RuntimeOrigin::signed(alice()),
+            bob(),
+            1,
+            300,
+            3600
+        ));
+        assert_eq!(Micropayment::channel(&alice(), &bob()).asset, Some(1));
+        assert_eq!(Assets::balance(1, alice()), 700);
+        assert_eq!(Assets::balance(1, Micropayment::escrow_account()), 300);
+        assert_eq!(Balances::free_balance(alice()), 500);
+        assert_eq!(
+            Micropayment::total_asset_channel_balance(alice(), 1),
+            Some(300)
+        );
+        assert_eq!(
+            Micropayment::total_micropayment_chanel_balance(alice()),
+            None
+        );
+
+        assert_ok!(Micropayment::set_asset_burn_rate(
+            RuntimeOrigin::root(),
+            1,
+            Some(Percent::from_percent(20))
+        ));
+        assert_ok!(Micropayment::claim_payment(
+            RuntimeOrigin::signed(bob()),
+            alice(),
+            1,
+            100,
+            voucher_signature("Alice", &bob(), 1, 100)
+        ));
+        assert_eq!(Assets::balance(1, bob()), 80);
+        assert_eq!(Assets::balance(1, Micropayment::escrow_account()), 200);
+        assert_eq!(Assets::total_supply(1), 980);
+        assert_eq!(Balances::free_balance(bob()), 500);
+        assert_eq!(Micropayment::total_asset_burned(1), 20);
+        assert_eq!(
+            Micropayment::total_asset_channel_balance(alice(), 1),
+            Some(200)
+        );
+
+        assert_ok!(Micropayment::close_channel(
+            RuntimeOrigin::signed(bob()),
+            alice()
+        ));
+        assert_eq!(Assets::balance(1, alice()), 860);
+        assert_eq!(Assets::balance(1, Micropayment::escrow_account()), 0);
+        assert_eq!(Assets::total_supply(1), 940);
+        assert_eq!(Micropayment::total_asset_burned(1), 60);
+        assert_eq!(Micropayment::total_asset_channel_balance(alice(), 1), None);
+    });
+}
+
+#[test]
+fn migrate_channels_to_asset_channels() {
+    new_test_ext().execute_with(|| {
+        // channel encoded before multi-asset support
+        let old = (alice(), bob(), 300u128, 2u64, 5u64, 725u64);
+        frame_support::storage::unhashed::put_raw(
+            &Channel::<Test>::hashed_key_for(alice(), bob()),
+            &old.encode(),
+        );
+        StorageVersion::new(0).put::<Micropayment>();
+
+        Micropayment::on_runtime_upgrade();
+        assert_eq!(
+            Micropayment::channel(&alice(), &bob()),
+            Chan {
+                client: alice(),
+                server: bob(),
+                balance: 300,
+                nonce: 2,
+                opened: 5,
+                expiration: 725,
+                asset: None,
+            }
+        );
+        assert_eq!(StorageVersion::get::<Micropayment>(), 1);
+    });
+}
+
 #[test]
 fn test_blake2_hash() {
     let bob: [u8; 32] = [
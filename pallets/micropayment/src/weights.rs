@@ -48,10 +48,20 @@ use sp_std::marker::PhantomData;
 /// Weight functions needed for pallet_micropayment.
 pub trait WeightInfo {
     fn open_channel() -> Weight;
-    fn close_channel() -> Weight;
-    fn close_expired_channels() -> Weight;
+    fn open_asset_channel() -> Weight;
+    fn set_asset_burn_rate() -> Weight;
+    fn close_channel(l: u32) -> Weight;
+    fn close_expired_channels(l: u32) -> Weight;
     fn add_balance() -> Weight;
     fn claim_payment() -> Weight;
+    fn lock_payment() -> Weight;
+    fn claim_locked_payment() -> Weight;
+    fn refund_locked_payment() -> Weight;
+    fn cooperative_close(l: u32) -> Weight;
+    fn initiate_close() -> Weight;
+    fn dispute_close() -> Weight;
+    fn finalize_close(l: u32) -> Weight;
+    fn claim_payments_batch(n: u32) -> Weight;
 }
 
 /// Weights for pallet_micropayment using the Substrate node and recommended hardware.
@@ -62,15 +72,31 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
             .saturating_add(T::DbWeight::get().reads(4 as u64))
             .saturating_add(T::DbWeight::get().writes(3 as u64))
     }
-    fn close_channel() -> Weight {
+    fn open_asset_channel() -> Weight {
+        Weight::from_ref_time(47_826_000 as u64)
+            .saturating_add(T::DbWeight::get().reads(5 as u64))
+            .saturating_add(T::DbWeight::get().writes(4 as u64))
+    }
+    fn set_asset_burn_rate() -> Weight {
+        Weight::from_ref_time(14_302_000 as u64).saturating_add(T::DbWeight::get().writes(1 as u64))
+    }
+    // per-lock terms not measured yet, regenerate with the `l` component
+    fn close_channel(l: u32) -> Weight {
         Weight::from_ref_time(64_244_000 as u64)
+            .saturating_add(Weight::from_ref_time(7_215_000 as u64).saturating_mul(l as u64))
             .saturating_add(T::DbWeight::get().reads(4 as u64))
+            .saturating_add(T::DbWeight::get().reads((1 as u64).saturating_mul(l as u64)))
             .saturating_add(T::DbWeight::get().writes(5 as u64))
+            .saturating_add(T::DbWeight::get().writes((1 as u64).saturating_mul(l as u64)))
     }
-    fn close_expired_channels() -> Weight {
+    // per-lock terms not measured yet, regenerate with the `l` component
+    fn close_expired_channels(l: u32) -> Weight {
         Weight::from_ref_time(71_999_000 as u64)
+            .saturating_add(Weight::from_ref_time(7_215_000 as u64).saturating_mul(l as u64))
             .saturating_add(T::DbWeight::get().reads(5 as u64))
+            .saturating_add(T::DbWeight::get().reads((1 as u64).saturating_mul(l as u64)))
             .saturating_add(T::DbWeight::get().writes(5 as u64))
+            .saturating_add(T::DbWeight::get().writes((1 as u64).saturating_mul(l as u64)))
     }
     fn add_balance() -> Weight {
         Weight::from_ref_time(39_308_000 as u64)
@@ -82,6 +108,57 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
             .saturating_add(T::DbWeight::get().reads(4 as u64))
             .saturating_add(T::DbWeight::get().writes(4 as u64))
     }
+    fn lock_payment() -> Weight {
+        Weight::from_ref_time(118_426_000 as u64)
+            .saturating_add(T::DbWeight::get().reads(4 as u64))
+            .saturating_add(T::DbWeight::get().writes(3 as u64))
+    }
+    fn claim_locked_payment() -> Weight {
+        Weight::from_ref_time(74_913_000 as u64)
+            .saturating_add(T::DbWeight::get().reads(4 as u64))
+            .saturating_add(T::DbWeight::get().writes(5 as u64))
+    }
+    fn refund_locked_payment() -> Weight {
+        Weight::from_ref_time(36_582_000 as u64)
+            .saturating_add(T::DbWeight::get().reads(3 as u64))
+            .saturating_add(T::DbWeight::get().writes(4 as u64))
+    }
+    // per-lock terms not measured yet, regenerate with the `l` component
+    fn cooperative_close(l: u32) -> Weight {
+        Weight::from_ref_time(187_354_000 as u64)
+            .saturating_add(Weight::from_ref_time(7_215_000 as u64).saturating_mul(l as u64))
+            .saturating_add(T::DbWeight::get().reads(6 as u64))
+            .saturating_add(T::DbWeight::get().reads((1 as u64).saturating_mul(l as u64)))
+            .saturating_add(T::DbWeight::get().writes(8 as u64))
+            .saturating_add(T::DbWeight::get().writes((1 as u64).saturating_mul(l as u64)))
+    }
+    fn initiate_close() -> Weight {
+        Weight::from_ref_time(112_870_000 as u64)
+            .saturating_add(T::DbWeight::get().reads(4 as u64))
+            .saturating_add(T::DbWeight::get().writes(1 as u64))
+    }
+    fn dispute_close() -> Weight {
+        Weight::from_ref_time(114_205_000 as u64)
+            .saturating_add(T::DbWeight::get().reads(5 as u64))
+            .saturating_add(T::DbWeight::get().writes(1 as u64))
+    }
+    // per-lock terms not measured yet, regenerate with the `l` component
+    fn finalize_close(l: u32) -> Weight {
+        Weight::from_ref_time(78_641_000 as u64)
+            .saturating_add(Weight::from_ref_time(7_215_000 as u64).saturating_mul(l as u64))
+            .saturating_add(T::DbWeight::get().reads(6 as u64))
+            .saturating_add(T::DbWeight::get().reads((1 as u64).saturating_mul(l as u64)))
+            .saturating_add(T::DbWeight::get().writes(8 as u64))
+            .saturating_add(T::DbWeight::get().writes((1 as u64).saturating_mul(l as u64)))
+    }
+    // not measured yet, one `claim_payment` per voucher until regenerated with
+    // `--extrinsic=claim_payments_batch`
+    fn claim_payments_batch(n: u32) -> Weight {
+        Weight::from_ref_time(129_698_000 as u64)
+            .saturating_mul(n as u64)
+            .saturating_add(T::DbWeight::get().reads((4 as u64).saturating_mul(n as u64)))
+            .saturating_add(T::DbWeight::get().writes((4 as u64).saturating_mul(n as u64)))
+    }
 }
 
 // For backwards compatibility and tests
@@ -91,15 +168,32 @@ impl WeightInfo for () {
             .saturating_add(RocksDbWeight::get().reads(4 as u64))
             .saturating_add(RocksDbWeight::get().writes(3 as u64))
     }
-    fn close_channel() -> Weight {
+    fn open_asset_channel() -> Weight {
+        Weight::from_ref_time(47_826_000 as u64)
+            .saturating_add(RocksDbWeight::get().reads(5 as u64))
+            .saturating_add(RocksDbWeight::get().writes(4 as u64))
+    }
+    fn set_asset_burn_rate() -> Weight {
+        Weight::from_ref_time(14_302_000 as u64)
+            .saturating_add(RocksDbWeight::get().writes(1 as u64))
+    }
+    // per-lock terms not measured yet, regenerate with the `l` component
+    fn close_channel(l: u32) -> Weight {
         Weight::from_ref_time(64_244_000 as u64)
+            .saturating_add(Weight::from_ref_time(7_215_000 as u64).saturating_mul(l as u64))
             .saturating_add(RocksDbWeight::get().reads(4 as u64))
+            .saturating_add(RocksDbWeight::get().reads((1 as u64).saturating_mul(l as u64)))
             .saturating_add(RocksDbWeight::get().writes(5 as u64))
+            .saturating_add(RocksDbWeight::get().writes((1 as u64).saturating_mul(l as u64)))
     }
-    fn close_expired_channels() -> Weight {
+    // per-lock terms not measured yet, regenerate with the `l` component
+    fn close_expired_channels(l: u32) -> Weight {
         Weight::from_ref_time(71_999_000 as u64)
+            .saturating_add(Weight::from_ref_time(7_215_000 as u64).saturating_mul(l as u64))
             .saturating_add(RocksDbWeight::get().reads(5 as u64))
+            .saturating_add(RocksDbWeight::get().reads((1 as u64).saturating_mul(l as u64)))
             .saturating_add(RocksDbWeight::get().writes(5 as u64))
+            .saturating_add(RocksDbWeight::get().writes((1 as u64).saturating_mul(l as u64)))
     }
     fn add_balance() -> Weight {
         Weight::from_ref_time(39_308_000 as u64)
@@ -111,4 +205,55 @@ impl WeightInfo for () {
             .saturating_add(RocksDbWeight::get().reads(4 as u64))
             .saturating_add(RocksDbWeight::get().writes(4 as u64))
     }
+    fn lock_payment() -> Weight {
+        Weight::from_ref_time(118_426_000 as u64)
+            .saturating_add(RocksDbWeight::get().reads(4 as u64))
+            .saturating_add(RocksDbWeight::get().writes(3 as u64))
+    }
+    fn claim_locked_payment() -> Weight {
+        Weight::from_ref_time(74_913_000 as u64)
+            .saturating_add(RocksDbWeight::get().reads(4 as u64))
+            .saturating_add(RocksDbWeight::get().writes(5 as u64))
+    }
+    fn refund_locked_payment() -> Weight {
+        Weight::from_ref_time(36_582_000 as u64)
+            .saturating_add(RocksDbWeight::get().reads(3 as u64))
+            .saturating_add(RocksDbWeight::get().writes(4 as u64))
+    }
+    // per-lock terms not measured yet, regenerate with the `l` component
+    fn cooperative_close(l: u32) -> Weight {
+        Weight::from_ref_time(187_354_000 as u64)
+            .saturating_add(Weight::from_ref_time(7_215_000 as u64).saturating_mul(l as u64))
+            .saturating_add(RocksDbWeight::get().reads(6 as u64))
+            .saturating_add(RocksDbWeight::get().reads((1 as u64).saturating_mul(l as u64)))
+            .saturating_add(RocksDbWeight::get().writes(8 as u64))
+            .saturating_add(RocksDbWeight::get().writes((1 as u64).saturating_mul(l as u64)))
+    }
+    fn initiate_close() -> Weight {
+        Weight::from_ref_time(112_870_000 as u64)
+            .saturating_add(RocksDbWeight::get().reads(4 as u64))
+            .saturating_add(RocksDbWeight::get().writes(1 as u64))
+    }
+    fn dispute_close() -> Weight {
+        Weight::from_ref_time(114_205_000 as u64)
+            .saturating_add(RocksDbWeight::get().reads(5 as u64))
+            .saturating_add(RocksDbWeight::get().writes(1 as u64))
+    }
+    // per-lock terms not measured yet, regenerate with the `l` component
+    fn finalize_close(l: u32) -> Weight {
+        Weight::from_ref_time(78_641_000 as u64)
+            .saturating_add(Weight::from_ref_time(7_215_000 as u64).saturating_mul(l as u64))
+            .saturating_add(RocksDbWeight::get().reads(6 as u64))
+            .saturating_add(RocksDbWeight::get().reads((1 as u64).saturating_mul(l as u64)))
+            .saturating_add(RocksDbWeight::get().writes(8 as u64))
+            .saturating_add(RocksDbWeight::get().writes((1 as u64).saturating_mul(l as u64)))
+    }
+    // not measured yet, one `claim_payment` per voucher until regenerated with
+    // `--extrinsic=claim_payments_batch`
+    fn claim_payments_batch(n: u32) -> Weight {
+        Weight::from_ref_time(129_698_000 as u64)
+            .saturating_mul(n as u64)
+            .saturating_add(RocksDbWeight::get().reads((4 as u64).saturating_mul(n as u64)))
+            .saturating_add(RocksDbWeight::get().writes((4 as u64).saturating_mul(n as u64)))
+    }
 }
@@ -0,0 +1,159 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `voucher` subcommand of the node binary.
+
+use crate::{state::VoucherStore, AccountId, Balance, Error, Voucher};
+use sp_core::{crypto::Pair as _, sr25519};
+use std::{path::PathBuf, str::FromStr};
+
+/// Generate and verify micropayment vouchers.
+#[derive(Debug, clap::Subcommand)]
+pub enum VoucherCmd {
+    /// Sign a voucher with the client's secret key.
+    Sign(SignVoucherCmd),
+    /// Verify a voucher signature.
+    Verify(VerifyVoucherCmd),
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct SignVoucherCmd {
+    /// Secret URI of the client, e.g. a mnemonic phrase or `//Alice`.
+    #[arg(long)]
+    suri: String,
+
+    /// Password of the secret URI.
+    #[arg(long)]
+    password: Option<String>,
+
+    /// SS58 address of the server.
+    #[arg(long)]
+    server: String,
+
+    /// Channel nonce. Required unless the channel is tracked in `--state`.
+    #[arg(long)]
+    nonce: Option<u64>,
+
+    /// Session id. Required unless the channel is tracked in `--state`.
+    #[arg(long)]
+    session_id: Option<u32>,
+
+    /// Accumulated amount of the session, or the increment with `--state`.
+    #[arg(long)]
+    amount: Balance,
+
+    /// JSON file tracking the channel. The amount is added to the current session.
+    #[arg(long)]
+    state: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct VerifyVoucherCmd {
+    /// SS58 address of the client.
+    #[arg(long)]
+    client: String,
+
+    /// SS58 address of the server.
+    #[arg(long)]
+    server: String,
+
+    #[arg(long)]
+    nonce: u64,
+
+    #[arg(long)]
+    session_id: u32,
+
+    #[arg(long)]
+    amount: Balance,
+
+    /// Hex encoded sr25519 signature.
+    #[arg(long)]
+    signature: String,
+}
+
+fn parse_account(address: &str) -> Result<AccountId, String> {
+    AccountId::from_str(address).map_err(|e| format!("invalid address {}: {}", address, e))
+}
+
+fn print_voucher(voucher: &Voucher) {
+    println!("Hash: 0x{}", hex::encode(voucher.message()));
+    println!("Signature: 0x{}", hex::encode(voucher.signature));
+}
+
+impl VoucherCmd {
+    pub fn run(&self) -> Result<(), String> {
+        match self {
+            VoucherCmd::Sign(cmd) => cmd.run(),
+            VoucherCmd::Verify(cmd) => cmd.run(),
+        }
+    }
+}
+
+impl SignVoucherCmd {
+    pub fn run(&self) -> Result<(), String> {
+        let pair = sr25519::Pair::from_string(&self.suri, self.password.as_deref())
+            .map_err(|e| format!("invalid secret uri: {:?}", e))?;
+        let server = parse_account(&self.server)?;
+
+        let voucher = match &self.state {
+            Some(path) => {
+                let client: AccountId = pair.public().into();
+                let mut store = VoucherStore::load(path).map_err(|e| e.to_string())?;
+                if let (Some(nonce), Some(session_id)) = (self.nonce, self.session_id) {
+                    store.open(&client, &server, nonce, session_id);
+                }
+                let voucher = store
+                    .pay(&pair, &server, self.amount)
+                    .map_err(|e| match e {
+                        Error::UnknownChannel => {
+                            "channel is not tracked, pass --nonce and --session-id".to_string()
+                        }
+                        e => e.to_string(),
+                    })?;
+                store.save(path).map_err(|e| e.to_string())?;
+                voucher
+            }
+            None => {
+                let nonce = self.nonce.ok_or("--nonce is required without --state")?;
+                let session_id = self
+                    .session_id
+                    .ok_or("--session-id is required without --state")?;
+                Voucher::sign(&pair, server, nonce, session_id, self.amount)
+            }
+        };
+        print_voucher(&voucher);
+        Ok(())
+    }
+}
+
+impl VerifyVoucherCmd {
+    pub fn run(&self) -> Result<(), String> {
+        let signature = hex::decode(self.signature.trim_start_matches("0x"))
+            .map_err(|e| format!("invalid signature: {}", e))?
+            .try_into()
+            .map_err(|_| "signature must be 64 bytes".to_string())?;
+        let voucher = Voucher {
+            client: parse_account(&self.client)?,
+            server: parse_account(&self.server)?,
+            nonce: self.nonce,
+            session_id: self.session_id,
+            amount: self.amount,
+            signature,
+        };
+        voucher.verify().map_err(|e| e.to_string())?;
+        println!("Voucher is valid");
+        Ok(())
+    }
+}
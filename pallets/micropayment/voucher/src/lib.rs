@@ -0,0 +1,279 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Off-chain micropayment vouchers for deeper chain
+//!
+//! A voucher is a client's sr25519 signature on |server_addr|nonce|session_id|amount|,
+//! the message `pallet_micropayment` verifies in `claim_payment`. Within one session the
+//! client sends vouchers with accumulated amounts, and the server only claims the latest one.
+//!
+//! This crate generates, signs, verifies and aggregates vouchers without a node, and tracks
+//! channel nonce and session state locally, see [`state`].
+
+use codec::Encode;
+use serde::{Deserialize, Serialize};
+use sp_core::{crypto::Pair as _, sr25519};
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[cfg(feature = "cli")]
+pub mod cli;
+pub mod state;
+
+pub use sp_core::crypto::AccountId32 as AccountId;
+
+/// Balance type of deeper chain, same as `node_primitives::Balance`.
+pub type Balance = u128;
+
+/// Errors of voucher handling.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Signature does not match the voucher.
+    InvalidSignature,
+    /// Voucher belongs to another channel.
+    ChannelMismatch,
+    /// Voucher is not newer than the one already accepted.
+    StaleVoucher,
+    /// Session has already been claimed on chain.
+    SessionClaimed,
+    /// Channel is not tracked in the local state.
+    UnknownChannel,
+    /// Local state can not be read or written.
+    Storage(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidSignature => write!(f, "invalid voucher signature"),
+            Error::ChannelMismatch => write!(f, "voucher belongs to another channel"),
+            Error::StaleVoucher => write!(f, "voucher is not newer than the accepted one"),
+            Error::SessionClaimed => write!(f, "session has already been claimed"),
+            Error::UnknownChannel => write!(f, "channel is not tracked"),
+            Error::Storage(e) => write!(f, "voucher state storage error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Construct data from |server_addr|nonce|session_id|amount| and hash it.
+///
+/// Byte-identical to `pallet_micropayment::Pallet::construct_byte_array_and_hash`.
+pub fn voucher_hash(server: &AccountId, nonce: u64, session_id: u32, amount: Balance) -> [u8; 32] {
+    let mut data = Vec::new();
+    data.extend_from_slice(&server.encode());
+    data.extend_from_slice(&nonce.to_be_bytes());
+    data.extend_from_slice(&session_id.to_be_bytes());
+    data.extend_from_slice(&amount.encode());
+    sp_core::hashing::blake2_256(&data)
+}
+
+/// Construct data from |client_addr|server_addr|nonce|amount| and hash it.
+///
+/// Byte-identical to `pallet_micropayment::Pallet::construct_close_hash`,
+/// both client and server sign it for cooperative close.
+pub fn close_hash(client: &AccountId, server: &AccountId, nonce: u64, amount: Balance) -> [u8; 32] {
+    let mut data = Vec::new();
+    data.extend_from_slice(&client.encode());
+    data.extend_from_slice(&server.encode());
+    data.extend_from_slice(&nonce.to_be_bytes());
+    data.extend_from_slice(&amount.encode());
+    sp_core::hashing::blake2_256(&data)
+}
+
+/// A signed micropayment from the client to the server.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Voucher {
+    pub client: AccountId,
+    pub server: AccountId,
+    pub nonce: u64,
+    pub session_id: u32,
+    /// accumulated amount of the session
+    pub amount: Balance,
+    #[serde(with = "signature_hex")]
+    pub signature: [u8; 64],
+}
+
+impl Voucher {
+    /// Sign a voucher with the client's key pair.
+    pub fn sign(
+        pair: &sr25519::Pair,
+        server: AccountId,
+        nonce: u64,
+        session_id: u32,
+        amount: Balance,
+    ) -> Self {
+        let msg = voucher_hash(&server, nonce, session_id, amount);
+        Voucher {
+            client: pair.public().into(),
+            server,
+            nonce,
+            session_id,
+            amount,
+            signature: pair.sign(&msg).0,
+        }
+    }
+
+    /// Hash of the voucher that the client signs.
+    pub fn message(&self) -> [u8; 32] {
+        voucher_hash(&self.server, self.nonce, self.session_id, self.amount)
+    }
+
+    /// Verify the signature against the client account.
+    pub fn verify(&self) -> Result<(), Error> {
+        let public = sr25519::Public::from_raw(self.client.clone().into());
+        let signature = sr25519::Signature::from_raw(self.signature);
+        if sr25519::Pair::verify(&signature, self.message(), &public) {
+            Ok(())
+        } else {
+            Err(Error::InvalidSignature)
+        }
+    }
+
+    /// Whether this voucher supersedes the other one of the same channel:
+    /// a later session, or more accumulated amount in the same session.
+    pub fn is_newer_than(&self, other: &Voucher) -> bool {
+        (self.session_id, self.amount) > (other.session_id, other.amount)
+    }
+
+    /// Arguments of `claim_payment`: (client, session_id, amount, signature).
+    pub fn claim_args(&self) -> (AccountId, u32, Balance, Vec<u8>) {
+        (
+            self.client.clone(),
+            self.session_id,
+            self.amount,
+            self.signature.to_vec(),
+        )
+    }
+}
+
+/// Keep the latest valid voucher of each channel and session, in the order they can be
+/// claimed. Vouchers with invalid signatures are dropped.
+///
+/// The result can be passed to `claim_payments_batch` as is, up to `MaxBatchClaims` vouchers
+/// at a time: the vouchers of a channel are sorted by session and each claim moves the channel
+/// to the next one. A voucher whose previous session is missing fails with `SessionError`.
+pub fn aggregate<I: IntoIterator<Item = Voucher>>(vouchers: I) -> Vec<Voucher> {
+    let mut latest: BTreeMap<(AccountId, AccountId, u64, u32), Voucher> = BTreeMap::new();
+    for voucher in vouchers.into_iter().filter(|v| v.verify().is_ok()) {
+        let key = (
+            voucher.client.clone(),
+            voucher.server.clone(),
+            voucher.nonce,
+            voucher.session_id,
+        );
+        match latest.get(&key) {
+            Some(current) if current.amount >= voucher.amount => {}
+            _ => {
+                latest.insert(key, voucher);
+            }
+        }
+    }
+    latest.into_values().collect()
+}
+
+mod signature_hex {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(signature: &[u8; 64], s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&hex::encode(signature))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<[u8; 64], D::Error> {
+        let s = String::deserialize(d)?;
+        let bytes = hex::decode(s.trim_start_matches("0x")).map_err(D::Error::custom)?;
+        bytes
+            .try_into()
+            .map_err(|_| D::Error::custom("signature must be 64 bytes"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+    use sp_core::crypto::Ss58Codec;
+
+    fn account(seed: &str) -> (sr25519::Pair, AccountId) {
+        let pair = sr25519::Pair::from_string(&format!("//{}", seed), None).unwrap();
+        let account = pair.public().into();
+        (pair, account)
+    }
+
+    #[test]
+    fn voucher_hash_matches_pallet() {
+        // voucher of the `claim_payment` test in pallet_micropayment
+        let alice = account("Alice").1;
+        let bob = account("Bob").1;
+        let voucher = Voucher {
+            client: alice,
+            server: bob,
+            nonce: 0,
+            session_id: 1,
+            amount: 30,
+            signature: hex!("1a2157be0e159a600502c5c6435539672bcbce956355a1ca35201762fd1fb72e0b48e853e812011919e5d25b07e4056b9b98e6b2de612652d450bd14063a6185"),
+        };
+        assert_eq!(voucher.verify(), Ok(()));
+        assert_eq!(
+            voucher.server.to_ss58check(),
+            "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty"
+        );
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let (alice, _) = account("Alice");
+        let bob = account("Bob").1;
+        let mut voucher = Voucher::sign(&alice, bob, 3, 7, 1_000);
+        assert_eq!(voucher.verify(), Ok(()));
+        voucher.amount += 1;
+        assert_eq!(voucher.verify(), Err(Error::InvalidSignature));
+    }
+
+    #[test]
+    fn aggregate_keeps_latest_of_each_session() {
+        let (alice, _) = account("Alice");
+        let (charlie, _) = account("Charlie");
+        let bob = account("Bob").1;
+        let mut forged = Voucher::sign(&alice, bob.clone(), 0, 1, 10);
+        forged.amount = 100;
+        let vouchers = vec![
+            Voucher::sign(&alice, bob.clone(), 0, 1, 10),
+            Voucher::sign(&alice, bob.clone(), 0, 1, 30),
+            Voucher::sign(&alice, bob.clone(), 0, 1, 20),
+            Voucher::sign(&alice, bob.clone(), 0, 2, 5),
+            Voucher::sign(&charlie, bob.clone(), 0, 1, 40),
+            forged,
+        ];
+        let aggregated = aggregate(vouchers);
+        let amounts: Vec<_> = aggregated
+            .iter()
+            .map(|v| (v.session_id, v.amount))
+            .collect();
+        assert_eq!(aggregated.len(), 3);
+        assert!(amounts.contains(&(1, 30)));
+        assert!(amounts.contains(&(2, 5)));
+        assert!(amounts.contains(&(1, 40)));
+    }
+
+    #[test]
+    fn voucher_json_round_trip() {
+        let (alice, _) = account("Alice");
+        let voucher = Voucher::sign(&alice, account("Bob").1, 0, 1, 10);
+        let json = serde_json::to_string(&voucher).unwrap();
+        assert_eq!(serde_json::from_str::<Voucher>(&json).unwrap(), voucher);
+    }
+}
@@ -0,0 +1,274 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Local channel state for clients and servers.
+//!
+//! The chain only knows the channel nonce and the last claimed session id, so both sides
+//! keep the rest locally: the client the accumulated amount of the current session, the
+//! server the latest voucher of each session it has not claimed yet. The state is stored
+//! as JSON.
+
+use crate::{AccountId, Balance, Error, Voucher};
+use serde::{Deserialize, Serialize};
+use sp_core::{crypto::Pair as _, sr25519};
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// Channel state kept by one side of a channel.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelState {
+    /// channel nonce, increased by the pallet each time the channel is reopened
+    pub nonce: u64,
+    /// session id of the current session
+    pub session_id: u32,
+    /// accumulated amount of the current session
+    pub amount: Balance,
+    /// latest voucher of the current session
+    pub latest: Option<Voucher>,
+    /// server side: latest voucher of each session not claimed on chain yet
+    #[serde(default)]
+    pub unclaimed: BTreeMap<u32, Voucher>,
+    /// last session claimed on chain
+    pub claimed_session: Option<u32>,
+}
+
+/// Channel states keyed by (client, server).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoucherStore {
+    channels: BTreeMap<String, ChannelState>,
+}
+
+fn channel_key(client: &AccountId, server: &AccountId) -> String {
+    format!("{}:{}", client, server)
+}
+
+impl VoucherStore {
+    /// Load the store from a JSON file, an absent file is an empty store.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = fs::read(path).map_err(|e| Error::Storage(e.to_string()))?;
+        serde_json::from_slice(&data).map_err(|e| Error::Storage(e.to_string()))
+    }
+
+    /// Save the store to a JSON file.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let data = serde_json::to_vec_pretty(self).map_err(|e| Error::Storage(e.to_string()))?;
+        fs::write(path, data).map_err(|e| Error::Storage(e.to_string()))
+    }
+
+    pub fn channel(&self, client: &AccountId, server: &AccountId) -> Option<&ChannelState> {
+        self.channels.get(&channel_key(client, server))
+    }
+
+    /// Start tracking a channel, or restart it after the channel is reopened on chain
+    /// with a new nonce. Session ids restart from `session_id`.
+    pub fn open(&mut self, client: &AccountId, server: &AccountId, nonce: u64, session_id: u32) {
+        self.channels.insert(
+            channel_key(client, server),
+            ChannelState {
+                nonce,
+                session_id,
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Client side: pay `increment` more in the current session and sign the voucher
+    /// with the accumulated amount.
+    pub fn pay(
+        &mut self,
+        pair: &sr25519::Pair,
+        server: &AccountId,
+        increment: Balance,
+    ) -> Result<Voucher, Error> {
+        let client: AccountId = pair.public().into();
+        let state = self
+            .channels
+            .get_mut(&channel_key(&client, server))
+            .ok_or(Error::UnknownChannel)?;
+        let amount = state.amount.saturating_add(increment);
+        let voucher = Voucher::sign(pair, server.clone(), state.nonce, state.session_id, amount);
+        state.amount = amount;
+        state.latest = Some(voucher.clone());
+        Ok(voucher)
+    }
+
+    /// Client side: start the next session, e.g. after the server claimed the current one.
+    pub fn next_session(&mut self, client: &AccountId, server: &AccountId) -> Result<u32, Error> {
+        let state = self
+            .channels
+            .get_mut(&channel_key(client, server))
+            .ok_or(Error::UnknownChannel)?;
+        state.session_id = state.session_id.saturating_add(1);
+        state.amount = 0;
+        state.latest = None;
+        Ok(state.session_id)
+    }
+
+    /// Server side: verify a voucher and keep it if it supersedes the one accepted in its
+    /// session. Vouchers of older sessions are kept until they are claimed.
+    pub fn accept(&mut self, voucher: Voucher) -> Result<(), Error> {
+        voucher.verify()?;
+        let state = self
+            .channels
+            .get_mut(&channel_key(&voucher.client, &voucher.server))
+            .ok_or(Error::UnknownChannel)?;
+        if voucher.nonce != state.nonce {
+            return Err(Error::ChannelMismatch);
+        }
+        if let Some(claimed) = state.claimed_session {
+            if voucher.session_id <= claimed {
+                return Err(Error::SessionClaimed);
+            }
+        }
+        if let Some(accepted) = state.unclaimed.get(&voucher.session_id) {
+            if !voucher.is_newer_than(accepted) {
+                return Err(Error::StaleVoucher);
+            }
+        }
+        if voucher.session_id >= state.session_id {
+            state.session_id = voucher.session_id;
+            state.amount = voucher.amount;
+            state.latest = Some(voucher.clone());
+        }
+        state.unclaimed.insert(voucher.session_id, voucher);
+        Ok(())
+    }
+
+    /// Server side: record that the sessions up to `session_id` have been claimed on chain.
+    pub fn mark_claimed(
+        &mut self,
+        client: &AccountId,
+        server: &AccountId,
+        session_id: u32,
+    ) -> Result<(), Error> {
+        let state = self
+            .channels
+            .get_mut(&channel_key(client, server))
+            .ok_or(Error::UnknownChannel)?;
+        state.claimed_session = state.claimed_session.max(Some(session_id));
+        state.unclaimed.retain(|session, _| *session > session_id);
+        if state.session_id <= session_id {
+            state.latest = None;
+        }
+        Ok(())
+    }
+
+    /// Server side: latest voucher of each unclaimed session, in the order the sessions
+    /// have to be claimed, ready for `claim_payments_batch`.
+    pub fn claimable(&self, server: &AccountId) -> Vec<Voucher> {
+        self.channels
+            .values()
+            .flat_map(|state| state.unclaimed.values().cloned())
+            .filter(|voucher| &voucher.server == server)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(seed: &str) -> sr25519::Pair {
+        sr25519::Pair::from_string(&format!("//{}", seed), None).unwrap()
+    }
+
+    #[test]
+    fn client_and_server_track_sessions() {
+        let alice = pair("Alice");
+        let client: AccountId = alice.public().into();
+        let server: AccountId = pair("Bob").public().into();
+        let mut client_store = VoucherStore::default();
+        let mut server_store = VoucherStore::default();
+        client_store.open(&client, &server, 0, 1);
+        server_store.open(&client, &server, 0, 1);
+
+        let first = client_store.pay(&alice, &server, 10).unwrap();
+        let second = client_store.pay(&alice, &server, 20).unwrap();
+        assert_eq!(second.amount, 30);
+        assert_eq!(server_store.accept(second.clone()), Ok(()));
+        assert_eq!(server_store.accept(first), Err(Error::StaleVoucher));
+        assert_eq!(server_store.claimable(&server), vec![second.clone()]);
+
+        server_store.mark_claimed(&client, &server, 1).unwrap();
+        assert_eq!(server_store.accept(second), Err(Error::SessionClaimed));
+        assert!(server_store.claimable(&server).is_empty());
+
+        assert_eq!(client_store.next_session(&client, &server), Ok(2));
+        let third = client_store.pay(&alice, &server, 5).unwrap();
+        assert_eq!((third.session_id, third.amount), (2, 5));
+        assert_eq!(server_store.accept(third), Ok(()));
+
+        // reopened channel has a new nonce
+        client_store.open(&client, &server, 1, 1);
+        let reopened = client_store.pay(&alice, &server, 5).unwrap();
+        assert_eq!(server_store.accept(reopened), Err(Error::ChannelMismatch));
+    }
+
+    #[test]
+    fn older_session_kept_until_claimed() {
+        let alice = pair("Alice");
+        let client: AccountId = alice.public().into();
+        let server: AccountId = pair("Bob").public().into();
+        let mut client_store = VoucherStore::default();
+        let mut server_store = VoucherStore::default();
+        client_store.open(&client, &server, 0, 1);
+        server_store.open(&client, &server, 0, 1);
+
+        let first = client_store.pay(&alice, &server, 10).unwrap();
+        assert_eq!(server_store.accept(first.clone()), Ok(()));
+        client_store.next_session(&client, &server).unwrap();
+        let second = client_store.pay(&alice, &server, 20).unwrap();
+        assert_eq!(server_store.accept(second.clone()), Ok(()));
+        // a newer voucher of the older session is still accepted
+        let late = Voucher::sign(&alice, server.clone(), 0, 1, 15);
+        assert_eq!(server_store.accept(late.clone()), Ok(()));
+        assert_eq!(server_store.accept(first), Err(Error::StaleVoucher));
+        assert_eq!(server_store.claimable(&server), vec![late, second.clone()]);
+
+        server_store.mark_claimed(&client, &server, 1).unwrap();
+        assert_eq!(server_store.claimable(&server), vec![second.clone()]);
+        assert_eq!(
+            server_store.channel(&client, &server).unwrap().latest,
+            Some(second)
+        );
+        server_store.mark_claimed(&client, &server, 2).unwrap();
+        assert!(server_store.claimable(&server).is_empty());
+    }
+
+    #[test]
+    fn store_round_trip() {
+        let alice = pair("Alice");
+        let client: AccountId = alice.public().into();
+        let server: AccountId = pair("Bob").public().into();
+        let mut store = VoucherStore::default();
+        store.open(&client, &server, 2, 1);
+        store.pay(&alice, &server, 10).unwrap();
+
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .subsec_nanos();
+        let path = std::env::temp_dir().join(format!(
+            "micropayment-voucher-store-{}-{}.json",
+            std::process::id(),
+            nanos
+        ));
+        store.save(&path).unwrap();
+        assert_eq!(VoucherStore::load(&path).unwrap(), store);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.10;
+
+/// @dev The ADSC precompile address, `0xFFFFFFFF` followed by the asset id of ADSC.
+/// Every other `pallet_assets` token answers at `0xFFFFFFFF` followed by its 16 bytes asset id.
+address constant ADSC_ADDRESS = 0xFFFFFFFF00000000000000000000000000000001;
+
+/// @dev The ADSC precompile instance.
+IERC20 constant ADSC_CONTRACT = IERC20(ADSC_ADDRESS);
+
+/// @title Deeper Chain ERC-20 precompile of a `pallet_assets` token
+/// @dev Addresses act as, and are queried as, the Substrate account they map to through the
+/// EVM address mapping. Approvals are `pallet_assets` approvals and reserve a deposit from the
+/// owner.
+interface IERC20 {
+    /// @dev Emitted when `value` tokens are moved from `from` to `to`.
+    event Transfer(address indexed from, address indexed to, uint256 value);
+
+    /// @dev Emitted when the allowance of `spender` for the tokens of `owner` is set to `value`.
+    event Approval(address indexed owner, address indexed spender, uint256 value);
+
+    /// @dev Name of the token, from the asset metadata.
+    /// Selector: 06fdde03
+    function name() external view returns (string memory);
+
+    /// @dev Symbol of the token, from the asset metadata.
+    /// Selector: 95d89b41
+    function symbol() external view returns (string memory);
+
+    /// @dev Decimals of the token, from the asset metadata.
+    /// Selector: 313ce567
+    function decimals() external view returns (uint8);
+
+    /// @dev Total supply of the token.
+    /// Selector: 18160ddd
+    function totalSupply() external view returns (uint256);
+
+    /// @dev Balance of `owner`.
+    /// Selector: 70a08231
+    function balanceOf(address owner) external view returns (uint256);
+
+    /// @dev Tokens of `owner` that `spender` may still transfer.
+    /// Selector: dd62ed3e
+    function allowance(address owner, address spender)
+        external
+        view
+        returns (uint256);
+
+    /// @dev Transfer `value` tokens of the caller to `to`.
+    /// Selector: a9059cbb
+    function transfer(address to, uint256 value) external returns (bool);
+
+    /// @dev Set the allowance of `spender` for the tokens of the caller to `value`.
+    /// Selector: 095ea7b3
+    function approve(address spender, uint256 value) external returns (bool);
+
+    /// @dev Transfer `value` tokens of `from` to `to`, spending the allowance of the caller.
+    /// Selector: 23b872dd
+    function transferFrom(
+        address from,
+        address to,
+        uint256 value
+    ) external returns (bool);
+}
@@ -0,0 +1,399 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ERC-20 precompiles of `pallet_assets` tokens: every existing asset answers at the address
+//! made of `ASSET_ADDRESS_PREFIX` followed by its id, so ADSC is visible to EVM wallets and
+//! DEXes. Addresses act as the Substrate account they map to through the EVM `AddressMapping`,
+//! and approvals are the ones of `pallet_assets`. See `ERC20.sol`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+use fp_evm::{ExitSucceed, PrecompileHandle, PrecompileOutput, PrecompileResult, PrecompileSet};
+use frame_support::{
+    dispatch::{Dispatchable, GetDispatchInfo, PostDispatchInfo},
+    traits::fungibles::{
+        approvals::Inspect as ApprovalsInspect, metadata::Inspect as MetadataInspect,
+    },
+};
+use pallet_assets::Call as AssetsCall;
+use pallet_evm::AddressMapping;
+use pallet_evm_precompile_credit::util::{
+    self, EvmDataReader, EvmDataWriter, EvmResult, FunctionModifier, LogsBuilder, RuntimeHelper,
+};
+use sp_core::{H160, U256};
+use sp_runtime::traits::{StaticLookup, Zero};
+use sp_std::vec::Vec;
+
+/// First bytes of the address of an asset precompile, the last 16 bytes are the asset id.
+pub const ASSET_ADDRESS_PREFIX: [u8; 4] = [0xff; 4];
+
+/// Selectors of the functions of the `IERC20` interface.
+const SELECTOR_NAME: u32 = 0x06fdde03;
+const SELECTOR_SYMBOL: u32 = 0x95d89b41;
+const SELECTOR_DECIMALS: u32 = 0x313ce567;
+const SELECTOR_TOTAL_SUPPLY: u32 = 0x18160ddd;
+const SELECTOR_BALANCE_OF: u32 = 0x70a08231;
+const SELECTOR_ALLOWANCE: u32 = 0xdd62ed3e;
+const SELECTOR_TRANSFER: u32 = 0xa9059cbb;
+const SELECTOR_APPROVE: u32 = 0x095ea7b3;
+const SELECTOR_TRANSFER_FROM: u32 = 0x23b872dd;
+
+/// Signatures of the events of the `IERC20` interface.
+const EVENT_TRANSFER: &[u8] = b"Transfer(address,address,uint256)";
+const EVENT_APPROVAL: &[u8] = b"Approval(address,address,uint256)";
+
+type RuntimeCallOf<Runtime> = <Runtime as frame_system::Config>::RuntimeCall;
+type AssetIdOf<Runtime> = <Runtime as pallet_assets::Config>::AssetId;
+type BalanceOf<Runtime> = <Runtime as pallet_assets::Config>::Balance;
+
+/// Address of the precompile of an asset.
+pub fn asset_id_to_address<AssetId: Into<u128>>(asset_id: AssetId) -> H160 {
+    let mut address = [0u8; 20];
+    address[..4].copy_from_slice(&ASSET_ADDRESS_PREFIX);
+    address[4..].copy_from_slice(&asset_id.into().to_be_bytes());
+    H160(address)
+}
+
+/// Asset id of an asset precompile address, `None` for any other address.
+pub fn address_to_asset_id<AssetId: TryFrom<u128>>(address: H160) -> Option<AssetId> {
+    if address.0[..4] != ASSET_ADDRESS_PREFIX {
+        return None;
+    }
+    let mut id = [0u8; 16];
+    id.copy_from_slice(&address.0[4..]);
+    AssetId::try_from(u128::from_be_bytes(id)).ok()
+}
+
+pub struct Erc20AssetsPrecompileSet<Runtime>(PhantomData<Runtime>);
+
+impl<Runtime> Erc20AssetsPrecompileSet<Runtime> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<Runtime> Default for Erc20AssetsPrecompileSet<Runtime> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Runtime> PrecompileSet for Erc20AssetsPrecompileSet<Runtime>
+where
+    Runtime: pallet_assets::Config + pallet_evm::Config,
+    RuntimeCallOf<Runtime>: Dispatchable<PostInfo = PostDispatchInfo> + GetDispatchInfo,
+    <RuntimeCallOf<Runtime> as Dispatchable>::RuntimeOrigin: From<Option<Runtime::AccountId>>,
+    RuntimeCallOf<Runtime>: From<AssetsCall<Runtime>>,
+    AssetIdOf<Runtime>: Into<u128> + TryFrom<u128>,
+    BalanceOf<Runtime>: TryFrom<U256> + Into<U256>,
+{
+    fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+        let asset_id = address_to_asset_id::<AssetIdOf<Runtime>>(handle.code_address())?;
+        // an address is only a precompile while its asset exists
+        pallet_assets::Pallet::<Runtime>::maybe_total_supply(asset_id)?;
+
+        Some(Self::dispatch(handle, asset_id))
+    }
+
+    fn is_precompile(&self, address: H160) -> bool {
+        address_to_asset_id::<AssetIdOf<Runtime>>(address)
+            .and_then(pallet_assets::Pallet::<Runtime>::maybe_total_supply)
+            .is_some()
+    }
+}
+
+impl<Runtime> Erc20AssetsPrecompileSet<Runtime>
+where
+    Runtime: pallet_assets::Config + pallet_evm::Config,
+    RuntimeCallOf<Runtime>: Dispatchable<PostInfo = PostDispatchInfo> + GetDispatchInfo,
+    <RuntimeCallOf<Runtime> as Dispatchable>::RuntimeOrigin: From<Option<Runtime::AccountId>>,
+    RuntimeCallOf<Runtime>: From<AssetsCall<Runtime>>,
+    AssetIdOf<Runtime>: Into<u128> + TryFrom<u128>,
+    BalanceOf<Runtime>: TryFrom<U256> + Into<U256>,
+{
+    fn dispatch(
+        handle: &mut impl PrecompileHandle,
+        asset_id: AssetIdOf<Runtime>,
+    ) -> PrecompileResult {
+        let selector = util::read_selector(handle.input())?;
+        let output = match selector {
+            SELECTOR_NAME => Self::name(handle, asset_id)?,
+            SELECTOR_SYMBOL => Self::symbol(handle, asset_id)?,
+            SELECTOR_DECIMALS => Self::decimals(handle, asset_id)?,
+            SELECTOR_TOTAL_SUPPLY => Self::total_supply(handle, asset_id)?,
+            SELECTOR_BALANCE_OF => Self::balance_of(handle, asset_id)?,
+            SELECTOR_ALLOWANCE => Self::allowance(handle, asset_id)?,
+            SELECTOR_TRANSFER => Self::transfer(handle, asset_id)?,
+            SELECTOR_APPROVE => Self::approve(handle, asset_id)?,
+            SELECTOR_TRANSFER_FROM => Self::transfer_from(handle, asset_id)?,
+            _ => return Err(util::revert("unknown selector")),
+        };
+
+        Ok(PrecompileOutput {
+            exit_status: ExitSucceed::Returned,
+            output,
+        })
+    }
+
+    /// `name() returns (string)`, from the asset metadata
+    pub fn name(
+        handle: &mut impl PrecompileHandle,
+        asset_id: AssetIdOf<Runtime>,
+    ) -> EvmResult<Vec<u8>> {
+        Self::read_view(handle, 0)?;
+        let name = <pallet_assets::Pallet<Runtime> as MetadataInspect<Runtime::AccountId>>::name(
+            &asset_id,
+        );
+
+        Ok(EvmDataWriter::new()
+            .write_u256(U256::from(32))
+            .write_bytes(&name)
+            .build())
+    }
+
+    /// `symbol() returns (string)`, from the asset metadata
+    pub fn symbol(
+        handle: &mut impl PrecompileHandle,
+        asset_id: AssetIdOf<Runtime>,
+    ) -> EvmResult<Vec<u8>> {
+        Self::read_view(handle, 0)?;
+        let symbol =
+            <pallet_assets::Pallet<Runtime> as MetadataInspect<Runtime::AccountId>>::symbol(
+                &asset_id,
+            );
+
+        Ok(EvmDataWriter::new()
+            .write_u256(U256::from(32))
+            .write_bytes(&symbol)
+            .build())
+    }
+
+    /// `decimals() returns (uint8)`, from the asset metadata
+    pub fn decimals(
+        handle: &mut impl PrecompileHandle,
+        asset_id: AssetIdOf<Runtime>,
+    ) -> EvmResult<Vec<u8>> {
+        Self::read_view(handle, 0)?;
+        let decimals =
+            <pallet_assets::Pallet<Runtime> as MetadataInspect<Runtime::AccountId>>::decimals(
+                &asset_id,
+            );
+
+        Ok(EvmDataWriter::new().write_u256(decimals.into()).build())
+    }
+
+    /// `totalSupply() returns (uint256)`
+    pub fn total_supply(
+        handle: &mut impl PrecompileHandle,
+        asset_id: AssetIdOf<Runtime>,
+    ) -> EvmResult<Vec<u8>> {
+        Self::read_view(handle, 0)?;
+        let supply = pallet_assets::Pallet::<Runtime>::total_supply(asset_id);
+
+        Ok(EvmDataWriter::new().write_u256(supply.into()).build())
+    }
+
+    /// `balanceOf(address owner) returns (uint256)`
+    pub fn balance_of(
+        handle: &mut impl PrecompileHandle,
+        asset_id: AssetIdOf<Runtime>,
+    ) -> EvmResult<Vec<u8>> {
+        let mut input = Self::read_view(handle, 1)?;
+        let owner = Self::mapped_account(input.read_address()?);
+        let balance = pallet_assets::Pallet::<Runtime>::balance(asset_id, &owner);
+
+        Ok(EvmDataWriter::new().write_u256(balance.into()).build())
+    }
+
+    /// `allowance(address owner, address spender) returns (uint256)`
+    pub fn allowance(
+        handle: &mut impl PrecompileHandle,
+        asset_id: AssetIdOf<Runtime>,
+    ) -> EvmResult<Vec<u8>> {
+        let mut input = Self::read_view(handle, 2)?;
+        let owner = Self::mapped_account(input.read_address()?);
+        let spender = Self::mapped_account(input.read_address()?);
+
+        Ok(EvmDataWriter::new()
+            .write_u256(Self::approved(asset_id, &owner, &spender).into())
+            .build())
+    }
+
+    /// `transfer(address to, uint256 value) returns (bool)`
+    pub fn transfer(
+        handle: &mut impl PrecompileHandle,
+        asset_id: AssetIdOf<Runtime>,
+    ) -> EvmResult<Vec<u8>> {
+        util::check_modifier(handle, FunctionModifier::NonPayable)?;
+        let mut input = EvmDataReader::new_skip_selector(handle.input())?;
+        input.expect_arguments(2)?;
+        let to = input.read_address()?;
+        let value = input.read_u256()?;
+        let amount = Self::balance(value)?;
+
+        let caller = handle.context().caller;
+        RuntimeHelper::<Runtime>::try_dispatch(
+            handle,
+            Some(Self::mapped_account(caller)).into(),
+            AssetsCall::<Runtime>::transfer {
+                id: asset_id.into(),
+                target: Self::lookup_source(to),
+                amount,
+            },
+        )?;
+
+        LogsBuilder::new(handle.context().address)
+            .log3(
+                util::event_topic(EVENT_TRANSFER),
+                caller,
+                to,
+                EvmDataWriter::new().write_u256(value).build(),
+            )
+            .record(handle)?;
+
+        Ok(EvmDataWriter::new().write_bool(true).build())
+    }
+
+    /// `approve(address spender, uint256 value) returns (bool)`, replaces the approval of the
+    /// spender like ERC-20 does, while `pallet_assets` approvals add up
+    pub fn approve(
+        handle: &mut impl PrecompileHandle,
+        asset_id: AssetIdOf<Runtime>,
+    ) -> EvmResult<Vec<u8>> {
+        util::check_modifier(handle, FunctionModifier::NonPayable)?;
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+        let mut input = EvmDataReader::new_skip_selector(handle.input())?;
+        input.expect_arguments(2)?;
+        let spender = input.read_address()?;
+        let value = input.read_u256()?;
+        let amount = Self::balance(value)?;
+
+        let caller = handle.context().caller;
+        let owner = Self::mapped_account(caller);
+        let delegate = Self::mapped_account(spender);
+        if !Self::approved(asset_id, &owner, &delegate).is_zero() {
+            RuntimeHelper::<Runtime>::try_dispatch(
+                handle,
+                Some(owner.clone()).into(),
+                AssetsCall::<Runtime>::cancel_approval {
+                    id: asset_id.into(),
+                    delegate: Self::lookup_source(spender),
+                },
+            )?;
+        }
+        if !amount.is_zero() {
+            RuntimeHelper::<Runtime>::try_dispatch(
+                handle,
+                Some(owner).into(),
+                AssetsCall::<Runtime>::approve_transfer {
+                    id: asset_id.into(),
+                    delegate: Self::lookup_source(spender),
+                    amount,
+                },
+            )?;
+        }
+
+        LogsBuilder::new(handle.context().address)
+            .log3(
+                util::event_topic(EVENT_APPROVAL),
+                caller,
+                spender,
+                EvmDataWriter::new().write_u256(value).build(),
+            )
+            .record(handle)?;
+
+        Ok(EvmDataWriter::new().write_bool(true).build())
+    }
+
+    /// `transferFrom(address from, address to, uint256 value) returns (bool)`,
+    /// spends the approval of the caller
+    pub fn transfer_from(
+        handle: &mut impl PrecompileHandle,
+        asset_id: AssetIdOf<Runtime>,
+    ) -> EvmResult<Vec<u8>> {
+        util::check_modifier(handle, FunctionModifier::NonPayable)?;
+        let mut input = EvmDataReader::new_skip_selector(handle.input())?;
+        input.expect_arguments(3)?;
+        let from = input.read_address()?;
+        let to = input.read_address()?;
+        let value = input.read_u256()?;
+        let amount = Self::balance(value)?;
+
+        let caller = handle.context().caller;
+        RuntimeHelper::<Runtime>::try_dispatch(
+            handle,
+            Some(Self::mapped_account(caller)).into(),
+            AssetsCall::<Runtime>::transfer_approved {
+                id: asset_id.into(),
+                owner: Self::lookup_source(from),
+                destination: Self::lookup_source(to),
+                amount,
+            },
+        )?;
+
+        LogsBuilder::new(handle.context().address)
+            .log3(
+                util::event_topic(EVENT_TRANSFER),
+                from,
+                to,
+                EvmDataWriter::new().write_u256(value).build(),
+            )
+            .record(handle)?;
+
+        Ok(EvmDataWriter::new().write_bool(true).build())
+    }
+
+    /// check a view call, record the cost of a read and return the reader of its arguments
+    fn read_view(
+        handle: &mut impl PrecompileHandle,
+        arguments: usize,
+    ) -> EvmResult<EvmDataReader<'_>> {
+        util::check_modifier(handle, FunctionModifier::View)?;
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+        let mut input = EvmDataReader::new_skip_selector(handle.input())?;
+        input.expect_arguments(arguments)?;
+        Ok(input)
+    }
+
+    fn approved(
+        asset_id: AssetIdOf<Runtime>,
+        owner: &Runtime::AccountId,
+        spender: &Runtime::AccountId,
+    ) -> BalanceOf<Runtime> {
+        <pallet_assets::Pallet<Runtime> as ApprovalsInspect<Runtime::AccountId>>::allowance(
+            asset_id, owner, spender,
+        )
+    }
+
+    fn balance(value: U256) -> EvmResult<BalanceOf<Runtime>> {
+        BalanceOf::<Runtime>::try_from(value).map_err(|_| util::revert("amount out of range"))
+    }
+
+    fn mapped_account(address: H160) -> Runtime::AccountId {
+        <Runtime as pallet_evm::Config>::AddressMapping::into_account_id(address)
+    }
+
+    fn lookup_source(
+        address: H160,
+    ) -> <<Runtime as frame_system::Config>::Lookup as StaticLookup>::Source {
+        <Runtime as frame_system::Config>::Lookup::unlookup(Self::mapped_account(address))
+    }
+}
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,294 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use crate::mock::*;
+use pallet_evm_precompile_credit::testing::{
+    assert_dispatch_reverted, assert_selectors, event_log, output, word, MockHandle,
+};
+
+fn transfer_input(to: AccountId, value: U256) -> Vec<u8> {
+    EvmDataWriter::new_with_selector(SELECTOR_TRANSFER)
+        .write_address(address(to))
+        .write_u256(value)
+        .build()
+}
+
+fn approve_input(spender: AccountId, value: u128) -> Vec<u8> {
+    EvmDataWriter::new_with_selector(SELECTOR_APPROVE)
+        .write_address(address(spender))
+        .write_u256(value.into())
+        .build()
+}
+
+fn transfer_from_input(from: AccountId, to: AccountId, value: u128) -> Vec<u8> {
+    EvmDataWriter::new_with_selector(SELECTOR_TRANSFER_FROM)
+        .write_address(address(from))
+        .write_address(address(to))
+        .write_u256(value.into())
+        .build()
+}
+
+fn allowance(owner: AccountId, spender: AccountId) -> Vec<u8> {
+    let input = EvmDataWriter::new_with_selector(SELECTOR_ALLOWANCE)
+        .write_address(address(owner))
+        .write_address(address(spender))
+        .build();
+    output(call(CHARLIE, input).0)
+}
+
+#[test]
+fn selectors_match_signatures() {
+    let selectors = [
+        (SELECTOR_NAME, "name()"),
+        (SELECTOR_SYMBOL, "symbol()"),
+        (SELECTOR_DECIMALS, "decimals()"),
+        (SELECTOR_TOTAL_SUPPLY, "totalSupply()"),
+        (SELECTOR_BALANCE_OF, "balanceOf(address)"),
+        (SELECTOR_ALLOWANCE, "allowance(address,address)"),
+        (SELECTOR_TRANSFER, "transfer(address,uint256)"),
+        (SELECTOR_APPROVE, "approve(address,uint256)"),
+        (
+            SELECTOR_TRANSFER_FROM,
+            "transferFrom(address,address,uint256)",
+        ),
+    ];
+    assert_selectors(&selectors);
+}
+
+#[test]
+fn asset_addresses() {
+    let address = asset_id_to_address(1u32);
+    assert_eq!(
+        address,
+        H160::from_slice(&[0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1])
+    );
+    assert_eq!(address_to_asset_id::<u32>(address), Some(1));
+    assert_eq!(
+        address_to_asset_id::<u32>(asset_id_to_address(u32::MAX)),
+        Some(u32::MAX)
+    );
+
+    // ids that don't fit the asset id type, and addresses without the prefix
+    assert_eq!(
+        address_to_asset_id::<u32>(asset_id_to_address(u128::from(u32::MAX) + 1)),
+        None
+    );
+    assert_eq!(address_to_asset_id::<u32>(H160::from_low_u64_be(1)), None);
+}
+
+#[test]
+fn only_existing_assets_are_precompiles() {
+    new_test_ext().execute_with(|| {
+        let precompiles = Erc20AssetsPrecompileSet::<Test>::new();
+        assert!(precompiles.is_precompile(asset_id_to_address(ASSET)));
+        assert!(!precompiles.is_precompile(asset_id_to_address(ASSET + 1)));
+        assert!(!precompiles.is_precompile(address(ALICE)));
+
+        let mut handle = MockHandle::new(
+            asset_id_to_address(ASSET + 1),
+            address(ALICE),
+            EvmDataWriter::new_with_selector(SELECTOR_TOTAL_SUPPLY).build(),
+        );
+        assert!(precompiles.execute(&mut handle).is_none());
+    });
+}
+
+#[test]
+fn metadata_and_balances() {
+    new_test_ext().execute_with(|| {
+        let selector = |selector| EvmDataWriter::new_with_selector(selector).build();
+        assert_eq!(
+            output(call(ALICE, selector(SELECTOR_NAME)).0),
+            EvmDataWriter::new()
+                .write_u256(U256::from(32))
+                .write_bytes(b"Deeper Stable Coin")
+                .build()
+        );
+        assert_eq!(
+            output(call(ALICE, selector(SELECTOR_SYMBOL)).0),
+            EvmDataWriter::new()
+                .write_u256(U256::from(32))
+                .write_bytes(b"ADSC")
+                .build()
+        );
+        assert_eq!(output(call(ALICE, selector(SELECTOR_DECIMALS)).0), word(6));
+        assert_eq!(
+            output(call(ALICE, selector(SELECTOR_TOTAL_SUPPLY)).0),
+            word(1_000)
+        );
+
+        let balance_of = |owner| {
+            EvmDataWriter::new_with_selector(SELECTOR_BALANCE_OF)
+                .write_address(address(owner))
+                .build()
+        };
+        assert_eq!(output(call(BOB, balance_of(ALICE)).0), word(1_000));
+        assert_eq!(output(call(BOB, balance_of(BOB)).0), word(0));
+
+        // missing argument
+        assert_eq!(
+            call(BOB, selector(SELECTOR_BALANCE_OF)).0.map(|o| o.output),
+            Err(util::revert("input doesn't match expected length"))
+        );
+    });
+}
+
+#[test]
+fn transfer() {
+    new_test_ext().execute_with(|| {
+        let (result, handle) = call(ALICE, transfer_input(BOB, 100.into()));
+        assert_eq!(
+            output(result),
+            EvmDataWriter::new().write_bool(true).build()
+        );
+        assert_eq!(Assets::balance(ASSET, ALICE), 900);
+        assert_eq!(Assets::balance(ASSET, BOB), 100);
+        assert_eq!(
+            handle.logs,
+            vec![event_log(
+                asset_id_to_address(ASSET),
+                EVENT_TRANSFER,
+                &[address(ALICE).into(), address(BOB).into()],
+                word(100)
+            )]
+        );
+        assert!(handle.gas_used > 0);
+
+        // more than the balance
+        let (result, handle) = call(BOB, transfer_input(CHARLIE, 101.into()));
+        assert_dispatch_reverted(result);
+        assert!(handle.logs.is_empty());
+        assert_eq!(Assets::balance(ASSET, BOB), 100);
+
+        // more than the asset balance type holds
+        let (result, _) = call(BOB, transfer_input(CHARLIE, U256::from(u128::MAX) + 1));
+        assert_eq!(
+            result.map(|o| o.output),
+            Err(util::revert("amount out of range"))
+        );
+
+        // in a static context
+        let mut handle = MockHandle::new(
+            asset_id_to_address(ASSET),
+            address(ALICE),
+            transfer_input(BOB, 1.into()),
+        )
+        .with_static();
+        assert_eq!(
+            Erc20AssetsPrecompileSet::<Test>::new()
+                .execute(&mut handle)
+                .unwrap()
+                .map(|o| o.output),
+            Err(util::revert(
+                "can't call non-static function in static context"
+            ))
+        );
+        assert_eq!(Assets::balance(ASSET, ALICE), 900);
+    });
+}
+
+#[test]
+fn approve_and_transfer_from() {
+    new_test_ext().execute_with(|| {
+        let (result, handle) = call(ALICE, approve_input(BOB, 300));
+        assert_eq!(
+            output(result),
+            EvmDataWriter::new().write_bool(true).build()
+        );
+        assert_eq!(
+            handle.logs,
+            vec![event_log(
+                asset_id_to_address(ASSET),
+                EVENT_APPROVAL,
+                &[address(ALICE).into(), address(BOB).into()],
+                word(300)
+            )]
+        );
+        assert_eq!(allowance(ALICE, BOB), word(300));
+
+        let (result, handle) = call(BOB, transfer_from_input(ALICE, CHARLIE, 200));
+        assert_eq!(
+            output(result),
+            EvmDataWriter::new().write_bool(true).build()
+        );
+        assert_eq!(
+            handle.logs,
+            vec![event_log(
+                asset_id_to_address(ASSET),
+                EVENT_TRANSFER,
+                &[address(ALICE).into(), address(CHARLIE).into()],
+                word(200)
+            )]
+        );
+        assert_eq!(Assets::balance(ASSET, ALICE), 800);
+        assert_eq!(Assets::balance(ASSET, CHARLIE), 200);
+        assert_eq!(allowance(ALICE, BOB), word(100));
+
+        // more than the allowance
+        let (result, handle) = call(BOB, transfer_from_input(ALICE, CHARLIE, 101));
+        assert_dispatch_reverted(result);
+        assert!(handle.logs.is_empty());
+        assert_eq!(Assets::balance(ASSET, ALICE), 800);
+
+        // without an allowance
+        let (result, _) = call(CHARLIE, transfer_from_input(ALICE, CHARLIE, 1));
+        assert_dispatch_reverted(result);
+    });
+}
+
+#[test]
+fn approve_replaces_the_allowance() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(
+            output(call(ALICE, approve_input(BOB, 300)).0),
+            EvmDataWriter::new().write_bool(true).build()
+        );
+        // the previous approval is cancelled instead of added to
+        let (result, handle) = call(ALICE, approve_input(BOB, 50));
+        assert_eq!(
+            output(result),
+            EvmDataWriter::new().write_bool(true).build()
+        );
+        assert_eq!(
+            handle.logs,
+            vec![event_log(
+                asset_id_to_address(ASSET),
+                EVENT_APPROVAL,
+                &[address(ALICE).into(), address(BOB).into()],
+                word(50)
+            )]
+        );
+        assert_eq!(allowance(ALICE, BOB), word(50));
+
+        // zero only cancels
+        let (result, handle) = call(ALICE, approve_input(BOB, 0));
+        assert_eq!(
+            output(result),
+            EvmDataWriter::new().write_bool(true).build()
+        );
+        assert_eq!(
+            handle.logs,
+            vec![event_log(
+                asset_id_to_address(ASSET),
+                EVENT_APPROVAL,
+                &[address(ALICE).into(), address(BOB).into()],
+                word(0)
+            )]
+        );
+        assert_eq!(allowance(ALICE, BOB), word(0));
+        assert_dispatch_reverted(call(BOB, transfer_from_input(ALICE, BOB, 1)).0);
+    });
+}
@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.10;
+
+/// @dev The Credit precompile address.
+address constant CREDIT_ADDRESS = 0x0000000000000000000000000000000000000406;
+
+/// @dev The Credit precompile instance.
+Credit constant CREDIT_CONTRACT = Credit(CREDIT_ADDRESS);
+
+/// @title Deeper Chain credit precompile
+/// @dev Reads and updates the credit of the Substrate account an EVM address is mapped to.
+/// Addresses which are not mapped or have no credit read as zero.
+interface Credit {
+    /// @dev Emitted when `operator` added credit score to `target`.
+    event CreditScoreAdded(
+        address indexed operator,
+        address indexed target,
+        uint256 oldScore,
+        uint256 newScore
+    );
+
+    /// @dev Emitted when `operator` slashed credit score from `target`.
+    event CreditScoreSlashed(
+        address indexed operator,
+        address indexed target,
+        uint256 oldScore,
+        uint256 newScore
+    );
+
+    /// @dev Credit score of the target.
+    /// Selector: 87135d7d
+    function get_credit_score(address target) external view returns (uint256);
+
+    /// @dev Credit level of the target, from 0 to 8.
+    /// Selector: ef98300e
+    function get_credit_level(address target) external view returns (uint8);
+
+    /// @dev Campaign the credit of the target belongs to.
+    /// Selector: 68570c92
+    function get_campaign_id(address target) external view returns (uint16);
+
+    /// @dev Number of entries of the credit history of the target.
+    /// Selector: a4e15bda
+    function get_credit_history_len(address target)
+        external
+        view
+        returns (uint256);
+
+    /// @dev Substrate account the target is mapped to, zero if not mapped.
+    /// Selector: e53cc566
+    function get_substrate_account(address target)
+        external
+        view
+        returns (bytes32);
+
+    /// @dev Add credit score to the target, the caller needs the EvmCreditOperation privilege.
+    /// Reverts if the target is not mapped or has no credit.
+    /// Selector: 5915ad98
+    function add_credit_score(address target, uint256 score)
+        external
+        returns (uint256 newScore);
+
+    /// @dev Slash credit score from the target, the caller needs the EvmCreditOperation privilege.
+    /// Reverts if the target is not mapped or has no credit.
+    /// Selector: a62184b3
+    function slash_credit_score(address target, uint256 score)
+        external
+        returns (uint256 newScore);
+}
@@ -1,59 +1,33 @@
 pragma solidity ^0.8.10;
 
-interface Credit {
-    function get_credit_score(address target) external view returns (uint256);
-
-    function add_credit_score(address target, uint256 score) external;
-
-    function slash_credit_score(address target, uint256 score) external;
-}
+import "../Credit.sol";
 
 contract deeper {
-    address private constant DISPATCH =
-        0x0000000000000000000000000000000000000406;
     event score(uint256 indexed);
 
-    function toUint256(bytes memory _bytes)
-        internal
-        pure
-        returns (uint256 value)
-    {
-        assembly {
-            value := mload(add(_bytes, 0x20))
-        }
-    }
-
     function get_credit_score(address query_address) public returns (uint256) {
-        (bool success, bytes memory x) = DISPATCH.call(
-            abi.encodeWithSignature("get_credit_score(address)", query_address)
-        );
-        require(success, "get_credit_score not ok");
-
-        uint256 y = toUint256(x);
+        uint256 y = CREDIT_CONTRACT.get_credit_score(query_address);
         emit score(y);
 
         return y;
     }
 
+    function get_credit_level(address query_address)
+        public
+        view
+        returns (uint8)
+    {
+        return CREDIT_CONTRACT.get_credit_level(query_address);
+    }
+
     function add_credit_score(address query_address, uint256 num) public {
-        (bool success, ) = DISPATCH.call(
-            abi.encodeWithSignature(
-                "add_credit_score(address,uint256)",
-                query_address,
-                num
-            )
-        );
-        require(success, "add_credit_score not ok");
+        // reverts with the reason of the precompile on failure
+        uint256 y = CREDIT_CONTRACT.add_credit_score(query_address, num);
+        emit score(y);
     }
 
     function slash_credit_score(address query_address, uint256 num) public {
-        (bool success, ) = DISPATCH.call(
-            abi.encodeWithSignature(
-                "slash_credit_score(address,uint256)",
-                query_address,
-                num
-            )
-        );
-        require(success, "slash_credit_score not ok");
+        uint256 y = CREDIT_CONTRACT.slash_credit_score(query_address, num);
+        emit score(y);
     }
 }
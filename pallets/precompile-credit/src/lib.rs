@@ -19,26 +19,36 @@
 
 extern crate alloc;
 
-mod util;
+/// ABI, gas and log helpers shared with the other Deeper Chain precompiles.
+pub mod util;
 
-use crate::util::{Gasometer, RuntimeHelper};
-use alloc::{borrow::ToOwned, vec};
-use arrayref::array_ref;
+/// Precompile handle shared by the mock runtime tests of the precompiles.
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+use crate::util::{EvmDataReader, EvmDataWriter, EvmResult, LogsBuilder, RuntimeHelper};
 use codec::Decode;
 use core::marker::PhantomData;
-use fp_evm::{
-    ExitError, ExitSucceed, Precompile, PrecompileFailure, PrecompileHandle, PrecompileOutput,
-    PrecompileResult,
-};
+use fp_evm::{ExitSucceed, Precompile, PrecompileHandle, PrecompileOutput, PrecompileResult};
 use frame_support::dispatch::{Dispatchable, GetDispatchInfo, PostDispatchInfo};
-use node_primitives::credit::CreditInterface;
-use pallet_credit::Call as CreditCall;
-use sp_core::{H160, U256};
+use node_primitives::{credit::CreditInterface, deeper_node::NodeInterface};
+use pallet_credit::{Call as CreditCall, Error as CreditError};
+use pallet_evm::GasWeightMapping;
+use sp_core::{H160, H256, U256};
+use sp_std::vec::Vec;
 
-const BASIC_LEN: usize = 4 + 32;
+/// Selectors of the functions of the `Credit` interface, see `Credit.sol`.
 const SELECTOR_GET_CREDIT_SCORE: u32 = 0x87135d7d;
 const SELECTOR_ADD_CREDIT_SCORE: u32 = 0x5915ad98;
 const SELECTOR_SLASH_CREDIT_SCORE: u32 = 0xa62184b3;
+const SELECTOR_GET_CREDIT_LEVEL: u32 = 0xef98300e;
+const SELECTOR_GET_CAMPAIGN_ID: u32 = 0x68570c92;
+const SELECTOR_GET_CREDIT_HISTORY_LEN: u32 = 0xa4e15bda;
+const SELECTOR_GET_SUBSTRATE_ACCOUNT: u32 = 0xe53cc566;
+
+/// Signatures of the events of the `Credit` interface.
+const EVENT_CREDIT_SCORE_ADDED: &[u8] = b"CreditScoreAdded(address,address,uint256,uint256)";
+const EVENT_CREDIT_SCORE_SLASHED: &[u8] = b"CreditScoreSlashed(address,address,uint256,uint256)";
 
 // from moonbeam
 /// Represents modifiers a Solidity function can be annotated with.
@@ -63,30 +73,25 @@ where
     Runtime::RuntimeCall: Dispatchable<PostInfo = PostDispatchInfo> + GetDispatchInfo + Decode,
     <Runtime::RuntimeCall as Dispatchable>::RuntimeOrigin: From<Option<Runtime::AccountId>>,
     Runtime::RuntimeCall: From<CreditCall<Runtime>>,
+    Runtime::AccountId: Into<[u8; 32]>,
 {
     fn execute(handle: &mut impl PrecompileHandle) -> PrecompileResult {
-        /*
-        selector:
-        0x5915ad98: add_credit_score
-        0xa62184b3: slash_credit_score
-        0x87135d7d: get_credit_score
-        */
-
-        let input = handle.input();
-        if input.len() < BASIC_LEN {
-            return Err(PrecompileFailure::Error {
-                exit_status: ExitError::Other("input len not enough".into()),
-            });
-        }
-        let real_type = u32::from_be_bytes(array_ref!(input, 0, 4).to_owned());
-        match real_type {
-            SELECTOR_GET_CREDIT_SCORE => Self::get_credit_score(handle),
-            SELECTOR_ADD_CREDIT_SCORE => Self::add_credit_score(handle),
-            SELECTOR_SLASH_CREDIT_SCORE => Self::slash_credit_score(handle),
-            _ => Err(PrecompileFailure::Error {
-                exit_status: ExitError::Other("InvalidCode".into()),
-            }),
-        }
+        let selector = util::read_selector(handle.input())?;
+        let output = match selector {
+            SELECTOR_GET_CREDIT_SCORE => Self::get_credit_score(handle)?,
+            SELECTOR_ADD_CREDIT_SCORE => Self::add_credit_score(handle)?,
+            SELECTOR_SLASH_CREDIT_SCORE => Self::slash_credit_score(handle)?,
+            SELECTOR_GET_CREDIT_LEVEL => Self::get_credit_level(handle)?,
+            SELECTOR_GET_CAMPAIGN_ID => Self::get_campaign_id(handle)?,
+            SELECTOR_GET_CREDIT_HISTORY_LEN => Self::get_credit_history_len(handle)?,
+            SELECTOR_GET_SUBSTRATE_ACCOUNT => Self::get_substrate_account(handle)?,
+            _ => return Err(util::revert("unknown selector")),
+        };
+
+        Ok(PrecompileOutput {
+            exit_status: ExitSucceed::Returned,
+            output,
+        })
     }
 }
 
@@ -96,91 +101,153 @@ where
     Runtime::RuntimeCall: Dispatchable<PostInfo = PostDispatchInfo> + GetDispatchInfo + Decode,
     <Runtime::RuntimeCall as Dispatchable>::RuntimeOrigin: From<Option<Runtime::AccountId>>,
     Runtime::RuntimeCall: From<CreditCall<Runtime>>,
+    Runtime::AccountId: Into<[u8; 32]>,
 {
-    pub fn get_credit_score(handle: &mut impl PrecompileHandle) -> PrecompileResult {
-        // Bound check
-        let input = handle.input();
-        Self::check_input_len(input, BASIC_LEN)?;
-
-        let gasometer = Gasometer::new(None);
-        gasometer.check_function_modifier(
-            handle.context(),
-            handle.is_static(),
-            util::FunctionModifier::View,
-        )?;
+    /// `get_credit_score(address) returns (uint256)`
+    pub fn get_credit_score(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let account = Self::read_view_target(handle, 2)?;
+        let score = pallet_credit::Pallet::<Runtime>::get_evm_credit_score(&account);
 
-        let account = H160::from(array_ref!(input, BASIC_LEN - 20, 20));
+        Ok(EvmDataWriter::new()
+            .write_u256(score.unwrap_or(0).into())
+            .build())
+    }
 
-        let score = pallet_credit::Pallet::<Runtime>::get_evm_credit_score(&account);
-        let score = U256::from(score.unwrap_or(0));
-        let mut output = vec![0; 32];
-        score.to_big_endian(&mut output);
-        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost() * 2)?;
+    /// `get_credit_level(address) returns (uint8)`
+    pub fn get_credit_level(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let account = Self::read_view_target(handle, 2)?;
+        let level = Self::mapped_account(&account)
+            .and_then(|account_id| {
+                pallet_credit::Pallet::<Runtime>::user_credit_relevelled(&account_id)
+            })
+            .map_or(0, |credit_data| credit_data.current_credit_level as u8);
 
-        Ok(PrecompileOutput {
-            exit_status: ExitSucceed::Returned,
-            output,
-        })
+        Ok(EvmDataWriter::new().write_u256(level.into()).build())
+    }
+
+    /// `get_campaign_id(address) returns (uint16)`
+    pub fn get_campaign_id(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let account = Self::read_view_target(handle, 2)?;
+        let campaign_id = Self::mapped_account(&account)
+            .and_then(pallet_credit::Pallet::<Runtime>::user_credit)
+            .map_or(0, |credit_data| credit_data.campaign_id);
+
+        Ok(EvmDataWriter::new().write_u256(campaign_id.into()).build())
+    }
+
+    /// `get_credit_history_len(address) returns (uint256)`
+    pub fn get_credit_history_len(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let account = Self::read_view_target(handle, 4)?;
+        let len = Self::mapped_account(&account).map_or(0, |account_id| {
+            pallet_credit::Pallet::<Runtime>::user_credit_history(&account_id).len()
+        });
+
+        Ok(EvmDataWriter::new().write_u256(len.into()).build())
     }
 
-    pub fn add_credit_score(handle: &mut impl PrecompileHandle) -> PrecompileResult {
-        Self::do_update_credit(handle, true)?;
+    /// `get_substrate_account(address) returns (bytes32)`, zero if the address is not mapped
+    pub fn get_substrate_account(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let account = Self::read_view_target(handle, 1)?;
+        let account_id = Self::mapped_account(&account)
+            .map_or_else(H256::zero, |account_id| H256(account_id.into()));
+
+        Ok(EvmDataWriter::new().write_h256(account_id).build())
+    }
 
+    /// `add_credit_score(address,uint256) returns (uint256)`, returns the new credit score
+    pub fn add_credit_score(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
         let weight = RuntimeHelper::<Runtime>::db_read_gas_cost() * 2
             + RuntimeHelper::<Runtime>::db_write_gas_cost();
-        handle.record_cost(weight)?;
-
-        Ok(PrecompileOutput {
-            exit_status: ExitSucceed::Returned,
-            output: Default::default(),
-        })
+        Self::do_update_credit(handle, true, weight)
     }
 
-    pub fn slash_credit_score(handle: &mut impl PrecompileHandle) -> PrecompileResult {
-        Self::do_update_credit(handle, false)?;
+    /// `slash_credit_score(address,uint256) returns (uint256)`, returns the new credit score
+    pub fn slash_credit_score(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
         let weight = RuntimeHelper::<Runtime>::db_read_gas_cost() * 4
             + RuntimeHelper::<Runtime>::db_write_gas_cost() * 2;
-        handle.record_cost(weight)?;
-
-        Ok(PrecompileOutput {
-            exit_status: ExitSucceed::Returned,
-            output: Default::default(),
-        })
+        Self::do_update_credit(handle, false, weight)
     }
 
     fn do_update_credit(
         handle: &mut impl PrecompileHandle,
         add_flag: bool,
-    ) -> Result<(), PrecompileFailure> {
-        let input = handle.input();
-
-        Self::check_input_len(input, BASIC_LEN + 32)?;
+        weight: u64,
+    ) -> EvmResult<Vec<u8>> {
+        util::check_modifier(handle, util::FunctionModifier::NonPayable)?;
+
+        let mut input = EvmDataReader::new_skip_selector(handle.input())?;
+        input.expect_arguments(2)?;
+        let account = input.read_address()?;
+        let score = input.read_u256()?;
+        if score > U256::from(u64::MAX) {
+            return Err(util::revert("score out of range"));
+        }
 
-        let gasometer = Gasometer::new(None);
-        gasometer.check_function_modifier(
-            handle.context(),
-            handle.is_static(),
-            util::FunctionModifier::NonPayable,
+        // privilege check, credit change record and a possible badge mint
+        handle.record_cost(
+            weight
+                + RuntimeHelper::<Runtime>::db_read_gas_cost()
+                + <Runtime as pallet_evm::Config>::GasWeightMapping::weight_to_gas(
+                    pallet_credit::Pallet::<Runtime>::max_evm_credit_change_weight(),
+                ),
         )?;
 
-        let account = H160::from(array_ref!(input, BASIC_LEN - 20, 20));
-        let score = U256::from(array_ref!(input, BASIC_LEN, 32)).low_u64();
-
-        pallet_credit::Pallet::<Runtime>::evm_update_credit(
-            &(handle.context()).caller,
+        let caller = handle.context().caller;
+        let (old_score, new_score) = pallet_credit::Pallet::<Runtime>::evm_update_credit(
+            &caller,
             &account,
-            score,
+            score.low_u64(),
             add_flag,
-        );
-        Ok(())
+        )
+        .map_err(|e| util::revert(Self::update_error_reason(e)))?;
+
+        let event = if add_flag {
+            EVENT_CREDIT_SCORE_ADDED
+        } else {
+            EVENT_CREDIT_SCORE_SLASHED
+        };
+        LogsBuilder::new(handle.context().address)
+            .log3(
+                util::event_topic(event),
+                caller,
+                account,
+                EvmDataWriter::new()
+                    .write_u256(old_score.into())
+                    .write_u256(new_score.into())
+                    .build(),
+            )
+            .record(handle)?;
+
+        Ok(EvmDataWriter::new().write_u256(new_score.into()).build())
+    }
+
+    /// check a view call, record the cost of `reads` reads and read the target address
+    fn read_view_target(handle: &mut impl PrecompileHandle, reads: u64) -> EvmResult<H160> {
+        util::check_modifier(handle, util::FunctionModifier::View)?;
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost() * reads)?;
+
+        let mut input = EvmDataReader::new_skip_selector(handle.input())?;
+        input.expect_arguments(1)?;
+        input.read_address()
     }
 
-    fn check_input_len(input: &[u8], base_len: usize) -> Result<(), PrecompileFailure> {
-        if input.len() < base_len {
-            return Err(PrecompileFailure::Error {
-                exit_status: ExitError::Other("input len not enough".into()),
-            });
+    fn mapped_account(account: &H160) -> Option<Runtime::AccountId> {
+        <Runtime as pallet_credit::Config>::NodeInterface::get_accounts_evm_deeper(account)
+    }
+
+    fn update_error_reason(error: CreditError<Runtime>) -> &'static str {
+        match error {
+            CreditError::<Runtime>::NotAdmin => "caller has no evm credit operation privilege",
+            CreditError::<Runtime>::AccountNotFound => {
+                "target address is not mapped to a substrate account"
+            }
+            CreditError::<Runtime>::AccountNoExistInUserCredit => "target account has no credit",
+            _ => "credit update failed",
         }
-        Ok(())
     }
 }
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,301 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{testing::MockHandle, CreditDispatch};
+use fp_evm::{Precompile, PrecompileResult};
+use frame_support::{
+    parameter_types,
+    traits::{AsEnsureOriginWithArg, ConstU128, ConstU32, ConstU64, GenesisBuild},
+    weights::Weight,
+};
+use node_primitives::{
+    credit::{CreditData, CreditLevel, EraIndex},
+    deeper_node::NodeInterface,
+    user_privileges::{Privilege, UserPrivilegeInterface},
+};
+use sp_core::{crypto::AccountId32, H160, H256, U256};
+use sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+pub type AccountId = AccountId32;
+pub type Balance = u128;
+
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+        Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+        Timestamp: pallet_timestamp::{Pallet, Call, Storage, Inherent},
+        EVM: pallet_evm::{Pallet, Call, Storage, Event<T>},
+        Uniques: pallet_uniques::{Pallet, Call, Storage, Event<T>},
+        Credit: pallet_credit::{Pallet, Call, Storage, Event<T>},
+    }
+);
+
+impl frame_system::Config for Test {
+    type BaseCallFilter = frame_support::traits::Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = frame_support::weights::constants::RocksDbWeight;
+    type RuntimeOrigin = RuntimeOrigin;
+    type RuntimeCall = RuntimeCall;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type RuntimeEvent = RuntimeEvent;
+    type BlockHashCount = ConstU64<250>;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ();
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+    type MaxLocks = ();
+    type MaxReserves = ();
+    type ReserveIdentifier = [u8; 8];
+    type Balance = Balance;
+    type RuntimeEvent = RuntimeEvent;
+    type DustRemoval = ();
+    type ExistentialDeposit = ConstU128<1>;
+    type AccountStore = System;
+    type WeightInfo = ();
+}
+
+impl pallet_timestamp::Config for Test {
+    type Moment = u64;
+    type OnTimestampSet = ();
+    type MinimumPeriod = ConstU64<5>;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub BlockGasLimit: U256 = U256::from(u64::MAX);
+    pub WeightPerGas: Weight = Weight::from_ref_time(20_000);
+}
+
+impl pallet_evm::Config for Test {
+    type FeeCalculator = ();
+    type GasWeightMapping = pallet_evm::FixedGasWeightMapping<Self>;
+    type WeightPerGas = WeightPerGas;
+    type BlockHashMapping = pallet_evm::SubstrateBlockHashMapping<Self>;
+    type AddressMapping = pallet_evm::HashedAddressMapping<BlakeTwo256>;
+    type Currency = Balances;
+    type RuntimeEvent = RuntimeEvent;
+    type Runner = pallet_evm::runner::stack::Runner<Self>;
+    type PrecompilesType = ();
+    type PrecompilesValue = ();
+    type ChainId = ();
+    type BlockGasLimit = BlockGasLimit;
+    type OnChargeTransaction = ();
+    type FindAuthor = ();
+}
+
+impl pallet_uniques::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type CollectionId = u32;
+    type ItemId = u32;
+    type Currency = Balances;
+    type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+    type CollectionDeposit = ConstU128<1>;
+    type ItemDeposit = ConstU128<1>;
+    type MetadataDepositBase = ConstU128<1>;
+    type AttributeDepositBase = ConstU128<1>;
+    type DepositPerByte = ConstU128<1>;
+    type StringLimit = ConstU32<50>;
+    type KeyLimit = ConstU32<50>;
+    type ValueLimit = ConstU32<50>;
+    type WeightInfo = ();
+    type CreateOrigin = AsEnsureOriginWithArg<frame_system::EnsureSigned<AccountId>>;
+    type Locker = ();
+}
+
+pub fn alice() -> AccountId {
+    AccountId32::new([1u8; 32])
+}
+
+pub fn bob() -> AccountId {
+    AccountId32::new([2u8; 32])
+}
+
+/// EVM address paired with Alice.
+pub fn alice_address() -> H160 {
+    H160::repeat_byte(1)
+}
+
+/// EVM address paired with Bob, who has no credit.
+pub fn bob_address() -> H160 {
+    H160::repeat_byte(2)
+}
+
+/// EVM address with the `EvmCreditOperation` privilege, not paired with any account.
+pub fn admin_address() -> H160 {
+    H160::repeat_byte(0xad)
+}
+
+/// The EVM address pairing of the deeper-node pallet.
+pub struct MockNodeInterface;
+
+impl NodeInterface<AccountId, u64> for MockNodeInterface {
+    fn get_onboard_time(_account_id: &AccountId) -> Option<u64> {
+        None
+    }
+
+    fn im_ever_online(_account_id: &AccountId) -> bool {
+        true
+    }
+
+    fn get_eras_offline(_account_id: &AccountId) -> u32 {
+        0
+    }
+
+    fn get_accounts_deeper_evm(account_id: &AccountId) -> Option<H160> {
+        [(alice(), alice_address()), (bob(), bob_address())]
+            .into_iter()
+            .find(|(account, _)| account == account_id)
+            .map(|(_, address)| address)
+    }
+
+    fn get_accounts_evm_deeper(address: &H160) -> Option<AccountId> {
+        [(alice(), alice_address()), (bob(), bob_address())]
+            .into_iter()
+            .find(|(_, paired)| paired == address)
+            .map(|(account, _)| account)
+    }
+
+    fn attestation_satisfied(_account_id: &AccountId) -> bool {
+        true
+    }
+}
+
+/// Only `admin_address` may operate credit from the EVM.
+pub struct MockUserPrivileges;
+
+impl UserPrivilegeInterface<AccountId> for MockUserPrivileges {
+    fn has_privilege(_user: &AccountId, _p: Privilege) -> bool {
+        false
+    }
+
+    fn has_evm_privilege(user: &H160, p: Privilege) -> bool {
+        *user == admin_address() && p == Privilege::EvmCreditOperation
+    }
+}
+
+parameter_types! {
+    pub const BlocksPerEra: u64 = 12;
+    pub const CreditAttenuationStep: u64 = 1;
+    pub const MinCreditToDelegate: u64 = 100;
+    pub const MicropaymentToCreditFactor: u128 = 1_000_000_000_000_000;
+    pub const SecsPerBlock: u32 = 5u32;
+    pub const DPRPerCreditBurned: Balance = 50;
+    pub const MaxBurnCreditPerAddress: u32 = 50;
+    pub const MinSettingEnactmentDelay: EraIndex = 2;
+    pub const MaxScheduledSettingsPerEra: u32 = 10;
+    pub const MaxCreditHistory: u32 = 20;
+    pub const PriceQuorum: u32 = 1;
+    pub const PriceStaleAfter: u64 = 24 * 60 * 60;
+    pub const MaxPriceHistory: u32 = 10;
+    pub const PriceAggregationInterval: u64 = 0;
+    pub const PriceFeedInterval: u64 = 10;
+    pub const CreditBadgeCollection: Option<u32> = None;
+    pub const MaxCreditChanges: u32 = 3;
+}
+
+impl pallet_credit::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Currency = Balances;
+    type BlocksPerEra = BlocksPerEra;
+    type CreditAttenuationStep = CreditAttenuationStep;
+    type MinCreditToDelegate = MinCreditToDelegate;
+    type MicropaymentToCreditFactor = MicropaymentToCreditFactor;
+    type NodeInterface = MockNodeInterface;
+    type WeightInfo = ();
+    type UnixTime = Timestamp;
+    type SecsPerBlock = SecsPerBlock;
+    type DPRPerCreditBurned = DPRPerCreditBurned;
+    type BurnedTo = ();
+    type UserPrivilegeInterface = MockUserPrivileges;
+    type MaxBurnCreditPerAddress = MaxBurnCreditPerAddress;
+    type CreditSettingOrigin = frame_system::EnsureRoot<AccountId>;
+    type MinSettingEnactmentDelay = MinSettingEnactmentDelay;
+    type MaxScheduledSettingsPerEra = MaxScheduledSettingsPerEra;
+    type MaxCreditHistory = MaxCreditHistory;
+    type PriceQuorum = PriceQuorum;
+    type PriceStaleAfter = PriceStaleAfter;
+    type MaxPriceHistory = MaxPriceHistory;
+    type PriceAggregationInterval = PriceAggregationInterval;
+    type PriceFeed = ();
+    type PriceFeedInterval = PriceFeedInterval;
+    type MaxPriceFeedEndpoints = ConstU32<2>;
+    type MaxPriceFeedEndpointLength = ConstU32<64>;
+    type CreditBadgeCollection = CreditBadgeCollection;
+    type MaxCreditChanges = MaxCreditChanges;
+}
+
+/// Address of the precompile in the tests.
+pub fn precompile_address() -> H160 {
+    H160::from_low_u64_be(0x0b00)
+}
+
+/// Alice has 100 credit, level One, in campaign 1, Bob has no credit.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let mut storage = frame_system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+    pallet_credit::GenesisConfig::<Test> {
+        credit_settings: vec![],
+        user_credit_data: vec![(
+            alice(),
+            CreditData {
+                campaign_id: 1,
+                credit: 100,
+                initial_credit_level: CreditLevel::One,
+                current_credit_level: CreditLevel::One,
+                ..Default::default()
+            },
+        )],
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+
+    let mut ext = sp_io::TestExternalities::new(storage);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
+
+/// Call the precompile from `caller`, returns the result and the handle holding the used gas
+/// and the logs.
+pub fn call(caller: H160, input: Vec<u8>) -> (PrecompileResult, MockHandle) {
+    let mut handle = MockHandle::new(precompile_address(), caller, input);
+    let result = CreditDispatch::<Test>::execute(&mut handle);
+    (result, handle)
+}
@@ -0,0 +1,164 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Precompile handle and assertions for the mock runtime tests of the Deeper Chain
+//! precompiles.
+
+use crate::util::{event_topic, EvmDataWriter};
+use fp_evm::{
+    Context, ExitError, ExitReason, PrecompileFailure, PrecompileHandle, PrecompileResult, Transfer,
+};
+use pallet_evm::Log;
+use sp_core::{H160, H256, U256};
+use sp_std::vec::Vec;
+
+/// Gas limit of the calls of a `MockHandle`.
+pub const MOCK_GAS_LIMIT: u64 = 1_000_000_000;
+
+/// Call of a precompile at `code_address` from `context.caller`, recording the used gas and
+/// the emitted logs.
+pub struct MockHandle {
+    pub input: Vec<u8>,
+    pub code_address: H160,
+    pub context: Context,
+    pub is_static: bool,
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub logs: Vec<Log>,
+}
+
+impl MockHandle {
+    pub fn new(code_address: H160, caller: H160, input: Vec<u8>) -> Self {
+        Self {
+            input,
+            code_address,
+            context: Context {
+                address: code_address,
+                caller,
+                apparent_value: U256::zero(),
+            },
+            is_static: false,
+            gas_limit: MOCK_GAS_LIMIT,
+            gas_used: 0,
+            logs: Vec::new(),
+        }
+    }
+
+    /// the same call in a static context, e.g. through `staticcall`
+    pub fn with_static(mut self) -> Self {
+        self.is_static = true;
+        self
+    }
+}
+
+impl PrecompileHandle for MockHandle {
+    fn call(
+        &mut self,
+        _to: H160,
+        _transfer: Option<Transfer>,
+        _input: Vec<u8>,
+        _gas_limit: Option<u64>,
+        _is_static: bool,
+        _context: &Context,
+    ) -> (ExitReason, Vec<u8>) {
+        // the precompiles don't call other contracts
+        (
+            ExitReason::Error(ExitError::Other("calls not supported by MockHandle".into())),
+            Vec::new(),
+        )
+    }
+
+    fn record_cost(&mut self, cost: u64) -> Result<(), ExitError> {
+        self.gas_used = self.gas_used.saturating_add(cost);
+        if self.gas_used > self.gas_limit {
+            return Err(ExitError::OutOfGas);
+        }
+        Ok(())
+    }
+
+    fn remaining_gas(&self) -> u64 {
+        self.gas_limit.saturating_sub(self.gas_used)
+    }
+
+    fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError> {
+        self.logs.push(Log {
+            address,
+            topics,
+            data,
+        });
+        Ok(())
+    }
+
+    fn code_address(&self) -> H160 {
+        self.code_address
+    }
+
+    fn input(&self) -> &[u8] {
+        &self.input
+    }
+
+    fn context(&self) -> &Context {
+        &self.context
+    }
+
+    fn is_static(&self) -> bool {
+        self.is_static
+    }
+
+    fn gas_limit(&self) -> Option<u64> {
+        Some(self.gas_limit)
+    }
+}
+
+/// Selector of the function with the given Solidity signature.
+pub fn selector(signature: &str) -> u32 {
+    let hash = sp_io::hashing::keccak_256(signature.as_bytes());
+    u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]])
+}
+
+/// Check each selector is the one of its function signature.
+pub fn assert_selectors(selectors: &[(u32, &str)]) {
+    for (value, signature) in selectors {
+        assert_eq!(*value, selector(signature), "{}", signature);
+    }
+}
+
+/// Output of a call expected to succeed.
+pub fn output(result: PrecompileResult) -> Vec<u8> {
+    result.expect("the call succeeds").output
+}
+
+/// A single `uint256` word.
+pub fn word(value: impl Into<U256>) -> Vec<u8> {
+    EvmDataWriter::new().write_u256(value.into()).build()
+}
+
+/// Log of the event with the given signature emitted by the precompile at `address`, the
+/// signature topic followed by `topics`.
+pub fn event_log(address: H160, signature: &[u8], topics: &[H256], data: Vec<u8>) -> Log {
+    let mut all_topics = Vec::with_capacity(topics.len() + 1);
+    all_topics.push(event_topic(signature));
+    all_topics.extend_from_slice(topics);
+    Log {
+        address,
+        topics: all_topics,
+        data,
+    }
+}
+
+/// Check the call reverted, e.g. because the dispatched call failed.
+pub fn assert_dispatch_reverted(result: PrecompileResult) {
+    assert!(matches!(result, Err(PrecompileFailure::Revert { .. })));
+}
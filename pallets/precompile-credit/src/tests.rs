@@ -0,0 +1,208 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use crate::mock::*;
+use crate::testing::{assert_selectors, event_log, output, selector, word, MockHandle};
+use fp_evm::PrecompileFailure;
+use node_primitives::credit::{CreditChange, CreditLevel, CreditSource};
+
+fn view(selector: u32, account: H160) -> Vec<u8> {
+    let input = EvmDataWriter::new_with_selector(selector)
+        .write_address(account)
+        .build();
+    let mut handle = MockHandle::new(precompile_address(), bob_address(), input).with_static();
+    output(CreditDispatch::<Test>::execute(&mut handle))
+}
+
+fn update_input(selector: u32, account: H160, score: U256) -> Vec<u8> {
+    EvmDataWriter::new_with_selector(selector)
+        .write_address(account)
+        .write_u256(score)
+        .build()
+}
+
+#[test]
+fn selectors_match_signatures() {
+    let selectors = [
+        (SELECTOR_GET_CREDIT_SCORE, "get_credit_score(address)"),
+        (
+            SELECTOR_ADD_CREDIT_SCORE,
+            "add_credit_score(address,uint256)",
+        ),
+        (
+            SELECTOR_SLASH_CREDIT_SCORE,
+            "slash_credit_score(address,uint256)",
+        ),
+        (SELECTOR_GET_CREDIT_LEVEL, "get_credit_level(address)"),
+        (SELECTOR_GET_CAMPAIGN_ID, "get_campaign_id(address)"),
+        (
+            SELECTOR_GET_CREDIT_HISTORY_LEN,
+            "get_credit_history_len(address)",
+        ),
+        (
+            SELECTOR_GET_SUBSTRATE_ACCOUNT,
+            "get_substrate_account(address)",
+        ),
+    ];
+    assert_selectors(&selectors);
+}
+
+#[test]
+fn revert_reason_is_abi_encoded() {
+    let output = match util::revert("no credit") {
+        PrecompileFailure::Revert { output, .. } => output,
+        _ => unreachable!(),
+    };
+    assert_eq!(output.len(), 4 + 32 * 3);
+    assert_eq!(&output[..4], &selector("Error(string)").to_be_bytes());
+    assert_eq!(U256::from_big_endian(&output[4..36]), U256::from(32));
+    assert_eq!(U256::from_big_endian(&output[36..68]), U256::from(9));
+    assert_eq!(&output[68..77], b"no credit");
+    assert!(output[77..].iter().all(|b| *b == 0));
+}
+
+#[test]
+fn views() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(view(SELECTOR_GET_CREDIT_SCORE, alice_address()), word(100));
+        assert_eq!(view(SELECTOR_GET_CREDIT_LEVEL, alice_address()), word(1));
+        assert_eq!(view(SELECTOR_GET_CAMPAIGN_ID, alice_address()), word(1));
+        assert_eq!(
+            view(SELECTOR_GET_CREDIT_HISTORY_LEN, alice_address()),
+            word(0)
+        );
+        assert_eq!(
+            view(SELECTOR_GET_SUBSTRATE_ACCOUNT, alice_address()),
+            EvmDataWriter::new().write_h256(H256([1u8; 32])).build()
+        );
+
+        // mapped without credit, and not mapped
+        for account in [bob_address(), admin_address()] {
+            assert_eq!(view(SELECTOR_GET_CREDIT_SCORE, account), word(0));
+            assert_eq!(view(SELECTOR_GET_CREDIT_LEVEL, account), word(0));
+            assert_eq!(view(SELECTOR_GET_CAMPAIGN_ID, account), word(0));
+        }
+        assert_eq!(
+            view(SELECTOR_GET_SUBSTRATE_ACCOUNT, admin_address()),
+            EvmDataWriter::new().write_h256(H256::zero()).build()
+        );
+    });
+}
+
+#[test]
+fn add_and_slash_credit_score() {
+    new_test_ext().execute_with(|| {
+        let input = update_input(SELECTOR_ADD_CREDIT_SCORE, alice_address(), 100.into());
+        let (result, handle) = call(admin_address(), input);
+        assert_eq!(output(result), word(200));
+        assert_eq!(
+            handle.logs,
+            vec![event_log(
+                precompile_address(),
+                EVENT_CREDIT_SCORE_ADDED,
+                &[admin_address().into(), alice_address().into()],
+                [word(100), word(200)].concat()
+            )]
+        );
+        // the worst case of the credit change record and the badge sync is charged
+        assert!(
+            handle.gas_used
+                > <Test as pallet_evm::Config>::GasWeightMapping::weight_to_gas(
+                    Credit::max_evm_credit_change_weight()
+                )
+        );
+        let credit_data = Credit::user_credit(alice()).unwrap();
+        assert_eq!(credit_data.credit, 200);
+        assert_eq!(credit_data.current_credit_level, CreditLevel::Two);
+
+        let input = update_input(SELECTOR_SLASH_CREDIT_SCORE, alice_address(), 130.into());
+        let (result, handle) = call(admin_address(), input);
+        assert_eq!(output(result), word(70));
+        assert_eq!(
+            handle.logs,
+            vec![event_log(
+                precompile_address(),
+                EVENT_CREDIT_SCORE_SLASHED,
+                &[admin_address().into(), alice_address().into()],
+                [word(200), word(70)].concat()
+            )]
+        );
+        assert_eq!(view(SELECTOR_GET_CREDIT_LEVEL, alice_address()), word(0));
+
+        assert_eq!(
+            Credit::recent_credit_changes(&alice(), 2),
+            vec![
+                CreditChange {
+                    block_number: 1,
+                    source: CreditSource::Evm,
+                    old_credit: 200,
+                    new_credit: 70,
+                },
+                CreditChange {
+                    block_number: 1,
+                    source: CreditSource::Evm,
+                    old_credit: 100,
+                    new_credit: 200,
+                },
+            ]
+        );
+    });
+}
+
+#[test]
+fn update_credit_reverts() {
+    new_test_ext().execute_with(|| {
+        let reverted = |caller, account, score| {
+            let input = update_input(SELECTOR_ADD_CREDIT_SCORE, account, score);
+            let (result, handle) = call(caller, input);
+            assert!(handle.logs.is_empty());
+            result.map(|o| o.output)
+        };
+
+        assert_eq!(
+            reverted(alice_address(), alice_address(), 1.into()),
+            Err(util::revert("caller has no evm credit operation privilege"))
+        );
+        assert_eq!(
+            reverted(admin_address(), admin_address(), 1.into()),
+            Err(util::revert(
+                "target address is not mapped to a substrate account"
+            ))
+        );
+        assert_eq!(
+            reverted(admin_address(), bob_address(), 1.into()),
+            Err(util::revert("target account has no credit"))
+        );
+        assert_eq!(
+            reverted(admin_address(), alice_address(), U256::from(u64::MAX) + 1),
+            Err(util::revert("score out of range"))
+        );
+
+        let mut handle = MockHandle::new(
+            precompile_address(),
+            admin_address(),
+            update_input(SELECTOR_ADD_CREDIT_SCORE, alice_address(), 1.into()),
+        )
+        .with_static();
+        assert_eq!(
+            CreditDispatch::<Test>::execute(&mut handle).map(|o| o.output),
+            Err(util::revert(
+                "can't call non-static function in static context"
+            ))
+        );
+        assert_eq!(Credit::user_credit(alice()).unwrap().credit, 100);
+    });
+}
@@ -18,8 +18,7 @@
 
 extern crate alloc;
 
-use crate::alloc::borrow::ToOwned;
-use fp_evm::{Context, ExitError, ExitRevert, PrecompileFailure};
+use fp_evm::{Context, ExitError, ExitRevert, PrecompileFailure, PrecompileHandle};
 use frame_support::{
     dispatch::{Dispatchable, GetDispatchInfo, PostDispatchInfo},
     traits::Get,
@@ -43,6 +42,244 @@ pub fn error<T: Into<alloc::borrow::Cow<'static, str>>>(text: T) -> PrecompileFa
     }
 }
 
+/// Revert the execution with a Solidity `Error(string)` reason, so the calling contract
+/// and the clients can decode the message.
+pub fn revert(reason: impl AsRef<[u8]>) -> PrecompileFailure {
+    PrecompileFailure::Revert {
+        exit_status: ExitRevert::Reverted,
+        output: EvmDataWriter::new_with_selector(ERROR_SELECTOR)
+            .write_u256(32u32.into())
+            .write_bytes(reason.as_ref())
+            .build(),
+    }
+}
+
+/// Selector of the Solidity `Error(string)` revert reason.
+const ERROR_SELECTOR: u32 = 0x08c379a0;
+
+/// Read the selector of the called function from the input.
+pub fn read_selector(input: &[u8]) -> EvmResult<u32> {
+    if input.len() < 4 {
+        return Err(revert("tried to parse selector out of bounds"));
+    }
+    let mut selector = [0u8; 4];
+    selector.copy_from_slice(&input[0..4]);
+    Ok(u32::from_be_bytes(selector))
+}
+
+/// Reader of the ABI encoded arguments of a call, one 32 bytes word at a time.
+#[derive(Clone, Copy, Debug)]
+pub struct EvmDataReader<'a> {
+    input: &'a [u8],
+    cursor: usize,
+}
+
+impl<'a> EvmDataReader<'a> {
+    /// Create a reader of the arguments following the selector of the input.
+    pub fn new_skip_selector(input: &'a [u8]) -> EvmResult<Self> {
+        read_selector(input)?;
+        Ok(Self {
+            input: &input[4..],
+            cursor: 0,
+        })
+    }
+
+    /// Check the input holds at least `args` arguments.
+    pub fn expect_arguments(&self, args: usize) -> EvmResult {
+        match args
+            .checked_mul(32)
+            .and_then(|len| len.checked_add(self.cursor))
+        {
+            Some(end) if end <= self.input.len() => Ok(()),
+            _ => Err(revert("input doesn't match expected length")),
+        }
+    }
+
+    /// Read the next 32 bytes word.
+    fn read_word(&mut self) -> EvmResult<&'a [u8]> {
+        let word = self
+            .cursor
+            .checked_add(32)
+            .and_then(|end| self.input.get(self.cursor..end))
+            .ok_or_else(|| revert("tried to parse word out of bounds"))?;
+        self.cursor += 32;
+        Ok(word)
+    }
+
+    /// Read an `address` argument.
+    pub fn read_address(&mut self) -> EvmResult<H160> {
+        Ok(H160::from_slice(&self.read_word()?[12..]))
+    }
+
+    /// Read a `uint256` argument.
+    pub fn read_u256(&mut self) -> EvmResult<U256> {
+        Ok(U256::from_big_endian(self.read_word()?))
+    }
+
+    /// Read a `uint32` argument.
+    pub fn read_u32(&mut self) -> EvmResult<u32> {
+        let value = self.read_u256()?;
+        if value > U256::from(u32::MAX) {
+            return Err(revert("value out of range of uint32"));
+        }
+        Ok(value.low_u32())
+    }
+
+    /// Read a `uint64` argument.
+    pub fn read_u64(&mut self) -> EvmResult<u64> {
+        let value = self.read_u256()?;
+        if value > U256::from(u64::MAX) {
+            return Err(revert("value out of range of uint64"));
+        }
+        Ok(value.low_u64())
+    }
+
+    /// Read a `bytes32` argument.
+    pub fn read_h256(&mut self) -> EvmResult<H256> {
+        Ok(H256::from_slice(self.read_word()?))
+    }
+
+    /// Read a `bytes` argument, stored at the offset given by its head word.
+    pub fn read_bytes(&mut self) -> EvmResult<Vec<u8>> {
+        let offset = Self::read_usize(self.read_word()?)?;
+        let mut tail = Self {
+            input: self.input,
+            cursor: offset,
+        };
+        let len = Self::read_usize(tail.read_word()?)?;
+        let start = tail.cursor;
+        let bytes = start
+            .checked_add(len)
+            .and_then(|end| self.input.get(start..end))
+            .ok_or_else(|| revert("tried to parse bytes out of bounds"))?;
+        Ok(bytes.to_vec())
+    }
+
+    /// Read a `bytes32[]` argument, stored at the offset given by its head word.
+    pub fn read_h256_array(&mut self) -> EvmResult<Vec<H256>> {
+        let offset = Self::read_usize(self.read_word()?)?;
+        let mut tail = Self {
+            input: self.input,
+            cursor: offset,
+        };
+        let len = Self::read_usize(tail.read_word()?)?;
+        tail.expect_arguments(len)?;
+        (0..len).map(|_| tail.read_h256()).collect()
+    }
+
+    fn read_usize(word: &[u8]) -> EvmResult<usize> {
+        let value = U256::from_big_endian(word);
+        if value > U256::from(u32::MAX) {
+            return Err(revert("offset or length out of bounds"));
+        }
+        Ok(value.low_u32() as usize)
+    }
+
+    /// Read a `bool` argument.
+    pub fn read_bool(&mut self) -> EvmResult<bool> {
+        let value = self.read_u256()?;
+        if value > U256::one() {
+            return Err(revert("value is not a bool"));
+        }
+        Ok(value == U256::one())
+    }
+}
+
+/// Writer of ABI encoded return values and log data.
+/// Only static types are supported, apart from a single trailing `bytes`.
+#[derive(Clone, Debug, Default)]
+pub struct EvmDataWriter {
+    data: Vec<u8>,
+}
+
+impl EvmDataWriter {
+    /// Create a new empty writer.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Create a writer starting with the given selector.
+    pub fn new_with_selector(selector: u32) -> Self {
+        Self {
+            data: selector.to_be_bytes().to_vec(),
+        }
+    }
+
+    /// Returns the encoded data.
+    pub fn build(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Write a `uint256`.
+    pub fn write_u256(mut self, value: U256) -> Self {
+        let mut word = [0u8; 32];
+        value.to_big_endian(&mut word);
+        self.data.extend_from_slice(&word);
+        self
+    }
+
+    /// Write a `bytes32`.
+    pub fn write_h256(mut self, value: H256) -> Self {
+        self.data.extend_from_slice(value.as_bytes());
+        self
+    }
+
+    /// Write an `address`.
+    pub fn write_address(self, value: H160) -> Self {
+        self.write_h256(value.into())
+    }
+
+    /// Write a `bool`.
+    pub fn write_bool(self, value: bool) -> Self {
+        self.write_u256(U256::from(value as u8))
+    }
+
+    /// Write the length and the items of a `bytes32[]`,
+    /// the offset pointing to it must have been written before.
+    pub fn write_h256_array(mut self, value: &[H256]) -> Self {
+        self = self.write_u256(U256::from(value.len()));
+        for item in value {
+            self = self.write_h256(*item);
+        }
+        self
+    }
+
+    /// Write the length and the right padded content of a `bytes` or `string`,
+    /// the offset pointing to it must have been written before.
+    pub fn write_bytes(mut self, value: &[u8]) -> Self {
+        self = self.write_u256(U256::from(value.len()));
+        self.data.extend_from_slice(value);
+        let padding = (32 - value.len() % 32) % 32;
+        self.data.extend(core::iter::repeat(0u8).take(padding));
+        self
+    }
+}
+
+/// Gas cost of a log, as charged by the `LOG0..LOG4` opcodes.
+pub fn log_cost(topics: usize, data_len: usize) -> EvmResult<u64> {
+    const G_LOG: u64 = 375;
+    const G_LOGDATA: u64 = 8;
+    const G_LOGTOPIC: u64 = 375;
+
+    G_LOGDATA
+        .checked_mul(data_len as u64)
+        .and_then(|cost| cost.checked_add(G_LOG))
+        .and_then(|cost| cost.checked_add(G_LOGTOPIC * topics as u64))
+        .ok_or(PrecompileFailure::Error {
+            exit_status: ExitError::OutOfGas,
+        })
+}
+
+/// Topic of the event with the given Solidity signature.
+pub fn event_topic(signature: &[u8]) -> H256 {
+    H256(sp_io::hashing::keccak_256(signature))
+}
+
+/// Check that the call is compatible with the modifier of the called function.
+pub fn check_modifier(handle: &impl PrecompileHandle, modifier: FunctionModifier) -> EvmResult {
+    Gasometer::new(None).check_function_modifier(handle.context(), handle.is_static(), modifier)
+}
+
 /// Builder for PrecompileOutput.
 #[derive(Clone, Debug)]
 pub struct LogsBuilder {
@@ -50,7 +287,6 @@ pub struct LogsBuilder {
     logs: Vec<Log>,
 }
 
-#[allow(dead_code)]
 impl LogsBuilder {
     /// Create a new builder with no logs.
     /// Takes the address of the precompile (usually `context.address`).
@@ -66,6 +302,17 @@ impl LogsBuilder {
         self.logs
     }
 
+    /// Record the cost of the logs, then emit them through the handle.
+    pub fn record(self, handle: &mut impl PrecompileHandle) -> EvmResult {
+        for log in &self.logs {
+            handle.record_cost(log_cost(log.topics.len(), log.data.len())?)?;
+        }
+        for log in self.logs {
+            handle.log(log.address, log.topics, log.data)?;
+        }
+        Ok(())
+    }
+
     /// Add a 0-topic log.
     pub fn log0<D>(mut self, data: D) -> Self
     where
@@ -162,11 +409,10 @@ where
     /// Try to dispatch a Substrate call.
     /// Return an error if there are not enough gas, or if the call fails.
     /// If successful returns the used gas using the Runtime GasWeightMapping.
-    #[allow(dead_code)]
     pub fn try_dispatch<Call>(
+        handle: &mut impl PrecompileHandle,
         origin: <Runtime::RuntimeCall as Dispatchable>::RuntimeOrigin,
         call: Call,
-        gasometer: &mut Gasometer,
     ) -> EvmResult<()>
     where
         Runtime::RuntimeCall: From<Call>,
@@ -175,13 +421,11 @@ where
         let dispatch_info = call.get_dispatch_info();
 
         // Make sure there is enough gas.
-        if let Some(gas_limit) = gasometer.remaining_gas()? {
-            let required_gas = Runtime::GasWeightMapping::weight_to_gas(dispatch_info.weight);
-            if required_gas > gas_limit {
-                return Err(PrecompileFailure::Error {
-                    exit_status: ExitError::OutOfGas,
-                });
-            }
+        let required_gas = Runtime::GasWeightMapping::weight_to_gas(dispatch_info.weight);
+        if required_gas > handle.remaining_gas() {
+            return Err(PrecompileFailure::Error {
+                exit_status: ExitError::OutOfGas,
+            });
         }
 
         // Dispatch call.
@@ -191,15 +435,13 @@ where
         // computations.
         let used_weight = call
             .dispatch(origin)
-            .map_err(|e| {
-                gasometer.revert(alloc::format!("Dispatched call failed with error: {:?}", e))
-            })?
+            .map_err(|e| revert(alloc::format!("Dispatched call failed with error: {:?}", e)))?
             .actual_weight;
 
         let used_gas =
             Runtime::GasWeightMapping::weight_to_gas(used_weight.unwrap_or(dispatch_info.weight));
 
-        gasometer.record_cost(used_gas)?;
+        handle.record_cost(used_gas)?;
 
         Ok(())
     }
@@ -297,10 +539,7 @@ impl Gasometer {
     /// TODO : Record cost of the input based on its size and handle Out of Gas ?
     /// This might be required if we format revert messages using user data.
     pub fn revert(&self, output: impl AsRef<[u8]>) -> PrecompileFailure {
-        PrecompileFailure::Revert {
-            exit_status: ExitRevert::Reverted,
-            output: output.as_ref().to_owned(),
-        }
+        revert(output)
     }
 
     /// Check that a function call is compatible with the context it is
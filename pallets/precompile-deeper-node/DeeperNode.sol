@@ -0,0 +1,56 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.10;
+
+/// @dev The DeeperNode precompile address.
+address constant DEEPER_NODE_ADDRESS = 0x0000000000000000000000000000000000000408;
+
+/// @dev The DeeperNode precompile instance.
+DeeperNode constant DEEPER_NODE_CONTRACT = DeeperNode(DEEPER_NODE_ADDRESS);
+
+/// @title Deeper Chain device registry precompile
+/// @dev Read-only view of registered devices and servers. Devices are Substrate accounts,
+/// country and region codes are the codes used by the deeper-node pallet, e.g. "US" or "AMN".
+interface DeeperNode {
+    /// @dev Registration of the device, `registered` is false if it has none.
+    /// Selector: eb01b6cf
+    function get_device_info(bytes32 device)
+        external
+        view
+        returns (
+            bool registered,
+            bytes memory country,
+            uint256 expire,
+            uint32 protocols,
+            uint32 bandwidth,
+            uint64 pricePerGb
+        );
+
+    /// @dev Servers registered in the country, sorted.
+    /// Selector: b79b4021
+    function get_country_servers(bytes calldata country)
+        external
+        view
+        returns (bytes32[] memory);
+
+    /// @dev Servers registered in the level 2 or level 3 region, sorted.
+    /// Selector: 9d75d3db
+    function get_region_servers(bytes calldata region)
+        external
+        view
+        returns (bytes32[] memory);
+
+    /// @dev Block the device was last seen online, zero if never.
+    /// Selector: ebfa4a74
+    function get_im_online(bytes32 device) external view returns (uint256);
+
+    /// @dev Block the device was onboarded, zero if never.
+    /// Selector: ce618cb5
+    function get_onboard_time(bytes32 device) external view returns (uint256);
+
+    /// @dev Substrate account rewarded for the EVM address, zero if not mapped.
+    /// Selector: a5209b75
+    function get_deeper_account(address evmAddress)
+        external
+        view
+        returns (bytes32);
+}
@@ -0,0 +1,171 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Read-only device registry precompile: contracts query registered devices, the servers of a
+//! country or region, the liveness and onboard time of devices and the EVM reward mapping.
+//! Devices are Substrate accounts, passed as `bytes32`. See `DeeperNode.sol`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+use fp_evm::{ExitSucceed, Precompile, PrecompileHandle, PrecompileOutput, PrecompileResult};
+use pallet_evm_precompile_credit::util::{
+    self, EvmDataReader, EvmDataWriter, EvmResult, FunctionModifier, RuntimeHelper,
+};
+use sp_core::{H256, U256};
+use sp_std::vec::Vec;
+
+/// Selectors of the functions of the `DeeperNode` interface.
+const SELECTOR_GET_DEVICE_INFO: u32 = 0xeb01b6cf;
+const SELECTOR_GET_COUNTRY_SERVERS: u32 = 0xb79b4021;
+const SELECTOR_GET_REGION_SERVERS: u32 = 0x9d75d3db;
+const SELECTOR_GET_IM_ONLINE: u32 = 0xebfa4a74;
+const SELECTOR_GET_ONBOARD_TIME: u32 = 0xce618cb5;
+const SELECTOR_GET_DEEPER_ACCOUNT: u32 = 0xa5209b75;
+
+pub struct DeeperNodeDispatch<Runtime> {
+    _marker: PhantomData<Runtime>,
+}
+
+impl<Runtime> Precompile for DeeperNodeDispatch<Runtime>
+where
+    Runtime: pallet_deeper_node::Config + pallet_evm::Config,
+    Runtime::AccountId: From<[u8; 32]> + Into<[u8; 32]>,
+    Runtime::BlockNumber: Into<U256>,
+{
+    fn execute(handle: &mut impl PrecompileHandle) -> PrecompileResult {
+        let selector = util::read_selector(handle.input())?;
+        util::check_modifier(handle, FunctionModifier::View)?;
+        let output = match selector {
+            SELECTOR_GET_DEVICE_INFO => Self::get_device_info(handle)?,
+            SELECTOR_GET_COUNTRY_SERVERS => Self::get_servers(handle, false)?,
+            SELECTOR_GET_REGION_SERVERS => Self::get_servers(handle, true)?,
+            SELECTOR_GET_IM_ONLINE => Self::get_im_online(handle)?,
+            SELECTOR_GET_ONBOARD_TIME => Self::get_onboard_time(handle)?,
+            SELECTOR_GET_DEEPER_ACCOUNT => Self::get_deeper_account(handle)?,
+            _ => return Err(util::revert("unknown selector")),
+        };
+
+        Ok(PrecompileOutput {
+            exit_status: ExitSucceed::Returned,
+            output,
+        })
+    }
+}
+
+impl<Runtime> DeeperNodeDispatch<Runtime>
+where
+    Runtime: pallet_deeper_node::Config + pallet_evm::Config,
+    Runtime::AccountId: From<[u8; 32]> + Into<[u8; 32]>,
+    Runtime::BlockNumber: Into<U256>,
+{
+    /// `get_device_info(bytes32 device) returns (bool registered, bytes country,
+    /// uint256 expire, uint32 protocols, uint32 bandwidth, uint64 pricePerGb)`
+    pub fn get_device_info(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let device = Self::read_device(handle)?;
+        let node = pallet_deeper_node::Pallet::<Runtime>::device_info(&device);
+        let registered = node.account_id == device;
+        let capabilities = node.capabilities();
+
+        Ok(EvmDataWriter::new()
+            .write_bool(registered)
+            // offset of the country, after the 6 words of the head
+            .write_u256(U256::from(6 * 32))
+            .write_u256((*node.expire()).into())
+            .write_u256(capabilities.protocols.into())
+            .write_u256(capabilities.bandwidth.into())
+            .write_u256(capabilities.price_per_gb.into())
+            .write_bytes(node.country())
+            .build())
+    }
+
+    /// `get_country_servers(bytes country) returns (bytes32[])` and
+    /// `get_region_servers(bytes region) returns (bytes32[])`, sorted
+    pub fn get_servers(handle: &mut impl PrecompileHandle, region: bool) -> EvmResult<Vec<u8>> {
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+        let mut input = EvmDataReader::new_skip_selector(handle.input())?;
+        input.expect_arguments(1)?;
+        let code = input.read_bytes()?;
+
+        let count = if region {
+            pallet_deeper_node::Pallet::<Runtime>::region_server_count(&code)
+        } else {
+            pallet_deeper_node::Pallet::<Runtime>::country_server_count(&code)
+        };
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost() * u64::from(count))?;
+        let servers: Vec<H256> = if region {
+            pallet_deeper_node::Pallet::<Runtime>::servers_by_region(code)
+        } else {
+            pallet_deeper_node::Pallet::<Runtime>::servers_by_country(code)
+        }
+        .into_iter()
+        .map(|server| H256(server.into()))
+        .collect();
+
+        Ok(EvmDataWriter::new()
+            .write_u256(U256::from(32))
+            .write_h256_array(&servers)
+            .build())
+    }
+
+    /// `get_im_online(bytes32 device) returns (uint256)`,
+    /// block the device was last seen online, zero if never
+    pub fn get_im_online(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let device = Self::read_device(handle)?;
+        let block = pallet_deeper_node::Pallet::<Runtime>::get_im_online(&device);
+
+        Ok(EvmDataWriter::new()
+            .write_u256(block.map_or_else(U256::zero, Into::into))
+            .build())
+    }
+
+    /// `get_onboard_time(bytes32 device) returns (uint256)`,
+    /// block the device was onboarded, zero if never
+    pub fn get_onboard_time(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let device = Self::read_device(handle)?;
+        let block = pallet_deeper_node::Pallet::<Runtime>::onboard_time(&device);
+
+        Ok(EvmDataWriter::new()
+            .write_u256(block.map_or_else(U256::zero, Into::into))
+            .build())
+    }
+
+    /// `get_deeper_account(address evmAddress) returns (bytes32)`,
+    /// Substrate account rewarded for the EVM address, zero if not mapped
+    pub fn get_deeper_account(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+        let mut input = EvmDataReader::new_skip_selector(handle.input())?;
+        input.expect_arguments(1)?;
+        let address = input.read_address()?;
+        let account = pallet_deeper_node::Pallet::<Runtime>::rewards_accounts_evm_deeper(address);
+
+        Ok(EvmDataWriter::new()
+            .write_h256(account.map_or_else(H256::zero, |account| H256(account.into())))
+            .build())
+    }
+
+    /// record the cost of a read and read the device argument
+    fn read_device(handle: &mut impl PrecompileHandle) -> EvmResult<Runtime::AccountId> {
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+        let mut input = EvmDataReader::new_skip_selector(handle.input())?;
+        input.expect_arguments(1)?;
+        Ok(input.read_h256()?.0.into())
+    }
+}
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,229 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::DeeperNodeDispatch;
+use fp_evm::{Precompile, PrecompileResult};
+use frame_support::{
+    assert_ok, parameter_types,
+    traits::{ConstU128, ConstU32, ConstU64, GenesisBuild},
+    weights::Weight,
+};
+use node_primitives::deeper_node::{protocol, DeviceCapabilities};
+use pallet_evm_precompile_credit::testing::MockHandle;
+use sp_core::{crypto::AccountId32, H160, H256, U256};
+use sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+pub type AccountId = AccountId32;
+pub type Balance = u128;
+
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+        Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+        Timestamp: pallet_timestamp::{Pallet, Call, Storage, Inherent},
+        EVM: pallet_evm::{Pallet, Call, Storage, Event<T>},
+        DeeperNode: pallet_deeper_node::{Pallet, Call, Storage, Event<T>},
+    }
+);
+
+impl frame_system::Config for Test {
+    type BaseCallFilter = frame_support::traits::Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = frame_support::weights::constants::RocksDbWeight;
+    type RuntimeOrigin = RuntimeOrigin;
+    type RuntimeCall = RuntimeCall;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type RuntimeEvent = RuntimeEvent;
+    type BlockHashCount = ConstU64<250>;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ();
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+    type MaxLocks = ();
+    type MaxReserves = ();
+    type ReserveIdentifier = [u8; 8];
+    type Balance = Balance;
+    type RuntimeEvent = RuntimeEvent;
+    type DustRemoval = ();
+    type ExistentialDeposit = ConstU128<1>;
+    type AccountStore = System;
+    type WeightInfo = ();
+}
+
+impl pallet_timestamp::Config for Test {
+    type Moment = u64;
+    type OnTimestampSet = ();
+    type MinimumPeriod = ConstU64<5>;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub BlockGasLimit: U256 = U256::from(u64::MAX);
+    pub WeightPerGas: Weight = Weight::from_ref_time(20_000);
+}
+
+impl pallet_evm::Config for Test {
+    type FeeCalculator = ();
+    type GasWeightMapping = pallet_evm::FixedGasWeightMapping<Self>;
+    type WeightPerGas = WeightPerGas;
+    type BlockHashMapping = pallet_evm::SubstrateBlockHashMapping<Self>;
+    type AddressMapping = pallet_evm::HashedAddressMapping<BlakeTwo256>;
+    type Currency = Balances;
+    type RuntimeEvent = RuntimeEvent;
+    type Runner = pallet_evm::runner::stack::Runner<Self>;
+    type PrecompilesType = ();
+    type PrecompilesValue = ();
+    type ChainId = ();
+    type BlockGasLimit = BlockGasLimit;
+    type OnChargeTransaction = ();
+    type FindAuthor = ();
+}
+
+parameter_types! {
+    pub const MinLockAmt: u32 = 100;
+    pub const MaxDurationEras: u8 = 7;
+    pub const BlocksPerEra: u64 = 10;
+    pub const MaxIpLength: usize = 256;
+    pub const MaxSweepDevices: u32 = 10;
+    pub const MaxServersPerRegion: u32 = 10;
+    pub const MaxFirmwareLength: u32 = 64;
+    pub const DeviceMigrationCooldown: u64 = 100;
+}
+
+impl pallet_deeper_node::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Currency = Balances;
+    type MinLockAmt = MinLockAmt;
+    type MaxDurationEras = MaxDurationEras;
+    type BlocksPerEra = BlocksPerEra;
+    type MaxIpLength = MaxIpLength;
+    type WeightInfo = ();
+    type VerifySignatureInterface = ();
+    type CreditInterface = ();
+    type MaxSweepDevices = MaxSweepDevices;
+    type MaxServersPerRegion = MaxServersPerRegion;
+    type MaxFirmwareLength = MaxFirmwareLength;
+    type UserPrivilegeInterface = ();
+    type DeviceMigration = ();
+    type DeviceMigrationCooldown = DeviceMigrationCooldown;
+}
+
+pub fn alice() -> AccountId {
+    AccountId32::new([1u8; 32])
+}
+
+pub fn bob() -> AccountId {
+    AccountId32::new([2u8; 32])
+}
+
+pub fn charlie() -> AccountId {
+    AccountId32::new([3u8; 32])
+}
+
+/// EVM address Alice is rewarded for.
+pub fn alice_address() -> H160 {
+    H160::repeat_byte(1)
+}
+
+/// Address of the precompile in the tests.
+pub fn precompile_address() -> H160 {
+    H160::from_low_u64_be(0x0d00)
+}
+
+/// Capabilities Alice advertises.
+pub fn capabilities() -> DeviceCapabilities {
+    DeviceCapabilities {
+        protocols: protocol::TCP | protocol::WIREGUARD,
+        ipv6: vec![],
+        bandwidth: 100,
+        price_per_gb: 5,
+        firmware: b"1.0.0".to_vec(),
+    }
+}
+
+/// Alice and Bob are US servers registered for 1 era at block 1, Alice came online and is
+/// rewarded for `alice_address`, Charlie has no device.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let mut storage = frame_system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![(alice(), 500), (bob(), 500), (charlie(), 500)],
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+
+    let mut ext = sp_io::TestExternalities::new(storage);
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        DeeperNode::setup_region_map();
+        for account in [alice(), bob()] {
+            assert_ok!(DeeperNode::register_device(
+                RuntimeOrigin::signed(account.clone()),
+                vec![1, 2, 3, 4],
+                b"US".to_vec()
+            ));
+            assert_ok!(DeeperNode::register_server(
+                RuntimeOrigin::signed(account),
+                1
+            ));
+        }
+        assert_ok!(DeeperNode::set_capabilities(
+            RuntimeOrigin::signed(alice()),
+            capabilities()
+        ));
+        assert_ok!(DeeperNode::im_online(RuntimeOrigin::signed(alice())));
+        assert_ok!(DeeperNode::reward_mapping(
+            RuntimeOrigin::signed(alice()),
+            0,
+            vec![],
+            alice_address()
+        ));
+    });
+    ext
+}
+
+/// Call the precompile, returns the result and the handle holding the used gas.
+pub fn call(input: Vec<u8>) -> (PrecompileResult, MockHandle) {
+    let mut handle =
+        MockHandle::new(precompile_address(), H160::repeat_byte(0xee), input).with_static();
+    let result = DeeperNodeDispatch::<Test>::execute(&mut handle);
+    (result, handle)
+}
@@ -0,0 +1,180 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use crate::mock::*;
+use pallet_evm_precompile_credit::testing::{assert_selectors, output, word};
+use sp_core::H160;
+
+fn account(account: &AccountId) -> H256 {
+    H256(account.clone().into())
+}
+
+fn device_input(selector: u32, device: &AccountId) -> Vec<u8> {
+    EvmDataWriter::new_with_selector(selector)
+        .write_h256(account(device))
+        .build()
+}
+
+fn servers_input(selector: u32, code: &[u8]) -> Vec<u8> {
+    // the code is stored after the head word
+    EvmDataWriter::new_with_selector(selector)
+        .write_u256(U256::from(32))
+        .write_bytes(code)
+        .build()
+}
+
+fn servers(servers: &[AccountId]) -> Vec<u8> {
+    let servers: Vec<H256> = servers.iter().map(account).collect();
+    EvmDataWriter::new()
+        .write_u256(U256::from(32))
+        .write_h256_array(&servers)
+        .build()
+}
+
+#[test]
+fn selectors_match_signatures() {
+    let selectors = [
+        (SELECTOR_GET_DEVICE_INFO, "get_device_info(bytes32)"),
+        (SELECTOR_GET_COUNTRY_SERVERS, "get_country_servers(bytes)"),
+        (SELECTOR_GET_REGION_SERVERS, "get_region_servers(bytes)"),
+        (SELECTOR_GET_IM_ONLINE, "get_im_online(bytes32)"),
+        (SELECTOR_GET_ONBOARD_TIME, "get_onboard_time(bytes32)"),
+        (SELECTOR_GET_DEEPER_ACCOUNT, "get_deeper_account(address)"),
+    ];
+    assert_selectors(&selectors);
+}
+
+#[test]
+fn get_device_info() {
+    new_test_ext().execute_with(|| {
+        let (result, handle) = call(device_input(SELECTOR_GET_DEVICE_INFO, &alice()));
+        assert_eq!(
+            output(result),
+            EvmDataWriter::new()
+                .write_bool(true)
+                .write_u256(U256::from(6 * 32))
+                .write_u256(11u32.into())
+                .write_u256(capabilities().protocols.into())
+                .write_u256(100u32.into())
+                .write_u256(5u32.into())
+                .write_bytes(b"US")
+                .build()
+        );
+        assert!(handle.gas_used > 0);
+
+        // no device
+        assert_eq!(
+            output(call(device_input(SELECTOR_GET_DEVICE_INFO, &charlie())).0),
+            EvmDataWriter::new()
+                .write_bool(false)
+                .write_u256(U256::from(6 * 32))
+                .write_u256(U256::zero())
+                .write_u256(U256::zero())
+                .write_u256(U256::zero())
+                .write_u256(U256::zero())
+                .write_bytes(b"")
+                .build()
+        );
+    });
+}
+
+#[test]
+fn get_servers() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(
+            output(call(servers_input(SELECTOR_GET_COUNTRY_SERVERS, b"US")).0),
+            servers(&[alice(), bob()])
+        );
+        // the level 3 region of the US
+        assert_eq!(
+            output(call(servers_input(SELECTOR_GET_REGION_SERVERS, b"AMN")).0),
+            servers(&[alice(), bob()])
+        );
+        assert_eq!(
+            output(call(servers_input(SELECTOR_GET_COUNTRY_SERVERS, b"CA")).0),
+            servers(&[])
+        );
+
+        // the country is charged a read per server
+        let (_, no_servers) = call(servers_input(SELECTOR_GET_COUNTRY_SERVERS, b"CA"));
+        let (_, two_servers) = call(servers_input(SELECTOR_GET_COUNTRY_SERVERS, b"US"));
+        assert_eq!(
+            two_servers.gas_used - no_servers.gas_used,
+            RuntimeHelper::<Test>::db_read_gas_cost() * 2
+        );
+    });
+}
+
+#[test]
+fn get_im_online_and_onboard_time() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(
+            output(call(device_input(SELECTOR_GET_IM_ONLINE, &alice())).0),
+            word(1)
+        );
+        assert_eq!(
+            output(call(device_input(SELECTOR_GET_ONBOARD_TIME, &alice())).0),
+            word(1)
+        );
+
+        // never online
+        assert_eq!(
+            output(call(device_input(SELECTOR_GET_IM_ONLINE, &bob())).0),
+            word(0)
+        );
+        assert_eq!(
+            output(call(device_input(SELECTOR_GET_ONBOARD_TIME, &bob())).0),
+            word(0)
+        );
+    });
+}
+
+#[test]
+fn get_deeper_account() {
+    new_test_ext().execute_with(|| {
+        let input = |address: H160| {
+            EvmDataWriter::new_with_selector(SELECTOR_GET_DEEPER_ACCOUNT)
+                .write_address(address)
+                .build()
+        };
+        assert_eq!(
+            output(call(input(alice_address())).0),
+            EvmDataWriter::new().write_h256(account(&alice())).build()
+        );
+        assert_eq!(
+            output(call(input(H160::repeat_byte(2))).0),
+            EvmDataWriter::new().write_h256(H256::zero()).build()
+        );
+    });
+}
+
+#[test]
+fn invalid_input_reverts() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(
+            call(EvmDataWriter::new_with_selector(SELECTOR_GET_IM_ONLINE).build())
+                .0
+                .map(|o| o.output),
+            Err(util::revert("input doesn't match expected length"))
+        );
+        assert_eq!(
+            call(EvmDataWriter::new_with_selector(0x12345678).build())
+                .0
+                .map(|o| o.output),
+            Err(util::revert("unknown selector"))
+        );
+    });
+}
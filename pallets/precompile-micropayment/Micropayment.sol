@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.10;
+
+/// @dev The Micropayment precompile address.
+address constant MICROPAYMENT_ADDRESS = 0x0000000000000000000000000000000000000407;
+
+/// @dev The Micropayment precompile instance.
+Micropayment constant MICROPAYMENT_CONTRACT = Micropayment(MICROPAYMENT_ADDRESS);
+
+/// @title Deeper Chain micropayment channel precompile
+/// @dev Channel ends are Substrate accounts. The caller acts as the Substrate account its
+/// address is mapped to, calls from unmapped addresses revert.
+interface Micropayment {
+    /// @dev Emitted when `client` opened a channel to `server`.
+    event ChannelOpened(
+        bytes32 indexed client,
+        bytes32 indexed server,
+        uint256 balance,
+        uint64 nonce,
+        uint256 opened,
+        uint256 expiration
+    );
+
+    /// @dev Emitted when `client` added `amount` to its channel to `server`.
+    event BalanceAdded(
+        bytes32 indexed client,
+        bytes32 indexed server,
+        uint256 amount
+    );
+
+    /// @dev Emitted when `server` claimed `amount` from the channel of `client`.
+    event ClaimPayment(
+        bytes32 indexed client,
+        bytes32 indexed server,
+        uint256 amount
+    );
+
+    /// @dev Emitted when the channel from `client` to `server` was closed at `endBlock`.
+    event ChannelClosed(
+        bytes32 indexed client,
+        bytes32 indexed server,
+        uint256 endBlock
+    );
+
+    /// @dev Open a channel to the server, locking `lockAmount` DPR for `duration` seconds.
+    /// Selector: 22d43cab
+    function open_channel(
+        bytes32 server,
+        uint256 lockAmount,
+        uint32 duration
+    ) external;
+
+    /// @dev Add DPR to the channel of the caller to the server.
+    /// Selector: c14d33e6
+    function add_balance(bytes32 server, uint256 amount) external;
+
+    /// @dev Close the channel with the counterparty. The server can close it at any time,
+    /// the client only once it expired.
+    /// Selector: 624ca541
+    function close_channel(bytes32 counterparty) external;
+
+    /// @dev Claim the payment of a voucher signed by the client, as the server.
+    /// An expired channel is closed instead.
+    /// Selector: 61cc4563
+    function claim_payment(
+        bytes32 client,
+        uint32 sessionId,
+        uint256 amount,
+        bytes calldata signature
+    ) external;
+
+    /// @dev Channel from the client to the server, `exists` is false if there is none.
+    /// Selector: 59ac1a93
+    function get_channel(bytes32 client, bytes32 server)
+        external
+        view
+        returns (
+            bool exists,
+            uint256 balance,
+            uint64 nonce,
+            uint256 opened,
+            uint256 expiration,
+            bool isAsset,
+            uint256 assetId
+        );
+
+    /// @dev Nonce of the next channel between the client and the server.
+    /// Selector: b4ab7ba0
+    function get_nonce(bytes32 client, bytes32 server)
+        external
+        view
+        returns (uint64);
+
+    /// @dev Last claimed session of the channel, zero if none.
+    /// Selector: 55d3992e
+    function get_session_id(bytes32 client, bytes32 server)
+        external
+        view
+        returns (uint32);
+
+    /// @dev Check the sr25519 signature of the client on a voucher.
+    /// Selector: ca08d494
+    function verify_voucher(
+        bytes32 client,
+        bytes32 server,
+        uint64 nonce,
+        uint32 sessionId,
+        uint256 amount,
+        bytes calldata signature
+    ) external view returns (bool);
+}
@@ -0,0 +1,360 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Micropayment channel precompile: EVM accounts mapped to a Substrate account open, top up,
+//! claim and close bandwidth channels, and contracts inspect channels and verify vouchers.
+//! Channel ends are Substrate accounts, passed as `bytes32`. See `Micropayment.sol`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+use fp_evm::{ExitSucceed, Precompile, PrecompileHandle, PrecompileOutput, PrecompileResult};
+use frame_support::dispatch::{Dispatchable, GetDispatchInfo, PostDispatchInfo};
+use node_primitives::deeper_node::NodeInterface;
+use pallet_evm_precompile_credit::util::{
+    self, EvmDataReader, EvmDataWriter, EvmResult, FunctionModifier, LogsBuilder, RuntimeHelper,
+};
+use pallet_micropayment::{AssetIdOf, BalanceOf, Call as MicropaymentCall};
+use sp_core::{H256, U256};
+use sp_std::vec::Vec;
+
+/// Selectors of the functions of the `Micropayment` interface.
+const SELECTOR_OPEN_CHANNEL: u32 = 0x22d43cab;
+const SELECTOR_ADD_BALANCE: u32 = 0xc14d33e6;
+const SELECTOR_CLOSE_CHANNEL: u32 = 0x624ca541;
+const SELECTOR_CLAIM_PAYMENT: u32 = 0x61cc4563;
+const SELECTOR_GET_CHANNEL: u32 = 0x59ac1a93;
+const SELECTOR_GET_NONCE: u32 = 0xb4ab7ba0;
+const SELECTOR_GET_SESSION_ID: u32 = 0x55d3992e;
+const SELECTOR_VERIFY_VOUCHER: u32 = 0xca08d494;
+
+/// Signatures of the events of the `Micropayment` interface.
+const EVENT_CHANNEL_OPENED: &[u8] =
+    b"ChannelOpened(bytes32,bytes32,uint256,uint64,uint256,uint256)";
+const EVENT_BALANCE_ADDED: &[u8] = b"BalanceAdded(bytes32,bytes32,uint256)";
+const EVENT_CLAIM_PAYMENT: &[u8] = b"ClaimPayment(bytes32,bytes32,uint256)";
+const EVENT_CHANNEL_CLOSED: &[u8] = b"ChannelClosed(bytes32,bytes32,uint256)";
+
+/// Gas cost of verifying a sr25519 voucher signature.
+const VERIFY_VOUCHER_GAS_COST: u64 = 3_000;
+
+pub struct MicropaymentDispatch<Runtime> {
+    _marker: PhantomData<Runtime>,
+}
+
+impl<Runtime> Precompile for MicropaymentDispatch<Runtime>
+where
+    Runtime: pallet_micropayment::Config + pallet_evm::Config,
+    Runtime::RuntimeCall: Dispatchable<PostInfo = PostDispatchInfo> + GetDispatchInfo,
+    <Runtime::RuntimeCall as Dispatchable>::RuntimeOrigin: From<Option<Runtime::AccountId>>,
+    Runtime::RuntimeCall: From<MicropaymentCall<Runtime>>,
+    Runtime::AccountId: From<[u8; 32]> + Into<[u8; 32]>,
+    Runtime::BlockNumber: Into<U256>,
+    BalanceOf<Runtime>: TryFrom<U256> + Into<U256>,
+    AssetIdOf<Runtime>: Into<U256>,
+{
+    fn execute(handle: &mut impl PrecompileHandle) -> PrecompileResult {
+        let selector = util::read_selector(handle.input())?;
+        let output = match selector {
+            SELECTOR_OPEN_CHANNEL => Self::open_channel(handle)?,
+            SELECTOR_ADD_BALANCE => Self::add_balance(handle)?,
+            SELECTOR_CLOSE_CHANNEL => Self::close_channel(handle)?,
+            SELECTOR_CLAIM_PAYMENT => Self::claim_payment(handle)?,
+            SELECTOR_GET_CHANNEL => Self::get_channel(handle)?,
+            SELECTOR_GET_NONCE => Self::get_nonce(handle)?,
+            SELECTOR_GET_SESSION_ID => Self::get_session_id(handle)?,
+            SELECTOR_VERIFY_VOUCHER => Self::verify_voucher(handle)?,
+            _ => return Err(util::revert("unknown selector")),
+        };
+
+        Ok(PrecompileOutput {
+            exit_status: ExitSucceed::Returned,
+            output,
+        })
+    }
+}
+
+impl<Runtime> MicropaymentDispatch<Runtime>
+where
+    Runtime: pallet_micropayment::Config + pallet_evm::Config,
+    Runtime::RuntimeCall: Dispatchable<PostInfo = PostDispatchInfo> + GetDispatchInfo,
+    <Runtime::RuntimeCall as Dispatchable>::RuntimeOrigin: From<Option<Runtime::AccountId>>,
+    Runtime::RuntimeCall: From<MicropaymentCall<Runtime>>,
+    Runtime::AccountId: From<[u8; 32]> + Into<[u8; 32]>,
+    Runtime::BlockNumber: Into<U256>,
+    BalanceOf<Runtime>: TryFrom<U256> + Into<U256>,
+    AssetIdOf<Runtime>: Into<U256>,
+{
+    /// `open_channel(bytes32 server, uint256 lockAmount, uint32 duration)`,
+    /// `duration` is in seconds
+    pub fn open_channel(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let client = Self::caller_account(handle)?;
+        let mut input = EvmDataReader::new_skip_selector(handle.input())?;
+        input.expect_arguments(3)?;
+        let server = Self::read_account(&mut input)?;
+        let lock_amount = Self::read_balance(&mut input)?;
+        let duration = input.read_u32()?;
+
+        RuntimeHelper::<Runtime>::try_dispatch(
+            handle,
+            Some(client.clone()).into(),
+            MicropaymentCall::<Runtime>::open_channel {
+                server: server.clone(),
+                lock_amount,
+                duration,
+            },
+        )?;
+
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+        let chan = pallet_micropayment::Pallet::<Runtime>::channel(&client, &server);
+        LogsBuilder::new(handle.context().address)
+            .log3(
+                util::event_topic(EVENT_CHANNEL_OPENED),
+                Self::account_topic(client),
+                Self::account_topic(server),
+                EvmDataWriter::new()
+                    .write_u256(chan.balance.into())
+                    .write_u256(chan.nonce.into())
+                    .write_u256(chan.opened.into())
+                    .write_u256(chan.expiration.into())
+                    .build(),
+            )
+            .record(handle)?;
+
+        Ok(Vec::new())
+    }
+
+    /// `add_balance(bytes32 server, uint256 amount)`
+    pub fn add_balance(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let client = Self::caller_account(handle)?;
+        let mut input = EvmDataReader::new_skip_selector(handle.input())?;
+        input.expect_arguments(2)?;
+        let server = Self::read_account(&mut input)?;
+        let amount = input.read_u256()?;
+        let balance = BalanceOf::<Runtime>::try_from(amount)
+            .map_err(|_| util::revert("amount out of range"))?;
+
+        RuntimeHelper::<Runtime>::try_dispatch(
+            handle,
+            Some(client.clone()).into(),
+            MicropaymentCall::<Runtime>::add_balance {
+                server: server.clone(),
+                amount: balance,
+            },
+        )?;
+
+        LogsBuilder::new(handle.context().address)
+            .log3(
+                util::event_topic(EVENT_BALANCE_ADDED),
+                Self::account_topic(client),
+                Self::account_topic(server),
+                EvmDataWriter::new().write_u256(amount).build(),
+            )
+            .record(handle)?;
+
+        Ok(Vec::new())
+    }
+
+    /// `close_channel(bytes32 counterparty)`, the server can close the channel at any time,
+    /// the client only once it expired
+    pub fn close_channel(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let caller = Self::caller_account(handle)?;
+        let mut input = EvmDataReader::new_skip_selector(handle.input())?;
+        input.expect_arguments(1)?;
+        let counterparty = Self::read_account(&mut input)?;
+
+        // the pallet looks the caller up as the server first
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+        let (client, server) = if Self::channel_exists(&counterparty, &caller) {
+            (counterparty.clone(), caller.clone())
+        } else {
+            (caller.clone(), counterparty.clone())
+        };
+
+        RuntimeHelper::<Runtime>::try_dispatch(
+            handle,
+            Some(caller).into(),
+            MicropaymentCall::<Runtime>::close_channel {
+                account_id: counterparty,
+            },
+        )?;
+
+        Self::log_channel_closed(handle, client, server)?;
+        Ok(Vec::new())
+    }
+
+    /// `claim_payment(bytes32 client, uint32 sessionId, uint256 amount, bytes signature)`,
+    /// an expired channel is closed instead
+    pub fn claim_payment(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let server = Self::caller_account(handle)?;
+        let mut input = EvmDataReader::new_skip_selector(handle.input())?;
+        input.expect_arguments(4)?;
+        let client = Self::read_account(&mut input)?;
+        let session_id = input.read_u32()?;
+        let amount = input.read_u256()?;
+        let balance = BalanceOf::<Runtime>::try_from(amount)
+            .map_err(|_| util::revert("amount out of range"))?;
+        let signature = input.read_bytes()?;
+
+        RuntimeHelper::<Runtime>::try_dispatch(
+            handle,
+            Some(server.clone()).into(),
+            MicropaymentCall::<Runtime>::claim_payment {
+                client: client.clone(),
+                session_id,
+                amount: balance,
+                signature,
+            },
+        )?;
+
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+        if !Self::channel_exists(&client, &server) {
+            return Self::log_channel_closed(handle, client, server).map(|_| Vec::new());
+        }
+        LogsBuilder::new(handle.context().address)
+            .log3(
+                util::event_topic(EVENT_CLAIM_PAYMENT),
+                Self::account_topic(client),
+                Self::account_topic(server),
+                EvmDataWriter::new().write_u256(amount).build(),
+            )
+            .record(handle)?;
+
+        Ok(Vec::new())
+    }
+
+    /// `get_channel(bytes32 client, bytes32 server) returns (bool exists, uint256 balance,
+    /// uint64 nonce, uint256 opened, uint256 expiration, bool isAsset, uint256 assetId)`
+    pub fn get_channel(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let (client, server) = Self::read_channel_ends(handle)?;
+        let exists = Self::channel_exists(&client, &server);
+        let chan = pallet_micropayment::Pallet::<Runtime>::channel(&client, &server);
+
+        Ok(EvmDataWriter::new()
+            .write_bool(exists)
+            .write_u256(chan.balance.into())
+            .write_u256(chan.nonce.into())
+            .write_u256(chan.opened.into())
+            .write_u256(chan.expiration.into())
+            .write_bool(chan.asset.is_some())
+            .write_u256(chan.asset.map_or_else(U256::zero, Into::into))
+            .build())
+    }
+
+    /// `get_nonce(bytes32 client, bytes32 server) returns (uint64)`
+    pub fn get_nonce(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let (client, server) = Self::read_channel_ends(handle)?;
+        let nonce = pallet_micropayment::Pallet::<Runtime>::nonce((client, server));
+
+        Ok(EvmDataWriter::new().write_u256(nonce.into()).build())
+    }
+
+    /// `get_session_id(bytes32 client, bytes32 server) returns (uint32)`,
+    /// the last claimed session, zero if none
+    pub fn get_session_id(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let (client, server) = Self::read_channel_ends(handle)?;
+        let session_id =
+            pallet_micropayment::Pallet::<Runtime>::session_id((client, server)).unwrap_or(0);
+
+        Ok(EvmDataWriter::new().write_u256(session_id.into()).build())
+    }
+
+    /// `verify_voucher(bytes32 client, bytes32 server, uint64 nonce, uint32 sessionId,
+    /// uint256 amount, bytes signature) returns (bool)`
+    pub fn verify_voucher(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        util::check_modifier(handle, FunctionModifier::View)?;
+        handle.record_cost(VERIFY_VOUCHER_GAS_COST)?;
+        let mut input = EvmDataReader::new_skip_selector(handle.input())?;
+        input.expect_arguments(6)?;
+        let client = Self::read_account(&mut input)?;
+        let server = Self::read_account(&mut input)?;
+        let nonce = input.read_u64()?;
+        let session_id = input.read_u32()?;
+        let amount = Self::read_balance(&mut input)?;
+        let signature = input.read_bytes()?;
+
+        let verified = pallet_micropayment::Pallet::<Runtime>::verify_signature(
+            &client, &server, nonce, session_id, amount, &signature,
+        )
+        .is_ok();
+
+        Ok(EvmDataWriter::new().write_bool(verified).build())
+    }
+
+    /// the Substrate account the caller is mapped to
+    fn caller_account(handle: &mut impl PrecompileHandle) -> EvmResult<Runtime::AccountId> {
+        util::check_modifier(handle, FunctionModifier::NonPayable)?;
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+
+        let caller = handle.context().caller;
+        <Runtime as pallet_micropayment::Config>::NodeInterface::get_accounts_evm_deeper(&caller)
+            .ok_or_else(|| util::revert("caller is not mapped to a substrate account"))
+    }
+
+    /// check a view call on a channel, record the cost of a read and read its ends
+    fn read_channel_ends(
+        handle: &mut impl PrecompileHandle,
+    ) -> EvmResult<(Runtime::AccountId, Runtime::AccountId)> {
+        util::check_modifier(handle, FunctionModifier::View)?;
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+
+        let mut input = EvmDataReader::new_skip_selector(handle.input())?;
+        input.expect_arguments(2)?;
+        Ok((
+            Self::read_account(&mut input)?,
+            Self::read_account(&mut input)?,
+        ))
+    }
+
+    fn read_account(input: &mut EvmDataReader) -> EvmResult<Runtime::AccountId> {
+        Ok(input.read_h256()?.0.into())
+    }
+
+    fn read_balance(input: &mut EvmDataReader) -> EvmResult<BalanceOf<Runtime>> {
+        BalanceOf::<Runtime>::try_from(input.read_u256()?)
+            .map_err(|_| util::revert("amount out of range"))
+    }
+
+    /// channels are stored with a default value, an opened channel always expires after block 0
+    fn channel_exists(client: &Runtime::AccountId, server: &Runtime::AccountId) -> bool {
+        let chan = pallet_micropayment::Pallet::<Runtime>::channel(client, server);
+        chan.expiration != Default::default()
+    }
+
+    fn log_channel_closed(
+        handle: &mut impl PrecompileHandle,
+        client: Runtime::AccountId,
+        server: Runtime::AccountId,
+    ) -> EvmResult {
+        let end_block = frame_system::Pallet::<Runtime>::block_number();
+        LogsBuilder::new(handle.context().address)
+            .log3(
+                util::event_topic(EVENT_CHANNEL_CLOSED),
+                Self::account_topic(client),
+                Self::account_topic(server),
+                EvmDataWriter::new().write_u256(end_block.into()).build(),
+            )
+            .record(handle)
+    }
+
+    fn account_topic(account: Runtime::AccountId) -> H256 {
+        H256(account.into())
+    }
+}
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
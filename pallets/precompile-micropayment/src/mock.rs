@@ -0,0 +1,231 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::MicropaymentDispatch;
+use fp_evm::{Precompile, PrecompileResult};
+use frame_support::{
+    parameter_types,
+    traits::{AsEnsureOriginWithArg, ConstU128, ConstU32, ConstU64, GenesisBuild},
+    weights::Weight,
+};
+use node_primitives::deeper_node::NodeInterface;
+use pallet_evm_precompile_credit::testing::MockHandle;
+use pallet_micropayment::testing_utils::{alice, bob, charlie};
+use sp_core::{crypto::AccountId32, H160, H256, U256};
+use sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, IdentityLookup},
+    Percent,
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+pub type AccountId = AccountId32;
+pub type Balance = u128;
+
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+        Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+        Timestamp: pallet_timestamp::{Pallet, Call, Storage, Inherent},
+        EVM: pallet_evm::{Pallet, Call, Storage, Event<T>},
+        Assets: pallet_assets::{Pallet, Call, Storage, Event<T>},
+        Micropayment: pallet_micropayment::{Pallet, Call, Storage, Event<T>},
+    }
+);
+
+impl frame_system::Config for Test {
+    type BaseCallFilter = frame_support::traits::Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = frame_support::weights::constants::RocksDbWeight;
+    type RuntimeOrigin = RuntimeOrigin;
+    type RuntimeCall = RuntimeCall;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type RuntimeEvent = RuntimeEvent;
+    type BlockHashCount = ConstU64<250>;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ();
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+    type MaxLocks = ();
+    type MaxReserves = ();
+    type ReserveIdentifier = [u8; 8];
+    type Balance = Balance;
+    type RuntimeEvent = RuntimeEvent;
+    type DustRemoval = ();
+    type ExistentialDeposit = ConstU128<1>;
+    type AccountStore = System;
+    type WeightInfo = ();
+}
+
+impl pallet_timestamp::Config for Test {
+    type Moment = u64;
+    type OnTimestampSet = ();
+    type MinimumPeriod = ConstU64<5>;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub BlockGasLimit: U256 = U256::from(u64::MAX);
+    pub WeightPerGas: Weight = Weight::from_ref_time(20_000);
+}
+
+impl pallet_evm::Config for Test {
+    type FeeCalculator = ();
+    type GasWeightMapping = pallet_evm::FixedGasWeightMapping<Self>;
+    type WeightPerGas = WeightPerGas;
+    type BlockHashMapping = pallet_evm::SubstrateBlockHashMapping<Self>;
+    type AddressMapping = pallet_evm::HashedAddressMapping<BlakeTwo256>;
+    type Currency = Balances;
+    type RuntimeEvent = RuntimeEvent;
+    type Runner = pallet_evm::runner::stack::Runner<Self>;
+    type PrecompilesType = ();
+    type PrecompilesValue = ();
+    type ChainId = ();
+    type BlockGasLimit = BlockGasLimit;
+    type OnChargeTransaction = ();
+    type FindAuthor = ();
+}
+
+impl pallet_assets::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Balance = Balance;
+    type AssetId = u32;
+    type AssetIdParameter = u32;
+    type Currency = Balances;
+    type CreateOrigin = AsEnsureOriginWithArg<frame_system::EnsureSigned<AccountId>>;
+    type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+    type AssetDeposit = ConstU128<1>;
+    type AssetAccountDeposit = ConstU128<1>;
+    type MetadataDepositBase = ConstU128<1>;
+    type MetadataDepositPerByte = ConstU128<1>;
+    type ApprovalDeposit = ConstU128<1>;
+    type StringLimit = ConstU32<50>;
+    type Freezer = ();
+    type WeightInfo = ();
+    type Extra = ();
+    type RemoveItemsLimit = ConstU32<5>;
+}
+
+/// EVM address of the account, only the ones of Alice, Bob and Charlie are paired.
+pub fn evm_address(account: &AccountId) -> H160 {
+    let bytes: &[u8; 32] = account.as_ref();
+    H160::from_slice(&bytes[..20])
+}
+
+/// The EVM address pairing of the deeper-node pallet.
+pub struct MockNodeInterface;
+
+impl NodeInterface<AccountId, u64> for MockNodeInterface {
+    fn get_onboard_time(_account_id: &AccountId) -> Option<u64> {
+        None
+    }
+
+    fn im_ever_online(_account_id: &AccountId) -> bool {
+        true
+    }
+
+    fn get_eras_offline(_account_id: &AccountId) -> u32 {
+        0
+    }
+
+    fn get_accounts_deeper_evm(account_id: &AccountId) -> Option<H160> {
+        Some(evm_address(account_id))
+            .filter(|address| Self::get_accounts_evm_deeper(address).is_some())
+    }
+
+    fn get_accounts_evm_deeper(address: &H160) -> Option<AccountId> {
+        [alice(), bob(), charlie()]
+            .into_iter()
+            .find(|account| evm_address(account) == *address)
+    }
+
+    fn attestation_satisfied(_account_id: &AccountId) -> bool {
+        true
+    }
+}
+
+parameter_types! {
+    pub const SecsPerBlock: u32 = 5u32;
+    pub const DataPerDPR: u64 = 1024 * 1024 * 1024 * 1024;
+    pub const MicropaymentBurn: Percent = Percent::from_percent(10);
+    pub const MaxPendingLocks: u32 = 16;
+    pub const MaxBatchClaims: u32 = 10;
+    pub const ChannelDisputePeriod: u64 = 10;
+}
+
+impl pallet_micropayment::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Currency = Balances;
+    type CreditInterface = ();
+    type SecsPerBlock = SecsPerBlock;
+    type DataPerDPR = DataPerDPR;
+    type WeightInfo = ();
+    type Assets = Assets;
+    type NodeInterface = MockNodeInterface;
+    type MicropaymentBurn = MicropaymentBurn;
+    type MaxPendingLocks = MaxPendingLocks;
+    type ChannelDisputePeriod = ChannelDisputePeriod;
+    type MaxBatchClaims = MaxBatchClaims;
+    type Slash = ();
+}
+
+/// Address of the precompile in the tests.
+pub fn precompile_address() -> H160 {
+    H160::from_low_u64_be(0x0c00)
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let mut storage = frame_system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![(alice(), 500), (bob(), 500), (charlie(), 500)],
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+
+    let mut ext = sp_io::TestExternalities::new(storage);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
+
+/// Call the precompile from the EVM address of `caller`, returns the result and the handle
+/// holding the used gas and the logs.
+pub fn call(caller: H160, input: Vec<u8>) -> (PrecompileResult, MockHandle) {
+    let mut handle = MockHandle::new(precompile_address(), caller, input);
+    let result = MicropaymentDispatch::<Test>::execute(&mut handle);
+    (result, handle)
+}
@@ -0,0 +1,334 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use crate::mock::*;
+use pallet_evm_precompile_credit::testing::{
+    assert_dispatch_reverted, assert_selectors, event_log, output, word, MockHandle,
+};
+use pallet_micropayment::testing_utils::{alice, bob, charlie, dave, sign_with_seed};
+
+fn account(account: &AccountId) -> H256 {
+    H256(account.clone().into())
+}
+
+/// Alice signs the voucher of `amount` for the session of her channel to Bob.
+fn voucher(nonce: u64, session_id: u32, amount: Balance) -> Vec<u8> {
+    let msg = Micropayment::construct_byte_array_and_hash(&bob(), nonce, session_id, amount);
+    sign_with_seed("Alice", &msg)
+}
+
+fn open_channel_input(server: &AccountId, lock_amount: u128, duration: u32) -> Vec<u8> {
+    EvmDataWriter::new_with_selector(SELECTOR_OPEN_CHANNEL)
+        .write_h256(account(server))
+        .write_u256(lock_amount.into())
+        .write_u256(duration.into())
+        .build()
+}
+
+fn close_channel_input(counterparty: &AccountId) -> Vec<u8> {
+    EvmDataWriter::new_with_selector(SELECTOR_CLOSE_CHANNEL)
+        .write_h256(account(counterparty))
+        .build()
+}
+
+fn claim_payment_input(
+    client: &AccountId,
+    session_id: u32,
+    amount: u128,
+    signature: &[u8],
+) -> Vec<u8> {
+    // the signature is stored after the 4 head words
+    EvmDataWriter::new_with_selector(SELECTOR_CLAIM_PAYMENT)
+        .write_h256(account(client))
+        .write_u256(session_id.into())
+        .write_u256(amount.into())
+        .write_u256(128u32.into())
+        .write_bytes(signature)
+        .build()
+}
+
+fn verify_voucher_input(session_id: u32, amount: u128, signature: &[u8]) -> Vec<u8> {
+    // the signature is stored after the 6 head words
+    EvmDataWriter::new_with_selector(SELECTOR_VERIFY_VOUCHER)
+        .write_h256(account(&alice()))
+        .write_h256(account(&bob()))
+        .write_u256(0u32.into())
+        .write_u256(session_id.into())
+        .write_u256(amount.into())
+        .write_u256(192u32.into())
+        .write_bytes(signature)
+        .build()
+}
+
+fn view_input(selector: u32) -> Vec<u8> {
+    EvmDataWriter::new_with_selector(selector)
+        .write_h256(account(&alice()))
+        .write_h256(account(&bob()))
+        .build()
+}
+
+#[test]
+fn selectors_match_signatures() {
+    let selectors = [
+        (
+            SELECTOR_OPEN_CHANNEL,
+            "open_channel(bytes32,uint256,uint32)",
+        ),
+        (SELECTOR_ADD_BALANCE, "add_balance(bytes32,uint256)"),
+        (SELECTOR_CLOSE_CHANNEL, "close_channel(bytes32)"),
+        (
+            SELECTOR_CLAIM_PAYMENT,
+            "claim_payment(bytes32,uint32,uint256,bytes)",
+        ),
+        (SELECTOR_GET_CHANNEL, "get_channel(bytes32,bytes32)"),
+        (SELECTOR_GET_NONCE, "get_nonce(bytes32,bytes32)"),
+        (SELECTOR_GET_SESSION_ID, "get_session_id(bytes32,bytes32)"),
+        (
+            SELECTOR_VERIFY_VOUCHER,
+            "verify_voucher(bytes32,bytes32,uint64,uint32,uint256,bytes)",
+        ),
+    ];
+    assert_selectors(&selectors);
+}
+
+#[test]
+fn read_voucher_signature() {
+    let signature = [7u8; 64];
+    // selector, client, offset of the signature, then its length and content
+    let mut input = vec![0u8; 4];
+    input.extend_from_slice(&[1u8; 32]);
+    input.extend_from_slice(&EvmDataWriter::new().write_u256(64u32.into()).build());
+    input.extend_from_slice(&EvmDataWriter::new().write_bytes(&signature).build());
+
+    let mut reader = EvmDataReader::new_skip_selector(&input).unwrap();
+    assert_eq!(reader.read_h256().unwrap(), H256([1u8; 32]));
+    assert_eq!(reader.read_bytes().unwrap(), signature.to_vec());
+
+    // the length points past the end of the input
+    input.truncate(input.len() - 32);
+    let mut reader = EvmDataReader::new_skip_selector(&input).unwrap();
+    reader.read_h256().unwrap();
+    assert!(reader.read_bytes().is_err());
+}
+
+#[test]
+fn open_add_balance_and_close_channel() {
+    new_test_ext().execute_with(|| {
+        // 100 seconds are 20 blocks
+        let (result, handle) = call(evm_address(&alice()), open_channel_input(&bob(), 100, 100));
+        assert_eq!(output(result), Vec::<u8>::new());
+        assert_eq!(
+            handle.logs,
+            vec![event_log(
+                precompile_address(),
+                EVENT_CHANNEL_OPENED,
+                &[account(&alice()), account(&bob())],
+                [word(100), word(0), word(1), word(21)].concat()
+            )]
+        );
+        assert!(handle.gas_used > 0);
+        assert_eq!(Balances::free_balance(alice()), 400);
+
+        let input = EvmDataWriter::new_with_selector(SELECTOR_ADD_BALANCE)
+            .write_h256(account(&bob()))
+            .write_u256(50u32.into())
+            .build();
+        let (result, handle) = call(evm_address(&alice()), input);
+        assert_eq!(output(result), Vec::<u8>::new());
+        assert_eq!(
+            handle.logs,
+            vec![event_log(
+                precompile_address(),
+                EVENT_BALANCE_ADDED,
+                &[account(&alice()), account(&bob())],
+                word(50)
+            )]
+        );
+
+        assert_eq!(
+            output(call(evm_address(&charlie()), view_input(SELECTOR_GET_CHANNEL)).0),
+            [
+                EvmDataWriter::new().write_bool(true).build(),
+                word(150),
+                word(0),
+                word(1),
+                word(21),
+                EvmDataWriter::new().write_bool(false).build(),
+                word(0),
+            ]
+            .concat()
+        );
+
+        // the server closes the channel at any time, the client is refunded minus the burn
+        let (result, handle) = call(evm_address(&bob()), close_channel_input(&alice()));
+        assert_eq!(output(result), Vec::<u8>::new());
+        assert_eq!(
+            handle.logs,
+            vec![event_log(
+                precompile_address(),
+                EVENT_CHANNEL_CLOSED,
+                &[account(&alice()), account(&bob())],
+                word(1)
+            )]
+        );
+        assert_eq!(Balances::free_balance(alice()), 485);
+        assert_eq!(
+            output(call(evm_address(&charlie()), view_input(SELECTOR_GET_NONCE)).0),
+            word(1)
+        );
+        assert_eq!(
+            output(call(evm_address(&charlie()), view_input(SELECTOR_GET_CHANNEL)).0)[..32],
+            EvmDataWriter::new().write_bool(false).build()[..]
+        );
+    });
+}
+
+#[test]
+fn client_cannot_close_an_unexpired_channel() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(
+            output(call(evm_address(&alice()), open_channel_input(&bob(), 100, 100)).0),
+            Vec::<u8>::new()
+        );
+
+        let (result, handle) = call(evm_address(&alice()), close_channel_input(&bob()));
+        assert_dispatch_reverted(result);
+        assert!(handle.logs.is_empty());
+        assert_eq!(Balances::free_balance(alice()), 400);
+
+        // once expired
+        System::set_block_number(22);
+        let (result, handle) = call(evm_address(&alice()), close_channel_input(&bob()));
+        assert_eq!(output(result), Vec::<u8>::new());
+        assert_eq!(
+            handle.logs,
+            vec![event_log(
+                precompile_address(),
+                EVENT_CHANNEL_CLOSED,
+                &[account(&alice()), account(&bob())],
+                word(22)
+            )]
+        );
+        assert_eq!(Balances::free_balance(alice()), 490);
+    });
+}
+
+#[test]
+fn claim_payment() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(
+            output(call(evm_address(&alice()), open_channel_input(&bob(), 100, 100)).0),
+            Vec::<u8>::new()
+        );
+        assert_eq!(
+            output(call(evm_address(&charlie()), view_input(SELECTOR_GET_SESSION_ID)).0),
+            word(0)
+        );
+
+        let input = claim_payment_input(&alice(), 1, 30, &voucher(0, 1, 30));
+        let (result, handle) = call(evm_address(&bob()), input);
+        assert_eq!(output(result), Vec::<u8>::new());
+        assert_eq!(
+            handle.logs,
+            vec![event_log(
+                precompile_address(),
+                EVENT_CLAIM_PAYMENT,
+                &[account(&alice()), account(&bob())],
+                word(30)
+            )]
+        );
+        assert_eq!(Balances::free_balance(bob()), 527);
+        assert_eq!(
+            output(call(evm_address(&charlie()), view_input(SELECTOR_GET_SESSION_ID)).0),
+            word(1)
+        );
+
+        // a replayed session
+        let input = claim_payment_input(&alice(), 1, 30, &voucher(0, 1, 30));
+        let (result, handle) = call(evm_address(&bob()), input);
+        assert_dispatch_reverted(result);
+        assert!(handle.logs.is_empty());
+        assert_eq!(Balances::free_balance(bob()), 527);
+
+        // an expired channel is closed instead, the client is refunded minus the burn
+        System::set_block_number(22);
+        let input = claim_payment_input(&alice(), 2, 10, &voucher(0, 2, 10));
+        let (result, handle) = call(evm_address(&bob()), input);
+        assert_eq!(output(result), Vec::<u8>::new());
+        assert_eq!(
+            handle.logs,
+            vec![event_log(
+                precompile_address(),
+                EVENT_CHANNEL_CLOSED,
+                &[account(&alice()), account(&bob())],
+                word(22)
+            )]
+        );
+        assert_eq!(Balances::free_balance(alice()), 463);
+        assert_eq!(Balances::free_balance(bob()), 527);
+    });
+}
+
+#[test]
+fn verify_voucher() {
+    new_test_ext().execute_with(|| {
+        let signature = voucher(0, 1, 100);
+        let verified = |input| {
+            let mut handle =
+                MockHandle::new(precompile_address(), evm_address(&dave()), input).with_static();
+            output(MicropaymentDispatch::<Test>::execute(&mut handle))
+        };
+
+        assert_eq!(
+            verified(verify_voucher_input(1, 100, &signature)),
+            EvmDataWriter::new().write_bool(true).build()
+        );
+        assert_eq!(
+            verified(verify_voucher_input(1, 99, &signature)),
+            EvmDataWriter::new().write_bool(false).build()
+        );
+        assert_eq!(
+            verified(verify_voucher_input(2, 100, &signature)),
+            EvmDataWriter::new().write_bool(false).build()
+        );
+    });
+}
+
+#[test]
+fn caller_must_be_mapped_and_not_static() {
+    new_test_ext().execute_with(|| {
+        let (result, handle) = call(evm_address(&dave()), open_channel_input(&bob(), 100, 100));
+        assert_eq!(
+            result.map(|o| o.output),
+            Err(util::revert("caller is not mapped to a substrate account"))
+        );
+        assert!(handle.logs.is_empty());
+
+        let mut handle = MockHandle::new(
+            precompile_address(),
+            evm_address(&alice()),
+            open_channel_input(&bob(), 100, 100),
+        )
+        .with_static();
+        assert_eq!(
+            MicropaymentDispatch::<Test>::execute(&mut handle).map(|o| o.output),
+            Err(util::revert(
+                "can't call non-static function in static context"
+            ))
+        );
+        assert_eq!(Balances::free_balance(alice()), 500);
+    });
+}
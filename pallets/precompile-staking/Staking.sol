@@ -0,0 +1,59 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.10;
+
+/// @dev The Staking precompile address.
+address constant STAKING_ADDRESS = 0x0000000000000000000000000000000000000409;
+
+/// @dev The Staking precompile instance.
+Staking constant STAKING_CONTRACT = Staking(STAKING_ADDRESS);
+
+/// @title Deeper Chain staking delegation precompile
+/// @dev Addresses act as, and are queried as, the Substrate account they map to through the
+/// EVM address mapping. Validators are Substrate accounts.
+interface Staking {
+    /// @dev Emitted when `delegator` delegated its credit to `validators`.
+    event Delegated(address indexed delegator, bytes32[] validators);
+
+    /// @dev Emitted when `delegator` undelegated its credit.
+    event Undelegated(address indexed delegator);
+
+    /// @dev Delegate the credit of the caller to the validators, replacing its delegation.
+    /// Selector: 0cc03c0f
+    function delegate(bytes32[] calldata validators) external;
+
+    /// @dev Undelegate the credit of the caller.
+    /// Selector: 92ab89bb
+    function undelegate() external;
+
+    /// @dev Rewards of the delegator, zero if it has none.
+    /// Selector: dbf2f3c2
+    function get_reward(address delegator)
+        external
+        view
+        returns (
+            uint256 totalRefereeReward,
+            uint256 receivedRefereeReward,
+            uint256 refereeReward,
+            uint256 receivedPocrReward,
+            uint256 pocReward
+        );
+
+    /// @dev Delegation of the delegator, `unrewardedSince` is only set if `hasUnrewarded`.
+    /// Selector: 3a5f56d0
+    function get_delegator_data(address delegator)
+        external
+        view
+        returns (
+            bool delegating,
+            bytes32[] memory validators,
+            bool hasUnrewarded,
+            uint32 unrewardedSince
+        );
+
+    /// @dev Delegators of the validator, sorted.
+    /// Selector: b6cc3e75
+    function get_delegators(bytes32 validator)
+        external
+        view
+        returns (bytes32[] memory);
+}
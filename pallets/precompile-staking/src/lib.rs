@@ -0,0 +1,230 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Staking delegation precompile: the caller delegates its credit to validators, or undelegates
+//! it, as the Substrate account its address is mapped to by the deeper-node pallet, and
+//! contracts query rewards and delegations. See `Staking.sol`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+use fp_evm::{ExitSucceed, Precompile, PrecompileHandle, PrecompileOutput, PrecompileResult};
+use frame_support::dispatch::{Dispatchable, GetDispatchInfo, PostDispatchInfo};
+use node_primitives::deeper_node::NodeInterface;
+use pallet_evm_precompile_credit::util::{
+    self, EvmDataReader, EvmDataWriter, EvmResult, FunctionModifier, LogsBuilder, RuntimeHelper,
+};
+use pallet_staking::{BalanceOf, Call as StakingCall};
+use sp_core::{H160, H256, U256};
+use sp_std::vec::Vec;
+
+/// Selectors of the functions of the `Staking` interface.
+const SELECTOR_DELEGATE: u32 = 0x0cc03c0f;
+const SELECTOR_UNDELEGATE: u32 = 0x92ab89bb;
+const SELECTOR_GET_REWARD: u32 = 0xdbf2f3c2;
+const SELECTOR_GET_DELEGATOR_DATA: u32 = 0x3a5f56d0;
+const SELECTOR_GET_DELEGATORS: u32 = 0xb6cc3e75;
+
+/// Signatures of the events of the `Staking` interface.
+const EVENT_DELEGATED: &[u8] = b"Delegated(address,bytes32[])";
+const EVENT_UNDELEGATED: &[u8] = b"Undelegated(address)";
+
+type RuntimeCallOf<Runtime> = <Runtime as frame_system::Config>::RuntimeCall;
+
+pub struct StakingDispatch<Runtime> {
+    _marker: PhantomData<Runtime>,
+}
+
+impl<Runtime> Precompile for StakingDispatch<Runtime>
+where
+    Runtime: pallet_staking::Config + pallet_evm::Config,
+    RuntimeCallOf<Runtime>: Dispatchable<PostInfo = PostDispatchInfo> + GetDispatchInfo,
+    <RuntimeCallOf<Runtime> as Dispatchable>::RuntimeOrigin: From<Option<Runtime::AccountId>>,
+    RuntimeCallOf<Runtime>: From<StakingCall<Runtime>>,
+    Runtime::AccountId: From<[u8; 32]> + Into<[u8; 32]>,
+    BalanceOf<Runtime>: Into<U256>,
+{
+    fn execute(handle: &mut impl PrecompileHandle) -> PrecompileResult {
+        let selector = util::read_selector(handle.input())?;
+        let output = match selector {
+            SELECTOR_DELEGATE => Self::delegate(handle)?,
+            SELECTOR_UNDELEGATE => Self::undelegate(handle)?,
+            SELECTOR_GET_REWARD => Self::get_reward(handle)?,
+            SELECTOR_GET_DELEGATOR_DATA => Self::get_delegator_data(handle)?,
+            SELECTOR_GET_DELEGATORS => Self::get_delegators(handle)?,
+            _ => return Err(util::revert("unknown selector")),
+        };
+
+        Ok(PrecompileOutput {
+            exit_status: ExitSucceed::Returned,
+            output,
+        })
+    }
+}
+
+impl<Runtime> StakingDispatch<Runtime>
+where
+    Runtime: pallet_staking::Config + pallet_evm::Config,
+    RuntimeCallOf<Runtime>: Dispatchable<PostInfo = PostDispatchInfo> + GetDispatchInfo,
+    <RuntimeCallOf<Runtime> as Dispatchable>::RuntimeOrigin: From<Option<Runtime::AccountId>>,
+    RuntimeCallOf<Runtime>: From<StakingCall<Runtime>>,
+    Runtime::AccountId: From<[u8; 32]> + Into<[u8; 32]>,
+    BalanceOf<Runtime>: Into<U256>,
+{
+    /// `delegate(bytes32[] validators)`
+    pub fn delegate(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let account = Self::caller_account(handle)?;
+        let mut input = EvmDataReader::new_skip_selector(handle.input())?;
+        input.expect_arguments(1)?;
+        let validators = input.read_h256_array()?;
+
+        let caller = handle.context().caller;
+        RuntimeHelper::<Runtime>::try_dispatch(
+            handle,
+            Some(account).into(),
+            StakingCall::<Runtime>::delegate {
+                validators: validators
+                    .iter()
+                    .map(|validator| validator.0.into())
+                    .collect(),
+            },
+        )?;
+
+        LogsBuilder::new(handle.context().address)
+            .log2(
+                util::event_topic(EVENT_DELEGATED),
+                caller,
+                EvmDataWriter::new()
+                    .write_u256(U256::from(32))
+                    .write_h256_array(&validators)
+                    .build(),
+            )
+            .record(handle)?;
+
+        Ok(Vec::new())
+    }
+
+    /// `undelegate()`
+    pub fn undelegate(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let account = Self::caller_account(handle)?;
+
+        let caller = handle.context().caller;
+        RuntimeHelper::<Runtime>::try_dispatch(
+            handle,
+            Some(account).into(),
+            StakingCall::<Runtime>::undelegate {},
+        )?;
+
+        LogsBuilder::new(handle.context().address)
+            .log2(util::event_topic(EVENT_UNDELEGATED), caller, Vec::new())
+            .record(handle)?;
+
+        Ok(Vec::new())
+    }
+
+    /// `get_reward(address delegator) returns (uint256 totalRefereeReward,
+    /// uint256 receivedRefereeReward, uint256 refereeReward, uint256 receivedPocrReward,
+    /// uint256 pocReward)`, zero if the delegator is not mapped or has no reward data
+    pub fn get_reward(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let account = Self::read_view_address(handle)?;
+        let reward = account
+            .and_then(pallet_staking::Pallet::<Runtime>::reward)
+            .unwrap_or_default();
+
+        Ok(EvmDataWriter::new()
+            .write_u256(reward.total_referee_reward.into())
+            .write_u256(reward.received_referee_reward.into())
+            .write_u256(reward.referee_reward.into())
+            .write_u256(reward.received_pocr_reward.into())
+            .write_u256(reward.poc_reward.into())
+            .build())
+    }
+
+    /// `get_delegator_data(address delegator) returns (bool delegating,
+    /// bytes32[] validators, bool hasUnrewarded, uint32 unrewardedSince)`, not delegating if the
+    /// delegator is not mapped
+    pub fn get_delegator_data(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        let account = Self::read_view_address(handle)?;
+        let data = account
+            .map(pallet_staking::Pallet::<Runtime>::delegators)
+            .unwrap_or_default();
+        let validators: Vec<H256> = data
+            .delegated_validators
+            .into_iter()
+            .map(|validator| H256(validator.into()))
+            .collect();
+
+        Ok(EvmDataWriter::new()
+            .write_bool(data.delegating)
+            // offset of the validators, after the 4 words of the head
+            .write_u256(U256::from(4 * 32))
+            .write_bool(data.unrewarded_since.is_some())
+            .write_u256(data.unrewarded_since.unwrap_or(0).into())
+            .write_h256_array(&validators)
+            .build())
+    }
+
+    /// `get_delegators(bytes32 validator) returns (bytes32[])`, sorted
+    pub fn get_delegators(handle: &mut impl PrecompileHandle) -> EvmResult<Vec<u8>> {
+        util::check_modifier(handle, FunctionModifier::View)?;
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+        let mut input = EvmDataReader::new_skip_selector(handle.input())?;
+        input.expect_arguments(1)?;
+        let validator: Runtime::AccountId = input.read_h256()?.0.into();
+
+        let delegators: Vec<H256> =
+            pallet_staking::Pallet::<Runtime>::candidate_validators(&validator)
+                .delegators
+                .into_iter()
+                .map(|delegator| H256(delegator.into()))
+                .collect();
+
+        Ok(EvmDataWriter::new()
+            .write_u256(U256::from(32))
+            .write_h256_array(&delegators)
+            .build())
+    }
+
+    /// the Substrate account the caller is mapped to
+    fn caller_account(handle: &mut impl PrecompileHandle) -> EvmResult<Runtime::AccountId> {
+        util::check_modifier(handle, FunctionModifier::NonPayable)?;
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+
+        let caller = handle.context().caller;
+        Self::mapped_account(&caller)
+            .ok_or_else(|| util::revert("caller is not mapped to a substrate account"))
+    }
+
+    /// check a view call, record the cost of the mapping and data reads and read the address
+    /// argument, None if it is not mapped
+    fn read_view_address(
+        handle: &mut impl PrecompileHandle,
+    ) -> EvmResult<Option<Runtime::AccountId>> {
+        util::check_modifier(handle, FunctionModifier::View)?;
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost() * 2)?;
+        let mut input = EvmDataReader::new_skip_selector(handle.input())?;
+        input.expect_arguments(1)?;
+        Ok(Self::mapped_account(&input.read_address()?))
+    }
+
+    fn mapped_account(address: &H160) -> Option<Runtime::AccountId> {
+        <Runtime as pallet_staking::Config>::NodeInterface::get_accounts_evm_deeper(address)
+    }
+}
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
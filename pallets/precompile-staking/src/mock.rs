@@ -0,0 +1,385 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::StakingDispatch;
+use fp_evm::{Precompile, PrecompileResult};
+use frame_support::{
+    parameter_types,
+    traits::{AsEnsureOriginWithArg, ConstU128, ConstU32, ConstU64, GenesisBuild},
+    weights::Weight,
+    PalletId,
+};
+use node_primitives::{
+    credit::{CreditData, CreditLevel, EraIndex},
+    deeper_node::NodeInterface,
+};
+use pallet_evm_precompile_credit::testing::MockHandle;
+use pallet_staking::StakerStatus;
+use sp_core::{crypto::AccountId32, H160, H256, U256};
+use sp_runtime::{
+    testing::{Header, TestXt, UintAuthorityId},
+    traits::{BlakeTwo256, Convert, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+pub type AccountId = AccountId32;
+pub type Balance = u128;
+
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+        Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+        Timestamp: pallet_timestamp::{Pallet, Call, Storage, Inherent},
+        EVM: pallet_evm::{Pallet, Call, Storage, Event<T>},
+        Uniques: pallet_uniques::{Pallet, Call, Storage, Event<T>},
+        Credit: pallet_credit::{Pallet, Call, Storage, Event<T>},
+        Staking: pallet_staking::{Pallet, Call, Config<T>, Storage, Event<T>},
+        Session: pallet_session::{Pallet, Call, Storage, Event},
+        Historical: pallet_session::historical::{Pallet, Storage},
+    }
+);
+
+impl frame_system::Config for Test {
+    type BaseCallFilter = frame_support::traits::Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = frame_support::weights::constants::RocksDbWeight;
+    type RuntimeOrigin = RuntimeOrigin;
+    type RuntimeCall = RuntimeCall;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type RuntimeEvent = RuntimeEvent;
+    type BlockHashCount = ConstU64<250>;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ();
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+    type MaxLocks = ConstU32<16>;
+    type MaxReserves = ();
+    type ReserveIdentifier = [u8; 8];
+    type Balance = Balance;
+    type RuntimeEvent = RuntimeEvent;
+    type DustRemoval = ();
+    type ExistentialDeposit = ConstU128<1>;
+    type AccountStore = System;
+    type WeightInfo = ();
+}
+
+impl pallet_timestamp::Config for Test {
+    type Moment = u64;
+    type OnTimestampSet = ();
+    type MinimumPeriod = ConstU64<5>;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub BlockGasLimit: U256 = U256::from(u64::MAX);
+    pub WeightPerGas: Weight = Weight::from_ref_time(20_000);
+}
+
+impl pallet_evm::Config for Test {
+    type FeeCalculator = ();
+    type GasWeightMapping = pallet_evm::FixedGasWeightMapping<Self>;
+    type WeightPerGas = WeightPerGas;
+    type BlockHashMapping = pallet_evm::SubstrateBlockHashMapping<Self>;
+    type AddressMapping = pallet_evm::HashedAddressMapping<BlakeTwo256>;
+    type Currency = Balances;
+    type RuntimeEvent = RuntimeEvent;
+    type Runner = pallet_evm::runner::stack::Runner<Self>;
+    type PrecompilesType = ();
+    type PrecompilesValue = ();
+    type ChainId = ();
+    type BlockGasLimit = BlockGasLimit;
+    type OnChargeTransaction = ();
+    type FindAuthor = ();
+}
+
+impl pallet_uniques::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type CollectionId = u32;
+    type ItemId = u32;
+    type Currency = Balances;
+    type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+    type CollectionDeposit = ConstU128<1>;
+    type ItemDeposit = ConstU128<1>;
+    type MetadataDepositBase = ConstU128<1>;
+    type AttributeDepositBase = ConstU128<1>;
+    type DepositPerByte = ConstU128<1>;
+    type StringLimit = ConstU32<50>;
+    type KeyLimit = ConstU32<50>;
+    type ValueLimit = ConstU32<50>;
+    type WeightInfo = ();
+    type CreateOrigin = AsEnsureOriginWithArg<frame_system::EnsureSigned<AccountId>>;
+    type Locker = ();
+}
+
+pub fn alice() -> AccountId {
+    AccountId32::new([1u8; 32])
+}
+
+pub fn bob() -> AccountId {
+    AccountId32::new([2u8; 32])
+}
+
+pub fn charlie() -> AccountId {
+    AccountId32::new([3u8; 32])
+}
+
+pub fn dave() -> AccountId {
+    AccountId32::new([4u8; 32])
+}
+
+pub fn eve() -> AccountId {
+    AccountId32::new([5u8; 32])
+}
+
+/// EVM address paired with `account`, the first 20 bytes of the account.
+pub fn evm_address(account: &AccountId) -> H160 {
+    let bytes: &[u8] = account.as_ref();
+    H160::from_slice(&bytes[..20])
+}
+
+/// The EVM address pairing of the deeper-node pallet, every account but Eve is paired.
+pub struct MockNodeInterface;
+
+impl MockNodeInterface {
+    fn paired() -> [AccountId; 4] {
+        [alice(), bob(), charlie(), dave()]
+    }
+}
+
+impl NodeInterface<AccountId, u64> for MockNodeInterface {
+    fn get_onboard_time(_account_id: &AccountId) -> Option<u64> {
+        None
+    }
+
+    fn im_ever_online(_account_id: &AccountId) -> bool {
+        true
+    }
+
+    fn get_eras_offline(_account_id: &AccountId) -> u32 {
+        0
+    }
+
+    fn get_accounts_deeper_evm(account_id: &AccountId) -> Option<H160> {
+        Self::paired()
+            .contains(account_id)
+            .then(|| evm_address(account_id))
+    }
+
+    fn get_accounts_evm_deeper(address: &H160) -> Option<AccountId> {
+        Self::paired()
+            .into_iter()
+            .find(|account| evm_address(account) == *address)
+    }
+
+    fn attestation_satisfied(_account_id: &AccountId) -> bool {
+        true
+    }
+}
+
+parameter_types! {
+    pub const BlocksPerEra: u64 = 10;
+    pub const CreditAttenuationStep: u64 = 1;
+    pub const MinCreditToDelegate: u64 = 100;
+    pub const MicropaymentToCreditFactor: u128 = 1_000_000_000_000_000;
+    pub const SecsPerBlock: u32 = 5u32;
+    pub const DPRPerCreditBurned: Balance = 50;
+    pub const MaxBurnCreditPerAddress: u32 = 50;
+    pub const MinSettingEnactmentDelay: EraIndex = 2;
+    pub const MaxScheduledSettingsPerEra: u32 = 10;
+    pub const MaxCreditHistory: u32 = 20;
+    pub const PriceQuorum: u32 = 1;
+    pub const PriceStaleAfter: u64 = 24 * 60 * 60;
+    pub const MaxPriceHistory: u32 = 10;
+    pub const PriceAggregationInterval: u64 = 0;
+    pub const PriceFeedInterval: u64 = 10;
+    pub const CreditBadgeCollection: Option<u32> = None;
+    pub const MaxCreditChanges: u32 = 3;
+}
+
+impl pallet_credit::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Currency = Balances;
+    type BlocksPerEra = BlocksPerEra;
+    type CreditAttenuationStep = CreditAttenuationStep;
+    type MinCreditToDelegate = MinCreditToDelegate;
+    type MicropaymentToCreditFactor = MicropaymentToCreditFactor;
+    type NodeInterface = MockNodeInterface;
+    type WeightInfo = ();
+    type UnixTime = Timestamp;
+    type SecsPerBlock = SecsPerBlock;
+    type DPRPerCreditBurned = DPRPerCreditBurned;
+    type BurnedTo = ();
+    type UserPrivilegeInterface = ();
+    type MaxBurnCreditPerAddress = MaxBurnCreditPerAddress;
+    type CreditSettingOrigin = frame_system::EnsureRoot<AccountId>;
+    type MinSettingEnactmentDelay = MinSettingEnactmentDelay;
+    type MaxScheduledSettingsPerEra = MaxScheduledSettingsPerEra;
+    type MaxCreditHistory = MaxCreditHistory;
+    type PriceQuorum = PriceQuorum;
+    type PriceStaleAfter = PriceStaleAfter;
+    type MaxPriceHistory = MaxPriceHistory;
+    type PriceAggregationInterval = PriceAggregationInterval;
+    type PriceFeed = ();
+    type PriceFeedInterval = PriceFeedInterval;
+    type MaxPriceFeedEndpoints = ConstU32<2>;
+    type MaxPriceFeedEndpointLength = ConstU32<64>;
+    type CreditBadgeCollection = CreditBadgeCollection;
+    type MaxCreditChanges = MaxCreditChanges;
+}
+
+impl pallet_session::Config for Test {
+    type SessionManager = ();
+    type Keys = UintAuthorityId;
+    type ShouldEndSession = pallet_session::PeriodicSessions<ConstU64<5>, ConstU64<0>>;
+    type SessionHandler = pallet_session::TestSessionHandler;
+    type RuntimeEvent = RuntimeEvent;
+    type ValidatorId = AccountId;
+    type ValidatorIdOf = pallet_staking::StashOf<Test>;
+    type NextSessionRotation = pallet_session::PeriodicSessions<ConstU64<5>, ConstU64<0>>;
+    type WeightInfo = ();
+}
+
+impl pallet_session::historical::Config for Test {
+    type FullIdentification = pallet_staking::Exposure<AccountId, Balance>;
+    type FullIdentificationOf = pallet_staking::ExposureOf<Test>;
+}
+
+pub struct NumberCurrencyConverter;
+
+impl Convert<u128, Balance> for NumberCurrencyConverter {
+    fn convert(x: u128) -> Balance {
+        x
+    }
+}
+
+parameter_types! {
+    pub const SessionsPerEra: u32 = 2;
+    pub const BondingDuration: EraIndex = 3;
+    pub const SlashDeferDuration: EraIndex = 0;
+    pub const MaxDelegates: usize = 2;
+    pub const TotalMiningReward: u128 = 1_000_000;
+    pub const StakingPalletId: PalletId = PalletId(*b"stak_ing");
+}
+
+impl pallet_staking::Config for Test {
+    type PalletId = StakingPalletId;
+    type BlocksPerEra = BlocksPerEra;
+    type Currency = Balances;
+    type UnixTime = Timestamp;
+    type RuntimeEvent = RuntimeEvent;
+    type Slash = ();
+    type SessionsPerEra = SessionsPerEra;
+    type SlashDeferDuration = SlashDeferDuration;
+    type SlashCancelOrigin = frame_system::EnsureRoot<AccountId>;
+    type BondingDuration = BondingDuration;
+    type SessionInterface = Self;
+    type RuntimeCall = RuntimeCall;
+    type WeightInfo = ();
+    type CreditInterface = Credit;
+    type NodeInterface = MockNodeInterface;
+    type MaxDelegates = MaxDelegates;
+    type NumberToCurrency = NumberCurrencyConverter;
+    type TotalMiningReward = TotalMiningReward;
+    type AlertMiningReward = TotalMiningReward;
+    type VerifySignatureInterface = ();
+    type UserPrivilegeInterface = ();
+    type OperationInterface = ();
+}
+
+impl<LocalCall> frame_system::offchain::SendTransactionTypes<LocalCall> for Test
+where
+    RuntimeCall: From<LocalCall>,
+{
+    type OverarchingCall = RuntimeCall;
+    type Extrinsic = TestXt<RuntimeCall, ()>;
+}
+
+/// Address of the precompile in the tests.
+pub fn precompile_address() -> H160 {
+    H160::from_low_u64_be(0x0e00)
+}
+
+/// Alice and Charlie have enough credit to delegate, Bob and Dave are validators, Eve has no
+/// credit and is not paired.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let mut storage = frame_system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![
+            (alice(), 1_000),
+            (bob(), 1_000),
+            (charlie(), 1_000),
+            (dave(), 1_000),
+            (eve(), 1_000),
+        ],
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+    let credit_data = CreditData {
+        credit: 100,
+        initial_credit_level: CreditLevel::One,
+        current_credit_level: CreditLevel::One,
+        ..Default::default()
+    };
+    pallet_credit::GenesisConfig::<Test> {
+        credit_settings: vec![],
+        user_credit_data: vec![(alice(), credit_data.clone()), (charlie(), credit_data)],
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+    pallet_staking::GenesisConfig::<Test> {
+        stakers: vec![
+            (bob(), bob(), 500, StakerStatus::Validator),
+            (dave(), dave(), 500, StakerStatus::Validator),
+        ],
+        ..Default::default()
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+
+    let mut ext = sp_io::TestExternalities::new(storage);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
+
+/// Call the precompile from `caller`, returns the result and the handle holding the used gas
+/// and the logs.
+pub fn call(caller: H160, input: Vec<u8>) -> (PrecompileResult, MockHandle) {
+    let mut handle = MockHandle::new(precompile_address(), caller, input);
+    let result = StakingDispatch::<Test>::execute(&mut handle);
+    (result, handle)
+}
@@ -0,0 +1,245 @@
+// Copyright (C) 2021 Deeper Network Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use crate::mock::*;
+use pallet_evm_precompile_credit::testing::{
+    assert_dispatch_reverted, assert_selectors, event_log, output, word, MockHandle,
+};
+use pallet_staking::RewardData;
+
+fn account(account: &AccountId) -> H256 {
+    H256(account.clone().into())
+}
+
+fn accounts(accounts: &[AccountId]) -> Vec<u8> {
+    let accounts: Vec<H256> = accounts.iter().map(account).collect();
+    EvmDataWriter::new()
+        .write_u256(U256::from(32))
+        .write_h256_array(&accounts)
+        .build()
+}
+
+fn delegate_input(validators: &[AccountId]) -> Vec<u8> {
+    let validators: Vec<H256> = validators.iter().map(account).collect();
+    EvmDataWriter::new_with_selector(SELECTOR_DELEGATE)
+        .write_u256(U256::from(32))
+        .write_h256_array(&validators)
+        .build()
+}
+
+fn view(selector: u32, input: Vec<u8>) -> Vec<u8> {
+    let mut input_with_selector = selector.to_be_bytes().to_vec();
+    input_with_selector.extend(input);
+    let mut handle = MockHandle::new(
+        precompile_address(),
+        evm_address(&eve()),
+        input_with_selector,
+    )
+    .with_static();
+    output(StakingDispatch::<Test>::execute(&mut handle))
+}
+
+fn delegator_data(delegator: &AccountId) -> Vec<u8> {
+    view(
+        SELECTOR_GET_DELEGATOR_DATA,
+        EvmDataWriter::new()
+            .write_address(evm_address(delegator))
+            .build(),
+    )
+}
+
+fn delegators(validator: &AccountId) -> Vec<u8> {
+    view(
+        SELECTOR_GET_DELEGATORS,
+        EvmDataWriter::new().write_h256(account(validator)).build(),
+    )
+}
+
+#[test]
+fn selectors_match_signatures() {
+    let selectors = [
+        (SELECTOR_DELEGATE, "delegate(bytes32[])"),
+        (SELECTOR_UNDELEGATE, "undelegate()"),
+        (SELECTOR_GET_REWARD, "get_reward(address)"),
+        (SELECTOR_GET_DELEGATOR_DATA, "get_delegator_data(address)"),
+        (SELECTOR_GET_DELEGATORS, "get_delegators(bytes32)"),
+    ];
+    assert_selectors(&selectors);
+}
+
+#[test]
+fn read_validators() {
+    let validators = [H256::repeat_byte(1), H256::repeat_byte(2)];
+    let mut input = SELECTOR_DELEGATE.to_be_bytes().to_vec();
+    input.extend(
+        EvmDataWriter::new()
+            .write_u256(U256::from(32))
+            .write_h256_array(&validators)
+            .build(),
+    );
+
+    let mut reader = EvmDataReader::new_skip_selector(&input).unwrap();
+    assert_eq!(reader.read_h256_array().unwrap(), validators.to_vec());
+
+    // the length claims more validators than the input holds
+    input.truncate(input.len() - 32);
+    let mut reader = EvmDataReader::new_skip_selector(&input).unwrap();
+    assert!(reader.read_h256_array().is_err());
+}
+
+#[test]
+fn delegate_and_undelegate() {
+    new_test_ext().execute_with(|| {
+        let (result, handle) = call(evm_address(&alice()), delegate_input(&[bob(), dave()]));
+        assert_eq!(output(result), Vec::<u8>::new());
+        assert_eq!(
+            handle.logs,
+            vec![event_log(
+                precompile_address(),
+                EVENT_DELEGATED,
+                &[evm_address(&alice()).into()],
+                accounts(&[bob(), dave()])
+            )]
+        );
+        assert!(handle.gas_used > 0);
+
+        // delegating since era 0
+        assert_eq!(
+            delegator_data(&alice()),
+            EvmDataWriter::new()
+                .write_bool(true)
+                .write_u256(U256::from(4 * 32))
+                .write_bool(true)
+                .write_u256(U256::zero())
+                .write_h256_array(&[account(&bob()), account(&dave())])
+                .build()
+        );
+        assert_eq!(delegators(&bob()), accounts(&[alice()]));
+        assert_eq!(delegators(&dave()), accounts(&[alice()]));
+
+        let input = SELECTOR_UNDELEGATE.to_be_bytes().to_vec();
+        let (result, handle) = call(evm_address(&alice()), input);
+        assert_eq!(output(result), Vec::<u8>::new());
+        assert_eq!(
+            handle.logs,
+            vec![event_log(
+                precompile_address(),
+                EVENT_UNDELEGATED,
+                &[evm_address(&alice()).into()],
+                Vec::new()
+            )]
+        );
+
+        // undelegated in the era it delegated, the delegator is removed
+        assert_eq!(
+            delegator_data(&alice()),
+            EvmDataWriter::new()
+                .write_bool(false)
+                .write_u256(U256::from(4 * 32))
+                .write_bool(false)
+                .write_u256(U256::zero())
+                .write_h256_array(&[])
+                .build()
+        );
+        assert_eq!(delegators(&bob()), accounts(&[]));
+    });
+}
+
+#[test]
+fn get_delegators_is_sorted() {
+    new_test_ext().execute_with(|| {
+        for delegator in [charlie(), alice()] {
+            assert_eq!(
+                output(call(evm_address(&delegator), delegate_input(&[bob()])).0),
+                Vec::<u8>::new()
+            );
+        }
+        assert_eq!(delegators(&bob()), accounts(&[alice(), charlie()]));
+        assert_eq!(delegators(&dave()), accounts(&[]));
+    });
+}
+
+#[test]
+fn get_reward() {
+    new_test_ext().execute_with(|| {
+        let reward = |delegator: &AccountId| {
+            view(
+                SELECTOR_GET_REWARD,
+                EvmDataWriter::new()
+                    .write_address(evm_address(delegator))
+                    .build(),
+            )
+        };
+        let words = |values: [u128; 5]| values.map(word).concat();
+
+        assert_eq!(reward(&alice()), words([0; 5]));
+
+        pallet_staking::Reward::<Test>::insert(
+            alice(),
+            RewardData {
+                total_referee_reward: 5,
+                received_referee_reward: 4,
+                referee_reward: 3,
+                received_pocr_reward: 2,
+                poc_reward: 1,
+            },
+        );
+        assert_eq!(reward(&alice()), words([5, 4, 3, 2, 1]));
+        // not paired
+        assert_eq!(reward(&eve()), words([0; 5]));
+    });
+}
+
+#[test]
+fn delegate_reverts() {
+    new_test_ext().execute_with(|| {
+        let (result, handle) = call(evm_address(&eve()), delegate_input(&[bob()]));
+        assert_eq!(
+            result.map(|o| o.output),
+            Err(util::revert("caller is not mapped to a substrate account"))
+        );
+        assert!(handle.logs.is_empty());
+
+        // a validator can't delegate, and the delegated accounts must be validators
+        for (caller, validator) in [(dave(), bob()), (alice(), charlie())] {
+            let (result, handle) = call(evm_address(&caller), delegate_input(&[validator]));
+            assert_dispatch_reverted(result);
+            assert!(handle.logs.is_empty());
+        }
+
+        // not delegating
+        let (result, handle) = call(
+            evm_address(&alice()),
+            SELECTOR_UNDELEGATE.to_be_bytes().to_vec(),
+        );
+        assert_dispatch_reverted(result);
+        assert!(handle.logs.is_empty());
+
+        let mut handle = MockHandle::new(
+            precompile_address(),
+            evm_address(&alice()),
+            delegate_input(&[bob()]),
+        )
+        .with_static();
+        assert_eq!(
+            StakingDispatch::<Test>::execute(&mut handle).map(|o| o.output),
+            Err(util::revert(
+                "can't call non-static function in static context"
+            ))
+        );
+        assert_eq!(delegators(&bob()), accounts(&[]));
+    });
+}
@@ -53,8 +53,8 @@ use frame_support::{
 };
 use frame_system::{ensure_root, ensure_signed, offchain::SendTransactionTypes, pallet_prelude::*};
 use node_primitives::{
-    credit::{CreditInterface, CreditLevel},
-    deeper_node::NodeInterface,
+    credit::{CreditInterface, RewardDeferred},
+    deeper_node::{DeviceMigration, NodeInterface},
     user_privileges::{Privilege, UserPrivilegeInterface},
     OperationInterface, VerifySignatureInterface, DPR,
 };
@@ -2737,6 +2737,11 @@ impl<T: Config> pallet::Pallet<T> {
         let (rewards, get_reward_weight) =
             T::CreditInterface::get_reward(delegator, earliest_unrewarded_era, current_era - 1);
         weight = weight.saturating_add(get_reward_weight);
+        let rewards = match rewards {
+            Ok(rewards) => rewards,
+            // keep the eras unrewarded until their reward can be computed
+            Err(RewardDeferred) => return (payout, weight),
+        };
         if let Some(poc_reward) = rewards {
             // update RewardData
             if Reward::<T>::contains_key(delegator) {
@@ -2767,6 +2772,7 @@ impl<T: Config> pallet::Pallet<T> {
             if current_era <= punish_era {
                 reward = BalanceOf::<T>::zero();
             }
+            weight = weight.saturating_add(T::CreditInterface::reward_paid(delegator, reward));
 
             let imbalance = T::Currency::deposit_creating(delegator, reward); // 1 write
             weight = weight.saturating_add(T::DbWeight::get().reads_writes(0, 1));
@@ -3137,11 +3143,12 @@ impl<T: Config> pallet::Pallet<T> {
         ensure!(credit_score.is_some(), Error::<T>::NotHasCreditLeger);
 
         let (need_balance, score_gap) = {
-            let cur_level = CreditLevel::get_credit_level(credit_score.unwrap()).into();
+            let cur_level = T::CreditInterface::get_credit_level(credit_score.unwrap()).into();
             ensure!(dst_level > cur_level, Error::<T>::TargetLevelLow);
             let need_balance = credit_balances[dst_level as usize]
                 .saturating_sub(credit_balances[cur_level as usize]);
-            let score_gap = CreditLevel::credit_level_gap(dst_level.into(), cur_level.into());
+            let score_gap =
+                T::CreditInterface::credit_level_gap(dst_level.into(), cur_level.into());
             (need_balance, score_gap)
         };
 
@@ -3179,7 +3186,7 @@ impl<T: Config> pallet::Pallet<T> {
         let cur_level = {
             let credit_score = T::CreditInterface::get_credit_score(&who);
             if let Some(credit_score) = credit_score {
-                let cur_level: u8 = CreditLevel::get_credit_level(credit_score).into();
+                let cur_level: u8 = T::CreditInterface::get_credit_level(credit_score).into();
                 cur_level as usize
             } else {
                 ensure!(
@@ -3201,8 +3208,10 @@ impl<T: Config> pallet::Pallet<T> {
             }
         }
 
-        let score_gap =
-            CreditLevel::credit_level_gap((dst_level as u8).into(), (cur_level as u8).into());
+        let score_gap = T::CreditInterface::credit_level_gap(
+            (dst_level as u8).into(),
+            (cur_level as u8).into(),
+        );
         T::CreditInterface::add_or_update_credit(who.clone(), score_gap, Some(campaign_id));
         T::CreditInterface::set_staking_balance(&who, usdt_amount, dpr_amount);
         // level::zero can't delegate
@@ -3214,6 +3223,47 @@ impl<T: Config> pallet::Pallet<T> {
     }
 }
 
+impl<T: Config> DeviceMigration<T::AccountId> for pallet::Pallet<T> {
+    fn can_migrate_device(old: &T::AccountId, new: &T::AccountId) -> bool {
+        // a blacklisted account can't move its delegation away from the punishment
+        !<BlackList<T>>::contains_key(old)
+            && !<Delegators<T>>::contains_key(new)
+            && !<Validators<T>>::contains_key(new)
+            && !<Reward<T>>::contains_key(new)
+            && Self::delegator_balances(new).is_zero()
+    }
+
+    fn migrate_device(old: &T::AccountId, new: &T::AccountId) -> Weight {
+        let mut validators = 0u64;
+        if <Delegators<T>>::contains_key(old) {
+            let mut delegator_data = <Delegators<T>>::take(old);
+            for validator in &delegator_data.delegated_validators {
+                <CandidateValidators<T>>::mutate(validator, |v| {
+                    if v.delegators.remove(old) {
+                        v.delegators.insert(new.clone());
+                    }
+                });
+            }
+            validators = delegator_data.delegated_validators.len() as u64;
+            delegator_data.delegator = new.clone();
+            <Delegators<T>>::insert(new, delegator_data);
+        }
+        let balance = <DelegatorBalances<T>>::take(old);
+        if !balance.is_zero() {
+            <DelegatorBalances<T>>::insert(new, balance);
+        }
+        if let Some(reward) = <Reward<T>>::take(old) {
+            <Reward<T>>::insert(new, reward);
+        }
+        T::DbWeight::get().reads_writes(3 + validators, 6 + validators)
+    }
+
+    fn max_migrate_device_weight() -> Weight {
+        let validators = T::MaxDelegates::get() as u64;
+        T::DbWeight::get().reads_writes(3 + validators, 6 + validators)
+    }
+}
+
 /// In this implementation `new_session(session)` must be called before `end_session(session-1)`
 /// i.e. the new session must be planned before the ending of the previous session.
 ///
@@ -25,7 +25,9 @@ use frame_support::{
 use frame_system::RawOrigin;
 use mock::*;
 use node_primitives::{
-    credit::{CreditData, CreditInterface, CreditLevel, CreditSetting, H160},
+    credit::{
+        Campaign, CreditData, CreditInterface, CreditLevel, CreditSetting, StakingType, H160,
+    },
     DPR,
 };
 use pallet_balances::Error as BalancesError;
@@ -876,6 +878,21 @@ fn no_rewards_in_blacklist() {
             assert_ok!(Staking::delegate(RuntimeOrigin::signed(1003), vec![11, 21]));
 
             let init_balance_1002 = Balances::total_balance(&1002);
+            let init_balance_1001 = Balances::total_balance(&1001);
+            let init_balance_1003 = Balances::total_balance(&1003);
+            assert_ok!(Credit::set_campaign(
+                RawOrigin::Root.into(),
+                0,
+                Campaign {
+                    name: Vec::new(),
+                    start_era: 0,
+                    end_era: None,
+                    budget: None,
+                    staking_type: StakingType::Dpr,
+                    min_credit: 0,
+                    successor: None,
+                }
+            ));
 
             Payee::<Test>::insert(11, RewardDestination::Controller);
             Payee::<Test>::insert(21, RewardDestination::Controller);
@@ -890,6 +907,11 @@ fn no_rewards_in_blacklist() {
             run_to_block(BLOCKS_PER_ERA + 1);
 
             assert_eq!(Balances::total_balance(&1002), init_balance_1002);
+            // the campaign is only charged with the reward actually paid
+            let paid_1001 = Balances::total_balance(&1001) - init_balance_1001;
+            let paid_1003 = Balances::total_balance(&1003) - init_balance_1003;
+            assert!(paid_1003 > 0);
+            assert_eq!(Credit::campaign_spent(0), paid_1001 + paid_1003);
         });
 }
 
@@ -3324,6 +3346,21 @@ fn staking_usdt_delegate() {
                 Balances::total_balance(&1002),
                 1643835616438356164 + 4109589041095890410
             );
+
+            // a stale price keeps the era unrewarded until the price is fresh again
+            run_to_block(BLOCKS_PER_ERA * 3);
+            Timestamp::set_timestamp(Timestamp::get() + (PriceStaleAfter::get() + 1) * 1000);
+            let (payout, _) =
+                Staking::pay_delegator(&Staking::delegators(&1002), 3, 1_000_000 * DPR);
+            assert_eq!(payout, 0);
+            assert_eq!(Staking::delegators(&1002).unrewarded_since, Some(2));
+
+            run_to_block(BLOCKS_PER_ERA * 3 + 2);
+            assert_eq!(Staking::delegators(&1002).unrewarded_since, Some(3));
+            assert_eq!(
+                Balances::total_balance(&1002),
+                1643835616438356164 + 4109589041095890410 * 2
+            );
         });
 }
 
@@ -3438,3 +3475,69 @@ fn slash_staker() {
         assert_eq!(Balances::free_balance(&11), 60000000000000000001);
     });
 }
+
+#[test]
+fn migrate_device_moves_delegation() {
+    ExtBuilder::default()
+        .existential_deposit(10)
+        .validator_pool(true) // 11, 21, 31, 41
+        .build_and_execute(|| {
+            assert_ok!(Staking::delegate(RuntimeOrigin::signed(1001), vec![11, 21]));
+            assert!(!<Staking as DeviceMigration<_>>::can_migrate_device(
+                &2001, &1001
+            ));
+            assert!(!<Staking as DeviceMigration<_>>::can_migrate_device(
+                &1001, &11
+            ));
+            assert!(<Staking as DeviceMigration<_>>::can_migrate_device(
+                &1001, &2001
+            ));
+
+            <Staking as DeviceMigration<_>>::migrate_device(&1001, &2001);
+            assert!(!Delegators::<Test>::contains_key(&1001));
+            let delegator_data = Staking::delegators(&2001);
+            assert_eq!(delegator_data.delegator, 2001);
+            assert_eq!(delegator_data.delegated_validators, vec![11, 21]);
+            assert!(delegator_data.delegating);
+            for validator in [11, 21] {
+                let delegators = Staking::candidate_validators(validator).delegators;
+                assert!(!delegators.contains(&1001));
+                assert!(delegators.contains(&2001));
+            }
+            assert_eq!(Staking::delegator_count(), 1);
+            assert_eq!(Staking::active_delegator_count(), 1);
+
+            assert_ok!(Staking::undelegate(RuntimeOrigin::signed(2001)));
+            assert!(Staking::candidate_validators(11).delegators.is_empty());
+        });
+}
+
+#[test]
+fn blacklisted_device_cannot_migrate() {
+    ExtBuilder::default()
+        .validator_pool(true) // 11, 21, 31, 41
+        .build_and_execute(|| {
+            assert_ok!(UserPrivileges::set_user_privilege(
+                RuntimeOrigin::root(),
+                2,
+                Privilege::BlackListAdmin
+            ));
+            assert_ok!(Staking::add_account_to_blacklist(
+                RuntimeOrigin::signed(2),
+                1001,
+                current_era() + 10
+            ));
+            assert_ok!(Staking::delegate(RuntimeOrigin::signed(1001), vec![11, 21]));
+            assert!(!<Staking as DeviceMigration<_>>::can_migrate_device(
+                &1001, &2001
+            ));
+
+            assert_ok!(Staking::remove_account_from_blacklist(
+                RuntimeOrigin::signed(2),
+                1001
+            ));
+            assert!(<Staking as DeviceMigration<_>>::can_migrate_device(
+                &1001, &2001
+            ));
+        });
+}
@@ -200,6 +200,14 @@ parameter_types! {
     pub const SecsPerBlock: u32 = 5u32;
     pub const DPRPerCreditBurned: u64 = 100;
     pub const MaxBurnCreditPerAddress: u32 = 50;
+    pub const MinSettingEnactmentDelay: u32 = 2;
+    pub const MaxScheduledSettingsPerEra: u32 = 10;
+    pub const MaxCreditHistory: u32 = 100;
+    pub const PriceQuorum: u32 = 1;
+    pub const PriceStaleAfter: u64 = 24 * 60 * 60;
+    pub const MaxPriceHistory: u32 = 10;
+    pub const PriceAggregationInterval: u64 = 0;
+    pub const PriceFeedInterval: u64 = 0;
 }
 impl pallet_credit::Config for Test {
     type RuntimeEvent = RuntimeEvent;
@@ -216,6 +224,20 @@ impl pallet_credit::Config for Test {
     type BurnedTo = ();
     type UserPrivilegeInterface = ();
     type MaxBurnCreditPerAddress = MaxBurnCreditPerAddress;
+    type CreditSettingOrigin = frame_system::EnsureRoot<u128>;
+    type MinSettingEnactmentDelay = MinSettingEnactmentDelay;
+    type MaxScheduledSettingsPerEra = MaxScheduledSettingsPerEra;
+    type MaxCreditHistory = MaxCreditHistory;
+    type PriceQuorum = PriceQuorum;
+    type PriceStaleAfter = PriceStaleAfter;
+    type MaxPriceHistory = MaxPriceHistory;
+    type PriceAggregationInterval = PriceAggregationInterval;
+    type PriceFeed = ();
+    type PriceFeedInterval = PriceFeedInterval;
+    type MaxPriceFeedEndpoints = ConstU32<2>;
+    type MaxPriceFeedEndpointLength = ConstU32<64>;
+    type CreditBadgeCollection = ();
+    type MaxCreditChanges = ConstU32<10>;
 }
 parameter_types! {
     pub const MinimumPeriod: u64 = 5;
@@ -230,6 +252,10 @@ parameter_types! {
     pub const MinLockAmt: u32 = 100;
     pub const MaxDurationEras: u8 = 7;
     pub const MaxIpLength: usize = 256;
+    pub const MaxSweepDevices: u32 = 10;
+    pub const MaxServersPerRegion: u32 = 100;
+    pub const MaxFirmwareLength: u32 = 64;
+    pub const DeviceMigrationCooldown: u64 = 100;
 }
 impl pallet_deeper_node::Config for Test {
     type RuntimeEvent = RuntimeEvent;
@@ -240,6 +266,13 @@ impl pallet_deeper_node::Config for Test {
     type MaxIpLength = MaxIpLength;
     type WeightInfo = ();
     type VerifySignatureInterface = ();
+    type CreditInterface = Credit;
+    type MaxSweepDevices = MaxSweepDevices;
+    type MaxServersPerRegion = MaxServersPerRegion;
+    type MaxFirmwareLength = MaxFirmwareLength;
+    type UserPrivilegeInterface = ();
+    type DeviceMigration = ();
+    type DeviceMigrationCooldown = DeviceMigrationCooldown;
 }
 parameter_types! {
     pub const TipCountdown: u64 = 1;
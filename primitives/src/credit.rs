@@ -21,6 +21,13 @@ pub const OLD_REWARD_ERAS: EraIndex = 270;
 // Allow 1 era to increase credit score once
 pub const CREDIT_CAP_ONE_ERAS: u64 = 1;
 
+/// minimum credit score of each credit level from `CreditLevel::One` to `CreditLevel::Eight`
+pub type CreditLevelThresholds = [u64; 8];
+
+/// credit level thresholds before they were stored on chain
+pub const DEFAULT_CREDIT_LEVEL_THRESHOLDS: CreditLevelThresholds =
+    [100, 200, 300, 400, 500, 600, 700, 800];
+
 /// settings for a specific campaign_id and credit level
 #[derive(Decode, Encode, Default, Clone, Debug, PartialEq, Eq, TypeInfo)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
@@ -36,6 +43,66 @@ pub struct CreditSetting<Balance> {
     pub reward_per_referee: Balance,
 }
 
+/// what participants of a campaign stake
+#[derive(Decode, Encode, Clone, Copy, Debug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum StakingType {
+    Dpr,
+    Usdt,
+}
+
+/// Proof-of-Credit promotion campaign registered on chain
+#[derive(Decode, Encode, Clone, Debug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct Campaign<Balance> {
+    pub name: Vec<u8>,
+    /// first era participants may be switched into the campaign
+    pub start_era: EraIndex,
+    /// last era rewarded by the campaign, open ended if none
+    pub end_era: Option<EraIndex>,
+    /// cap of the rewards paid by the campaign, uncapped if none
+    pub budget: Option<Balance>,
+    pub staking_type: StakingType,
+    /// minimum credit score rewarded by the campaign
+    pub min_credit: u64,
+    /// campaign participants are switched to when their reward eras end
+    pub successor: Option<CampaignId>,
+}
+
+/// cause of a credit change
+#[derive(Decode, Encode, Clone, Copy, Debug, PartialEq, Eq, Ord, PartialOrd, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum CreditSource {
+    /// traffic served by the device
+    Traffic,
+    Tip,
+    BurnNft,
+    /// DPR burned with `burn_for_add_credit`
+    BurnDpr,
+    /// credit operation of an evm contract
+    Evm,
+    OfflineSlash,
+    UnstakingSlash,
+}
+
+/// credit added and removed by one source
+#[derive(Decode, Encode, Default, Clone, Debug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct CreditCounter {
+    pub added: u64,
+    pub removed: u64,
+}
+
+/// credit change of an account
+#[derive(Decode, Encode, Clone, Debug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct CreditChange<BlockNumber> {
+    pub block_number: BlockNumber,
+    pub source: CreditSource,
+    pub old_credit: u64,
+    pub new_credit: u64,
+}
+
 #[derive(Decode, Encode, Default, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, TypeInfo)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub struct CreditData {
@@ -49,8 +116,8 @@ pub struct CreditData {
 }
 
 impl CreditData {
-    pub fn new(campaign_id: CampaignId, credit: u64) -> Self {
-        let lv = CreditLevel::get_credit_level(credit);
+    pub fn new(campaign_id: CampaignId, credit: u64, thresholds: &[u64]) -> Self {
+        let lv = CreditLevel::from_score(credit, thresholds);
         CreditData {
             campaign_id,
             credit,
@@ -61,17 +128,71 @@ impl CreditData {
         }
     }
 
-    pub fn update(&mut self, credit: u64) {
-        let lv = CreditLevel::get_credit_level(credit);
+    pub fn update(&mut self, credit: u64, thresholds: &[u64]) {
+        let lv = CreditLevel::from_score(credit, thresholds);
         self.current_credit_level = lv;
         self.credit = credit;
     }
 
+    /// recompute the current credit level, returns true if it changed
+    pub fn relevel(&mut self, thresholds: &[u64]) -> bool {
+        let lv = CreditLevel::from_score(self.credit, thresholds);
+        if lv == self.current_credit_level {
+            return false;
+        }
+        self.current_credit_level = lv;
+        true
+    }
+
     pub fn update_campaign(&mut self, campaign_id: CampaignId) {
         self.campaign_id = campaign_id;
     }
 }
 
+/// compacted summary of the credit history entries evicted from the recent history
+#[derive(Decode, Encode, Clone, Debug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct CreditHistorySummary {
+    /// era of the first credit history entry, i.e. the delegate era
+    pub first_era: EraIndex,
+    /// max credit score of the evicted entries
+    pub max_credit: u64,
+    /// last era each credit level was entered in the evicted entries
+    pub last_change_per_level: Vec<(CreditLevel, EraIndex)>,
+    /// latest evicted entry, still in effect until the first recent entry
+    pub last_evicted: (EraIndex, CreditData),
+}
+
+impl CreditHistorySummary {
+    pub fn new(era: EraIndex, credit_data: CreditData) -> Self {
+        let mut last_change_per_level = Vec::new();
+        last_change_per_level.push((credit_data.current_credit_level, era));
+        CreditHistorySummary {
+            first_era: era,
+            max_credit: credit_data.credit,
+            last_change_per_level,
+            last_evicted: (era, credit_data),
+        }
+    }
+
+    /// fold an entry evicted from the recent history into the summary
+    pub fn evict(&mut self, era: EraIndex, credit_data: CreditData) {
+        self.max_credit = self.max_credit.max(credit_data.credit);
+        let level = credit_data.current_credit_level;
+        if level != self.last_evicted.1.current_credit_level {
+            match self
+                .last_change_per_level
+                .iter_mut()
+                .find(|(l, _)| *l == level)
+            {
+                Some(change) => change.1 = era,
+                None => self.last_change_per_level.push((level, era)),
+            }
+        }
+        self.last_evicted = (era, credit_data);
+    }
+}
+
 #[derive(Decode, Encode, Clone, Debug, PartialEq, Eq, Copy, Ord, PartialOrd, TypeInfo)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub enum CreditLevel {
@@ -126,39 +247,68 @@ impl Into<u8> for CreditLevel {
 }
 
 impl CreditLevel {
+    /// credit level with the default thresholds
     pub fn get_credit_level(credit_score: u64) -> CreditLevel {
-        let credit_level = match credit_score {
-            0..=99 => CreditLevel::Zero,
-            100..=199 => CreditLevel::One,
-            200..=299 => CreditLevel::Two,
-            300..=399 => CreditLevel::Three,
-            400..=499 => CreditLevel::Four,
-            500..=599 => CreditLevel::Five,
-            600..=699 => CreditLevel::Six,
-            700..=799 => CreditLevel::Seven,
-            _ => CreditLevel::Eight,
-        };
-        credit_level
+        Self::from_score(credit_score, &DEFAULT_CREDIT_LEVEL_THRESHOLDS)
+    }
+
+    /// credit level with ascending `thresholds`, the minimum score of each level above zero
+    pub fn from_score(credit_score: u64, thresholds: &[u64]) -> CreditLevel {
+        let level = thresholds
+            .iter()
+            .take_while(|threshold| credit_score >= **threshold)
+            .count();
+        (level as u8).into()
     }
 
+    /// minimum credit score of the level with ascending `thresholds`
+    pub fn min_score(self, thresholds: &[u64]) -> u64 {
+        let level: u8 = self.into();
+        match level {
+            0 => 0,
+            _ => thresholds
+                .get(level as usize - 1)
+                .copied()
+                .unwrap_or(u64::MAX),
+        }
+    }
+
+    /// credit level gap with the default thresholds
     pub fn credit_level_gap(lhs: CreditLevel, rhs: CreditLevel) -> u64 {
-        let lhs: u8 = lhs.into();
-        let rhs: u8 = rhs.into();
-        100u64 * (lhs.saturating_sub(rhs) as u64)
+        Self::credit_level_gap_by_thresholds(lhs, rhs, &DEFAULT_CREDIT_LEVEL_THRESHOLDS)
+    }
+
+    /// credit score needed to move from level `rhs` up to level `lhs`
+    pub fn credit_level_gap_by_thresholds(
+        lhs: CreditLevel,
+        rhs: CreditLevel,
+        thresholds: &[u64],
+    ) -> u64 {
+        lhs.min_score(thresholds)
+            .saturating_sub(rhs.min_score(thresholds))
     }
 }
 
+/// The reward of the eras can't be computed yet and must be asked again later,
+/// e.g. while the DPR price of a usdt campaign is stale
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RewardDeferred;
+
 pub trait CreditInterface<AccountId, Balance> {
     fn get_credit_score(account_id: &AccountId) -> Option<u64>;
     fn get_evm_credit_score(account_id: &H160) -> Option<u64>;
     fn pass_threshold(account_id: &AccountId) -> bool;
     fn slash_credit(account_id: &AccountId, score: Option<u64>) -> Weight;
+    fn slash_offline_device_credit(account_id: &AccountId) -> Weight;
     fn get_credit_level(credit_score: u64) -> CreditLevel;
+    fn credit_level_gap(lhs: CreditLevel, rhs: CreditLevel) -> u64;
     fn get_reward(
         account_id: &AccountId,
         from: EraIndex,
         to: EraIndex,
-    ) -> (Option<Balance>, Weight);
+    ) -> (Result<Option<Balance>, RewardDeferred>, Weight);
+    /// `paid` out of the last reward of `get_reward` was paid to the account
+    fn reward_paid(account_id: &AccountId, paid: Balance) -> Weight;
     fn update_credit_by_traffic(server: AccountId);
     fn get_current_era() -> EraIndex;
     fn update_credit_by_tip(who: AccountId, add_credit: u64);
@@ -192,15 +342,24 @@ impl<AccountId, Balance: From<u32>> CreditInterface<AccountId, Balance> for () {
     fn slash_credit(_account_id: &AccountId, _score: Option<u64>) -> Weight {
         Weight::from_ref_time(0)
     }
+    fn slash_offline_device_credit(_account_id: &AccountId) -> Weight {
+        Weight::from_ref_time(0)
+    }
     fn get_credit_level(_credit_score: u64) -> CreditLevel {
         CreditLevel::Zero
     }
+    fn credit_level_gap(lhs: CreditLevel, rhs: CreditLevel) -> u64 {
+        CreditLevel::credit_level_gap(lhs, rhs)
+    }
     fn get_reward(
         _account_id: &AccountId,
         _from: EraIndex,
         _to: EraIndex,
-    ) -> (Option<Balance>, Weight) {
-        (None, Weight::from_ref_time(0))
+    ) -> (Result<Option<Balance>, RewardDeferred>, Weight) {
+        (Ok(None), Weight::from_ref_time(0))
+    }
+    fn reward_paid(_account_id: &AccountId, _paid: Balance) -> Weight {
+        Weight::from_ref_time(0)
     }
     fn update_credit_by_traffic(_server: AccountId) {}
     fn get_current_era() -> EraIndex {
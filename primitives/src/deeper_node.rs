@@ -1,5 +1,85 @@
+use crate::Vec;
+use codec::{Decode, Encode};
+use frame_support::weights::Weight;
+use scale_info::TypeInfo;
 use sp_core::H160;
 
+#[cfg(feature = "std")]
+use sp_runtime::{Deserialize, Serialize};
+
+/// Message a manufacturer signs to attest a device: blake2_256 of
+/// |"deeper-device-attestation"|manufacturer|device|
+pub fn attestation_message<AccountId: Encode>(
+    manufacturer: &AccountId,
+    device: &AccountId,
+) -> [u8; 32] {
+    let mut data = Vec::new();
+    data.extend_from_slice(b"deeper-device-attestation");
+    data.extend_from_slice(&manufacturer.encode());
+    data.extend_from_slice(&device.encode());
+    sp_io::hashing::blake2_256(&data)
+}
+
+/// Message the new account signs to take over an old device account: blake2_256 of
+/// |"deeper-device-migration"|old|new|
+pub fn device_migration_message<AccountId: Encode>(old: &AccountId, new: &AccountId) -> [u8; 32] {
+    let mut data = Vec::new();
+    data.extend_from_slice(b"deeper-device-migration");
+    data.extend_from_slice(&old.encode());
+    data.extend_from_slice(&new.encode());
+    sp_io::hashing::blake2_256(&data)
+}
+
+/// Protocols a device can serve, bit flags of `DeviceCapabilities::protocols`
+pub mod protocol {
+    pub const TCP: u32 = 1 << 0;
+    pub const UDP: u32 = 1 << 1;
+    pub const QUIC: u32 = 1 << 2;
+    pub const WIREGUARD: u32 = 1 << 3;
+}
+
+/// Capabilities a registered device advertises to clients
+#[derive(Decode, Encode, Default, Clone, Debug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct DeviceCapabilities {
+    /// supported protocols, bit flags of [`protocol`]
+    pub protocols: u32,
+    /// IPv6 endpoint, empty if the device has none
+    pub ipv6: Vec<u8>,
+    /// advertised bandwidth in Mbps
+    pub bandwidth: u32,
+    /// price of 1 GB of traffic, in the same unit as the micropayment `DataPerDPR` rate
+    pub price_per_gb: u64,
+    /// firmware version
+    pub firmware: Vec<u8>,
+}
+
+/// Filter of server discovery, the default filter matches every server
+#[derive(Decode, Encode, Default, Clone, Debug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(default))]
+pub struct ServerFilter {
+    /// protocols the server must all support, bit flags of [`protocol`]
+    pub protocols: u32,
+    /// minimum advertised bandwidth in Mbps
+    pub min_bandwidth: u32,
+    /// maximum price of 1 GB of traffic, any price if none
+    pub max_price_per_gb: Option<u64>,
+    /// whether the server must have an IPv6 endpoint
+    pub ipv6: bool,
+}
+
+impl ServerFilter {
+    pub fn matches(&self, capabilities: &DeviceCapabilities) -> bool {
+        capabilities.protocols & self.protocols == self.protocols
+            && capabilities.bandwidth >= self.min_bandwidth
+            && self
+                .max_price_per_gb
+                .map_or(true, |max| capabilities.price_per_gb <= max)
+            && (!self.ipv6 || !capabilities.ipv6.is_empty())
+    }
+}
+
 pub trait NodeInterface<AccountId, BlockNumber> {
     /// This function tells if the device has been offline for a day
     fn get_onboard_time(account_id: &AccountId) -> Option<BlockNumber>;
@@ -15,6 +95,10 @@ pub trait NodeInterface<AccountId, BlockNumber> {
 
     /// This function returns deeper address associated with evm account
     fn get_accounts_evm_deeper(evm_address: &H160) -> Option<AccountId>;
+
+    /// This function tells if the device may become a server and accrue traffic credit,
+    /// i.e. it is attested by a manufacturer or attestation is not required
+    fn attestation_satisfied(account_id: &AccountId) -> bool;
 }
 
 impl<AccountId, BlockNumber> NodeInterface<AccountId, BlockNumber> for () {
@@ -37,4 +121,51 @@ impl<AccountId, BlockNumber> NodeInterface<AccountId, BlockNumber> for () {
     fn get_accounts_evm_deeper(_evm_address: &H160) -> Option<AccountId> {
         None
     }
+
+    fn attestation_satisfied(_account_id: &AccountId) -> bool {
+        true
+    }
+}
+
+/// State tied to a device account that moves to a new account when the device is replaced
+pub trait DeviceMigration<AccountId> {
+    /// This function tells if the state of `old` can move to `new` without overwriting any
+    /// state of `new`
+    fn can_migrate_device(old: &AccountId, new: &AccountId) -> bool;
+
+    /// This function moves the state of `old` to `new`
+    fn migrate_device(old: &AccountId, new: &AccountId) -> Weight;
+
+    /// This function returns the upper bound of the weight returned by `migrate_device`
+    fn max_migrate_device_weight() -> Weight;
+}
+
+impl<AccountId> DeviceMigration<AccountId> for () {
+    fn can_migrate_device(_old: &AccountId, _new: &AccountId) -> bool {
+        true
+    }
+
+    fn migrate_device(_old: &AccountId, _new: &AccountId) -> Weight {
+        Weight::zero()
+    }
+
+    fn max_migrate_device_weight() -> Weight {
+        Weight::zero()
+    }
+}
+
+impl<AccountId, A: DeviceMigration<AccountId>, B: DeviceMigration<AccountId>>
+    DeviceMigration<AccountId> for (A, B)
+{
+    fn can_migrate_device(old: &AccountId, new: &AccountId) -> bool {
+        A::can_migrate_device(old, new) && B::can_migrate_device(old, new)
+    }
+
+    fn migrate_device(old: &AccountId, new: &AccountId) -> Weight {
+        A::migrate_device(old, new).saturating_add(B::migrate_device(old, new))
+    }
+
+    fn max_migrate_device_weight() -> Weight {
+        A::max_migrate_device_weight().saturating_add(B::max_migrate_device_weight())
+    }
 }
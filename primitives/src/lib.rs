@@ -76,6 +76,14 @@ pub trait VerifySignatureInterface<AccountId> {
     /// verify device signature
     fn verify_atomos_signature(nonce: u64, signature: Vec<u8>, sender: AccountId) -> bool;
     fn verify_atomos_new_signature(nonce: u64, signature: Vec<u8>, sender: AccountId) -> bool;
+    /// verify the manufacturer signature on `deeper_node::attestation_message`
+    fn verify_device_attestation(
+        manufacturer: &AccountId,
+        device: &AccountId,
+        signature: &[u8],
+    ) -> bool;
+    /// verify the new account signature on `deeper_node::device_migration_message`
+    fn verify_device_migration(old: &AccountId, new: &AccountId, signature: &[u8]) -> bool;
 }
 
 impl<AccountId> VerifySignatureInterface<AccountId> for () {
@@ -85,6 +93,16 @@ impl<AccountId> VerifySignatureInterface<AccountId> for () {
     fn verify_atomos_new_signature(_nonce: u64, _signature: Vec<u8>, _sender: AccountId) -> bool {
         true
     }
+    fn verify_device_attestation(
+        _manufacturer: &AccountId,
+        _device: &AccountId,
+        _signature: &[u8],
+    ) -> bool {
+        true
+    }
+    fn verify_device_migration(_old: &AccountId, _new: &AccountId, _signature: &[u8]) -> bool {
+        true
+    }
 }
 
 pub trait OperationInterface<AccountId, Balance> {
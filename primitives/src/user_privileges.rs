@@ -47,6 +47,8 @@ pub enum Privilege {
     BlackListAdmin = 1 << 10, // 1024
     #[codec(index = 11)]
     AdstAdmin = 1 << 11, // 2048
+    #[codec(index = 12)]
+    Manufacturer = 1 << 12, // 4096
 }
 
 /// Wrapper type for `BitFlags<Privilege>` that implements `Codec`.
@@ -194,6 +194,7 @@ where
         + StorageProvider<Block, B>
         + BlockchainEvents<Block>
         + HeaderMetadata<Block, Error = BlockChainError>
+        + sc_client_api::ProofProvider<Block>
         + Sync
         + Send
         + 'static,
@@ -204,6 +205,8 @@ where
     C::Api: fp_rpc::ConvertTransactionRuntimeApi<Block>,
     C::Api: fp_rpc::EthereumRuntimeRPCApi<Block>,
     C::Api: fp_rpc::TxPoolRuntimeRPCApi<Block>,
+    C::Api: pallet_credit_rpc::CreditRuntimeApi<Block, AccountId, Balance>,
+    C::Api: pallet_deeper_node_rpc::DeeperNodeRuntimeApi<Block, AccountId>,
     P: TransactionPool<Block = Block> + 'static,
     SC: SelectChain<Block> + 'static,
     B: sc_client_api::Backend<Block> + Send + Sync + 'static,
@@ -215,6 +218,8 @@ where
         EthPubSubApiServer, EthSigner, Net, NetApiServer, TxPool, TxPoolApiServer, Web3,
         Web3ApiServer,
     };
+    use pallet_credit_rpc::{Credit, CreditApiServer};
+    use pallet_deeper_node_rpc::{DeeperNode, DeeperNodeApiServer};
     use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
     use sc_consensus_babe_rpc::{Babe, BabeApiServer};
     use sc_finality_grandpa_rpc::{Grandpa, GrandpaApiServer};
@@ -258,6 +263,12 @@ where
 
     io.merge(System::new(client.clone(), pool.clone(), deny_unsafe).into_rpc())?;
     io.merge(TransactionPayment::new(client.clone()).into_rpc())?;
+    io.merge(
+        Credit::<_, Block, Balance>::new(client.clone())
+            .with_finality_provider(finality_provider.clone())
+            .into_rpc(),
+    )?;
+    io.merge(DeeperNode::<_, Block>::new(client.clone()).into_rpc())?;
 
     io.merge(
         Babe::new(
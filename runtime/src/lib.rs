@@ -43,6 +43,11 @@ use frame_system::{
     EnsureRoot,
 };
 pub use node_primitives::{AccountId, Signature};
+use node_primitives::credit::{
+    Campaign, CampaignId, CreditChange, CreditCounter, CreditData, CreditHistorySummary,
+    CreditInterface, CreditLevel, CreditSource, EraIndex,
+};
+use node_primitives::deeper_node::ServerFilter;
 use node_primitives::{AccountIndex, Balance, BlockNumber, Hash, Index, Moment};
 use pallet_grandpa::fg_primitives;
 use pallet_grandpa::{AuthorityId as GrandpaId, AuthorityList as GrandpaAuthorityList};
@@ -1210,6 +1215,9 @@ parameter_types! {
     pub const SecsPerBlock: u32 = MILLISECS_PER_BLOCK as u32 / 1000;
     pub const DataPerDPR: u64 = 1024 * 1024 * 1024 * 1024;
     pub const MicropaymentBurn: Percent = Percent::from_percent(10);
+    pub const MaxPendingLocks: u32 = 16;
+    pub const MaxBatchClaims: u32 = 200;
+    pub const ChannelDisputePeriod: BlockNumber = prod_or_fast!(1 * DAYS, 1 * MINUTES);
 }
 
 #[cfg(feature = "runtime-benchmarks")]
@@ -1245,8 +1253,12 @@ impl pallet_micropayment::Config for Runtime {
     type SecsPerBlock = SecsPerBlock;
     type DataPerDPR = DataPerDPR;
     type WeightInfo = pallet_micropayment::weights::SubstrateWeight<Runtime>;
+    type Assets = Assets;
     type NodeInterface = DeeperNode;
     type MicropaymentBurn = MicropaymentBurn;
+    type MaxPendingLocks = MaxPendingLocks;
+    type ChannelDisputePeriod = ChannelDisputePeriod;
+    type MaxBatchClaims = MaxBatchClaims;
     type Slash = Treasury;
     #[cfg(feature = "runtime-benchmarks")]
     type AccountCreator = bench_mark_account::DefaultAccountCreator;
@@ -1256,6 +1268,10 @@ parameter_types! {
     pub const MinLockAmt: u32 = 100000;
     pub const MaxDurationEras: u8 = 7;
     pub const MaxIpLength: usize = 256;
+    pub const MaxSweepDevices: u32 = 100;
+    pub const MaxServersPerRegion: u32 = 50_000;
+    pub const MaxFirmwareLength: u32 = 64;
+    pub const DeviceMigrationCooldown: BlockNumber = 30 * DAYS;
 }
 
 impl pallet_deeper_node::Config for Runtime {
@@ -1267,6 +1283,13 @@ impl pallet_deeper_node::Config for Runtime {
     type MaxIpLength = MaxIpLength;
     type WeightInfo = pallet_deeper_node::weights::SubstrateWeight<Runtime>;
     type VerifySignatureInterface = CreditAccumulation;
+    type CreditInterface = Credit;
+    type MaxSweepDevices = MaxSweepDevices;
+    type MaxServersPerRegion = MaxServersPerRegion;
+    type MaxFirmwareLength = MaxFirmwareLength;
+    type UserPrivilegeInterface = UserPrivileges;
+    type DeviceMigration = (Credit, Staking);
+    type DeviceMigrationCooldown = DeviceMigrationCooldown;
 }
 
 parameter_types! {
@@ -1300,11 +1323,28 @@ impl pallet_uniques::Config for Runtime {
     type ValueLimit = ValueLimit;
     type WeightInfo = pallet_uniques::weights::SubstrateWeight<Runtime>;
     type CreateOrigin = AsEnsureOriginWithArg<frame_system::EnsureSigned<AccountId>>;
-    type Locker = ();
+    type Locker = Credit;
 }
 
 parameter_types! {
     pub const MaxBurnCreditPerAddress: u32 = 50;
+    pub const MinSettingEnactmentDelay: EraIndex = 7;
+    pub const MaxScheduledSettingsPerEra: u32 = 100;
+    // covers the eras rewards can still be claimed for
+    pub const MaxCreditHistory: u32 = 128;
+    pub const PriceQuorum: u32 = 3;
+    // in seconds
+    pub const PriceStaleAfter: u64 = 24 * 60 * 60;
+    // a week of hourly prices
+    pub const MaxPriceHistory: u32 = 7 * 24;
+    pub const PriceAggregationInterval: u64 = 60 * 60;
+    pub const PriceFeedInterval: BlockNumber = 10 * MINUTES;
+    pub const MaxPriceFeedEndpoints: u32 = 10;
+    pub const MaxPriceFeedEndpointLength: u32 = 256;
+    // reserved for the soulbound credit level badges,
+    // created by the credit pallet in the runtime upgrade
+    pub const CreditBadgeCollection: Option<u32> = Some(u32::MAX);
+    pub const MaxCreditChanges: u32 = 20;
 }
 
 impl pallet_credit::Config for Runtime {
@@ -1322,12 +1362,33 @@ impl pallet_credit::Config for Runtime {
     type BurnedTo = Treasury;
     type UserPrivilegeInterface = UserPrivileges;
     type MaxBurnCreditPerAddress = MaxBurnCreditPerAddress;
+    type CreditSettingOrigin = EitherOfDiverse<
+        EnsureRoot<AccountId>,
+        pallet_collective::EnsureProportionAtLeast<AccountId, CouncilCollective, 3, 5>,
+    >;
+    type MinSettingEnactmentDelay = MinSettingEnactmentDelay;
+    type MaxScheduledSettingsPerEra = MaxScheduledSettingsPerEra;
+    type MaxCreditHistory = MaxCreditHistory;
+    type PriceQuorum = PriceQuorum;
+    type PriceStaleAfter = PriceStaleAfter;
+    type MaxPriceHistory = MaxPriceHistory;
+    type PriceAggregationInterval = PriceAggregationInterval;
+    type PriceFeed = pallet_credit::price_feed::SignedPriceFeed<
+        Runtime,
+        pallet_credit::price_feed::crypto::OracleAuthId,
+    >;
+    type PriceFeedInterval = PriceFeedInterval;
+    type MaxPriceFeedEndpoints = MaxPriceFeedEndpoints;
+    type MaxPriceFeedEndpointLength = MaxPriceFeedEndpointLength;
+    type CreditBadgeCollection = CreditBadgeCollection;
+    type MaxCreditChanges = MaxCreditChanges;
 }
 
 impl pallet_credit_accumulation::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
     type Currency = Balances;
     type CreditInterface = Credit;
+    type NodeInterface = DeeperNode;
     type WeightInfo = pallet_credit_accumulation::weights::SubstrateWeight<Runtime>;
     #[cfg(feature = "runtime-benchmarks")]
     type AccountCreator = bench_mark_account::DefaultAccountCreator;
@@ -1783,6 +1844,80 @@ impl_runtime_apis! {
 
 
 
+    impl pallet_credit_rpc_runtime_api::CreditApi<Block, AccountId, Balance> for Runtime {
+        fn get_credit_data(account_id: AccountId) -> Option<CreditData> {
+            Credit::user_credit(&account_id)
+        }
+
+        fn get_credit_history(
+            account_id: AccountId,
+        ) -> (Vec<(EraIndex, CreditData)>, Option<CreditHistorySummary>) {
+            Credit::load_credit_history(&account_id)
+        }
+
+        fn get_credit_level(account_id: AccountId) -> Option<CreditLevel> {
+            Credit::user_credit_relevelled(&account_id)
+                .map(|credit_data| credit_data.current_credit_level)
+        }
+
+        fn estimate_reward(account_id: AccountId, from: EraIndex, to: EraIndex) -> Option<Balance> {
+            <Credit as CreditInterface<AccountId, Balance>>::get_reward(&account_id, from, to)
+                .0
+                .ok()
+                .flatten()
+        }
+
+        fn evm_to_account(evm_address: H160) -> Option<AccountId> {
+            DeeperNode::rewards_accounts_evm_deeper(&evm_address)
+        }
+
+        fn get_campaign(campaign_id: CampaignId) -> Option<Campaign<Balance>> {
+            Credit::campaign(campaign_id)
+        }
+
+        fn campaign_remaining_budget(campaign_id: CampaignId) -> Option<Balance> {
+            Credit::campaign_remaining_budget(campaign_id)
+        }
+
+        fn dpr_twap(window: u64) -> Option<Balance> {
+            Credit::dpr_twap(window)
+        }
+
+        fn user_credit_storage_key(account_id: AccountId) -> Vec<u8> {
+            pallet_credit::UserCredit::<Runtime>::hashed_key_for(account_id)
+        }
+
+        fn get_credit_breakdown(account_id: AccountId) -> Vec<(CreditSource, CreditCounter)> {
+            Credit::credit_breakdown(&account_id)
+        }
+
+        fn get_credit_changes(account_id: AccountId, count: u32) -> Vec<CreditChange<BlockNumber>> {
+            Credit::recent_credit_changes(&account_id, count)
+        }
+    }
+
+    impl pallet_deeper_node_rpc_runtime_api::DeeperNodeApi<Block, AccountId> for Runtime {
+        fn discover_country_servers(
+            country: Vec<u8>,
+            filter: ServerFilter,
+            seed: u64,
+            offset: u32,
+            limit: u32,
+        ) -> (Vec<AccountId>, u32) {
+            DeeperNode::discover_country_servers(country, filter, seed, offset, limit)
+        }
+
+        fn discover_region_servers(
+            region: Vec<u8>,
+            filter: ServerFilter,
+            seed: u64,
+            offset: u32,
+            limit: u32,
+        ) -> (Vec<AccountId>, u32) {
+            DeeperNode::discover_region_servers(region, filter, seed, offset, limit)
+        }
+    }
+
     impl pallet_contracts::ContractsApi<Block, AccountId, Balance, BlockNumber, Hash>
         for Runtime
     {
@@ -6,12 +6,16 @@ use pallet_evm_precompile_modexp::Modexp;
 use pallet_evm_precompile_sha3fips::Sha3FIPS256;
 use pallet_evm_precompile_simple::{ECRecover, ECRecoverPublicKey, Identity, Ripemd160, Sha256};
 
+use pallet_evm_precompile_assets_erc20::Erc20AssetsPrecompileSet;
 use pallet_evm_precompile_blake2::Blake2F;
 use pallet_evm_precompile_bn128::{Bn128Add, Bn128Mul, Bn128Pairing};
 use pallet_evm_precompile_credit::CreditDispatch;
 use pallet_evm_precompile_curve25519::{Curve25519Add, Curve25519ScalarMul};
+use pallet_evm_precompile_deeper_node::DeeperNodeDispatch;
 use pallet_evm_precompile_dispatch::Dispatch;
 use pallet_evm_precompile_ed25519::Ed25519Verify;
+use pallet_evm_precompile_micropayment::MicropaymentDispatch;
+use pallet_evm_precompile_staking::StakingDispatch;
 
 pub struct FrontierPrecompiles<R>(PhantomData<R>);
 
@@ -23,17 +27,28 @@ where
         Self(Default::default())
     }
     pub fn used_addresses() -> sp_std::vec::Vec<H160> {
-        sp_std::vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 1024, 1025, 1026, 1027, 1028, 1029]
-            .into_iter()
-            .map(hash)
-            .collect()
+        sp_std::vec![
+            1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 1024, 1025, 1026, 1027, 1028, 1029, 1030, 1031, 1032,
+            1033,
+        ]
+        .into_iter()
+        .map(hash)
+        .collect()
     }
 }
 impl<R> PrecompileSet for FrontierPrecompiles<R>
 where
     CreditDispatch<R>: Precompile,
+    MicropaymentDispatch<R>: Precompile,
+    DeeperNodeDispatch<R>: Precompile,
+    StakingDispatch<R>: Precompile,
+    Erc20AssetsPrecompileSet<R>: PrecompileSet,
     Dispatch<R>: Precompile,
-    R: pallet_credit::Config + pallet_evm::Config,
+    R: pallet_credit::Config
+        + pallet_micropayment::Config
+        + pallet_deeper_node::Config
+        + pallet_staking::Config
+        + pallet_evm::Config,
 {
     fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
         match handle.code_address() {
@@ -56,12 +71,18 @@ where
             a if a == hash(1028) => Some(Curve25519ScalarMul::execute(handle)),
             a if a == hash(1029) => Some(Ed25519Verify::execute(handle)),
             a if a == hash(1030) => Some(CreditDispatch::<R>::execute(handle)),
-            _ => None,
+            a if a == hash(1031) => Some(MicropaymentDispatch::<R>::execute(handle)),
+            a if a == hash(1032) => Some(DeeperNodeDispatch::<R>::execute(handle)),
+            a if a == hash(1033) => Some(StakingDispatch::<R>::execute(handle)),
+
+            // ERC-20 precompiles of the `pallet_assets` tokens :
+            _ => Erc20AssetsPrecompileSet::<R>::new().execute(handle),
         }
     }
 
     fn is_precompile(&self, address: H160) -> bool {
         Self::used_addresses().contains(&address)
+            || Erc20AssetsPrecompileSet::<R>::new().is_precompile(address)
     }
 }
 